@@ -1,5 +1,8 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use sitewriter::{ChangeFreq, UrlEntry, UrlEntryBuilder};
+use sitewriter::{
+    synthetic::{generate_entries, Profile},
+    ChangeFreq, Sitemap, UrlEntry, UrlEntryBuilder, UrlEntryRef,
+};
 
 fn benchmark(c: &mut Criterion) {
     use chrono::Utc;
@@ -7,7 +10,7 @@ fn benchmark(c: &mut Criterion) {
     let urls = vec![
         // Builder pattern
         UrlEntryBuilder::default()
-            .loc("https://domain.com".parse().unwrap())
+            .loc("https://domain.com")
             .priority(0.2)
             .build()
             .unwrap(),
@@ -24,30 +27,45 @@ fn benchmark(c: &mut Criterion) {
             priority: None,
             changefreq: Some(ChangeFreq::Always),
             lastmod: None,
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
         },
         UrlEntry {
             loc: "https://domain.com/url".parse().unwrap(),
             changefreq: Some(ChangeFreq::Daily),
             priority: Some(0.8),
             lastmod: Some(Utc::now()),
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
         },
         UrlEntry {
             loc: "https://domain.com/aa".parse().unwrap(),
             changefreq: Some(ChangeFreq::Monthly),
             priority: None,
             lastmod: None,
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
         },
         UrlEntry {
             loc: "https://domain.com/bb".parse().unwrap(),
             changefreq: None,
             priority: None,
             lastmod: None,
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
         },
         UrlEntry {
             loc: "https://domain.com/bb&id='<test>'".parse().unwrap(),
             changefreq: None,
             priority: Some(0.4),
             lastmod: None,
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
         },
     ];
 
@@ -58,6 +76,28 @@ fn benchmark(c: &mut Criterion) {
     c.bench_function("generate_bytes", |b| {
         b.iter(|| sitewriter::generate_bytes(black_box(&urls)))
     });
+
+    let urls: Vec<UrlEntry> = generate_entries(10_000, &Profile::default()).collect();
+
+    let locs: Vec<String> = urls.iter().map(|entry| entry.loc.to_string()).collect();
+    c.bench_function("generate_refs_10k", |b| {
+        b.iter(|| {
+            let refs: Vec<UrlEntryRef> = locs.iter().map(|loc| UrlEntryRef::new(loc)).collect();
+            Sitemap::new().generate_refs(Vec::new(), black_box(&refs))
+        })
+    });
+
+    c.bench_function("generate_bytes_10k", |b| {
+        b.iter(|| sitewriter::generate_bytes(black_box(&urls)))
+    });
+
+    #[cfg(feature = "rayon")]
+    {
+        let sitemap = Sitemap::new().parallel(true);
+        c.bench_function("generate_bytes_10k_parallel", |b| {
+            b.iter(|| sitemap.generate_bytes(black_box(&urls)))
+        });
+    }
 }
 
 criterion_group!(benches, benchmark);
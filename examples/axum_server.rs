@@ -0,0 +1,41 @@
+//! Serves the sitemap from an axum handler, with caching headers.
+//!
+//! Route the handler like any other:
+//!
+//! ```text
+//! let app = axum::Router::new().route("/sitemap.xml", axum::routing::get(sitemap));
+//! ```
+
+use axum_core::response::{IntoResponse, Response};
+use sitewriter::{response::SitemapResponse, Sitemap, UrlEntry};
+
+/// The pages of the site, as loaded from its database.
+pub fn pages() -> Vec<UrlEntry> {
+    ["/", "/about", "/blog"]
+        .iter()
+        .map(|path| {
+            let loc = format!("https://example.com{path}").parse().unwrap();
+            UrlEntry::new(loc, None, None, None)
+        })
+        .collect()
+}
+
+/// The handler of `/sitemap.xml`.
+pub async fn sitemap() -> Response {
+    response()
+}
+
+/// The response of the handler, cached by clients and CDNs for ten minutes.
+pub fn response() -> Response {
+    SitemapResponse::generate(&Sitemap::new(), &pages())
+        .cache_control("public, max-age=600")
+        .into_response()
+}
+
+fn main() {
+    let response = response();
+    println!("{}", response.status());
+    for (name, value) in response.headers() {
+        println!("{name}: {}", value.to_str().unwrap_or_default());
+    }
+}
@@ -0,0 +1,39 @@
+//! Combines the urls of several subsystems, removing the pages listed more than once.
+
+use chrono::{TimeZone, Utc};
+use sitewriter::{ConflictStrategy, NormalizeOptions, Sitemap, TrailingSlash, UrlEntry};
+
+/// Urls as reported by the blog, the shop and the docs.
+pub fn sources() -> Vec<UrlEntry> {
+    let date = |day| Some(Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap());
+    let entry = |loc: &str, lastmod| UrlEntry::new(loc.parse().unwrap(), lastmod, None, None);
+
+    vec![
+        // Blog
+        entry("https://example.com/blog", date(1)),
+        entry("https://example.com/about", date(2)),
+        // Shop
+        entry("https://EXAMPLE.com/shop/", date(3)),
+        entry("https://example.com/about/", date(5)),
+        // Docs
+        entry("https://example.com/docs#install", date(4)),
+        entry("https://example.com/about#team", None),
+    ]
+}
+
+/// Removes the duplicates, keeping the most recently modified version of each page.
+pub fn dedup(urls: Vec<UrlEntry>) -> Vec<UrlEntry> {
+    Sitemap::new()
+        .normalize(NormalizeOptions {
+            trailing_slash: TrailingSlash::Add,
+            ..Default::default()
+        })
+        .on_conflict(ConflictStrategy::NewestLastmod)
+        .dedup(urls)
+        .expect("newest lastmod never fails")
+}
+
+fn main() {
+    let urls = dedup(sources());
+    println!("{}", sitewriter::generate_str(&urls));
+}
@@ -0,0 +1,74 @@
+//! Builds a sitemap from rows fetched page by page from a database.
+//!
+//! The database here is faked with a vector, swap `FakeDb` for your own client.
+
+use chrono::{DateTime, TimeZone, Utc};
+use sitewriter::{ChangeFreq, Url, UrlEntry};
+
+/// A row of the `posts` table.
+pub struct Post {
+    pub slug: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Stand-in for a real database connection.
+pub struct FakeDb {
+    posts: Vec<Post>,
+}
+
+impl FakeDb {
+    pub fn new(count: usize) -> Self {
+        let posts = (0..count)
+            .map(|i| Post {
+                slug: format!("post-{i}"),
+                updated_at: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+                    + chrono::Duration::days(i as i64),
+            })
+            .collect();
+        Self { posts }
+    }
+
+    /// Fetches a page of posts, like a `LIMIT .. OFFSET ..` query would.
+    pub fn fetch_page(&self, offset: usize, limit: usize) -> &[Post] {
+        let end = (offset + limit).min(self.posts.len());
+        &self.posts[offset.min(end)..end]
+    }
+}
+
+/// Writes the sitemap of every post to `writer`.
+pub fn write_sitemap<W: std::io::Write>(
+    db: &FakeDb,
+    base: &Url,
+    writer: W,
+) -> sitewriter::Result<W> {
+    const PAGE_SIZE: usize = 100;
+
+    let mut urls = Vec::new();
+    let mut offset = 0;
+    loop {
+        let page = db.fetch_page(offset, PAGE_SIZE);
+        if page.is_empty() {
+            break;
+        }
+        urls.extend(page.iter().map(|post| UrlEntry {
+            loc: base.join(&format!("blog/{}", post.slug)).unwrap(),
+            lastmod: Some(post.updated_at),
+            changefreq: Some(ChangeFreq::Monthly),
+            priority: Some(0.5),
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
+        }));
+        offset += page.len();
+    }
+
+    sitewriter::generate(writer, &urls)
+}
+
+fn main() {
+    let db = FakeDb::new(250);
+    let base = "https://example.com/".parse().unwrap();
+
+    let mut stdout = std::io::stdout().lock();
+    write_sitemap(&db, &base, &mut stdout).expect("failed to write the sitemap");
+}
@@ -4,7 +4,7 @@ use sitewriter::{ChangeFreq, UrlEntry, UrlEntryBuilder};
 fn main() {
     let urls = vec![
         UrlEntryBuilder::default()
-            .loc("https://edgarluque.com/projects".parse().unwrap())
+            .loc("https://edgarluque.com/projects")
             .build()
             .unwrap(),
         UrlEntry {
@@ -12,18 +12,27 @@ fn main() {
             changefreq: Some(ChangeFreq::Daily),
             priority: Some(1.0),
             lastmod: Some(Utc::now()),
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
         },
         UrlEntry {
             loc: "https://edgarluque.com/blog".parse().unwrap(),
             changefreq: Some(ChangeFreq::Weekly),
             priority: Some(0.8),
             lastmod: Some(Utc::now()),
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
         },
         UrlEntry {
             loc: "https://edgarluque.com/blog/sitewriter".parse().unwrap(),
             changefreq: Some(ChangeFreq::Never),
             priority: Some(0.5),
             lastmod: Some(Utc.with_ymd_and_hms(2020, 12, 5, 15, 30, 0).unwrap()),
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
         },
         UrlEntry {
             loc: "https://edgarluque.com/blog/some-future-post"
@@ -32,6 +41,9 @@ fn main() {
             changefreq: Some(ChangeFreq::Never),
             priority: Some(0.5),
             lastmod: Some(Utc.with_ymd_and_hms(2020, 12, 5, 12, 30, 0).unwrap()),
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
         },
         // Entity escaping
         UrlEntry {
@@ -41,6 +53,9 @@ fn main() {
             changefreq: Some(ChangeFreq::Never),
             priority: Some(0.5),
             lastmod: Some(Utc.with_ymd_and_hms(2020, 12, 5, 12, 30, 0).unwrap()),
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
         },
     ];
 
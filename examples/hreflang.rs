@@ -0,0 +1,60 @@
+//! Lists the translations of each page with `xhtml:link` alternates, for hreflang.
+
+use sitewriter::{ExtensionWriter, SitemapExtension, Url, UrlEntry};
+
+/// A translation of the page, written as `<xhtml:link rel="alternate" ...>`.
+#[derive(Debug, Clone)]
+pub struct Alternate {
+    pub hreflang: String,
+    pub href: Url,
+}
+
+impl SitemapExtension for Alternate {
+    fn prefix(&self) -> &str {
+        "xhtml"
+    }
+
+    fn namespace(&self) -> &str {
+        "http://www.w3.org/1999/xhtml"
+    }
+
+    fn write(&self, writer: &mut ExtensionWriter<'_>) -> sitewriter::Result<()> {
+        writer.empty(
+            "xhtml:link",
+            &[
+                ("rel", "alternate"),
+                ("hreflang", &self.hreflang),
+                ("href", self.href.as_str()),
+            ],
+        )
+    }
+}
+
+/// An entry per page and language, `https://example.com/<language>/<path>`, each listing
+/// every translation of the page, itself included.
+pub fn entries(paths: &[&str], languages: &[&str]) -> Vec<UrlEntry> {
+    let base: Url = "https://example.com/".parse().unwrap();
+    let mut urls = Vec::new();
+    for path in paths {
+        let alternates: Vec<Alternate> = languages
+            .iter()
+            .map(|language| Alternate {
+                hreflang: (*language).to_owned(),
+                href: base.join(&format!("{language}/{path}")).unwrap(),
+            })
+            .collect();
+        for alternate in &alternates {
+            let mut entry = UrlEntry::new(alternate.href.clone(), None, None, None);
+            for alternate in &alternates {
+                entry.extensions.push(alternate.clone());
+            }
+            urls.push(entry);
+        }
+    }
+    urls
+}
+
+fn main() {
+    let urls = entries(&["", "pricing"], &["en", "de", "fr"]);
+    println!("{}", sitewriter::generate_str(&urls));
+}
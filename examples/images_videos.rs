@@ -0,0 +1,81 @@
+//! Describes the images and videos of the pages with the Google extensions.
+
+use sitewriter::{ExtensionWriter, SitemapExtension, Url, UrlEntry};
+
+/// An image of the page, written as `<image:image>`.
+#[derive(Debug)]
+pub struct Image {
+    pub loc: Url,
+}
+
+impl SitemapExtension for Image {
+    fn prefix(&self) -> &str {
+        "image"
+    }
+
+    fn namespace(&self) -> &str {
+        "http://www.google.com/schemas/sitemap-image/1.1"
+    }
+
+    fn write(&self, writer: &mut ExtensionWriter<'_>) -> sitewriter::Result<()> {
+        writer.start("image:image", &[])?;
+        writer.text_element("image:loc", self.loc.as_str())?;
+        writer.end("image:image")
+    }
+}
+
+/// A video of the page, written as `<video:video>` with the required children.
+#[derive(Debug)]
+pub struct Video {
+    pub thumbnail_loc: Url,
+    pub title: String,
+    pub description: String,
+    pub content_loc: Url,
+}
+
+impl SitemapExtension for Video {
+    fn prefix(&self) -> &str {
+        "video"
+    }
+
+    fn namespace(&self) -> &str {
+        "http://www.google.com/schemas/sitemap-video/1.1"
+    }
+
+    fn write(&self, writer: &mut ExtensionWriter<'_>) -> sitewriter::Result<()> {
+        writer.start("video:video", &[])?;
+        writer.text_element("video:thumbnail_loc", self.thumbnail_loc.as_str())?;
+        writer.text_element("video:title", &self.title)?;
+        writer.text_element("video:description", &self.description)?;
+        writer.text_element("video:content_loc", self.content_loc.as_str())?;
+        writer.end("video:video")
+    }
+}
+
+/// The entry of a product page, with its photos and its demo video.
+pub fn product(slug: &str, photos: usize) -> UrlEntry {
+    let base: Url = "https://example.com/".parse().unwrap();
+    let mut entry = UrlEntry::new(
+        base.join(&format!("products/{slug}")).unwrap(),
+        None,
+        None,
+        None,
+    );
+    for i in 1..=photos {
+        entry.extensions.push(Image {
+            loc: base.join(&format!("img/{slug}-{i}.jpg")).unwrap(),
+        });
+    }
+    entry.extensions.push(Video {
+        thumbnail_loc: base.join(&format!("img/{slug}-demo.jpg")).unwrap(),
+        title: format!("The {slug} in action"),
+        description: format!("A two minute demo of the {slug}."),
+        content_loc: base.join(&format!("video/{slug}-demo.mp4")).unwrap(),
+    });
+    entry
+}
+
+fn main() {
+    let urls = [product("kettle", 2), product("toaster", 1)];
+    println!("{}", sitewriter::generate_str(&urls));
+}
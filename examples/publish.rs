@@ -0,0 +1,100 @@
+//! Publishes the sitemaps of a built static site: the whole pipeline, to fork and adapt.
+//!
+//! ```text
+//! cargo run --example publish --features fs,gzip,manifest,fetch -- \
+//!     public https://example.com/ [PING_ENDPOINT]...
+//! ```
+//!
+//! 1. scans the pages of the site directory, skipping the globs listed in its
+//!    `.sitemapignore`, one per line;
+//! 2. removes duplicates and sorts the entries, so runs over the same site are identical;
+//! 3. validates them, stopping on errors;
+//! 4. writes gzipped sitemaps split within the protocol limits and a sitemap index, each
+//!    file atomically;
+//! 5. records the written files in `sitemap-manifest.json`, for deploy checks;
+//! 6. sends the url of the index to each ping endpoint, as `ENDPOINT?sitemap=URL`.
+
+use std::{error::Error, path::Path};
+
+use sitewriter::{
+    client::{HttpClient, UreqClient},
+    fs::{scan_dir, ScanOptions},
+    manifest::{Manifest, ManifestFile},
+    split::{SplitOutput, WrittenFile},
+    validate::validate,
+    Sitemap, SortOrder, Url,
+};
+
+/// The file of the site directory listing the globs of pages to leave out.
+const IGNORE_FILE: &str = ".sitemapignore";
+
+/// The file the manifest is written to, in the site directory.
+const MANIFEST_FILE: &str = "sitemap-manifest.json";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    let (Some(site), Some(base)) = (args.next(), args.next()) else {
+        return Err("usage: publish SITE_DIR BASE_URL [PING_ENDPOINT]...".into());
+    };
+    let site = Path::new(&site);
+    let base: Url = base.parse()?;
+    let endpoints = args.map(|e| e.parse()).collect::<Result<Vec<Url>, _>>()?;
+
+    let files = publish(site, &base)?;
+    for file in &files {
+        println!(
+            "{} ({} urls) -> {}",
+            file.path.display(),
+            file.url_count,
+            file.url
+        );
+    }
+
+    let client = UreqClient::default();
+    for mut endpoint in endpoints {
+        endpoint
+            .query_pairs_mut()
+            .append_pair("sitemap", files[0].url.as_str());
+        match client.get(&endpoint) {
+            Ok(response) => println!("pinged {endpoint}: {}", response.status),
+            Err(e) => eprintln!("failed to ping {endpoint}: {e}"),
+        }
+    }
+    Ok(())
+}
+
+/// Writes the sitemaps of `site` and their manifest, returns the index followed by the parts.
+fn publish(site: &Path, base: &Url) -> Result<Vec<WrittenFile>, Box<dyn Error>> {
+    let mut options = ScanOptions::default();
+    if let Ok(ignore) = std::fs::read_to_string(site.join(IGNORE_FILE)) {
+        for glob in ignore.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            options = options.exclude(glob);
+        }
+    }
+
+    let sitemap = Sitemap::new().sort(SortOrder::Loc);
+    let urls = sitemap.dedup(scan_dir(site, base, &options)?)?;
+
+    let report = validate(&urls);
+    if !report.is_empty() {
+        eprintln!("{report}");
+    }
+    if report.has_errors() {
+        return Err("invalid entries".into());
+    }
+
+    let output = SplitOutput::new(site, base.clone()).template("sitemap-{index}.xml.gz");
+    let files = sitemap.write_split(&urls, &output)?;
+
+    let mut manifest = Manifest::new();
+    for file in &files {
+        let name = file.path.strip_prefix(site)?.to_string_lossy();
+        let contents = std::fs::read(&file.path)?;
+        manifest.push(
+            ManifestFile::new(name, &contents, file.url_count).destination(file.url.as_str()),
+        );
+    }
+    sitewriter::write_atomic(site.join(MANIFEST_FILE), manifest.to_json().as_bytes())?;
+
+    Ok(files)
+}
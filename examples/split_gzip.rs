@@ -0,0 +1,54 @@
+//! Publishes a large site as gzipped sitemaps split within the limits, plus an index.
+//!
+//! ```text
+//! cargo run --example split_gzip --features gzip -- public
+//! ```
+
+use std::path::Path;
+
+use sitewriter::{
+    split::{SplitOutput, WrittenFile},
+    Limits, Sitemap, Url, UrlEntry,
+};
+
+/// The pages of the site, far more than fit in a single sitemap.
+pub fn pages(count: usize) -> Vec<UrlEntry> {
+    (0..count)
+        .map(|i| {
+            let loc = format!("https://example.com/products/{i}").parse().unwrap();
+            UrlEntry::new(loc, None, None, None)
+        })
+        .collect()
+}
+
+/// Writes `sitemap-1.xml.gz`, `sitemap-2.xml.gz`... with at most `per_file` entries each,
+/// and the `sitemap_index.xml` listing them, to `dir`, which is served under `base`.
+pub fn publish(
+    urls: &[UrlEntry],
+    dir: &Path,
+    base: Url,
+    per_file: usize,
+) -> sitewriter::Result<Vec<WrittenFile>> {
+    let output = SplitOutput::new(dir, base).template("sitemap-{index}.xml.gz");
+    Sitemap::new()
+        .limits(Limits::new(per_file, sitewriter::MAX_SIZE))
+        .write_split(urls, &output)
+}
+
+fn main() {
+    let dir = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "public".to_owned());
+    let base = "https://example.com/".parse().unwrap();
+
+    let files = publish(
+        &pages(120_000),
+        Path::new(&dir),
+        base,
+        sitewriter::MAX_ENTRIES,
+    )
+    .expect("failed to write the sitemaps");
+    for file in files {
+        println!("{} -> {}", file.path.display(), file.url);
+    }
+}
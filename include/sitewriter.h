@@ -0,0 +1,64 @@
+#ifndef SITEWRITER_H
+#define SITEWRITER_H
+
+/* Generated with cbindgen from src/ffi.rs, don't edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Bytes allocated by the library, released with [`sitewriter_buffer_free`].
+ */
+typedef struct SitewriterBuffer {
+  /**
+   * The bytes, null when the buffer is empty.
+   */
+  uint8_t *data;
+  /**
+   * The number of bytes.
+   */
+  size_t len;
+} SitewriterBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif  // __cplusplus
+
+/**
+ * Generates the sitemap of `entries_json`, a JSON array of entries.
+ *
+ * Returns 0 with the XML stored in `out`, to release with [`sitewriter_buffer_free`].
+ * Returns -1 on failure, with `out` left empty.
+ *
+ * # Safety
+ *
+ * `entries_json` must be null or a NUL terminated string, and `out` must be null or
+ * point to a [`SitewriterBuffer`] that can be written.
+ */
+int sitewriter_generate(const char *entries_json, struct SitewriterBuffer *out);
+
+/**
+ * Releases the bytes of `buffer` and empties it. Releasing an empty buffer does nothing.
+ *
+ * # Safety
+ *
+ * `buffer` must be null or point to a buffer filled by this library, or emptied by this
+ * function.
+ */
+void sitewriter_buffer_free(struct SitewriterBuffer *buffer);
+
+/**
+ * The message of the last failure on the calling thread, or null if nothing failed.
+ *
+ * The string is valid until the next failure on the same thread.
+ */
+const char *sitewriter_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SITEWRITER_H */
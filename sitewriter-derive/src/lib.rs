@@ -0,0 +1,207 @@
+//! The derive macro of the `ToUrlEntry` trait of
+//! [sitewriter](https://docs.rs/sitewriter), enabled with its `derive` feature.
+
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+#![deny(warnings)]
+#![deny(clippy::nursery)]
+#![deny(clippy::all)]
+
+use proc_macro::TokenStream;
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    meta::ParseNestedMeta, parse_macro_input, Attribute, Data, DeriveInput, Error, Lit, LitStr,
+    Member, Result,
+};
+
+/// The variants of `ChangeFreq`, by their name in the sitemap protocol.
+const CHANGEFREQS: [(&str, &str); 7] = [
+    ("always", "Always"),
+    ("hourly", "Hourly"),
+    ("daily", "Daily"),
+    ("weekly", "Weekly"),
+    ("monthly", "Monthly"),
+    ("yearly", "Yearly"),
+    ("never", "Never"),
+];
+
+/// Implements `ToUrlEntry` from `#[sitemap(..)]` attributes.
+///
+/// On the fields:
+///
+/// - `#[sitemap(loc)]`: the path of the page, joined to the base url, the field is
+///   `AsRef<str>`;
+/// - `#[sitemap(loc = "blog/{}")]`: the same with the field formatted into the path, the
+///   field is `Display`;
+/// - `#[sitemap(lastmod)]`, `#[sitemap(changefreq)]`, `#[sitemap(priority)]`: the field is
+///   the value, or an `Option` of it.
+///
+/// On the struct, `#[sitemap(changefreq = "weekly")]` and `#[sitemap(priority = 0.8)]` set
+/// the values of the entries whose fields don't.
+///
+/// Exactly one field has to be the loc. The derived `to_url_entry` panics if the path
+/// can't be joined to the base url.
+#[proc_macro_derive(ToUrlEntry, attributes(sitemap))]
+pub fn derive_to_url_entry(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// The path of the loc of the entries.
+enum Loc {
+    /// The field is the path.
+    Path(Member),
+    /// The field is formatted into the path.
+    Format(Member, LitStr),
+}
+
+/// The values read from the `#[sitemap(..)]` attributes.
+#[derive(Default)]
+struct Attributes {
+    loc: Option<Loc>,
+    lastmod: Option<Member>,
+    changefreq: Option<Member>,
+    priority: Option<Member>,
+    default_changefreq: Option<TokenStream2>,
+    default_priority: Option<Literal>,
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            input,
+            "ToUrlEntry can only be derived for structs",
+        ));
+    };
+
+    let mut attributes = Attributes::default();
+    for_each_meta(&input.attrs, |meta| {
+        if meta.path.is_ident("changefreq") {
+            let name: LitStr = meta.value()?.parse()?;
+            let Some((_, variant)) = CHANGEFREQS.iter().find(|(n, _)| *n == name.value()) else {
+                return Err(meta.error(
+                    "expected one of always, hourly, daily, weekly, monthly, yearly or never",
+                ));
+            };
+            let variant = syn::Ident::new(variant, name.span());
+            set(
+                &meta,
+                &mut attributes.default_changefreq,
+                quote!(::sitewriter::ChangeFreq::#variant),
+            )
+        } else if meta.path.is_ident("priority") {
+            let priority = match meta.value()?.parse()? {
+                Lit::Float(lit) => lit.base10_parse::<f32>()?,
+                Lit::Int(lit) => lit.base10_parse::<f32>()?,
+                lit => return Err(Error::new_spanned(lit, "expected a number")),
+            };
+            if !(0.0..=1.0).contains(&priority) {
+                return Err(meta.error("the priority must be between 0.0 and 1.0"));
+            }
+            set(
+                &meta,
+                &mut attributes.default_priority,
+                Literal::f32_suffixed(priority),
+            )
+        } else {
+            Err(meta.error("expected `changefreq` or `priority`"))
+        }
+    })?;
+
+    for (i, field) in data.fields.iter().enumerate() {
+        let member = field
+            .ident
+            .clone()
+            .map_or_else(|| Member::from(i), Member::Named);
+        for_each_meta(&field.attrs, |meta| {
+            let member = member.clone();
+            if meta.path.is_ident("loc") {
+                let loc = if meta.input.peek(syn::Token![=]) {
+                    Loc::Format(member, meta.value()?.parse()?)
+                } else {
+                    Loc::Path(member)
+                };
+                set(&meta, &mut attributes.loc, loc)
+            } else if meta.path.is_ident("lastmod") {
+                set(&meta, &mut attributes.lastmod, member)
+            } else if meta.path.is_ident("changefreq") {
+                set(&meta, &mut attributes.changefreq, member)
+            } else if meta.path.is_ident("priority") {
+                set(&meta, &mut attributes.priority, member)
+            } else {
+                Err(meta.error("expected `loc`, `lastmod`, `changefreq` or `priority`"))
+            }
+        })?;
+    }
+
+    let path = match &attributes.loc {
+        Some(Loc::Path(member)) => quote!(::core::convert::AsRef::<str>::as_ref(&self.#member)),
+        Some(Loc::Format(member, format)) => quote!(&::std::format!(#format, self.#member)),
+        None => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "expected a field with `#[sitemap(loc)]`",
+            ))
+        }
+    };
+    let field = |member: &Option<Member>, name: &str| {
+        member.as_ref().map(|member| {
+            let name = syn::Ident::new(name, Span::call_site());
+            quote! {
+                entry.#name = ::core::convert::Into::into(::core::clone::Clone::clone(&self.#member));
+            }
+        })
+    };
+    let lastmod = field(&attributes.lastmod, "lastmod");
+    let changefreq = field(&attributes.changefreq, "changefreq");
+    let priority = field(&attributes.priority, "priority");
+    let default_changefreq = attributes.default_changefreq.map(|changefreq| {
+        quote!(entry.changefreq = entry.changefreq.or(::core::option::Option::Some(#changefreq));)
+    });
+    let default_priority = attributes.default_priority.map(|priority| {
+        quote!(entry.priority = entry.priority.or(::core::option::Option::Some(#priority));)
+    });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::sitewriter::ToUrlEntry for #name #ty_generics #where_clause {
+            fn to_url_entry(&self, base: &::sitewriter::Url) -> ::sitewriter::UrlEntry {
+                let path: &str = #path;
+                let loc = base
+                    .join(path)
+                    .unwrap_or_else(|e| ::core::panic!("invalid loc {path:?}: {e}"));
+                let mut entry = ::sitewriter::UrlEntry::new(loc, None, None, None);
+                #lastmod
+                #changefreq
+                #priority
+                #default_changefreq
+                #default_priority
+                entry
+            }
+        }
+    })
+}
+
+/// Calls `f` with each item of the `#[sitemap(..)]` attributes.
+fn for_each_meta(
+    attrs: &[Attribute],
+    mut f: impl FnMut(ParseNestedMeta<'_>) -> Result<()>,
+) -> Result<()> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("sitemap")) {
+        attr.parse_nested_meta(&mut f)?;
+    }
+    Ok(())
+}
+
+/// Sets `slot` to `value`, fails if it was already set.
+fn set<T>(meta: &ParseNestedMeta<'_>, slot: &mut Option<T>, value: T) -> Result<()> {
+    if slot.is_some() {
+        return Err(meta.error("duplicate attribute"));
+    }
+    *slot = Some(value);
+    Ok(())
+}
@@ -0,0 +1,263 @@
+//! Comparing the live sitemap of a site with the one about to replace it.
+//!
+//! A CMS bug that drops half the pages produces a perfectly valid sitemap, and crawlers
+//! then drop the pages too. [`audit_with`] fetches the live sitemap, diffs it with the
+//! new entries and checks the share of urls that vanish, appear or change their lastmod
+//! against [`Thresholds`], so a deploy can be stopped before it does harm.
+//!
+//! ```rust
+//! use sitewriter::{audit::{audit_with, AuditOptions}, client::HttpResponse, Url, UrlEntry};
+//!
+//! let client = |_: &Url| {
+//!     Ok(HttpResponse::ok(
+//!         "<urlset><url><loc>https://example.com/a</loc></url>\
+//!          <url><loc>https://example.com/b</loc></url></urlset>",
+//!     ))
+//! };
+//! let new = vec![UrlEntry::new("https://example.com/a".parse()?, None, None, None)];
+//!
+//! let report = audit_with(
+//!     &client,
+//!     &"https://example.com/sitemap.xml".parse()?,
+//!     &new,
+//!     &AuditOptions::default(),
+//! )?;
+//! assert_eq!(report.removed.len(), 1);
+//! // Half of the live urls vanish, over the default threshold of 20%.
+//! assert!(!report.is_ok());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt::Display;
+
+use url::Url;
+
+use crate::{
+    client::HttpClient,
+    diff::diff,
+    fetch::{fetch_index_recursive_with, FetchOptions},
+    Result, UrlEntry,
+};
+
+/// The largest shares of the live urls that may change, as fractions between 0 and 1.
+///
+/// `None` doesn't check the change.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// The share of live urls missing from the new entries, 20% by default.
+    pub max_removed: Option<f64>,
+    /// The number of new urls, as a share of the live urls.
+    pub max_added: Option<f64>,
+    /// The share of live urls whose lastmod changes.
+    pub max_lastmod_changed: Option<f64>,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            max_removed: Some(0.2),
+            max_added: None,
+            max_lastmod_changed: None,
+        }
+    }
+}
+
+/// The options of [`audit_with`].
+#[derive(Debug, Clone, Default)]
+pub struct AuditOptions {
+    /// How the live sitemap is fetched.
+    pub fetch: FetchOptions,
+    /// The changes that fail the audit.
+    pub thresholds: Thresholds,
+}
+
+/// A kind of change between the live and the new sitemap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Change {
+    /// Urls missing from the new entries.
+    Removed,
+    /// Urls missing from the live sitemap.
+    Added,
+    /// Urls whose lastmod changes.
+    LastmodChanged,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Removed => "removed",
+            Self::Added => "added",
+            Self::LastmodChanged => "lastmod changed",
+        })
+    }
+}
+
+/// A change over its threshold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Violation {
+    /// The kind of change.
+    pub change: Change,
+    /// Its share of the live urls.
+    pub ratio: f64,
+    /// The threshold.
+    pub max: f64,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1}% of the urls {}, over the maximum of {:.1}%",
+            self.ratio * 100.0,
+            self.change,
+            self.max * 100.0
+        )
+    }
+}
+
+/// The result of an audit.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditReport {
+    /// The number of live urls.
+    pub live_count: usize,
+    /// The urls that will disappear.
+    pub removed: Vec<UrlEntry>,
+    /// The urls that will appear.
+    pub added: Vec<UrlEntry>,
+    /// The urls whose lastmod changes, as the live and the new entry.
+    pub lastmod_changed: Vec<(UrlEntry, UrlEntry)>,
+    /// The changes over their threshold.
+    pub violations: Vec<Violation>,
+}
+
+impl AuditReport {
+    /// Whether no change is over its threshold.
+    #[must_use]
+    pub const fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Audits the new entries against the live sitemap at `url` with the default options.
+///
+/// See [`audit_with`].
+///
+/// # Errors
+///
+/// Will return `Err` as [`audit_with`].
+#[cfg(feature = "fetch")]
+pub fn audit(live_sitemap_url: &Url, new_entries: &[UrlEntry]) -> Result<AuditReport> {
+    audit_with(
+        &crate::client::UreqClient::default(),
+        live_sitemap_url,
+        new_entries,
+        &AuditOptions::default(),
+    )
+}
+
+/// Fetches the live sitemap at `live_sitemap_url`, following sitemap indexes, and audits the
+/// new entries against it, see [`audit_entries`].
+///
+/// # Errors
+///
+/// Will return `Err` if the live sitemap can't be fetched, see
+/// [`fetch_index_recursive_with`].
+pub fn audit_with<C: HttpClient>(
+    client: &C,
+    live_sitemap_url: &Url,
+    new_entries: &[UrlEntry],
+    options: &AuditOptions,
+) -> Result<AuditReport> {
+    let live = fetch_index_recursive_with(client, live_sitemap_url, &options.fetch)?;
+    Ok(audit_entries(&live, new_entries, &options.thresholds))
+}
+
+/// Compares the live entries with the new ones and checks the changes against `thresholds`.
+///
+/// The shares are of the live urls, so without live urls no threshold is exceeded. Changes
+/// other than the lastmod, such as the priority, aren't reported.
+#[must_use]
+pub fn audit_entries(live: &[UrlEntry], new: &[UrlEntry], thresholds: &Thresholds) -> AuditReport {
+    let diff = diff(live, new);
+    let mut report = AuditReport {
+        live_count: live.len(),
+        removed: diff.removed,
+        added: diff.added,
+        lastmod_changed: diff
+            .changed
+            .into_iter()
+            .filter(|(before, after)| before.lastmod != after.lastmod)
+            .collect(),
+        violations: Vec::new(),
+    };
+
+    let checks = [
+        (
+            Change::Removed,
+            report.removed.len(),
+            thresholds.max_removed,
+        ),
+        (Change::Added, report.added.len(), thresholds.max_added),
+        (
+            Change::LastmodChanged,
+            report.lastmod_changed.len(),
+            thresholds.max_lastmod_changed,
+        ),
+    ];
+    for (change, count, max) in checks {
+        let Some(max) = max.filter(|_| report.live_count > 0) else {
+            continue;
+        };
+        let ratio = count as f64 / report.live_count as f64;
+        if ratio > max {
+            report.violations.push(Violation { change, ratio, max });
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn entry(loc: &str, day: u32) -> UrlEntry {
+        let lastmod = Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap();
+        UrlEntry::new(
+            format!("https://a.com/{loc}").parse().unwrap(),
+            Some(lastmod),
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn checks_thresholds() {
+        let live: Vec<UrlEntry> = (1..=10).map(|i| entry(&i.to_string(), 1)).collect();
+        let mut new = live[..8].to_vec();
+        new[0] = entry("1", 2);
+        new.push(entry("new", 1));
+
+        let report = audit_entries(&live, &new, &Thresholds::default());
+        assert_eq!(report.removed.len(), 2);
+        assert_eq!(report.added.len(), 1);
+        assert_eq!(report.lastmod_changed.len(), 1);
+        assert!(report.is_ok());
+
+        let thresholds = Thresholds {
+            max_removed: Some(0.1),
+            max_added: Some(0.1),
+            max_lastmod_changed: Some(0.0),
+        };
+        let report = audit_entries(&live, &new, &thresholds);
+        let changes: Vec<Change> = report.violations.iter().map(|v| v.change).collect();
+        assert_eq!(changes, [Change::Removed, Change::LastmodChanged]);
+        assert_eq!(
+            report.violations[0].to_string(),
+            "20.0% of the urls removed, over the maximum of 10.0%"
+        );
+
+        assert!(audit_entries(&[], &new, &thresholds).is_ok());
+    }
+}
@@ -0,0 +1,366 @@
+//! The `sitewriter` command line tool, built with the `cli` feature.
+//!
+//! ```text
+//! sitewriter generate [CONFIG]
+//! sitewriter validate [--legacy-10mb] SITEMAP...
+//! sitewriter split SITEMAP OUTPUT_DIR BASE_URL
+//! sitewriter merge INDEX BASE_URL SITEMAP...
+//! sitewriter diff OLD NEW
+//! sitewriter ping SITEMAP_URL ENDPOINT...
+//! ```
+//!
+//! - `validate` checks sitemap files against the protocol and its limits, failing if one
+//!   has errors. `--legacy-10mb` checks the 10MB size limit of the protocol before 2016
+//!   instead of 50MB;
+//! - `split` writes the entries of a sitemap file split within the limits, with an index,
+//!   to a directory served at the base url;
+//! - `merge` writes a sitemap index listing sitemap files served at the base url;
+//! - `diff` prints the added (`+`), removed (`-`) and changed (`~`) locs between two
+//!   sitemap files, failing if there are any, like `diff`;
+//! - `ping` sends the url of a sitemap to each ping endpoint, see
+//!   [`ping`](sitewriter::ping).
+//!
+//! The sitemap files are read uncompressed.
+//!
+//! `generate` writes the sitemaps described by a TOML config file, `sitewriter.toml` by
+//! default:
+//!
+//! ```toml
+//! base_url = "https://example.com/"
+//! # Where the sitemaps are written, served at base_url.
+//! output_dir = "public"
+//! # Optional, the names of the parts and of the index.
+//! template = "sitemap-{index}.xml"
+//! index_name = "sitemap_index.xml"
+//! # Optional, a robots.txt whose `Sitemap:` lines are replaced with the index url.
+//! robots = "public/robots.txt"
+//!
+//! [[routes]]
+//! path = "/"
+//! changefreq = "daily"
+//! priority = 1.0
+//!
+//! [[routes]]
+//! path = "/about"
+//! lastmod = "2024-05-01"
+//!
+//! [[scan]]
+//! dir = "public"
+//! exclude = ["drafts/**"]
+//! strip_extension = true
+//! ```
+//!
+//! The other settings of [`SitemapConfig`], such as `defaults` or `limits`, can be set
+//! too. The routes are joined to the base url, the scanned directories list the pages of
+//! the files they contain. The entries are deduplicated, sorted by loc unless `sort` says
+//! otherwise and validated, then split within the limits with a sitemap index listing the
+//! parts.
+
+#![forbid(unsafe_code)]
+#![deny(missing_docs)]
+#![deny(warnings)]
+#![deny(clippy::nursery)]
+#![deny(clippy::all)]
+
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use serde::Deserialize;
+use sitewriter::{
+    client::UreqClient,
+    config::SitemapConfig,
+    diff::diff,
+    fs::{scan_dir, ScanOptions},
+    index::{generate_index_bytes, IndexEntry},
+    parse::parse_lastmod,
+    ping::ping,
+    split::{SplitOutput, WrittenFile},
+    validate::{validate, validate_with, ValidateOptions},
+    write_atomic, ChangeFreq, Limits, Parser, Sitemap, SortOrder, Url, UrlEntry,
+};
+
+const USAGE: &str = "usage:
+    sitewriter generate [CONFIG]
+    sitewriter validate [--legacy-10mb] SITEMAP...
+    sitewriter split SITEMAP OUTPUT_DIR BASE_URL
+    sitewriter merge INDEX BASE_URL SITEMAP...
+    sitewriter diff OLD NEW
+    sitewriter ping SITEMAP_URL ENDPOINT...";
+
+/// The config file read without an argument.
+const DEFAULT_CONFIG: &str = "sitewriter.toml";
+
+/// The config file of `generate`.
+#[derive(Debug, Deserialize)]
+struct Config {
+    #[serde(flatten)]
+    sitemap: SitemapConfig,
+    robots: Option<PathBuf>,
+    #[serde(default)]
+    routes: Vec<Route>,
+    #[serde(default)]
+    scan: Vec<Scan>,
+}
+
+/// A page listed in the config.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Route {
+    path: String,
+    lastmod: Option<String>,
+    changefreq: Option<ChangeFreq>,
+    priority: Option<f32>,
+}
+
+/// A directory of static files whose pages are listed.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Scan {
+    dir: PathBuf,
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+    #[serde(default)]
+    strip_extension: bool,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["generate"] => generate(Path::new(DEFAULT_CONFIG)),
+        ["generate", config] => generate(Path::new(config)),
+        ["validate", "--legacy-10mb", ref sitemaps @ ..] if !sitemaps.is_empty() => {
+            validate_files(sitemaps, Limits::legacy_10mb())
+        }
+        ["validate", ref sitemaps @ ..] if !sitemaps.is_empty() => {
+            validate_files(sitemaps, Limits::default())
+        }
+        ["split", sitemap, dir, base] => split(Path::new(sitemap), Path::new(dir), base),
+        ["merge", index, base, ref sitemaps @ ..] if !sitemaps.is_empty() => {
+            merge(Path::new(index), base, sitemaps)
+        }
+        ["diff", old, new] => diff_files(Path::new(old), Path::new(new)),
+        ["ping", sitemap, ref endpoints @ ..] if !endpoints.is_empty() => {
+            ping_endpoints(sitemap, endpoints)
+        }
+        ["-h" | "--help"] => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Writes the sitemaps described by the config at `path`.
+fn generate(path: &Path) -> Result<ExitCode, Box<dyn Error>> {
+    let config =
+        std::fs::read_to_string(path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    let config: Config = toml::from_str(&config)?;
+    // Relative paths in the config are relative to the config file.
+    let root = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut sitemap = config.sitemap;
+    sitemap.output_dir = root.join(&sitemap.output_dir);
+    sitemap.sort = sitemap.sort.or(Some(SortOrder::Loc));
+    let base = &sitemap.base_url;
+
+    let mut urls = Vec::new();
+    for route in &config.routes {
+        urls.push(route_entry(base, route)?);
+    }
+    for scan in &config.scan {
+        let mut options = ScanOptions::default();
+        options.include.clone_from(&scan.include);
+        options.exclude.clone_from(&scan.exclude);
+        options.strip_extension = scan.strip_extension;
+        urls.extend(scan_dir(root.join(&scan.dir), base, &options)?);
+    }
+
+    let urls = Sitemap::from_config(&sitemap).dedup(urls)?;
+    let report = validate(&urls);
+    if !report.is_empty() {
+        eprintln!("{report}");
+    }
+    if report.has_errors() {
+        return Err("invalid entries".into());
+    }
+
+    let files = sitemap.write(&urls)?;
+    if let (Some(robots), Some(index)) = (&config.robots, files.first()) {
+        update_robots(&root.join(robots), index)?;
+    }
+    print_files(&files);
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Checks each sitemap file within `limits`, fails if one has errors.
+fn validate_files(paths: &[&str], limits: Limits) -> Result<ExitCode, Box<dyn Error>> {
+    let mut code = ExitCode::SUCCESS;
+    for path in paths {
+        let (urls, size) = read_sitemap(Path::new(path))?;
+        // The file can be larger than the sitemap generated from its entries, which the
+        // report checks.
+        let too_large = size > limits.max_size;
+        if too_large {
+            println!(
+                "{path}: {size} bytes, over the limit of {} bytes",
+                limits.max_size
+            );
+        }
+        let options = ValidateOptions {
+            limits: Some(limits),
+            ..Default::default()
+        };
+        let report = validate_with(&urls, &options);
+        if !report.is_empty() {
+            println!("{path}:\n{report}");
+        }
+        if too_large || report.has_errors() {
+            code = ExitCode::FAILURE;
+        } else {
+            println!("{path}: {} urls, valid", urls.len());
+        }
+    }
+    Ok(code)
+}
+
+/// Writes the entries of the sitemap at `path` split within the limits, with an index.
+fn split(path: &Path, dir: &Path, base: &str) -> Result<ExitCode, Box<dyn Error>> {
+    let (urls, _) = read_sitemap(path)?;
+    let output = SplitOutput::new(dir, base.parse()?);
+    print_files(&Sitemap::new().write_split(&urls, &output)?);
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Writes a sitemap index listing the sitemap files, served at `base` under their name.
+fn merge(index: &Path, base: &str, paths: &[&str]) -> Result<ExitCode, Box<dyn Error>> {
+    let base: Url = base.parse()?;
+    let mut sitemaps = Vec::with_capacity(paths.len());
+    for path in paths {
+        let path = Path::new(path);
+        let (urls, _) = read_sitemap(path)?;
+        let name = path
+            .file_name()
+            .ok_or_else(|| format!("{} has no file name", path.display()))?;
+        let loc = base.join(&name.to_string_lossy())?;
+        sitemaps.push(IndexEntry::new(
+            loc,
+            urls.iter().filter_map(|u| u.lastmod).max(),
+        ));
+    }
+    write_atomic(index, &generate_index_bytes(&sitemaps))?;
+    println!("{} ({} sitemaps)", index.display(), sitemaps.len());
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints the differences between two sitemap files, fails if there are any.
+fn diff_files(old: &Path, new: &Path) -> Result<ExitCode, Box<dyn Error>> {
+    let diff = diff(&read_sitemap(old)?.0, &read_sitemap(new)?.0);
+    for entry in &diff.removed {
+        println!("- {}", entry.loc);
+    }
+    for entry in &diff.added {
+        println!("+ {}", entry.loc);
+    }
+    for (_, entry) in &diff.changed {
+        println!("~ {}", entry.loc);
+    }
+    Ok(if diff.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Pings each endpoint with `sitemap`, fails if one doesn't answer with a success.
+fn ping_endpoints(sitemap: &str, endpoints: &[&str]) -> Result<ExitCode, Box<dyn Error>> {
+    let sitemap: Url = sitemap.parse()?;
+    let client = UreqClient::default();
+    let mut code = ExitCode::SUCCESS;
+    for endpoint in endpoints {
+        match ping(&client, &endpoint.parse()?, &sitemap) {
+            Ok(response) if response.is_success() => {
+                println!("{endpoint}: {}", response.status);
+            }
+            Ok(response) => {
+                println!("{endpoint}: {}", response.status);
+                code = ExitCode::FAILURE;
+            }
+            Err(e) => {
+                println!("{endpoint}: {e}");
+                code = ExitCode::FAILURE;
+            }
+        }
+    }
+    Ok(code)
+}
+
+/// The entries of the sitemap file at `path`, and its size in bytes.
+fn read_sitemap(path: &Path) -> Result<(Vec<UrlEntry>, usize), Box<dyn Error>> {
+    let bytes = std::fs::read(path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    let urls = Parser::new()
+        .parse_bytes(&bytes)
+        .map_err(|e| format!("can't parse {}: {e}", path.display()))?;
+    Ok((urls, bytes.len()))
+}
+
+/// Prints the written files.
+fn print_files(files: &[WrittenFile]) {
+    for file in files {
+        println!(
+            "{} ({} urls) -> {}",
+            file.path.display(),
+            file.url_count,
+            file.url
+        );
+    }
+}
+
+/// The entry of a route of the config.
+fn route_entry(base: &Url, route: &Route) -> Result<UrlEntry, Box<dyn Error>> {
+    let lastmod = match &route.lastmod {
+        Some(lastmod) => Some(
+            parse_lastmod(lastmod)
+                .ok_or_else(|| format!("invalid lastmod '{lastmod}' of {}", route.path))?,
+        ),
+        None => None,
+    };
+    Ok(UrlEntry::new(
+        base.join(&route.path)?,
+        lastmod,
+        route.changefreq,
+        route.priority,
+    ))
+}
+
+/// Replaces the `Sitemap:` lines of the robots.txt at `path` with the url of `index`,
+/// creating the file if needed.
+fn update_robots(path: &Path, index: &WrittenFile) -> Result<(), Box<dyn Error>> {
+    let current = match std::fs::read_to_string(path) {
+        Ok(current) => current,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut robots: String = current
+        .lines()
+        .filter(|line| !line.to_ascii_lowercase().starts_with("sitemap:"))
+        .flat_map(|line| [line, "\n"])
+        .collect();
+    robots.push_str(&format!("Sitemap: {}\n", index.url));
+    write_atomic(path, robots.as_bytes())?;
+    Ok(())
+}
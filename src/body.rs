@@ -0,0 +1,267 @@
+//! Sitemaps generated as they are sent.
+//!
+//! A [`SitemapBody`] writes the entries as the client reads the response, a chunk at a
+//! time, so serving a sitemap of 50 000 entries takes the memory of a chunk instead of the
+//! whole document. It reads the entries from an iterator with [`Sitemap::body`], or from
+//! an async stream such as the rows of a database query with [`Sitemap::body_stream`].
+//!
+//! It is an [`http_body::Body`] for hyper, and with the `axum` feature it can be returned
+//! straight from a handler:
+//!
+//! ```rust
+//! # #[cfg(feature = "axum")]
+//! # {
+//! use std::convert::Infallible;
+//!
+//! use axum_core::response::IntoResponse;
+//! use sitewriter::{Sitemap, UrlEntry};
+//!
+//! async fn sitemap() -> impl IntoResponse {
+//!     let urls = (0..50_000).map(|i| {
+//!         let loc = format!("https://example.com/{i}").parse().unwrap();
+//!         Ok::<_, Infallible>(UrlEntry::new(loc, None, None, None))
+//!     });
+//!     Sitemap::new().body(urls)
+//! }
+//! # }
+//! ```
+//!
+//! The entries are written in the order they are read, as with
+//! [`Sitemap::generate_stream`]: the sort order doesn't apply, and the namespaces of the
+//! extensions and of the mobile flag are declared up front with [`Sitemap::namespace`]. The
+//! status and headers are sent before the entries are read, so an entry or a source failing
+//! midway aborts the response with the error instead of turning it into an error status.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_util::{ready, stream, Stream};
+use http_body::{Body, Frame};
+
+use crate::{urlset_writer, Error, Result, Sitemap, SitemapWriter, UrlEntry};
+
+/// The default size of the chunks of a [`SitemapBody`], in bytes.
+pub const DEFAULT_CHUNK_SIZE: usize = 16 * 1024;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A sitemap generated as it is sent, see the [module docs](self).
+///
+/// It yields the chunks of the sitemap as a [`Stream`] and as an [`http_body::Body`].
+pub struct SitemapBody<S> {
+    sitemap: Sitemap,
+    source: S,
+    writer: Option<SitemapWriter<Vec<u8>>>,
+    chunk_size: usize,
+    done: bool,
+}
+
+impl<S> std::fmt::Debug for SitemapBody<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SitemapBody")
+            .field("writer", &self.writer)
+            .field("chunk_size", &self.chunk_size)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> SitemapBody<S> {
+    fn new(sitemap: &Sitemap, source: S) -> Self {
+        Self {
+            sitemap: sitemap.clone(),
+            source,
+            writer: None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            done: false,
+        }
+    }
+
+    /// Sets the size from which the written XML is sent as a chunk,
+    /// [`DEFAULT_CHUNK_SIZE`] by default.
+    #[must_use]
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size.max(1);
+        self
+    }
+
+    /// Writes an entry read from the source, or closes the sitemap at the end of the
+    /// source, returning a chunk once enough XML is buffered.
+    fn step<E: Into<BoxError>>(
+        &mut self,
+        item: Option<std::result::Result<UrlEntry, E>>,
+    ) -> Result<Option<Bytes>> {
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => self.writer.insert(urlset_writer(
+                &self.sitemap,
+                Vec::new(),
+                std::iter::empty(),
+            )?),
+        };
+        let Some(item) = item else {
+            self.done = true;
+            if writer.written() == 0 {
+                self.sitemap.check_empty(&[])?;
+            }
+            let writer = self.writer.take().map(SitemapWriter::finish);
+            return writer.transpose().map(|xml| xml.map(Bytes::from));
+        };
+
+        let entry = item.map_err(|e| Error::Source(e.into()))?;
+        if let Some(entry) = self.sitemap.transform_entry(entry) {
+            writer.write(&entry)?;
+        }
+        let buffer = writer.get_mut();
+        Ok((buffer.len() >= self.chunk_size).then(|| Bytes::from(std::mem::take(buffer))))
+    }
+}
+
+impl<S, E> Stream for SitemapBody<S>
+where
+    S: Stream<Item = std::result::Result<UrlEntry, E>> + Unpin,
+    E: Into<BoxError>,
+{
+    type Item = Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while !this.done {
+            let item = ready!(Pin::new(&mut this.source).poll_next(cx));
+            match this.step(item) {
+                Ok(Some(chunk)) => return Poll::Ready(Some(Ok(chunk))),
+                Ok(None) => {}
+                Err(e) => {
+                    this.done = true;
+                    this.writer = None;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+impl<S, E> Body for SitemapBody<S>
+where
+    S: Stream<Item = std::result::Result<UrlEntry, E>> + Unpin,
+    E: Into<BoxError>,
+{
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>>>> {
+        self.poll_next(cx)
+            .map(|chunk| chunk.map(|chunk| chunk.map(Frame::data)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<S, E> axum_core::response::IntoResponse for SitemapBody<S>
+where
+    S: Stream<Item = std::result::Result<UrlEntry, E>> + Unpin + Send + 'static,
+    E: Into<BoxError>,
+{
+    fn into_response(self) -> axum_core::response::Response {
+        let mut response = axum_core::response::Response::new(axum_core::body::Body::new(self));
+        response.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("application/xml"),
+        );
+        response
+    }
+}
+
+impl Sitemap {
+    /// A body generating the sitemap of the entries read from `entries` as it is sent, see
+    /// the [module docs](crate::body).
+    pub fn body<I, E>(&self, entries: I) -> SitemapBody<stream::Iter<I::IntoIter>>
+    where
+        I: IntoIterator<Item = std::result::Result<UrlEntry, E>>,
+        E: Into<BoxError>,
+    {
+        SitemapBody::new(self, stream::iter(entries))
+    }
+
+    /// A body generating the sitemap of the entries read from the async `entries` as it is
+    /// sent, see the [module docs](crate::body).
+    ///
+    /// Use [`Box::pin`] for a stream that isn't [`Unpin`].
+    pub fn body_stream<S, E>(&self, entries: S) -> SitemapBody<S>
+    where
+        S: Stream<Item = std::result::Result<UrlEntry, E>> + Unpin,
+        E: Into<BoxError>,
+    {
+        SitemapBody::new(self, entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use futures_util::{FutureExt, StreamExt};
+
+    use super::*;
+    use crate::EmptyStrategy;
+
+    fn entries(count: usize) -> impl Iterator<Item = std::result::Result<UrlEntry, Infallible>> {
+        (0..count).map(|i| {
+            let loc = format!("https://a.com/{i}").parse().unwrap();
+            Ok(UrlEntry::new(loc, None, None, None))
+        })
+    }
+
+    /// The chunks of a body whose source never waits.
+    fn collect<S: Stream + Unpin>(body: S) -> Vec<S::Item> {
+        body.collect().now_or_never().unwrap()
+    }
+
+    #[test]
+    fn sends_chunks() {
+        let sitemap = Sitemap::new();
+        let chunks: Vec<Bytes> = collect(sitemap.body(entries(100)).chunk_size(1024))
+            .into_iter()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert!(chunks.len() > 1);
+        assert!(chunks[..chunks.len() - 1].iter().all(|c| c.len() >= 1024));
+        let urls: Vec<UrlEntry> = entries(100).map(Result::unwrap).collect();
+        assert_eq!(chunks.concat(), sitemap.generate_bytes(&urls));
+
+        let body = sitemap.body_stream(stream::iter(entries(0)));
+        let xml: Vec<Bytes> = collect(body).into_iter().collect::<Result<_>>().unwrap();
+        assert!(xml.concat().ends_with(b"</urlset>"));
+        let empty = Sitemap::new()
+            .on_empty(EmptyStrategy::Error)
+            .body(entries(0));
+        assert!(matches!(collect(empty)[..], [Err(Error::EmptyInput)]));
+    }
+
+    #[test]
+    fn stops_at_the_first_error() {
+        let rows = entries(3)
+            .map(|entry| entry.map_err(|e| match e {}))
+            .chain([Err("connection lost")]);
+        let mut body = Sitemap::new().body(rows).chunk_size(1);
+        let mut frames = 0;
+        while let Some(frame) = body.next().now_or_never().unwrap() {
+            match frame {
+                Ok(_) => frames += 1,
+                Err(e) => assert!(matches!(e, Error::Source(_))),
+            }
+        }
+        assert_eq!(frames, 3);
+        assert!(body.is_end_stream());
+    }
+}
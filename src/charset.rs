@@ -0,0 +1,123 @@
+//! Detection and transcoding of the character encoding of parsed documents.
+
+use crate::{Error, Result};
+
+/// The encodings the parser understands.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Charset {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Windows1252,
+}
+
+/// Detects the encoding of a document from its first bytes, returns it along with the
+/// length of the byte order mark.
+///
+/// A byte order mark wins over the XML declaration, since the declaration is often wrong.
+pub fn sniff(head: &[u8]) -> Result<(Charset, usize)> {
+    match head {
+        [0xEF, 0xBB, 0xBF, ..] => return Ok((Charset::Utf8, 3)),
+        [0xFF, 0xFE, ..] => return Ok((Charset::Utf16Le, 2)),
+        [0xFE, 0xFF, ..] => return Ok((Charset::Utf16Be, 2)),
+        [b'<', 0, b'?', 0, ..] => return Ok((Charset::Utf16Le, 0)),
+        [0, b'<', 0, b'?', ..] => return Ok((Charset::Utf16Be, 0)),
+        _ => {}
+    }
+
+    let charset = match declared_encoding(head) {
+        None => Charset::Utf8,
+        Some(label) => match label.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Charset::Utf8,
+            "iso-8859-1" | "iso8859-1" | "latin1" | "latin-1" | "l1" | "us-ascii" | "ascii" => {
+                Charset::Latin1
+            }
+            "windows-1252" | "cp1252" | "x-cp1252" => Charset::Windows1252,
+            // A document declaring UTF-16 without a byte order mark nor UTF-16 bytes is
+            // actually in an ASCII compatible encoding.
+            "utf-16" | "utf-16le" | "utf-16be" => Charset::Utf8,
+            _ => return Err(Error::UnsupportedEncoding(label)),
+        },
+    };
+    Ok((charset, 0))
+}
+
+/// Extracts the `encoding` of the XML declaration, if any.
+fn declared_encoding(head: &[u8]) -> Option<String> {
+    let head = head.strip_prefix(b"<?xml")?;
+    let end = head.windows(2).position(|w| w == b"?>")?;
+    let decl = std::str::from_utf8(&head[..end]).ok()?;
+
+    let rest = &decl[decl.find("encoding")? + "encoding".len()..];
+    let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let rest = &rest[1..];
+    Some(rest[..rest.find(quote)?].to_owned())
+}
+
+/// Decodes the document into UTF-8, invalid sequences are replaced by `U+FFFD`.
+pub fn decode(bytes: &[u8], charset: Charset) -> String {
+    match charset {
+        Charset::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+        Charset::Utf16Le | Charset::Utf16Be => {
+            let units = bytes.chunks_exact(2).map(|pair| {
+                let pair = [pair[0], pair[1]];
+                if charset == Charset::Utf16Le {
+                    u16::from_le_bytes(pair)
+                } else {
+                    u16::from_be_bytes(pair)
+                }
+            });
+            char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect()
+        }
+        Charset::Latin1 => bytes.iter().map(|&b| char::from(b)).collect(),
+        Charset::Windows1252 => bytes.iter().map(|&b| windows_1252(b)).collect(),
+    }
+}
+
+/// Maps a windows-1252 byte to its character, it only differs from Latin-1 in `0x80..=0x9F`.
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [u16; 32] = [
+        0x20AC, 0x0081, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160,
+        0x2039, 0x0152, 0x008D, 0x017D, 0x008F, 0x0090, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022,
+        0x2013, 0x2014, 0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0x009D, 0x017E, 0x0178,
+    ];
+
+    match byte {
+        0x80..=0x9F => char::from_u32(u32::from(HIGH[usize::from(byte - 0x80)]))
+            .unwrap_or(char::REPLACEMENT_CHARACTER),
+        _ => char::from(byte),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_encoding() {
+        assert_eq!(sniff(b"<urlset/>").unwrap(), (Charset::Utf8, 0));
+        assert_eq!(sniff(b"\xEF\xBB\xBF<urlset/>").unwrap(), (Charset::Utf8, 3));
+        assert_eq!(sniff(b"\xFF\xFE<\0").unwrap(), (Charset::Utf16Le, 2));
+        assert_eq!(sniff(b"\0<\0?\0x").unwrap(), (Charset::Utf16Be, 0));
+        assert_eq!(
+            sniff(b"<?xml version='1.0' encoding = 'ISO-8859-1'?><urlset/>").unwrap(),
+            (Charset::Latin1, 0)
+        );
+        assert!(matches!(
+            sniff(b"<?xml version=\"1.0\" encoding=\"Shift_JIS\"?>"),
+            Err(Error::UnsupportedEncoding(label)) if label == "Shift_JIS"
+        ));
+    }
+
+    #[test]
+    fn decodes() {
+        assert_eq!(decode(b"caf\xE9", Charset::Latin1), "café");
+        assert_eq!(decode(b"\x80 \x93x\x94", Charset::Windows1252), "€ “x”");
+        assert_eq!(decode(b"a\0\xE9\0", Charset::Utf16Le), "aé");
+        assert_eq!(decode(b"\0a\0\xE9", Charset::Utf16Be), "aé");
+    }
+}
@@ -0,0 +1,171 @@
+//! Resumable progress for long running jobs over many urls.
+//!
+//! A [`Checkpoint`] is an append-only file with one line per completed url and its result.
+//! A job that gets interrupted can be started again with the same file and skip every url
+//! that was already done:
+//!
+//! ```rust,no_run
+//! use sitewriter::checkpoint::Checkpoint;
+//!
+//! # let urls: Vec<sitewriter::Url> = Vec::new();
+//! let mut checkpoint = Checkpoint::open("audit.checkpoint")?;
+//! for url in &urls {
+//!     if checkpoint.is_done(url) {
+//!         continue;
+//!     }
+//!     // check the url
+//!     checkpoint.record(url, "200")?;
+//! }
+//! checkpoint.remove()?;
+//! # Ok::<(), sitewriter::Error>(())
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use url::Url;
+
+use crate::Result;
+
+/// Completed urls and their results, persisted to a file.
+#[derive(Debug)]
+pub struct Checkpoint {
+    path: PathBuf,
+    file: File,
+    done: HashMap<String, String>,
+}
+
+impl Checkpoint {
+    /// Opens the checkpoint at `path`, loading the progress of a previous run if the file
+    /// exists.
+    ///
+    /// A last line cut short by an interruption is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file can't be read or created.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        let mut done = HashMap::new();
+
+        let contents = match std::fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        // Only lines terminated by a newline were written completely.
+        let complete = contents
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(&[][..], |end| &contents[..end]);
+        for line in complete.split(|&b| b == b'\n') {
+            let Ok(line) = std::str::from_utf8(line) else {
+                continue;
+            };
+            if let Some((url, result)) = line.split_once('\t') {
+                done.insert(url.to_owned(), result.to_owned());
+            }
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        // Terminate a line cut short by an interruption so the next record starts clean.
+        if contents.last().is_some_and(|&b| b != b'\n') {
+            file.write_all(b"\n")?;
+        }
+
+        Ok(Self { path, file, done })
+    }
+
+    /// Whether `url` was recorded, in this run or a previous one.
+    #[must_use]
+    pub fn is_done(&self, url: &Url) -> bool {
+        self.done.contains_key(url.as_str())
+    }
+
+    /// The recorded result of `url`.
+    #[must_use]
+    pub fn result(&self, url: &Url) -> Option<&str> {
+        self.done.get(url.as_str()).map(String::as_str)
+    }
+
+    /// The number of recorded urls.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.done.len()
+    }
+
+    /// Whether nothing was recorded yet.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.done.is_empty()
+    }
+
+    /// Iterates over the recorded urls and their results, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.done.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Records `url` as done with the given result and writes it to the file.
+    ///
+    /// Line breaks and tabs in `result` are replaced with spaces.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if writing to the file fails.
+    pub fn record(&mut self, url: &Url, result: &str) -> Result<()> {
+        let result = result.replace(['\n', '\r', '\t'], " ");
+        writeln!(self.file, "{url}\t{result}")?;
+        self.file.flush()?;
+        self.done.insert(url.to_string(), result);
+        Ok(())
+    }
+
+    /// Deletes the checkpoint file, once the job is complete.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file can't be removed.
+    pub fn remove(self) -> Result<()> {
+        drop(self.file);
+        std::fs::remove_file(&self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn resumes_and_ignores_partial_lines() {
+        let dir = TempDir::new("checkpoint");
+        let path = dir.join("checkpoint.txt");
+        let a: Url = "https://a.com/".parse().unwrap();
+        let b: Url = "https://a.com/b".parse().unwrap();
+
+        let mut checkpoint = Checkpoint::open(&path).unwrap();
+        checkpoint.record(&a, "200\nOK").unwrap();
+        drop(checkpoint);
+        // An interrupted write.
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"https://a.com/b\t40").unwrap();
+        drop(file);
+
+        let mut checkpoint = Checkpoint::open(&path).unwrap();
+        assert_eq!(checkpoint.result(&a), Some("200 OK"));
+        assert!(!checkpoint.is_done(&b));
+        checkpoint.record(&b, "404").unwrap();
+        drop(checkpoint);
+
+        let checkpoint = Checkpoint::open(&path).unwrap();
+        assert_eq!(checkpoint.len(), 2);
+        assert_eq!(checkpoint.result(&b), Some("404"));
+        checkpoint.remove().unwrap();
+        assert!(!path.exists());
+    }
+}
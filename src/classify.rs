@@ -0,0 +1,248 @@
+//! Recognizing urls that are not regular pages.
+//!
+//! ```rust
+//! use sitewriter::{classify::{Classifier, UrlKind}, Url};
+//!
+//! let classifier = Classifier::new().rule("admin", |url: &Url| url.path().starts_with("/admin/"));
+//!
+//! let url: Url = "https://example.com/admin/style.css".parse()?;
+//! assert_eq!(classifier.classify(&url), [UrlKind::Asset, UrlKind::Custom("admin")]);
+//! assert!(classifier.is_page(&"https://example.com/blog/".parse()?));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::sync::Arc;
+
+use url::Url;
+
+/// Something a url was recognized as.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum UrlKind {
+    /// A static asset, such as an image, a stylesheet or a script.
+    Asset,
+    /// An RSS or Atom feed.
+    Feed,
+    /// A page after the first one of a paginated listing.
+    Paginated,
+    /// A url with query parameters, often a duplicate of the url without them.
+    Parameterized,
+    /// Matched the user rule with this name.
+    Custom(&'static str),
+}
+
+type RuleFn = dyn Fn(&Url) -> bool + Send + Sync;
+
+/// Classifies urls with the built-in heuristics and user rules.
+#[derive(Clone)]
+pub struct Classifier {
+    asset_extensions: Vec<String>,
+    pagination_params: Vec<String>,
+    ignored_params: Vec<String>,
+    rules: Vec<(&'static str, Arc<RuleFn>)>,
+}
+
+impl std::fmt::Debug for Classifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Classifier")
+            .field("asset_extensions", &self.asset_extensions)
+            .field("pagination_params", &self.pagination_params)
+            .field("ignored_params", &self.ignored_params)
+            .field(
+                "rules",
+                &self.rules.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+const ASSET_EXTENSIONS: &[&str] = &[
+    "css", "js", "mjs", "map", "json", "png", "jpg", "jpeg", "gif", "webp", "avif", "svg", "ico",
+    "bmp", "tif", "tiff", "woff", "woff2", "ttf", "otf", "eot", "mp3", "mp4", "webm", "ogg", "wav",
+    "zip", "gz", "tar", "wasm",
+];
+
+const PAGINATION_PARAMS: &[&str] = &["page", "p", "pg", "paged"];
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|&v| v.to_owned()).collect()
+}
+
+impl Default for Classifier {
+    fn default() -> Self {
+        Self {
+            asset_extensions: strings(ASSET_EXTENSIONS),
+            pagination_params: strings(PAGINATION_PARAMS),
+            ignored_params: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl Classifier {
+    /// Creates a classifier with the built-in heuristics.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file extension recognized as an asset.
+    #[must_use]
+    pub fn asset_extension(mut self, extension: &str) -> Self {
+        self.asset_extensions.push(extension.to_ascii_lowercase());
+        self
+    }
+
+    /// Adds a query parameter holding the page number of a listing.
+    #[must_use]
+    pub fn pagination_param(mut self, name: &str) -> Self {
+        self.pagination_params.push(name.to_owned());
+        self
+    }
+
+    /// Adds a query parameter that doesn't make a url parameterized, such as a language.
+    #[must_use]
+    pub fn ignore_param(mut self, name: &str) -> Self {
+        self.ignored_params.push(name.to_owned());
+        self
+    }
+
+    /// Adds a rule, urls for which it returns `true` are classified as
+    /// [`UrlKind::Custom`] with the given name.
+    #[must_use]
+    pub fn rule<F>(mut self, name: &'static str, rule: F) -> Self
+    where
+        F: Fn(&Url) -> bool + Send + Sync + 'static,
+    {
+        self.rules.push((name, Arc::new(rule)));
+        self
+    }
+
+    /// Whether the url points to a static asset, judging by its extension.
+    #[must_use]
+    pub fn is_asset(&self, url: &Url) -> bool {
+        extension(url).is_some_and(|ext| {
+            self.asset_extensions
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case(ext))
+        })
+    }
+
+    /// Whether the url points to an RSS or Atom feed.
+    #[must_use]
+    pub fn is_feed(&self, url: &Url) -> bool {
+        let name = last_segment(url).to_ascii_lowercase();
+        matches!(
+            name.as_str(),
+            "feed" | "rss" | "atom" | "feed.xml" | "rss.xml" | "atom.xml"
+        ) || matches!(extension(url), Some("rss" | "atom"))
+    }
+
+    /// Whether the url is a page after the first one of a paginated listing, either with a
+    /// `/page/2` path or a `?page=2` query.
+    #[must_use]
+    pub fn is_paginated(&self, url: &Url) -> bool {
+        let past_first = |value: &str| value.parse::<u64>().is_ok_and(|n| n > 1);
+
+        let in_path = url.path_segments().is_some_and(|segments| {
+            let segments: Vec<&str> = segments.collect();
+            segments
+                .windows(2)
+                .any(|w| w[0] == "page" && past_first(w[1]))
+        });
+
+        in_path
+            || url.query_pairs().any(|(name, value)| {
+                self.pagination_params.iter().any(|p| *p == name) && past_first(&value)
+            })
+    }
+
+    /// Whether the url has query parameters other than pagination and ignored ones.
+    #[must_use]
+    pub fn is_parameterized(&self, url: &Url) -> bool {
+        url.query_pairs().any(|(name, _)| {
+            !self.pagination_params.iter().any(|p| *p == name)
+                && !self.ignored_params.iter().any(|p| *p == name)
+        })
+    }
+
+    /// Returns everything the url was recognized as, in the order of [`UrlKind`] and then
+    /// of the rules.
+    #[must_use]
+    pub fn classify(&self, url: &Url) -> Vec<UrlKind> {
+        let mut kinds = Vec::new();
+        if self.is_asset(url) {
+            kinds.push(UrlKind::Asset);
+        }
+        if self.is_feed(url) {
+            kinds.push(UrlKind::Feed);
+        }
+        if self.is_paginated(url) {
+            kinds.push(UrlKind::Paginated);
+        }
+        if self.is_parameterized(url) {
+            kinds.push(UrlKind::Parameterized);
+        }
+        for (name, rule) in &self.rules {
+            if rule(url) {
+                kinds.push(UrlKind::Custom(name));
+            }
+        }
+        kinds
+    }
+
+    /// Whether the url looks like a regular page, that is, nothing else matched.
+    #[must_use]
+    pub fn is_page(&self, url: &Url) -> bool {
+        self.classify(url).is_empty()
+    }
+}
+
+fn last_segment(url: &Url) -> &str {
+    url.path_segments()
+        .and_then(Iterator::last)
+        .unwrap_or_default()
+}
+
+fn extension(url: &Url) -> Option<&str> {
+    last_segment(url)
+        .rsplit_once('.')
+        .map(|(_, ext)| ext)
+        .filter(|ext| !ext.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(url: &str) -> Vec<UrlKind> {
+        Classifier::new().classify(&url.parse().unwrap())
+    }
+
+    #[test]
+    fn builtin_heuristics() {
+        assert_eq!(kinds("https://a.com/img/logo.PNG"), [UrlKind::Asset]);
+        assert_eq!(kinds("https://a.com/blog/feed"), [UrlKind::Feed]);
+        assert_eq!(kinds("https://a.com/blog/rss.xml"), [UrlKind::Feed]);
+        assert_eq!(kinds("https://a.com/blog/page/3"), [UrlKind::Paginated]);
+        assert_eq!(kinds("https://a.com/blog?page=2"), [UrlKind::Paginated]);
+        assert_eq!(kinds("https://a.com/blog?page=1"), []);
+        assert_eq!(
+            kinds("https://a.com/shop?sort=price&page=2"),
+            [UrlKind::Paginated, UrlKind::Parameterized]
+        );
+        assert_eq!(kinds("https://a.com/blog/post.html"), []);
+        assert_eq!(kinds("https://a.com/"), []);
+    }
+
+    #[test]
+    fn custom_rules() {
+        let classifier = Classifier::new()
+            .ignore_param("lang")
+            .asset_extension("pdf")
+            .rule("search", |url| url.path() == "/search");
+
+        let url = "https://a.com/search?lang=en".parse().unwrap();
+        assert_eq!(classifier.classify(&url), [UrlKind::Custom("search")]);
+        assert!(classifier.is_asset(&"https://a.com/a.pdf".parse().unwrap()));
+    }
+}
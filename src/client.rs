@@ -0,0 +1,269 @@
+//! The HTTP client used by the parts of the crate that talk to remote hosts.
+//!
+//! Everything that sends requests takes an [`HttpClient`], so it works with any HTTP
+//! library. Closures of the right signature are clients, which is handy in tests, and with
+//! the `fetch` feature [`UreqClient`] is provided.
+//!
+//! The few APIs that need other methods than `GET`, such as the
+//! [`webmaster`](crate::webmaster) tools, send an [`HttpRequest`] with
+//! [`HttpClient::send`], which clients implement when they can.
+
+use url::Url;
+
+use crate::{Error, Result};
+
+/// An HTTP request to send with [`HttpClient::send`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HttpRequest {
+    /// The method, such as `GET` or `PUT`.
+    pub method: String,
+    /// The url.
+    pub url: Url,
+    /// The headers.
+    pub headers: Vec<(String, String)>,
+    /// The body, empty for none.
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// A request without headers or body.
+    #[must_use]
+    pub fn new(method: impl Into<String>, url: Url) -> Self {
+        Self {
+            method: method.into(),
+            url,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Adds a header.
+    #[must_use]
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the body.
+    #[must_use]
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+/// A received HTTP response, whatever its status.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct HttpResponse {
+    /// The status code.
+    pub status: u16,
+    /// The headers, with lowercase names.
+    pub headers: Vec<(String, String)>,
+    /// The body, after undoing any `Content-Encoding`.
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// A `200 OK` response with the given body.
+    #[must_use]
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// A response with the given status and no body.
+    #[must_use]
+    pub const fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Whether the status is `2xx`.
+    #[must_use]
+    pub const fn is_success(&self) -> bool {
+        self.status >= 200 && self.status < 300
+    }
+
+    /// The first value of the header with the given case-insensitive name.
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Sends `GET` requests.
+pub trait HttpClient {
+    /// Fetches `url`, following redirects.
+    ///
+    /// Responses with an error status are returned as `Ok`, only failures to get a response
+    /// at all are errors.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::Request`](crate::Error::Request) if no response was received.
+    fn get(&self, url: &Url) -> Result<HttpResponse>;
+
+    /// Sends `request`, following redirects, see [`HttpClient::get`].
+    ///
+    /// By default only `GET` requests without headers or body are sent, with
+    /// [`HttpClient::get`].
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::Request`] if no response was received, or the client can't send
+    /// the request.
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        if request.method == "GET" && request.headers.is_empty() && request.body.is_empty() {
+            return self.get(&request.url);
+        }
+        Err(Error::Request {
+            url: request.url.clone(),
+            source: format!("the client can't send this {} request", request.method).into(),
+        })
+    }
+}
+
+impl<F> HttpClient for F
+where
+    F: Fn(&Url) -> Result<HttpResponse>,
+{
+    fn get(&self, url: &Url) -> Result<HttpResponse> {
+        self(url)
+    }
+}
+
+/// An [`HttpClient`] backed by [`ureq`].
+#[cfg(feature = "fetch")]
+#[derive(Debug, Clone)]
+pub struct UreqClient {
+    agent: ureq::Agent,
+    max_body: u64,
+}
+
+#[cfg(feature = "fetch")]
+impl Default for UreqClient {
+    fn default() -> Self {
+        Self::new(
+            ureq::AgentBuilder::new()
+                .timeout(std::time::Duration::from_secs(30))
+                .user_agent(concat!("sitewriter/", env!("CARGO_PKG_VERSION")))
+                .build(),
+        )
+    }
+}
+
+#[cfg(feature = "fetch")]
+impl UreqClient {
+    /// The default maximum body size, larger than the biggest valid sitemap.
+    pub const DEFAULT_MAX_BODY: u64 = 64 * 1024 * 1024;
+
+    /// Wraps a configured agent.
+    #[must_use]
+    pub const fn new(agent: ureq::Agent) -> Self {
+        Self {
+            agent,
+            max_body: Self::DEFAULT_MAX_BODY,
+        }
+    }
+
+    /// Sets the maximum number of body bytes read, a larger body fails with
+    /// [`Error::LimitExceeded`](crate::Error::LimitExceeded).
+    #[must_use]
+    pub const fn max_body(mut self, bytes: u64) -> Self {
+        self.max_body = bytes;
+        self
+    }
+}
+
+#[cfg(feature = "fetch")]
+impl HttpClient for UreqClient {
+    fn get(&self, url: &Url) -> Result<HttpResponse> {
+        self.receive(url, self.agent.get(url.as_str()).call())
+    }
+
+    fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+        let mut builder = self.agent.request(&request.method, request.url.as_str());
+        for (name, value) in &request.headers {
+            builder = builder.set(name, value);
+        }
+        let result = if request.body.is_empty() {
+            builder.call()
+        } else {
+            builder.send_bytes(&request.body)
+        };
+        self.receive(&request.url, result)
+    }
+}
+
+#[cfg(feature = "fetch")]
+impl UreqClient {
+    /// Reads the response to a request of `url`.
+    fn receive(
+        &self,
+        url: &Url,
+        result: std::result::Result<ureq::Response, ureq::Error>,
+    ) -> Result<HttpResponse> {
+        use std::io::Read;
+
+        let response = match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(e) => {
+                return Err(crate::Error::Request {
+                    url: url.clone(),
+                    source: Box::new(e),
+                })
+            }
+        };
+
+        let status = response.status();
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = response.header(&name)?.to_owned();
+                Some((name.to_ascii_lowercase(), value))
+            })
+            .collect();
+
+        // One more byte than the maximum tells a body at the limit from a larger one.
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .take(self.max_body.saturating_add(1))
+            .read_to_end(&mut body)?;
+        if body.len() as u64 > self.max_body {
+            return Err(crate::Error::LimitExceeded {
+                limit: crate::Limit::Size,
+                max: usize::try_from(self.max_body).unwrap_or(usize::MAX),
+            });
+        }
+
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closures_are_clients() {
+        let client = |url: &Url| Ok(HttpResponse::ok(url.path()));
+        let response = client.get(&"https://a.com/x".parse().unwrap()).unwrap();
+        assert!(response.is_success());
+        assert_eq!(response.body, b"/x");
+    }
+}
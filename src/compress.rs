@@ -0,0 +1,303 @@
+//! Compression of generated output, and decompression of fetched one.
+//!
+//! A [`Compression`] is picked for files by their extension, and for responses by the
+//! `Accept-Encoding` of the client:
+//!
+//! ```rust
+//! use sitewriter::compress::Compression;
+//!
+//! assert_eq!(Compression::from_path("public/sitemap.xml.br"), Compression::Brotli);
+//! assert_eq!(Compression::Zstd.content_encoding(), Some("zstd"));
+//! let xml = b"<urlset/>";
+//! assert_eq!(Compression::None.compress(xml)?, &xml[..]);
+//! # Ok::<(), sitewriter::Error>(())
+//! ```
+//!
+//! Search engines only read plain and gzipped sitemaps, brotli and zstd are for serving
+//! with `Content-Encoding` and for archives.
+
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+    path::Path,
+};
+
+#[cfg(feature = "gzip")]
+use flate2::{read::GzDecoder, write::GzEncoder};
+
+use crate::{Error, Result};
+
+/// The brotli quality, close to the size of the best one in a fraction of the time.
+#[cfg(feature = "brotli")]
+const BROTLI_QUALITY: u32 = 9;
+
+/// The base 2 logarithm of the brotli window size.
+#[cfg(feature = "brotli")]
+const BROTLI_WINDOW: u32 = 22;
+
+/// An encoding of generated files and responses.
+///
+/// The encodings other than [`Compression::None`] need the feature of the same name,
+/// using one without it fails with [`Error::FeatureDisabled`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Compression {
+    /// Left uncompressed.
+    #[default]
+    None,
+    /// Gzip, the `.gz` extension.
+    Gzip,
+    /// Brotli, the `.br` extension.
+    Brotli,
+    /// Zstandard, the `.zst` extension.
+    Zstd,
+}
+
+impl Compression {
+    /// The encodings enabled by the features of the crate, the best first.
+    pub const ENABLED: &'static [Self] = &[
+        #[cfg(feature = "brotli")]
+        Self::Brotli,
+        #[cfg(feature = "zstd")]
+        Self::Zstd,
+        #[cfg(feature = "gzip")]
+        Self::Gzip,
+    ];
+
+    /// The encoding of the file at `path`, judging by its extension.
+    #[must_use]
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Self::Gzip,
+            Some("br") => Self::Brotli,
+            Some("zst") => Self::Zstd,
+            _ => Self::None,
+        }
+    }
+
+    /// The extension of the files, without the dot.
+    #[must_use]
+    pub const fn extension(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gz"),
+            Self::Brotli => Some("br"),
+            Self::Zstd => Some("zst"),
+        }
+    }
+
+    /// The name of the encoding in the `Content-Encoding` and `Accept-Encoding` headers.
+    #[must_use]
+    pub const fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gzip"),
+            Self::Brotli => Some("br"),
+            Self::Zstd => Some("zstd"),
+        }
+    }
+
+    /// Whether the feature of the encoding is enabled.
+    #[must_use]
+    pub const fn is_enabled(self) -> bool {
+        match self {
+            Self::None => true,
+            Self::Gzip => cfg!(feature = "gzip"),
+            Self::Brotli => cfg!(feature = "brotli"),
+            Self::Zstd => cfg!(feature = "zstd"),
+        }
+    }
+
+    /// Fails with [`Error::FeatureDisabled`] if the feature of the encoding is disabled.
+    pub(crate) const fn check_enabled(self) -> Result<()> {
+        match self {
+            Self::Gzip if !self.is_enabled() => Err(Error::FeatureDisabled("gzip")),
+            Self::Brotli if !self.is_enabled() => Err(Error::FeatureDisabled("brotli")),
+            Self::Zstd if !self.is_enabled() => Err(Error::FeatureDisabled("zstd")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Compresses the bytes, which are returned as they are with [`Compression::None`].
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::FeatureDisabled`] if the feature of the encoding is disabled.
+    pub fn compress(self, bytes: &[u8]) -> Result<Cow<'_, [u8]>> {
+        if self == Self::None {
+            return Ok(Cow::Borrowed(bytes));
+        }
+        let mut encoder = Encoder::new(self, Vec::with_capacity(bytes.len() / 4))?;
+        encoder.write_all(bytes)?;
+        Ok(Cow::Owned(encoder.finish()?))
+    }
+}
+
+/// A writer compressing what is written to it, which must be finished.
+pub(crate) enum Encoder<W: Write> {
+    Plain(W),
+    #[cfg(feature = "gzip")]
+    Gzip(GzEncoder<W>),
+    #[cfg(feature = "brotli")]
+    Brotli(Box<brotli::CompressorWriter<KeepError<W>>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Wraps `writer` to compress with `compression`.
+    pub(crate) fn new(compression: Compression, writer: W) -> Result<Self> {
+        compression.check_enabled()?;
+        Ok(match compression {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Self::Gzip(GzEncoder::new(writer, flate2::Compression::default())),
+            #[cfg(feature = "brotli")]
+            Compression::Brotli => Self::Brotli(Box::new(brotli::CompressorWriter::new(
+                KeepError::new(writer),
+                4096,
+                BROTLI_QUALITY,
+                BROTLI_WINDOW,
+            ))),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Self::Zstd(zstd::Encoder::new(writer, 0)?),
+            _ => Self::Plain(writer),
+        })
+    }
+
+    /// Writes the end of the compressed stream and returns the inner writer.
+    pub(crate) fn finish(self) -> Result<W> {
+        Ok(match self {
+            Self::Plain(writer) => writer,
+            #[cfg(feature = "gzip")]
+            Self::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "brotli")]
+            Self::Brotli(encoder) => encoder.into_inner().into_result()?,
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.finish()?,
+        })
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(writer) => writer.write(buf),
+            #[cfg(feature = "gzip")]
+            Self::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "brotli")]
+            Self::Brotli(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(writer) => writer.flush(),
+            #[cfg(feature = "gzip")]
+            Self::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "brotli")]
+            Self::Brotli(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Keeps the first error of the inner writer, the brotli encoder drops the errors of the
+/// last writes when it is finished.
+#[cfg(feature = "brotli")]
+pub(crate) struct KeepError<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "brotli")]
+impl<W> KeepError<W> {
+    const fn new(inner: W) -> Self {
+        Self { inner, error: None }
+    }
+
+    fn into_result(self) -> io::Result<W> {
+        self.error.map_or(Ok(self.inner), Err)
+    }
+}
+
+#[cfg(feature = "brotli")]
+impl<W: Write> Write for KeepError<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf).map_err(|e| {
+            let kind = e.kind();
+            self.error.get_or_insert(e);
+            kind.into()
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Compresses the bytes with gzip.
+#[cfg(feature = "gzip")]
+pub(crate) fn gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(
+        Vec::with_capacity(bytes.len() / 4),
+        flate2::Compression::default(),
+    );
+    encoder
+        .write_all(bytes)
+        .expect("writing to a Vec never fails");
+    encoder.finish().expect("writing to a Vec never fails")
+}
+
+/// Decompresses gzip bytes, failing once the output is over `max_size` bytes.
+#[cfg(feature = "gzip")]
+pub(crate) fn gunzip(bytes: &[u8], max_size: usize) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut out = Vec::new();
+    GzDecoder::new(bytes)
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut out)?;
+    if out.len() > max_size {
+        return Err(Error::LimitExceeded {
+            limit: crate::Limit::Size,
+            max: max_size,
+        });
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compresses() {
+        let xml = "<urlset><url><loc>https://a.com/</loc></url></urlset>".repeat(100);
+        for &compression in Compression::ENABLED {
+            let compressed = compression.compress(xml.as_bytes()).unwrap();
+            assert!(compressed.len() < xml.len() / 4, "{compression:?}");
+            let name = format!("sitemap.xml.{}", compression.extension().unwrap());
+            assert_eq!(Compression::from_path(name), compression);
+        }
+        #[cfg(feature = "brotli")]
+        {
+            let compressed = Compression::Brotli.compress(xml.as_bytes()).unwrap();
+            let mut out = Vec::new();
+            brotli::BrotliDecompress(&mut &compressed[..], &mut out).unwrap();
+            assert_eq!(out, xml.as_bytes());
+        }
+        #[cfg(feature = "zstd")]
+        {
+            let compressed = Compression::Zstd.compress(xml.as_bytes()).unwrap();
+            assert_eq!(zstd::decode_all(&compressed[..]).unwrap(), xml.as_bytes());
+        }
+        #[cfg(not(feature = "zstd"))]
+        assert!(matches!(
+            Compression::Zstd.compress(b""),
+            Err(Error::FeatureDisabled("zstd"))
+        ));
+    }
+}
@@ -0,0 +1,183 @@
+//! Collecting entries from several threads.
+//!
+//! A [`ConcurrentSitemapBuilder`] is shared by reference between worker threads, which
+//! push entries as they crawl or render pages. The entries are spread over several locked
+//! shards so the workers rarely wait on each other, and
+//! [`finish`](ConcurrentSitemapBuilder::finish) sorts them so the sitemap doesn't depend on
+//! which worker was faster:
+//!
+//! ```rust
+//! use sitewriter::{concurrent::ConcurrentSitemapBuilder, Sitemap, SortOrder, UrlEntry};
+//!
+//! let builder = ConcurrentSitemapBuilder::new();
+//! std::thread::scope(|scope| {
+//!     for worker in 0..4 {
+//!         let builder = &builder;
+//!         scope.spawn(move || {
+//!             for page in 0..10 {
+//!                 let loc = format!("https://example.com/{worker}/{page}").parse().unwrap();
+//!                 builder.push(UrlEntry::new(loc, None, None, None));
+//!             }
+//!         });
+//!     }
+//! });
+//!
+//! let urls = builder.finish(SortOrder::Loc);
+//! assert_eq!(urls.len(), 40);
+//! assert_eq!(urls[0].loc.as_str(), "https://example.com/0/0");
+//! let xml = Sitemap::new().generate_str(&urls);
+//! ```
+
+use std::{
+    cmp::Ordering,
+    hash::{BuildHasher, RandomState},
+    sync::Mutex,
+    thread,
+};
+
+use crate::{dedup::resolve, ConflictStrategy, Result, SortOrder, UrlEntry};
+
+/// Entries pushed from several threads, see the [module docs](self).
+#[derive(Debug)]
+pub struct ConcurrentSitemapBuilder {
+    shards: Box<[Mutex<Vec<UrlEntry>>]>,
+    hasher: RandomState,
+}
+
+impl Default for ConcurrentSitemapBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConcurrentSitemapBuilder {
+    /// A builder with four shards per available core.
+    #[must_use]
+    pub fn new() -> Self {
+        let cores = thread::available_parallelism().map_or(1, usize::from);
+        Self::with_shards(cores * 4)
+    }
+
+    /// A builder with `shards` shards, at least one.
+    #[must_use]
+    pub fn with_shards(shards: usize) -> Self {
+        Self {
+            shards: (0..shards.max(1)).map(|_| Mutex::default()).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// The shard of the current thread.
+    fn shard(&self) -> &Mutex<Vec<UrlEntry>> {
+        let hash = self.hasher.hash_one(thread::current().id());
+        &self.shards[(hash % self.shards.len() as u64) as usize]
+    }
+
+    /// Adds an entry.
+    pub fn push(&self, entry: UrlEntry) {
+        self.shard().lock().expect("poisoned").push(entry);
+    }
+
+    /// Adds the entries, taking the lock of the shard once.
+    pub fn extend(&self, entries: impl IntoIterator<Item = UrlEntry>) {
+        self.shard().lock().expect("poisoned").extend(entries);
+    }
+
+    /// The number of entries pushed so far.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().expect("poisoned").len())
+            .sum()
+    }
+
+    /// Whether no entry was pushed yet.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The entries sorted by `order`, whatever order they were pushed in.
+    ///
+    /// Duplicates are kept, entries with the same loc being ordered by their other fields.
+    #[must_use]
+    pub fn finish(self, order: SortOrder) -> Vec<UrlEntry> {
+        let mut entries: Vec<UrlEntry> = self
+            .shards
+            .into_vec()
+            .into_iter()
+            .flat_map(|shard| shard.into_inner().expect("poisoned"))
+            .collect();
+        entries.sort_by(|a, b| {
+            order
+                .compare(a, b)
+                .then_with(|| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        });
+        entries
+    }
+
+    /// Like [`finish`](ConcurrentSitemapBuilder::finish), keeping one entry per loc picked
+    /// with `conflict`.
+    ///
+    /// As the duplicates are sorted first, [`ConflictStrategy::KeepFirst`] and
+    /// [`ConflictStrategy::KeepLast`] keep the same entry on every run.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if there is a duplicate and the strategy is
+    /// [`ConflictStrategy::Error`].
+    pub fn finish_dedup(
+        self,
+        order: SortOrder,
+        conflict: ConflictStrategy,
+    ) -> Result<Vec<UrlEntry>> {
+        resolve(self.finish(order), conflict)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    fn entry(path: &str, priority: f32) -> UrlEntry {
+        UrlEntry::new(
+            format!("https://a.com/{path}").parse().unwrap(),
+            None,
+            None,
+            Some(priority),
+        )
+    }
+
+    #[test]
+    fn finishes_deterministically() {
+        let builder = ConcurrentSitemapBuilder::with_shards(3);
+        thread::scope(|scope| {
+            for worker in 0..8 {
+                let builder = &builder;
+                scope.spawn(move || {
+                    builder.extend((0..50).map(|i| entry(&i.to_string(), 0.5)));
+                    builder.push(entry("dup", worker as f32 / 10.0));
+                });
+            }
+        });
+        assert_eq!(builder.len(), 408);
+
+        let urls = builder
+            .finish_dedup(SortOrder::Loc, ConflictStrategy::KeepFirst)
+            .unwrap();
+        assert_eq!(urls.len(), 51);
+        assert_eq!(urls[0].loc.as_str(), "https://a.com/0");
+        let dup = urls.iter().find(|u| u.loc.path() == "/dup").unwrap();
+        assert_eq!(dup.priority, Some(0.0));
+
+        let builder = ConcurrentSitemapBuilder::with_shards(0);
+        builder.push(entry("a", 0.1));
+        builder.push(entry("a", 0.2));
+        assert!(matches!(
+            builder.finish_dedup(SortOrder::Loc, ConflictStrategy::Error),
+            Err(Error::DuplicateLoc(_))
+        ));
+    }
+}
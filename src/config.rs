@@ -0,0 +1,171 @@
+//! The settings of the generation in one struct, to read from a config file.
+//!
+//! With the `serde` feature a [`SitemapConfig`] deserializes from any format serde
+//! supports. Every field but `base_url` and `output_dir` is optional:
+//!
+//! ```rust
+//! # #[cfg(feature = "serde")]
+//! # {
+//! use sitewriter::{config::SitemapConfig, Sitemap, UrlEntry};
+//!
+//! let config: SitemapConfig = serde_json::from_str(
+//!     r#"{
+//!         "base_url": "https://example.com/",
+//!         "output_dir": "public",
+//!         "template": "sitemap-{index}.xml",
+//!         "defaults": {"changefreq": "weekly"},
+//!         "sort": "loc",
+//!         "limits": {"max_entries": 10000},
+//!         "on_empty": "skip"
+//!     }"#,
+//! )?;
+//! assert_eq!(config.limits.max_entries, 10_000);
+//! assert_eq!(config.limits.max_size, sitewriter::MAX_SIZE);
+//!
+//! # let urls: Vec<UrlEntry> = Vec::new();
+//! let sitemap = Sitemap::from_config(&config);
+//! # let _ = sitemap;
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`SitemapConfig::write`] then writes the entries where the config says.
+
+use std::path::PathBuf;
+
+use url::Url;
+
+use crate::{
+    split::{SplitOutput, WrittenFile, INDEX_PLACEHOLDER},
+    EmptyStrategy, EntryDefaults, Error, Limits, Result, Sitemap, SortOrder, UrlEntry,
+};
+
+/// The settings of [`Sitemap::from_config`] and where the files are written.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub struct SitemapConfig {
+    /// The url the files of `output_dir` are served at.
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::profile::deserialize_url")
+    )]
+    pub base_url: Url,
+    /// The directory the files are written to.
+    pub output_dir: PathBuf,
+    /// The name template of the parts, see [`SplitOutput::template`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub template: Option<String>,
+    /// The file name of the sitemap index, see [`SplitOutput::index_name`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub index_name: Option<String>,
+    /// The values of the entries without them, see [`Sitemap::defaults`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub defaults: EntryDefaults,
+    /// The order of the entries, see [`Sitemap::sort`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sort: Option<SortOrder>,
+    /// Whether the locs are written in ASCII, see [`Sitemap::ascii_locs`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ascii_locs: bool,
+    /// The limits each part stays within, see [`Sitemap::limits`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub limits: Limits,
+    /// What to do without entries, see [`Sitemap::on_empty`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub on_empty: EmptyStrategy,
+}
+
+impl SitemapConfig {
+    /// A config writing to `output_dir`, served at `base_url`, with the default settings.
+    #[must_use]
+    pub fn new(base_url: Url, output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_url,
+            output_dir: output_dir.into(),
+            template: None,
+            index_name: None,
+            defaults: EntryDefaults::default(),
+            sort: None,
+            ascii_locs: false,
+            limits: Limits::default(),
+            on_empty: EmptyStrategy::default(),
+        }
+    }
+
+    /// Where [`SitemapConfig::write`] writes the files.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::InvalidValue`] if the template doesn't contain
+    /// [`INDEX_PLACEHOLDER`].
+    pub fn output(&self) -> Result<SplitOutput> {
+        let mut output = SplitOutput::new(&self.output_dir, self.base_url.clone());
+        if let Some(template) = &self.template {
+            if !template.contains(INDEX_PLACEHOLDER) {
+                return Err(Error::InvalidValue {
+                    element: "template",
+                    value: template.clone(),
+                });
+            }
+            output = output.template(template.as_str());
+        }
+        if let Some(index_name) = &self.index_name {
+            output = output.index_name(index_name.as_str());
+        }
+        Ok(output)
+    }
+
+    /// Writes the split sitemap of `urls` and its index, see [`Sitemap::write_split`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`SitemapConfig::output`] and [`Sitemap::write_split`].
+    pub fn write(&self, urls: &[UrlEntry]) -> Result<Vec<WrittenFile>> {
+        Sitemap::from_config(self).write_split(urls, &self.output()?)
+    }
+}
+
+impl Sitemap {
+    /// A sitemap generator with the settings of `config`.
+    #[must_use]
+    pub fn from_config(config: &SitemapConfig) -> Self {
+        let mut sitemap = Self::new()
+            .defaults(config.defaults)
+            .ascii_locs(config.ascii_locs)
+            .limits(config.limits)
+            .on_empty(config.on_empty);
+        if let Some(order) = config.sort {
+            sitemap = sitemap.sort(order);
+        }
+        sitemap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn writes_where_configured() {
+        let dir = TempDir::new("config");
+        let mut config = SitemapConfig::new("https://a.com/maps/".parse().unwrap(), &dir);
+        config.template = Some("part-{index}.xml".to_owned());
+        config.limits = Limits::new(1, crate::MAX_SIZE);
+
+        let urls = vec![
+            UrlEntry::new("https://a.com/b".parse().unwrap(), None, None, None),
+            UrlEntry::new("https://a.com/a".parse().unwrap(), None, None, None),
+        ];
+        let files = config.write(&urls).unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[1].url.as_str(), "https://a.com/maps/part-1.xml");
+        assert!(dir.join("part-2.xml").exists());
+
+        config.template = Some("part.xml".to_owned());
+        assert!(matches!(
+            config.write(&urls),
+            Err(Error::InvalidValue { .. })
+        ));
+    }
+}
@@ -0,0 +1,70 @@
+//! Conversions of whole lists of entries between [`UrlEntry`] and [`UrlEntryRef`], for
+//! pipelines that switch representations between stages.
+//!
+//! ```rust
+//! use sitewriter::{convert, UrlEntryRef};
+//!
+//! let refs = [UrlEntryRef::new("https://example.com/"), UrlEntryRef::new("https://example.com/a")];
+//! let owned = convert::to_owned(&refs)?;
+//! assert_eq!(convert::to_borrowed(&owned), refs);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{Error, Result, UrlEntry, UrlEntryRef};
+
+/// Parses the locs of `entries` into owned entries, the extensions are shared.
+///
+/// # Errors
+///
+/// Will return [`Error::Entry`] with the index of the first entry whose loc isn't a valid
+/// url.
+pub fn to_owned(entries: &[UrlEntryRef<'_>]) -> Result<Vec<UrlEntry>> {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            entry.into_owned().map_err(|e| Error::Entry {
+                index,
+                source: Box::new(e),
+            })
+        })
+        .collect()
+}
+
+/// Borrows each of `entries` as a [`UrlEntryRef`].
+#[must_use]
+pub fn to_borrowed(entries: &[UrlEntry]) -> Vec<UrlEntryRef<'_>> {
+    entries.iter().map(UrlEntry::as_entry_ref).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChangeFreq;
+
+    #[test]
+    fn round_trips() {
+        let refs = [
+            UrlEntryRef::new("https://a.com/").priority(0.5),
+            UrlEntryRef::new("https://a.com/b").changefreq(ChangeFreq::Daily),
+        ];
+        let owned = to_owned(&refs).unwrap();
+        assert_eq!(owned[1].loc.as_str(), "https://a.com/b");
+        assert_eq!(to_borrowed(&owned), refs);
+    }
+
+    #[test]
+    fn reports_the_failing_index() {
+        let refs = [
+            UrlEntryRef::new("https://a.com/"),
+            UrlEntryRef::new("not a url"),
+        ];
+        match to_owned(&refs) {
+            Err(Error::Entry { index, source }) => {
+                assert_eq!(index, 1);
+                assert!(matches!(*source, Error::InvalidUrl { .. }));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+}
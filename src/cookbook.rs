@@ -0,0 +1,176 @@
+//! Recipes for common tasks.
+//!
+//! Every recipe has a runnable program in the
+//! [`examples`](https://github.com/edg-l/sitewriter/tree/master/examples) directory,
+//! run it with `cargo run --example <name>`. The same code is exercised by the
+//! integration tests, so the examples stay in sync with the library.
+//!
+//! ## Generating from a database (`from_database`)
+//!
+//! Fetch the rows page by page, map each one to a [`UrlEntry`](crate::UrlEntry) and write
+//! the result straight into any [`std::io::Write`], such as a file or stdout:
+//!
+//! ```rust
+//! use sitewriter::{ChangeFreq, UrlEntry, Url};
+//!
+//! # struct Post { slug: String }
+//! # let posts = vec![Post { slug: "hello".to_string() }];
+//! let base: Url = "https://example.com/".parse().unwrap();
+//! let urls: Vec<UrlEntry> = posts
+//!     .iter()
+//!     .map(|post| UrlEntry {
+//!         loc: base.join(&format!("blog/{}", post.slug)).unwrap(),
+//!         lastmod: None,
+//!         changefreq: Some(ChangeFreq::Monthly),
+//!         priority: None,
+//!         extensions: Default::default(),
+//!         mobile: false,
+//!         source: None,
+//!     })
+//!     .collect();
+//!
+//! let mut out = Vec::new();
+//! sitewriter::generate(&mut out, &urls).unwrap();
+//! ```
+//!
+//! ## Combining sources without duplicates (`dedup`)
+//!
+//! When several subsystems report urls, the same page can show up under slightly
+//! different urls. [`Sitemap::dedup`](crate::Sitemap::dedup) normalizes them and keeps
+//! one entry per page:
+//!
+//! ```rust
+//! use sitewriter::{ConflictStrategy, Sitemap, UrlEntry};
+//!
+//! let urls = vec![
+//!     UrlEntry::new("https://example.com/a".parse().unwrap(), None, None, None),
+//!     UrlEntry::new("https://example.com/a#top".parse().unwrap(), None, None, None),
+//! ];
+//! let urls = Sitemap::new()
+//!     .on_conflict(ConflictStrategy::KeepFirst)
+//!     .dedup(urls)?;
+//! assert_eq!(urls.len(), 1);
+//! # Ok::<(), sitewriter::Error>(())
+//! ```
+//!
+//! ## Splitting and gzipping a large site (`split_gzip`)
+//!
+//! A sitemap holds at most 50 000 urls. [`Sitemap::write_split`](crate::Sitemap::write_split)
+//! spreads the entries over as many files as needed and lists them in a sitemap index.
+//! Parts named `.gz` are gzipped, with the `gzip` feature:
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "gzip")]
+//! # {
+//! use sitewriter::{split::SplitOutput, Sitemap, UrlEntry};
+//!
+//! # let urls: Vec<UrlEntry> = Vec::new();
+//! let output = SplitOutput::new("public", "https://example.com/".parse()?)
+//!     .template("sitemap-{index}.xml.gz");
+//! let files = Sitemap::new().write_split(&urls, &output)?;
+//! // The index comes first, submit its url to the search engines.
+//! println!("{}", files[0].url);
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## Serving from axum (`axum_server`)
+//!
+//! With the `axum` feature, a [`SitemapResponse`](crate::response::SitemapResponse) is
+//! returned straight from a handler, with its content type, `ETag` and `Cache-Control`:
+//!
+//! ```rust
+//! # #[cfg(feature = "axum")]
+//! # {
+//! use sitewriter::{response::SitemapResponse, Sitemap, UrlEntry};
+//!
+//! async fn sitemap() -> SitemapResponse {
+//!     let urls = vec![UrlEntry::new("https://example.com/".parse().unwrap(), None, None, None)];
+//!     SitemapResponse::generate(&Sitemap::new(), &urls).cache_control("public, max-age=600")
+//! }
+//! # }
+//! ```
+//!
+//! ## Translations with hreflang (`hreflang`)
+//!
+//! Each translation of a page gets an entry listing every translation, itself included,
+//! as `xhtml:link` alternates written by a [`SitemapExtension`](crate::SitemapExtension):
+//!
+//! ```rust
+//! use sitewriter::{ExtensionWriter, SitemapExtension, Url, UrlEntry};
+//!
+//! #[derive(Debug, Clone)]
+//! struct Alternate {
+//!     hreflang: &'static str,
+//!     href: Url,
+//! }
+//!
+//! impl SitemapExtension for Alternate {
+//!     fn prefix(&self) -> &str {
+//!         "xhtml"
+//!     }
+//!
+//!     fn namespace(&self) -> &str {
+//!         "http://www.w3.org/1999/xhtml"
+//!     }
+//!
+//!     fn write(&self, writer: &mut ExtensionWriter<'_>) -> sitewriter::Result<()> {
+//!         let attributes = [("rel", "alternate"), ("hreflang", self.hreflang), ("href", self.href.as_str())];
+//!         writer.empty("xhtml:link", &attributes)
+//!     }
+//! }
+//!
+//! let alternates = [
+//!     Alternate { hreflang: "en", href: "https://example.com/en/".parse()? },
+//!     Alternate { hreflang: "de", href: "https://example.com/de/".parse()? },
+//! ];
+//! let urls: Vec<UrlEntry> = alternates
+//!     .iter()
+//!     .map(|page| {
+//!         let mut entry = UrlEntry::new(page.href.clone(), None, None, None);
+//!         for alternate in &alternates {
+//!             entry.extensions.push(alternate.clone());
+//!         }
+//!         entry
+//!     })
+//!     .collect();
+//! let xml = sitewriter::generate_str(&urls);
+//! assert_eq!(xml.matches("<xhtml:link").count(), 4);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## Images and videos (`images_videos`)
+//!
+//! The Google image and video extensions are written the same way, each element a
+//! [`SitemapExtension`](crate::SitemapExtension) pushed to the entry of its page:
+//!
+//! ```rust
+//! use sitewriter::{ExtensionWriter, SitemapExtension, Url, UrlEntry};
+//!
+//! #[derive(Debug)]
+//! struct Image(Url);
+//!
+//! impl SitemapExtension for Image {
+//!     fn prefix(&self) -> &str {
+//!         "image"
+//!     }
+//!
+//!     fn namespace(&self) -> &str {
+//!         "http://www.google.com/schemas/sitemap-image/1.1"
+//!     }
+//!
+//!     fn write(&self, writer: &mut ExtensionWriter<'_>) -> sitewriter::Result<()> {
+//!         writer.start("image:image", &[])?;
+//!         writer.text_element("image:loc", self.0.as_str())?;
+//!         writer.end("image:image")
+//!     }
+//! }
+//!
+//! let mut entry = UrlEntry::new("https://example.com/kettle".parse()?, None, None, None);
+//! entry.extensions.push(Image("https://example.com/kettle.jpg".parse()?));
+//! let xml = sitewriter::generate_str(&[entry]);
+//! assert!(xml.contains("<image:loc>https://example.com/kettle.jpg</image:loc>"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The `images_videos` example adds a `<video:video>` with its required children.
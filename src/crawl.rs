@@ -0,0 +1,726 @@
+//! Discovering the pages of a site by following its links.
+//!
+//! For a site without a content database, [`crawl_with`] starts from a url and follows the
+//! links of the HTML pages it finds, breadth first, staying on the same origin. It is
+//! bounded by a depth and a number of pages, obeys the `robots.txt` of the site, and
+//! spaces its requests with a [`Throttle`]. Every page found becomes an entry, with the
+//! lastmod of its `Last-Modified` header:
+//!
+//! ```rust
+//! use sitewriter::{
+//!     client::HttpResponse,
+//!     crawl::{crawl_with, CrawlOptions},
+//!     Sitemap, Url,
+//! };
+//!
+//! let client = |url: &Url| {
+//!     Ok(match url.path() {
+//!         "/" => HttpResponse::ok(r#"<a href="/about">About</a> <a href="https://other.com/">"#),
+//!         "/about" => HttpResponse::ok(r#"<a href="/">Home</a>"#),
+//!         _ => HttpResponse::status(404),
+//!     })
+//! };
+//! let report = crawl_with(&client, &"https://example.com/".parse()?, &CrawlOptions::default())?;
+//! let locs: Vec<&str> = report.entries.iter().map(|e| e.loc.as_str()).collect();
+//! assert_eq!(locs, ["https://example.com/", "https://example.com/about"]);
+//!
+//! let xml = Sitemap::new().generate_str(&report.entries);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Pages with a `noindex` robots meta tag are left out of the entries, and the links of
+//! pages with a `nofollow` one, or with a `rel="nofollow"` attribute, aren't followed.
+//! Links are found in `<a href>` attributes only, pages built by scripts aren't crawled.
+//!
+//! A long crawl can be interrupted and started again with [`crawl_resumable`], which
+//! records the result of each request, the links of the page included, in a
+//! [`Checkpoint`] and doesn't request the urls already recorded.
+
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use chrono::{DateTime, Utc};
+use url::Url;
+
+use crate::{
+    checkpoint::Checkpoint,
+    classify::Classifier,
+    client::{HttpClient, HttpResponse},
+    politeness::{PolitenessOptions, Throttle},
+    robots::RobotsRules,
+    Error, Result, UrlEntry,
+};
+
+/// Options of [`crawl_with`].
+#[derive(Debug, Clone)]
+pub struct CrawlOptions {
+    /// How many links away from the start url pages are crawled.
+    pub max_depth: usize,
+    /// The maximum number of distinct urls found, the start url included.
+    pub max_pages: usize,
+    /// The limits on concurrent requests, `max_concurrency` is the number of threads used.
+    pub politeness: PolitenessOptions,
+    /// Whether the `robots.txt` of the site is obeyed.
+    pub respect_robots: bool,
+    /// The name the crawler looks for in the `User-agent` lines of the `robots.txt`.
+    pub user_agent: String,
+    /// Recognizes the urls that aren't pages. Assets and feeds aren't requested, the other
+    /// urls that aren't pages, such as paginated listings, are crawled for their links but
+    /// left out of the entries.
+    pub classifier: Classifier,
+}
+
+impl Default for CrawlOptions {
+    fn default() -> Self {
+        Self {
+            max_depth: 5,
+            max_pages: 10_000,
+            politeness: PolitenessOptions::default(),
+            respect_robots: true,
+            user_agent: "sitewriter".to_owned(),
+            classifier: Classifier::new(),
+        }
+    }
+}
+
+/// A request of the crawl that didn't get a `2xx` response.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CrawlFailure {
+    /// The requested url.
+    pub url: Url,
+    /// The status of the response, or why none was received.
+    pub status: std::result::Result<u16, String>,
+}
+
+/// The result of [`crawl_with`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CrawlReport {
+    /// The pages found, sorted by loc.
+    pub entries: Vec<UrlEntry>,
+    /// The requests that failed, in the order they were made.
+    pub failures: Vec<CrawlFailure>,
+    /// The links not followed because the `robots.txt` disallows them, in the order they
+    /// were found.
+    pub disallowed: Vec<Url>,
+}
+
+/// Crawls the site of `start` with the `ureq` client, see [`crawl_with`].
+///
+/// # Errors
+///
+/// Will return `Err` as [`crawl_with`].
+#[cfg(feature = "fetch")]
+pub fn crawl(start: &Url, options: &CrawlOptions) -> Result<CrawlReport> {
+    crawl_with(&crate::client::UreqClient::default(), start, options)
+}
+
+/// Crawls the site of `start`, see the [module docs](self).
+///
+/// Failed requests are reported in the [`CrawlReport`].
+///
+/// # Errors
+///
+/// Will return `Err` if the `robots.txt` of the site can't be fetched: without a response,
+/// or with a `5xx` status, the crawler can't know what it may request.
+pub fn crawl_with<C>(client: &C, start: &Url, options: &CrawlOptions) -> Result<CrawlReport>
+where
+    C: HttpClient + Sync,
+{
+    crawl_from(client, start, options, None)
+}
+
+/// Crawls the site of `start` like [`crawl_with`], recording the result of each request
+/// in `checkpoint`.
+///
+/// The urls already recorded, by an interrupted crawl with the same checkpoint, aren't
+/// requested again: their recorded results are used, so the report is the same as the
+/// one of an uninterrupted crawl. Once the crawl is done, the checkpoint can be
+/// [removed](Checkpoint::remove).
+///
+/// ```rust,no_run
+/// use sitewriter::{checkpoint::Checkpoint, client::UreqClient, crawl::{crawl_resumable, CrawlOptions}};
+///
+/// let mut checkpoint = Checkpoint::open("crawl.checkpoint")?;
+/// let start = "https://example.com/".parse()?;
+/// let report = crawl_resumable(&UreqClient::default(), &start, &CrawlOptions::default(), &mut checkpoint)?;
+/// checkpoint.remove()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` as [`crawl_with`], or if writing to the checkpoint fails.
+pub fn crawl_resumable<C>(
+    client: &C,
+    start: &Url,
+    options: &CrawlOptions,
+    checkpoint: &mut Checkpoint,
+) -> Result<CrawlReport>
+where
+    C: HttpClient + Sync,
+{
+    crawl_from(client, start, options, Some(checkpoint))
+}
+
+/// Crawls the site of `start`, with the recorded results of `checkpoint` if any.
+fn crawl_from<C>(
+    client: &C,
+    start: &Url,
+    options: &CrawlOptions,
+    mut checkpoint: Option<&mut Checkpoint>,
+) -> Result<CrawlReport>
+where
+    C: HttpClient + Sync,
+{
+    let robots = if options.respect_robots {
+        fetch_rules(client, start, &options.user_agent)?
+    } else {
+        RobotsRules::default()
+    };
+    let throttle = Throttle::new(options.politeness.clone());
+
+    let mut report = CrawlReport::default();
+    let mut start = start.clone();
+    start.set_fragment(None);
+    let mut seen = HashSet::from([start.clone()]);
+    let mut level = Vec::new();
+    if robots.is_allowed(&start) {
+        level.push(start.clone());
+    } else {
+        report.disallowed.push(start.clone());
+    }
+
+    for depth in 0..=options.max_depth {
+        if level.is_empty() {
+            break;
+        }
+        let recorded: Vec<Option<Outcome>> = level
+            .iter()
+            .map(|url| {
+                let checkpoint = checkpoint.as_deref()?;
+                Outcome::parse(checkpoint.result(url)?)
+            })
+            .collect();
+        let pending: Vec<Url> = level
+            .iter()
+            .zip(&recorded)
+            .filter(|(_, outcome)| outcome.is_none())
+            .map(|(url, _)| url.clone())
+            .collect();
+        let mut fetched = fetch_all(client, &throttle, &pending, options).into_iter();
+
+        let mut next = Vec::new();
+        for (url, outcome) in level.into_iter().zip(recorded) {
+            let outcome = match outcome {
+                Some(outcome) => outcome,
+                None => {
+                    let Some((_, response)) = fetched.next() else {
+                        break;
+                    };
+                    let follow = depth < options.max_depth;
+                    let outcome = Outcome::of(&url, response, follow, &options.classifier);
+                    if let Some(checkpoint) = checkpoint.as_deref_mut() {
+                        checkpoint.record(&url, &outcome.to_string())?;
+                    }
+                    outcome
+                }
+            };
+            let links = match outcome {
+                Outcome::Page {
+                    entry,
+                    lastmod,
+                    links,
+                } => {
+                    if entry {
+                        report
+                            .entries
+                            .push(UrlEntry::new(url.clone(), lastmod, None, None));
+                    }
+                    links
+                }
+                Outcome::Other => continue,
+                Outcome::Failed(status) => {
+                    report.failures.push(CrawlFailure { url, status });
+                    continue;
+                }
+            };
+            if depth == options.max_depth {
+                continue;
+            }
+            for link in links {
+                if link.origin() != start.origin()
+                    || options.classifier.is_asset(&link)
+                    || options.classifier.is_feed(&link)
+                    || seen.len() >= options.max_pages
+                    || !seen.insert(link.clone())
+                {
+                    continue;
+                }
+                if robots.is_allowed(&link) {
+                    next.push(link);
+                } else {
+                    report.disallowed.push(link);
+                }
+            }
+        }
+        level = next;
+    }
+
+    report.entries.sort_by(|a, b| a.loc.cmp(&b.loc));
+    Ok(report)
+}
+
+/// What a request of the crawl gave, as recorded in a checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Outcome {
+    /// An HTML page, an entry unless it is `noindex` or not a page, with the links to
+    /// follow.
+    Page {
+        entry: bool,
+        lastmod: Option<DateTime<Utc>>,
+        links: Vec<Url>,
+    },
+    /// A successful response that isn't HTML.
+    Other,
+    /// A failed request.
+    Failed(std::result::Result<u16, String>),
+}
+
+impl Outcome {
+    /// Reads the response to the request of `url`, with the links of the page if `follow`.
+    fn of(
+        url: &Url,
+        response: std::result::Result<HttpResponse, String>,
+        follow: bool,
+        classifier: &Classifier,
+    ) -> Self {
+        let response = match response {
+            Ok(response) if response.is_success() => response,
+            Ok(response) => return Self::Failed(Ok(response.status)),
+            Err(e) => return Self::Failed(Err(e)),
+        };
+        if !is_html(&response) {
+            return Self::Other;
+        }
+
+        let page = Page::parse(&String::from_utf8_lossy(&response.body));
+        let mut links = Vec::new();
+        if follow && !page.nofollow {
+            let base = page
+                .base
+                .and_then(|base| url.join(&base).ok())
+                .unwrap_or_else(|| url.clone());
+            for href in page.links {
+                if let Ok(mut link) = base.join(&href) {
+                    link.set_fragment(None);
+                    links.push(link);
+                }
+            }
+        }
+        Self::Page {
+            entry: !page.noindex && classifier.is_page(url),
+            lastmod: last_modified(&response),
+            links,
+        }
+    }
+
+    /// Reads an outcome written by its [`Display`] impl.
+    fn parse(recorded: &str) -> Option<Self> {
+        let (kind, rest) = recorded.split_once(' ').unwrap_or((recorded, ""));
+        match kind {
+            "page" | "skip" => {
+                let mut fields = rest.split(' ').filter(|field| !field.is_empty());
+                let lastmod = match fields.next()? {
+                    "-" => None,
+                    lastmod => Some(DateTime::parse_from_rfc3339(lastmod).ok()?.to_utc()),
+                };
+                let links = fields
+                    .map(Url::parse)
+                    .collect::<std::result::Result<_, _>>();
+                Some(Self::Page {
+                    entry: kind == "page",
+                    lastmod,
+                    links: links.ok()?,
+                })
+            }
+            "other" => Some(Self::Other),
+            "status" => Some(Self::Failed(Ok(rest.parse().ok()?))),
+            "error" => Some(Self::Failed(Err(rest.to_owned()))),
+            _ => None,
+        }
+    }
+}
+
+/// One line, the links being urls, which have no spaces.
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Page {
+                entry,
+                lastmod,
+                links,
+            } => {
+                f.write_str(if *entry { "page" } else { "skip" })?;
+                match lastmod {
+                    Some(lastmod) => write!(f, " {}", lastmod.to_rfc3339())?,
+                    None => f.write_str(" -")?,
+                }
+                for link in links {
+                    write!(f, " {link}")?;
+                }
+                Ok(())
+            }
+            Self::Other => f.write_str("other"),
+            Self::Failed(Ok(status)) => write!(f, "status {status}"),
+            Self::Failed(Err(e)) => write!(f, "error {e}"),
+        }
+    }
+}
+
+/// The rules of the `robots.txt` of the site of `start`, none when it has none.
+fn fetch_rules<C: HttpClient>(client: &C, start: &Url, user_agent: &str) -> Result<RobotsRules> {
+    let mut url = start
+        .join("/robots.txt")
+        .map_err(|source| Error::InvalidUrl {
+            value: start.to_string(),
+            source,
+        })?;
+    url.set_query(None);
+    let response = client.get(&url)?;
+    match response.status {
+        200..=299 => Ok(RobotsRules::parse(
+            &String::from_utf8_lossy(&response.body),
+            user_agent,
+        )),
+        500..=599 => Err(Error::Request {
+            url,
+            source: format!("unexpected status {}", response.status).into(),
+        }),
+        _ => Ok(RobotsRules::default()),
+    }
+}
+
+/// Requests the urls on several threads, returning the responses in the order of `urls`.
+fn fetch_all<C>(
+    client: &C,
+    throttle: &Throttle,
+    urls: &[Url],
+    options: &CrawlOptions,
+) -> Vec<(Url, std::result::Result<HttpResponse, String>)>
+where
+    C: HttpClient + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![None; urls.len()]);
+    let workers = options
+        .politeness
+        .max_concurrency
+        .clamp(1, urls.len().max(1));
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(url) = urls.get(i) else {
+                    break;
+                };
+                let response = {
+                    let _permit = throttle.acquire(url);
+                    client.get(url).map_err(|e| e.to_string())
+                };
+                results.lock().unwrap_or_else(|e| e.into_inner())[i] = Some(response);
+            });
+        }
+    });
+
+    urls.iter()
+        .cloned()
+        .zip(
+            results
+                .into_inner()
+                .unwrap_or_else(|e| e.into_inner())
+                .into_iter()
+                .flatten(),
+        )
+        .collect()
+}
+
+/// Whether the response is an HTML page, assumed without a `Content-Type`.
+fn is_html(response: &HttpResponse) -> bool {
+    response
+        .header("content-type")
+        .is_none_or(|value| value.to_ascii_lowercase().contains("html"))
+}
+
+/// The `Last-Modified` header of the response.
+fn last_modified(response: &HttpResponse) -> Option<DateTime<Utc>> {
+    let value = response.header("last-modified")?;
+    DateTime::parse_from_rfc2822(value.trim())
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// What the crawler reads from an HTML page.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Page {
+    /// The `href` of the `<a>` tags without `rel="nofollow"`, entities decoded.
+    links: Vec<String>,
+    /// The `href` of the `<base>` tag.
+    base: Option<String>,
+    noindex: bool,
+    nofollow: bool,
+}
+
+impl Page {
+    /// Scans the tags of the page, skipping comments and the contents of scripts and
+    /// styles.
+    fn parse(html: &str) -> Self {
+        let mut page = Self::default();
+        let mut rest = html;
+        while let Some(start) = rest.find('<') {
+            rest = &rest[start + 1..];
+            if let Some(comment) = rest.strip_prefix("!--") {
+                rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+                continue;
+            }
+            let name_len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            let name = rest[..name_len].to_ascii_lowercase();
+            let (attributes, after) = parse_attributes(&rest[name_len..]);
+            rest = after;
+
+            let attribute = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                    .map(|(_, v)| decode_entities(v))
+            };
+            match name.as_str() {
+                "a" => {
+                    let nofollow = attribute("rel").is_some_and(|rel| {
+                        rel.split_ascii_whitespace()
+                            .any(|r| r.eq_ignore_ascii_case("nofollow"))
+                    });
+                    if let (Some(href), false) = (attribute("href"), nofollow) {
+                        page.links.push(href);
+                    }
+                }
+                "base" if page.base.is_none() => page.base = attribute("href"),
+                "meta" if attribute("name").is_some_and(|n| n.eq_ignore_ascii_case("robots")) => {
+                    let content = attribute("content")
+                        .unwrap_or_default()
+                        .to_ascii_lowercase();
+                    for directive in content.split(',').map(str::trim) {
+                        match directive {
+                            "noindex" => page.noindex = true,
+                            "nofollow" => page.nofollow = true,
+                            "none" => (page.noindex, page.nofollow) = (true, true),
+                            _ => {}
+                        }
+                    }
+                }
+                "script" | "style" => {
+                    rest = find_close_tag(rest, &name).map_or("", |end| &rest[end..]);
+                }
+                _ => {}
+            }
+        }
+        page
+    }
+}
+
+/// The position of the first closing tag of `name`, in any case.
+fn find_close_tag(text: &str, name: &str) -> Option<usize> {
+    text.as_bytes()
+        .windows(name.len() + 2)
+        .position(|w| w.starts_with(b"</") && w[2..].eq_ignore_ascii_case(name.as_bytes()))
+}
+
+/// Parses the attributes of a tag up to its `>`, returning them and the text after it.
+fn parse_attributes(mut rest: &str) -> (Vec<(&str, &str)>, &str) {
+    let mut attributes = Vec::new();
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+        if rest.is_empty() {
+            return (attributes, rest);
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            return (attributes, after);
+        }
+        let name_len = rest
+            .find(|c: char| c.is_ascii_whitespace() || matches!(c, '=' | '>' | '/'))
+            .unwrap_or(rest.len())
+            .max(1);
+        let name = &rest[..name_len];
+        rest = rest[name_len..].trim_start();
+        let Some(value) = rest.strip_prefix('=') else {
+            attributes.push((name, ""));
+            continue;
+        };
+        let value = value.trim_start();
+        let (value, after) = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = &value[1..];
+                let end = value.find(quote).unwrap_or(value.len());
+                (&value[..end], value.get(end + 1..).unwrap_or(""))
+            }
+            _ => {
+                let end = value
+                    .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                    .unwrap_or(value.len());
+                (&value[..end], &value[end..])
+            }
+        };
+        attributes.push((name, value));
+        rest = after;
+    }
+}
+
+/// Decodes the entities found in urls.
+fn decode_entities(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&#38;", "&")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn parses_pages() {
+        let page = Page::parse(
+            r#"<html><head><BASE href="/docs/"><meta name="robots" content="noindex, follow">
+<script>var a = "<a href='/script'>";</script><!-- <a href="/comment"> -->
+<STYLE>a::after { content: "é <a href='/style'>" }</Style></head>
+<body><a class=x href=/plain>1</a><a href='/q?a=1&amp;b=2'>2</a>
+<a rel="external nofollow" href="/ignored">3</a><a href="rel">4</a></body></html>"#,
+        );
+        assert_eq!(page.links, ["/plain", "/q?a=1&b=2", "rel"]);
+        assert_eq!(page.base.as_deref(), Some("/docs/"));
+        assert!(page.noindex);
+        assert!(!page.nofollow);
+    }
+
+    #[test]
+    fn crawls_within_limits() {
+        let client = |url: &Url| {
+            let body = match url.path() {
+                "/robots.txt" => "User-agent: *\nDisallow: /private",
+                "/" => {
+                    r#"<a href="/a">a</a><a href="/private/x">x</a><a href="/style.css">css</a>
+<a href="https://other.com/">other</a><a href="/missing">missing</a><a href="/a#top">a</a>"#
+                }
+                "/a" => r#"<a href="/b">b</a><a href="/list?page=2">more</a>"#,
+                "/list" => r#"<a href="/c">c</a>"#,
+                "/missing" => return Ok(HttpResponse::status(404)),
+                _ => "",
+            };
+            let mut response = HttpResponse::ok(body);
+            if url.path() == "/a" {
+                response.headers.push((
+                    "last-modified".to_owned(),
+                    "Wed, 01 May 2024 10:00:00 GMT".to_owned(),
+                ));
+            }
+            Ok(response)
+        };
+        let mut options = CrawlOptions {
+            politeness: PolitenessOptions {
+                per_host_delay: Duration::ZERO,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let start = "https://a.com/".parse().unwrap();
+
+        let report = crawl_with(&client, &start, &options).unwrap();
+        let locs: Vec<&str> = report.entries.iter().map(|e| e.loc.as_str()).collect();
+        assert_eq!(
+            locs,
+            [
+                "https://a.com/",
+                "https://a.com/a",
+                "https://a.com/b",
+                "https://a.com/c"
+            ]
+        );
+        assert_eq!(
+            report.entries[1].lastmod.unwrap().to_rfc3339(),
+            "2024-05-01T10:00:00+00:00"
+        );
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.disallowed.len(), 1);
+
+        options.max_depth = 1;
+        options.respect_robots = false;
+        let report = crawl_with(&client, &start, &options).unwrap();
+        assert_eq!(report.entries.len(), 3);
+        assert!(report.disallowed.is_empty());
+    }
+
+    #[test]
+    fn resumes_from_a_checkpoint() {
+        let dir = TempDir::new("crawl");
+        let path = dir.join("crawl.txt");
+        let pages = |url: &Url| {
+            let body = match url.path() {
+                "/" => r#"<a href="/a">a</a><a href="/missing">missing</a>"#,
+                "/a" => r#"<meta name="robots" content="noindex"><a href="/b">b</a>"#,
+                "/b" => "",
+                _ => return Ok(HttpResponse::status(404)),
+            };
+            let mut response = HttpResponse::ok(body);
+            response.headers.push((
+                "last-modified".to_owned(),
+                "Wed, 01 May 2024 10:00:00 GMT".to_owned(),
+            ));
+            Ok(response)
+        };
+        let options = CrawlOptions {
+            politeness: PolitenessOptions {
+                per_host_delay: Duration::ZERO,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let start = "https://a.com/".parse().unwrap();
+
+        let mut checkpoint = Checkpoint::open(&path).unwrap();
+        let report = crawl_resumable(&pages, &start, &options, &mut checkpoint).unwrap();
+        assert_eq!(report.entries.len(), 2);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(
+            checkpoint.result(&start),
+            Some("page 2024-05-01T10:00:00+00:00 https://a.com/a https://a.com/missing")
+        );
+        drop(checkpoint);
+
+        // Only the robots.txt is requested again.
+        let requests = Mutex::new(Vec::new());
+        let offline = |url: &Url| {
+            requests.lock().unwrap().push(url.path().to_owned());
+            Ok(HttpResponse::status(404))
+        };
+        let mut checkpoint = Checkpoint::open(&path).unwrap();
+        let resumed = crawl_resumable(&offline, &start, &options, &mut checkpoint).unwrap();
+        checkpoint.remove().unwrap();
+        assert_eq!(resumed, report);
+        assert_eq!(*requests.lock().unwrap(), ["/robots.txt"]);
+    }
+}
@@ -0,0 +1,152 @@
+//! Removal of duplicate entries.
+
+use std::collections::HashMap;
+
+use crate::{
+    normalize::{normalize_url, NormalizeOptions},
+    Error, Result, UrlEntry,
+};
+
+/// How to pick the entry to keep when two entries share the same normalized url.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum ConflictStrategy {
+    /// Keep the entry that came first.
+    KeepFirst,
+    /// Keep the entry that came last.
+    KeepLast,
+    /// Keep the entry with the most recent `lastmod`, an entry without one is older
+    /// than any entry with one. On a tie the first entry is kept.
+    #[default]
+    NewestLastmod,
+    /// Keep the entry with the highest `priority`, an entry without one has a lower
+    /// priority than any entry with one. On a tie the first entry is kept.
+    HighestPriority,
+    /// Fail with [`Error::DuplicateLoc`].
+    Error,
+}
+
+impl ConflictStrategy {
+    /// Whether `new` should replace `current`.
+    fn replaces(self, current: &UrlEntry, new: &UrlEntry) -> Result<bool> {
+        Ok(match self {
+            Self::KeepFirst => false,
+            Self::KeepLast => true,
+            Self::NewestLastmod => new.lastmod > current.lastmod,
+            Self::HighestPriority => new.priority > current.priority,
+            Self::Error => return Err(Error::DuplicateLoc(new.loc.clone())),
+        })
+    }
+}
+
+/// Options used by [`dedup`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DedupOptions {
+    /// Normalizations applied to each loc before comparing them.
+    pub normalize: NormalizeOptions,
+    /// Which entry survives when there are duplicates.
+    pub conflict: ConflictStrategy,
+}
+
+/// Normalizes the loc of every entry and removes the duplicates.
+///
+/// The order of the first occurrence of each url is preserved.
+///
+/// # Errors
+///
+/// Will return `Err` if there is a duplicate and the strategy is [`ConflictStrategy::Error`].
+pub fn dedup<I>(urls: I, options: &DedupOptions) -> Result<Vec<UrlEntry>>
+where
+    I: IntoIterator<Item = UrlEntry>,
+{
+    resolve(
+        urls.into_iter().map(|mut entry| {
+            entry.loc = normalize_url(&entry.loc, &options.normalize);
+            entry
+        }),
+        options.conflict,
+    )
+}
+
+/// Keeps one entry per loc, picked with `strategy`, preserving the order of first occurrence.
+pub(crate) fn resolve<I>(urls: I, strategy: ConflictStrategy) -> Result<Vec<UrlEntry>>
+where
+    I: IntoIterator<Item = UrlEntry>,
+{
+    let mut result: Vec<UrlEntry> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for entry in urls {
+        if let Some(&idx) = seen.get(entry.loc.as_str()) {
+            if strategy.replaces(&result[idx], &entry)? {
+                result[idx] = entry;
+            }
+        } else {
+            seen.insert(entry.loc.to_string(), result.len());
+            result.push(entry);
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn entry(loc: &str, day: Option<u32>) -> UrlEntry {
+        UrlEntry::new(
+            loc.parse().unwrap(),
+            day.map(|d| Utc.with_ymd_and_hms(2024, 1, d, 0, 0, 0).unwrap()),
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn removes_duplicates_keeping_order() {
+        let urls = vec![
+            entry("https://a.com/x", Some(1)),
+            entry("https://a.com/y", None),
+            entry("https://A.com/x#section", Some(3)),
+            entry("https://a.com/x", Some(2)),
+        ];
+
+        let result = dedup(urls, &DedupOptions::default()).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].loc.as_str(), "https://a.com/x");
+        assert_eq!(result[0].lastmod, entry("https://a.com", Some(3)).lastmod);
+        assert_eq!(result[1].loc.as_str(), "https://a.com/y");
+    }
+
+    #[test]
+    fn conflict_strategies() {
+        let urls = || {
+            vec![
+                entry("https://a.com/", Some(2)),
+                entry("https://a.com/", Some(1)),
+            ]
+        };
+
+        let first = dedup(
+            urls(),
+            &DedupOptions {
+                conflict: ConflictStrategy::KeepFirst,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let last = dedup(
+            urls(),
+            &DedupOptions {
+                conflict: ConflictStrategy::KeepLast,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(first[0].lastmod, urls()[0].lastmod);
+        assert_eq!(last[0].lastmod, urls()[1].lastmod);
+    }
+}
@@ -0,0 +1,273 @@
+//! Checking a deployed site against a [`Manifest`].
+//!
+//! [`verify_deployment`] fetches every file of the manifest from where it was published and
+//! compares it with what was generated, then checks that the sitemaps listed by the
+//! sitemap indexes can be fetched. This catches partial or stale uploads before crawlers do.
+//!
+//! ```rust
+//! use sitewriter::{
+//!     client::HttpResponse,
+//!     deploy::verify_deployment_with,
+//!     manifest::{Manifest, ManifestFile},
+//!     Url,
+//! };
+//!
+//! let mut manifest = Manifest::new();
+//! manifest.push(ManifestFile::new("sitemap.xml", b"<urlset/>", 0));
+//!
+//! let client = |_: &Url| Ok(HttpResponse::ok("<urlset/>"));
+//! let report = verify_deployment_with(&client, &manifest, &"https://example.com/".parse()?)?;
+//! assert!(report.is_ok());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::HashSet;
+
+use quick_xml::{events::Event, Reader};
+use url::Url;
+
+use crate::{
+    client::HttpClient,
+    manifest::{Manifest, ManifestFile},
+    Error, Result,
+};
+
+/// The outcome of checking one published file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FileStatus {
+    /// The published file is the generated one.
+    Ok,
+    /// The server answered with an error status.
+    Missing(u16),
+    /// The published file has a different size, likely a partial upload.
+    SizeMismatch {
+        /// The size in the manifest.
+        expected: u64,
+        /// The size of the published file.
+        actual: u64,
+    },
+    /// The published file has the right size but different contents.
+    HashMismatch,
+    /// No response was received.
+    Unreachable(String),
+}
+
+/// The check of one file of the manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FileCheck {
+    /// The path in the manifest.
+    pub path: String,
+    /// Where it was fetched from.
+    pub url: Url,
+    /// The outcome.
+    pub status: FileStatus,
+}
+
+/// A sitemap listed by a published sitemap index that can't be fetched.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BrokenLink {
+    /// The path in the manifest of the index listing it.
+    pub index: String,
+    /// The listed url.
+    pub url: String,
+    /// Why it is broken.
+    pub status: FileStatus,
+}
+
+/// The result of [`verify_deployment`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DeploymentReport {
+    /// One check per file of the manifest, in manifest order.
+    pub files: Vec<FileCheck>,
+    /// The broken links of the sitemap indexes.
+    pub broken_links: Vec<BrokenLink>,
+}
+
+impl DeploymentReport {
+    /// Whether every file matches and every index link resolves.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.broken_links.is_empty() && self.files.iter().all(|f| f.status == FileStatus::Ok)
+    }
+}
+
+/// Verifies a deployment with the default client.
+///
+/// See [`verify_deployment_with`].
+///
+/// # Errors
+///
+/// Will return `Err` if a path of the manifest can't be joined to `base`.
+#[cfg(feature = "fetch")]
+pub fn verify_deployment(manifest: &Manifest, base: &Url) -> Result<DeploymentReport> {
+    verify_deployment_with(&crate::client::UreqClient::default(), manifest, base)
+}
+
+/// Fetches each file of the manifest from `base` joined with its path and compares it with
+/// the manifest, then checks the links of the files that are sitemap indexes.
+///
+/// Links to files of the manifest aren't fetched again. Request failures are reported in
+/// the [`DeploymentReport`], not as errors.
+///
+/// # Errors
+///
+/// Will return `Err` if a path of the manifest can't be joined to `base`.
+pub fn verify_deployment_with<C: HttpClient>(
+    client: &C,
+    manifest: &Manifest,
+    base: &Url,
+) -> Result<DeploymentReport> {
+    let mut report = DeploymentReport::default();
+    let mut indexes = Vec::new();
+
+    for file in &manifest.files {
+        let url = base.join(&file.path).map_err(|source| Error::InvalidUrl {
+            value: file.path.clone(),
+            source,
+        })?;
+        let (status, body) = check_file(client, &url, file);
+        if status == FileStatus::Ok {
+            let locs = index_locs(&body);
+            if !locs.is_empty() {
+                indexes.push((file.path.clone(), locs));
+            }
+        }
+        report.files.push(FileCheck {
+            path: file.path.clone(),
+            url,
+            status,
+        });
+    }
+
+    let checked: HashSet<&str> = report.files.iter().map(|f| f.url.as_str()).collect();
+    let mut broken_links = Vec::new();
+    for (index, locs) in indexes {
+        for loc in locs {
+            let status = match Url::parse(&loc) {
+                Ok(url) if checked.contains(url.as_str()) => continue,
+                Ok(url) => match client.get(&url) {
+                    Ok(response) if response.is_success() => continue,
+                    Ok(response) => FileStatus::Missing(response.status),
+                    Err(e) => FileStatus::Unreachable(e.to_string()),
+                },
+                Err(e) => FileStatus::Unreachable(e.to_string()),
+            };
+            broken_links.push(BrokenLink {
+                index: index.clone(),
+                url: loc,
+                status,
+            });
+        }
+    }
+    report.broken_links = broken_links;
+
+    Ok(report)
+}
+
+fn check_file<C: HttpClient>(client: &C, url: &Url, file: &ManifestFile) -> (FileStatus, Vec<u8>) {
+    let response = match client.get(url) {
+        Ok(response) => response,
+        Err(e) => return (FileStatus::Unreachable(e.to_string()), Vec::new()),
+    };
+    if !response.is_success() {
+        return (FileStatus::Missing(response.status), Vec::new());
+    }
+
+    let actual = response.body.len() as u64;
+    let status = if actual != file.size {
+        FileStatus::SizeMismatch {
+            expected: file.size,
+            actual,
+        }
+    } else if file.matches(&response.body) {
+        FileStatus::Ok
+    } else {
+        FileStatus::HashMismatch
+    };
+    (status, response.body)
+}
+
+/// The `<loc>` of each `<sitemap>` if `xml` is a sitemap index, nothing otherwise.
+fn index_locs(xml: &[u8]) -> Vec<String> {
+    let mut reader = Reader::from_reader(xml);
+    reader.trim_text(true);
+
+    let mut locs = Vec::new();
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                if path.is_empty() && e.local_name().as_ref() != b"sitemapindex" {
+                    return Vec::new();
+                }
+                path.push(e.local_name().as_ref().to_vec());
+            }
+            Ok(Event::End(_)) => {
+                path.pop();
+            }
+            Ok(Event::Text(e)) if path.len() == 3 && path[1] == b"sitemap" && path[2] == b"loc" => {
+                if let Ok(text) = e.unescape() {
+                    locs.push(text.into_owned());
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    locs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::HttpResponse;
+
+    const INDEX: &str = "<sitemapindex><sitemap><loc>https://a.com/s1.xml</loc></sitemap>\
+        <sitemap><loc>https://a.com/gone.xml</loc></sitemap></sitemapindex>";
+
+    #[test]
+    fn reports_mismatches_and_broken_links() {
+        let mut manifest = Manifest::new();
+        manifest.push(ManifestFile::new("index.xml", INDEX.as_bytes(), 0));
+        manifest.push(ManifestFile::new("s1.xml", b"<urlset/>", 0));
+        manifest.push(ManifestFile::new("s2.xml", b"<urlset/>", 0));
+        manifest.push(ManifestFile::new("s3.xml", b"<urlset/>", 0));
+
+        let client = |url: &Url| {
+            Ok(match url.path() {
+                "/index.xml" => HttpResponse::ok(INDEX),
+                "/s1.xml" => HttpResponse::ok("<urlset/>"),
+                "/s2.xml" => HttpResponse::ok("<urlset"),
+                "/s3.xml" => HttpResponse::ok("<URLSET/>"),
+                _ => HttpResponse::status(404),
+            })
+        };
+
+        let report =
+            verify_deployment_with(&client, &manifest, &"https://a.com/".parse().unwrap()).unwrap();
+        let statuses: Vec<&FileStatus> = report.files.iter().map(|f| &f.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                &FileStatus::Ok,
+                &FileStatus::Ok,
+                &FileStatus::SizeMismatch {
+                    expected: 9,
+                    actual: 7
+                },
+                &FileStatus::HashMismatch,
+            ]
+        );
+        assert_eq!(
+            report.broken_links,
+            [BrokenLink {
+                index: "index.xml".to_owned(),
+                url: "https://a.com/gone.xml".to_owned(),
+                status: FileStatus::Missing(404),
+            }]
+        );
+        assert!(!report.is_ok());
+    }
+}
@@ -0,0 +1,107 @@
+//! Comparing two versions of a sitemap.
+//!
+//! ```rust
+//! use sitewriter::{diff::diff, UrlEntry};
+//!
+//! let old = vec![UrlEntry::new("https://example.com/a".parse()?, None, None, None)];
+//! let new = vec![UrlEntry::new("https://example.com/b".parse()?, None, None, None)];
+//! let diff = diff(&old, &new);
+//! assert_eq!(diff.added[0].loc.as_str(), "https://example.com/b");
+//! assert_eq!(diff.removed[0].loc.as_str(), "https://example.com/a");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::HashMap;
+
+use url::Url;
+
+use crate::UrlEntry;
+
+/// The differences between two versions of a sitemap, matching the entries by loc.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SitemapDiff {
+    /// The entries of the new version whose loc isn't in the old one, in the new order.
+    pub added: Vec<UrlEntry>,
+    /// The entries of the old version whose loc isn't in the new one, in the old order.
+    pub removed: Vec<UrlEntry>,
+    /// The entries whose loc is in both versions with other values, as the old and the
+    /// new entry, in the new order.
+    pub changed: Vec<(UrlEntry, UrlEntry)>,
+}
+
+impl SitemapDiff {
+    /// Whether both versions list the same entries.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares the entries of two versions of a sitemap.
+///
+/// The [`source`](UrlEntry::source) of the entries isn't compared. With duplicate locs,
+/// the last entry of each version counts.
+#[must_use]
+pub fn diff(old: &[UrlEntry], new: &[UrlEntry]) -> SitemapDiff {
+    let old_by_loc: HashMap<&Url, &UrlEntry> = old.iter().map(|e| (&e.loc, e)).collect();
+    let new_by_loc: HashMap<&Url, &UrlEntry> = new.iter().map(|e| (&e.loc, e)).collect();
+
+    let mut diff = SitemapDiff::default();
+    for entry in new {
+        if !std::ptr::eq(new_by_loc[&entry.loc], entry) {
+            continue;
+        }
+        match old_by_loc.get(&entry.loc) {
+            None => diff.added.push(entry.clone()),
+            Some(&before) if !same_values(before, entry) => {
+                diff.changed.push((before.clone(), entry.clone()));
+            }
+            Some(_) => {}
+        }
+    }
+    diff.removed = old
+        .iter()
+        .filter(|e| std::ptr::eq(old_by_loc[&e.loc], *e) && !new_by_loc.contains_key(&e.loc))
+        .cloned()
+        .collect();
+    diff
+}
+
+/// Whether the entries write the same `<url>` element.
+pub(crate) fn same_values(a: &UrlEntry, b: &UrlEntry) -> bool {
+    a.lastmod == b.lastmod
+        && a.changefreq == b.changefreq
+        && a.priority == b.priority
+        && a.extensions == b.extensions
+        && a.mobile == b.mobile
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChangeFreq;
+
+    fn entry(loc: &str, changefreq: Option<ChangeFreq>) -> UrlEntry {
+        UrlEntry::new(loc.parse().unwrap(), None, changefreq, None)
+    }
+
+    #[test]
+    fn matches_by_loc() {
+        let old = [
+            entry("https://a.com/kept", None),
+            entry("https://a.com/gone", None),
+            entry("https://a.com/edited", None),
+        ];
+        let new = [
+            entry("https://a.com/new", None),
+            entry("https://a.com/edited", Some(ChangeFreq::Daily)),
+            entry("https://a.com/kept", None).with_source("db"),
+        ];
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.added, [new[0].clone()]);
+        assert_eq!(diff.removed, [old[1].clone()]);
+        assert_eq!(diff.changed, [(old[2].clone(), new[1].clone())]);
+        assert!(super::diff(&new, &new).is_empty());
+    }
+}
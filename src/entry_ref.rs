@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+
+use crate::{ChangeFreq, Error, Extensions, UrlEntry};
+
+static NO_EXTENSIONS: Extensions = Extensions::new();
+
+/// A sitemap url entry borrowing its data, for callers that already have the locs as
+/// strings.
+///
+/// The loc isn't parsed, it is written as given, escaped. It must be a valid absolute url
+/// such as the ones [`Url`](crate::Url) serializes to, which is the caller's
+/// responsibility. Skipping the parsing makes generation much faster with millions of
+/// entries, see [`Sitemap::generate_refs`](crate::Sitemap::generate_refs).
+///
+/// ```rust
+/// use sitewriter::{ChangeFreq, UrlEntry, UrlEntryRef};
+///
+/// let entry = UrlEntryRef::new("https://example.com/a").changefreq(ChangeFreq::Daily);
+/// let owned = entry.into_owned()?;
+/// assert_eq!(owned.as_entry_ref(), entry);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct UrlEntryRef<'a> {
+    /// URL of the page, see [`UrlEntry::loc`].
+    pub loc: &'a str,
+    /// The date of last modification of the file.
+    pub lastmod: Option<DateTime<Utc>>,
+    /// How frequently the page is likely to change.
+    pub changefreq: Option<ChangeFreq>,
+    /// The priority of this URL relative to other URLs on your site.
+    pub priority: Option<f32>,
+    /// Custom elements written inside `<url>`.
+    pub extensions: &'a Extensions,
+    /// Whether the page is meant for feature phones, see [`UrlEntry::mobile`].
+    pub mobile: bool,
+}
+
+impl<'a> UrlEntryRef<'a> {
+    /// Creates an entry with only a loc.
+    #[must_use]
+    pub const fn new(loc: &'a str) -> Self {
+        Self {
+            loc,
+            lastmod: None,
+            changefreq: None,
+            priority: None,
+            extensions: &NO_EXTENSIONS,
+            mobile: false,
+        }
+    }
+
+    /// Sets the date of last modification.
+    #[must_use]
+    pub const fn lastmod(mut self, lastmod: DateTime<Utc>) -> Self {
+        self.lastmod = Some(lastmod);
+        self
+    }
+
+    /// Sets how frequently the page is likely to change.
+    #[must_use]
+    pub const fn changefreq(mut self, changefreq: ChangeFreq) -> Self {
+        self.changefreq = Some(changefreq);
+        self
+    }
+
+    /// Sets the priority.
+    #[must_use]
+    pub const fn priority(mut self, priority: f32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Sets the extensions.
+    #[must_use]
+    pub const fn extensions(mut self, extensions: &'a Extensions) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Sets whether the page is meant for feature phones.
+    #[must_use]
+    pub const fn mobile(mut self, mobile: bool) -> Self {
+        self.mobile = mobile;
+        self
+    }
+
+    /// Parses the loc into an owned [`UrlEntry`], the extensions are shared.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::InvalidUrl`] if the loc isn't a valid url.
+    pub fn into_owned(self) -> Result<UrlEntry, Error> {
+        UrlEntry::try_from(self)
+    }
+}
+
+impl UrlEntry {
+    /// Borrows the entry as a [`UrlEntryRef`], for the methods taking those.
+    #[must_use]
+    pub fn as_entry_ref(&self) -> UrlEntryRef<'_> {
+        self.into()
+    }
+}
+
+impl<'a> From<&'a UrlEntry> for UrlEntryRef<'a> {
+    fn from(entry: &'a UrlEntry) -> Self {
+        Self {
+            loc: entry.loc.as_str(),
+            lastmod: entry.lastmod,
+            changefreq: entry.changefreq,
+            priority: entry.priority,
+            extensions: &entry.extensions,
+            mobile: entry.mobile,
+        }
+    }
+}
+
+impl TryFrom<UrlEntryRef<'_>> for UrlEntry {
+    type Error = Error;
+
+    /// Parses the loc, fails with [`Error::InvalidUrl`] if it isn't a valid url.
+    fn try_from(entry: UrlEntryRef<'_>) -> Result<Self, Self::Error> {
+        let loc = entry.loc.parse().map_err(|source| Error::InvalidUrl {
+            value: entry.loc.to_owned(),
+            source,
+        })?;
+        Ok(Self {
+            loc,
+            lastmod: entry.lastmod,
+            changefreq: entry.changefreq,
+            priority: entry.priority,
+            extensions: entry.extensions.clone(),
+            mobile: entry.mobile,
+            source: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{regional::BaiduMobile, Sitemap, SortOrder};
+
+    #[test]
+    fn same_output_as_owned_entries() {
+        let mut extensions = Extensions::new();
+        extensions.push(BaiduMobile::Mobile);
+        let locs = ["https://a.com/b?x=1&y=2", "https://a.com/a"];
+        let refs = [
+            UrlEntryRef::new(locs[0])
+                .priority(0.5)
+                .extensions(&extensions),
+            UrlEntryRef::new(locs[1]).changefreq(ChangeFreq::Weekly),
+        ];
+        let owned: Vec<UrlEntry> = refs
+            .iter()
+            .map(|&entry| UrlEntry::try_from(entry).unwrap())
+            .collect();
+
+        let sitemap = Sitemap::new().sort(SortOrder::Loc);
+        assert_eq!(
+            sitemap.generate_refs(Vec::new(), &refs).unwrap(),
+            sitemap.generate_bytes(&owned)
+        );
+        assert!(UrlEntry::try_from(UrlEntryRef::new("/relative")).is_err());
+        for (entry, owned) in refs.iter().zip(&owned) {
+            assert_eq!(owned.as_entry_ref(), *entry);
+            assert_eq!(entry.into_owned().unwrap(), *owned);
+        }
+    }
+
+    #[test]
+    fn ascii_locs() {
+        let refs = [UrlEntryRef::new("https://bücher.de/straße?q=ö")];
+        let raw = Sitemap::new().generate_refs(Vec::new(), &refs).unwrap();
+        assert!(String::from_utf8(raw)
+            .unwrap()
+            .contains("https://bücher.de/straße"));
+
+        let sitemap = Sitemap::new().ascii_locs(true);
+        let xml = String::from_utf8(sitemap.generate_refs(Vec::new(), &refs).unwrap()).unwrap();
+        assert!(xml.contains("<loc>https://xn--bcher-kva.de/stra%C3%9Fe?q=%C3%B6</loc>"));
+
+        let invalid = sitemap.generate_refs(Vec::new(), &[UrlEntryRef::new("/é")]);
+        assert!(matches!(invalid, Err(Error::InvalidUrl { .. })));
+    }
+}
@@ -0,0 +1,176 @@
+use std::fmt::Display;
+
+/// The kind of resource limit that was exceeded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Limit {
+    /// Too many `<url>` entries.
+    Entries,
+    /// Elements nested too deeply.
+    Depth,
+    /// The text of an element is too long.
+    TextLength,
+    /// The sitemap is too large.
+    Size,
+    /// Sitemap indexes nested too deeply.
+    IndexDepth,
+    /// Too many sitemaps fetched from an index.
+    Sitemaps,
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match self {
+            Self::Entries => "number of entries",
+            Self::Depth => "element depth",
+            Self::TextLength => "text length",
+            Self::Size => "size in bytes",
+            Self::IndexDepth => "sitemap index depth",
+            Self::Sitemaps => "number of sitemaps",
+        };
+        f.write_str(what)
+    }
+}
+
+/// The errors returned by this crate.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading or writing the XML failed.
+    Xml(quick_xml::Error),
+    /// An I/O operation failed.
+    Io(std::io::Error),
+    /// A resource limit was exceeded while parsing, or a sitemap would exceed its
+    /// [`Limits`](crate::Limits).
+    LimitExceeded {
+        /// Which limit.
+        limit: Limit,
+        /// The configured maximum.
+        max: usize,
+    },
+    /// A loc is not a valid url.
+    InvalidUrl {
+        /// The text that failed to parse.
+        value: String,
+        /// Why it failed.
+        source: url::ParseError,
+    },
+    /// An element has a value that can't be parsed.
+    InvalidValue {
+        /// The element name.
+        element: &'static str,
+        /// The text that failed to parse.
+        value: String,
+    },
+    /// A required element is missing.
+    MissingElement(&'static str),
+    /// The document has an unexpected root element.
+    UnexpectedRoot(String),
+    /// The same loc appears more than once.
+    DuplicateLoc(url::Url),
+    /// The document has a `<!DOCTYPE>`, which the parser was configured to reject.
+    DoctypeNotAllowed,
+    /// The document is in a character encoding the parser can't decode.
+    UnsupportedEncoding(String),
+    /// Two extensions bind the same prefix to different namespaces.
+    NamespaceConflict {
+        /// The prefix.
+        prefix: String,
+    },
+    /// An extension uses a prefix that wasn't declared.
+    UndeclaredNamespace {
+        /// The prefix.
+        prefix: String,
+    },
+    /// The operation needs a cargo feature that isn't enabled.
+    FeatureDisabled(&'static str),
+    /// There are no entries and the sitemap was configured to reject an empty input.
+    EmptyInput,
+    /// The entries failed validation.
+    Invalid(crate::validate::ValidationReport),
+    /// An entry of a list failed.
+    Entry {
+        /// The index of the entry in the list.
+        index: usize,
+        /// Why it failed.
+        source: Box<Self>,
+    },
+    /// The source of the entries failed.
+    Source(Box<dyn std::error::Error + Send + Sync>),
+    /// A request to a remote host failed before a response was received.
+    Request {
+        /// The requested url.
+        url: url::Url,
+        /// Why it failed.
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Xml(e) => write!(f, "xml error: {e}"),
+            Self::Io(e) => write!(f, "io error: {e}"),
+            Self::LimitExceeded { limit, max } => {
+                write!(f, "limit exceeded: {limit} is over the maximum of {max}")
+            }
+            Self::InvalidUrl { value, source } => write!(f, "invalid url '{value}': {source}"),
+            Self::InvalidValue { element, value } => {
+                write!(f, "invalid value for <{element}>: '{value}'")
+            }
+            Self::MissingElement(name) => write!(f, "missing <{name}> element"),
+            Self::UnexpectedRoot(name) => write!(f, "unexpected root element <{name}>"),
+            Self::DuplicateLoc(loc) => write!(f, "duplicate loc {loc}"),
+            Self::DoctypeNotAllowed => f.write_str("document type declarations are not allowed"),
+            Self::UnsupportedEncoding(label) => write!(f, "unsupported encoding '{label}'"),
+            Self::NamespaceConflict { prefix } => {
+                write!(f, "the prefix '{prefix}' is bound to different namespaces")
+            }
+            Self::UndeclaredNamespace { prefix } => {
+                write!(f, "the prefix '{prefix}' is not declared")
+            }
+            Self::FeatureDisabled(feature) => {
+                write!(f, "the '{feature}' feature of sitewriter is not enabled")
+            }
+            Self::EmptyInput => f.write_str("there are no entries to write"),
+            Self::Invalid(report) => write!(f, "invalid entries:\n{report}"),
+            Self::Entry { index, source } => write!(f, "entry {index}: {source}"),
+            Self::Source(e) => write!(f, "reading the entries failed: {e}"),
+            Self::Request { url, source } => write!(f, "request to {url} failed: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Xml(e) => Some(e),
+            Self::Io(e) => Some(e),
+            Self::InvalidUrl { source, .. } => Some(source),
+            Self::Entry { source, .. } => Some(source.as_ref()),
+            Self::Source(e) => Some(e.as_ref()),
+            Self::Request { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(e: quick_xml::Error) -> Self {
+        Self::Xml(e)
+    }
+}
+
+impl From<derive_builder::UninitializedFieldError> for Error {
+    fn from(e: derive_builder::UninitializedFieldError) -> Self {
+        Self::MissingElement(e.field_name())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// A specialized `Result` type for this crate.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,247 @@
+//! Expanding sitemap indexes into the entries of their sitemaps.
+//!
+//! [`expand`] reads a sitemap index, then the sitemaps it lists, through a
+//! [`SitemapSource`], and yields the entries of every sitemap as one stream. Auditing the
+//! sitemaps published from a directory:
+//!
+//! ```rust,no_run
+//! use sitewriter::{
+//!     expand::{expand, DirSource},
+//!     fetch::FetchOptions,
+//! };
+//!
+//! let source = DirSource::new("public", "https://example.com/".parse()?);
+//! let root = "https://example.com/sitemap_index.xml".parse()?;
+//! for entry in expand(&source, &root, &FetchOptions::default()) {
+//!     println!("{}", entry?.loc);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! A closure taking the loc of a sitemap is a source too, for sitemaps kept in a
+//! database or an object store. [`fetch_index_recursive_with`] expands remote sitemaps.
+//!
+//! [`fetch_index_recursive_with`]: crate::fetch::fetch_index_recursive_with
+
+use std::{collections::HashSet, io, path::PathBuf, vec};
+
+use url::Url;
+
+use crate::{fetch::FetchOptions, parse::Document, Error, Limit, Result, UrlEntry};
+
+/// Reads the sitemaps listed by an index.
+pub trait SitemapSource {
+    /// The contents of the sitemap or sitemap index at `loc`, gzipped or not.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the document can't be read.
+    fn read(&self, loc: &Url) -> Result<Vec<u8>>;
+}
+
+impl<F: Fn(&Url) -> Result<Vec<u8>>> SitemapSource for F {
+    fn read(&self, loc: &Url) -> Result<Vec<u8>> {
+        self(loc)
+    }
+}
+
+/// Reads the sitemaps from the directory they are published from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DirSource {
+    dir: PathBuf,
+    base_url: Url,
+}
+
+impl DirSource {
+    /// Reads the sitemap at `{base_url}a/b.xml` from the file `{dir}/a/b.xml`.
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>, base_url: Url) -> Self {
+        Self {
+            dir: dir.into(),
+            base_url,
+        }
+    }
+}
+
+impl SitemapSource for DirSource {
+    fn read(&self, loc: &Url) -> Result<Vec<u8>> {
+        let path = loc
+            .as_str()
+            .strip_prefix(self.base_url.as_str())
+            .and_then(|relative| relative.split(['?', '#']).next())
+            .filter(|path| !path.split('/').any(|part| part == ".."))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{loc} is not under {}", self.base_url),
+                )
+            })?;
+        Ok(std::fs::read(self.dir.join(path))?)
+    }
+}
+
+/// Parses a document read from a source, decompressing it if it is gzipped.
+pub(crate) fn read_document(bytes: &[u8], options: &FetchOptions) -> Result<Document> {
+    if bytes.len() > options.max_size {
+        return Err(Error::LimitExceeded {
+            limit: Limit::Size,
+            max: options.max_size,
+        });
+    }
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        #[cfg(feature = "gzip")]
+        {
+            let bytes = crate::compress::gunzip(bytes, options.max_size)?;
+            return options.parser.parse_document(&bytes);
+        }
+        #[cfg(not(feature = "gzip"))]
+        return Err(Error::FeatureDisabled("gzip"));
+    }
+    options.parser.parse_document(bytes)
+}
+
+/// Reads the sitemap or sitemap index at `root` from `source`, following the sitemaps of
+/// indexes, and yields the entries of every sitemap in the order they are listed.
+///
+/// The sitemaps are read as the entries are consumed. A sitemap listed more than once, or
+/// by an index it leads to, is read once. The iterator stops after the first error:
+/// a document that fails to read or parse, or [`Error::LimitExceeded`] if indexes nest
+/// deeper than `max_depth` or more than `max_sitemaps` documents would be read.
+pub fn expand<'a, S: SitemapSource + ?Sized>(
+    source: &'a S,
+    root: &Url,
+    options: &'a FetchOptions,
+) -> Expand<'a, S> {
+    Expand {
+        source,
+        options,
+        pending: vec![(root.clone(), 0)],
+        visited: HashSet::new(),
+        entries: Vec::new().into_iter(),
+    }
+}
+
+/// The entries of the sitemaps of an index, see [`expand`].
+#[derive(Debug)]
+pub struct Expand<'a, S: ?Sized> {
+    source: &'a S,
+    options: &'a FetchOptions,
+    /// The sitemaps left to read with their depth, the next one last.
+    pending: Vec<(Url, usize)>,
+    visited: HashSet<Url>,
+    entries: vec::IntoIter<UrlEntry>,
+}
+
+impl<S: SitemapSource + ?Sized> Expand<'_, S> {
+    /// Reads the next sitemap, queueing the sitemaps of an index.
+    fn read_next(&mut self, loc: Url, depth: usize) -> Result<()> {
+        if !self.visited.insert(loc.clone()) {
+            return Ok(());
+        }
+        if self.visited.len() > self.options.max_sitemaps {
+            return Err(Error::LimitExceeded {
+                limit: Limit::Sitemaps,
+                max: self.options.max_sitemaps,
+            });
+        }
+        match read_document(&self.source.read(&loc)?, self.options)? {
+            Document::Urlset(urls) => self.entries = urls.into_iter(),
+            Document::Index(_) if depth >= self.options.max_depth => {
+                return Err(Error::LimitExceeded {
+                    limit: Limit::IndexDepth,
+                    max: self.options.max_depth,
+                });
+            }
+            Document::Index(sitemaps) => self.pending.extend(
+                sitemaps
+                    .into_iter()
+                    .rev()
+                    .map(|sitemap| (sitemap.loc, depth + 1)),
+            ),
+        }
+        Ok(())
+    }
+}
+
+impl<S: SitemapSource + ?Sized> Iterator for Expand<'_, S> {
+    type Item = Result<UrlEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.entries.next() {
+                return Some(Ok(entry));
+            }
+            let (loc, depth) = self.pending.pop()?;
+            if let Err(e) = self.read_next(loc, depth) {
+                self.pending.clear();
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn index(locs: &[&str]) -> Vec<u8> {
+        let sitemaps: String = locs
+            .iter()
+            .map(|loc| format!("<sitemap><loc>https://a.com/{loc}</loc></sitemap>"))
+            .collect();
+        format!("<sitemapindex>{sitemaps}</sitemapindex>").into_bytes()
+    }
+
+    #[test]
+    fn expands_lazily() {
+        let source = |loc: &Url| {
+            Ok(match loc.path() {
+                "/root.xml" => index(&["a.xml", "nested.xml", "broken.xml"]),
+                "/nested.xml" => index(&["b.xml", "a.xml"]),
+                "/broken.xml" => b"<urlset><url></url></urlset>".to_vec(),
+                path => format!("<urlset><url><loc>https://a.com{path}.html</loc></url></urlset>")
+                    .into_bytes(),
+            })
+        };
+        let root = "https://a.com/root.xml".parse().unwrap();
+        let options = FetchOptions::default();
+        let mut entries = expand(&source, &root, &options);
+        let locs: Vec<String> = entries
+            .by_ref()
+            .take(2)
+            .map(|entry| entry.unwrap().loc.into())
+            .collect();
+        assert_eq!(
+            locs,
+            ["https://a.com/a.xml.html", "https://a.com/b.xml.html"]
+        );
+        assert!(matches!(
+            entries.next(),
+            Some(Err(Error::MissingElement("loc")))
+        ));
+        assert!(entries.next().is_none());
+    }
+
+    #[test]
+    fn reads_from_dir() {
+        let dir = TempDir::new("expand");
+        std::fs::create_dir_all(dir.join("maps")).unwrap();
+        std::fs::write(dir.join("index.xml"), index(&["maps/a.xml?v=2"])).unwrap();
+        std::fs::write(
+            dir.join("maps/a.xml"),
+            "<urlset><url><loc>https://a.com/</loc></url></urlset>",
+        )
+        .unwrap();
+
+        let source = DirSource::new(&dir, "https://a.com/".parse().unwrap());
+        let root = "https://a.com/index.xml".parse().unwrap();
+        let entries: Vec<UrlEntry> = expand(&source, &root, &FetchOptions::default())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(entries[0].loc.as_str(), "https://a.com/");
+
+        let outside = "https://b.com/index.xml".parse().unwrap();
+        assert!(matches!(source.read(&outside), Err(Error::Io(_))));
+    }
+}
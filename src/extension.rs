@@ -0,0 +1,367 @@
+//! Custom child elements of `<url>`, for sitemap extensions the crate doesn't support.
+//!
+//! An extension writes its elements with an [`ExtensionWriter`] and names the namespace it
+//! uses, which is declared on the `<urlset>` element:
+//!
+//! ```rust
+//! use sitewriter::{ExtensionWriter, SitemapExtension, UrlEntry};
+//!
+//! #[derive(Debug)]
+//! struct Mobile;
+//!
+//! impl SitemapExtension for Mobile {
+//!     fn prefix(&self) -> &str {
+//!         "mobile"
+//!     }
+//!
+//!     fn namespace(&self) -> &str {
+//!         "http://www.google.com/schemas/sitemap-mobile/1.0"
+//!     }
+//!
+//!     fn write(&self, writer: &mut ExtensionWriter<'_>) -> sitewriter::Result<()> {
+//!         writer.empty("mobile:mobile", &[])
+//!     }
+//! }
+//!
+//! let mut entry = UrlEntry::new("https://example.com/".parse()?, None, None, None);
+//! entry.extensions.push(Mobile);
+//!
+//! let xml = sitewriter::generate_str(&[entry]);
+//! assert!(xml.contains(r#"xmlns:mobile="http://www.google.com/schemas/sitemap-mobile/1.0""#));
+//! assert!(xml.contains("<mobile:mobile/>"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{cmp::Ordering, fmt::Debug, io::Cursor, sync::Arc};
+
+use quick_xml::{
+    events::{BytesEnd, BytesStart, BytesText, Event},
+    Writer,
+};
+
+use crate::{Error, Result};
+
+/// A custom extension of a `<url>` entry.
+pub trait SitemapExtension: Debug + Send + Sync {
+    /// The prefix of the elements, such as `image`.
+    fn prefix(&self) -> &str;
+
+    /// The namespace uri the prefix stands for.
+    fn namespace(&self) -> &str;
+
+    /// Writes the elements inside `<url>`, after the standard ones.
+    ///
+    /// # Errors
+    ///
+    /// Should only forward the errors of the writer.
+    fn write(&self, writer: &mut ExtensionWriter<'_>) -> Result<()>;
+}
+
+/// Writes XML elements for a [`SitemapExtension`].
+///
+/// Element names are written as given, they should include the prefix of the extension.
+/// Text and attribute values are escaped.
+pub struct ExtensionWriter<'a> {
+    sink: &'a mut dyn EventSink,
+}
+
+impl Debug for ExtensionWriter<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtensionWriter").finish_non_exhaustive()
+    }
+}
+
+impl<'a> ExtensionWriter<'a> {
+    pub(crate) fn new<W: std::io::Write>(writer: &'a mut Writer<W>) -> Self {
+        Self { sink: writer }
+    }
+
+    /// Opens an element.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if writing fails.
+    pub fn start(&mut self, name: &str, attributes: &[(&str, &str)]) -> Result<()> {
+        self.sink.event(Event::Start(element(name, attributes)))
+    }
+
+    /// Closes an element.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if writing fails.
+    pub fn end(&mut self, name: &str) -> Result<()> {
+        self.sink.event(Event::End(BytesEnd::new(name)))
+    }
+
+    /// Writes an element without content.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if writing fails.
+    pub fn empty(&mut self, name: &str, attributes: &[(&str, &str)]) -> Result<()> {
+        self.sink.event(Event::Empty(element(name, attributes)))
+    }
+
+    /// Writes text inside the current element.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if writing fails.
+    pub fn text(&mut self, text: &str) -> Result<()> {
+        self.sink.event(Event::Text(BytesText::new(text)))
+    }
+
+    /// Writes an element containing only text.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if writing fails.
+    pub fn text_element(&mut self, name: &str, text: &str) -> Result<()> {
+        self.start(name, &[])?;
+        self.text(text)?;
+        self.end(name)
+    }
+}
+
+fn element<'a>(name: &'a str, attributes: &[(&str, &str)]) -> BytesStart<'a> {
+    let mut start = BytesStart::new(name);
+    for &attribute in attributes {
+        start.push_attribute(attribute);
+    }
+    start
+}
+
+/// Type erasure of the writer, so extensions don't depend on its type.
+trait EventSink {
+    fn event(&mut self, event: Event<'_>) -> Result<()>;
+}
+
+impl<W: std::io::Write> EventSink for Writer<W> {
+    fn event(&mut self, event: Event<'_>) -> Result<()> {
+        self.write_event(event)?;
+        Ok(())
+    }
+}
+
+/// An extension read from an existing sitemap, kept as the XML it was written with.
+///
+/// [`Parser::keep_extensions`](crate::parse::Parser::keep_extensions) attaches one to the
+/// entry for each child the crate doesn't understand, so a sitemap that is parsed, edited
+/// and generated again keeps them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawExtension {
+    prefix: String,
+    namespace: String,
+    events: Vec<Event<'static>>,
+}
+
+impl RawExtension {
+    pub(crate) const fn new(prefix: String, namespace: String) -> Self {
+        Self {
+            prefix,
+            namespace,
+            events: Vec::new(),
+        }
+    }
+
+    /// Parses `xml`, the elements written by an extension.
+    #[cfg(feature = "store")]
+    pub(crate) fn from_xml(prefix: String, namespace: String, xml: &str) -> Result<Self> {
+        let mut raw = Self::new(prefix, namespace);
+        let mut reader = quick_xml::Reader::from_str(xml);
+        loop {
+            match reader.read_event()? {
+                Event::Eof => return Ok(raw),
+                event => raw.push(event.into_owned()),
+            }
+        }
+    }
+
+    /// Adds an event of the element, text being kept escaped.
+    pub(crate) fn push(&mut self, event: Event<'static>) {
+        self.events.push(event);
+    }
+
+    /// The XML of the element, without indentation.
+    #[must_use]
+    pub fn xml(&self) -> String {
+        let mut writer = Writer::new(Vec::new());
+        for event in &self.events {
+            // Writing to a Vec doesn't fail.
+            let _ = writer.write_event(event.clone());
+        }
+        String::from_utf8_lossy(&writer.into_inner()).into_owned()
+    }
+}
+
+impl SitemapExtension for RawExtension {
+    fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn write(&self, writer: &mut ExtensionWriter<'_>) -> Result<()> {
+        for event in &self.events {
+            writer.sink.event(event.clone())?;
+        }
+        Ok(())
+    }
+}
+
+/// The XML written by `extension`, without indentation.
+pub(crate) fn to_xml(extension: &dyn SitemapExtension) -> Result<String> {
+    let mut writer = Writer::new(Vec::new());
+    extension.write(&mut ExtensionWriter::new(&mut writer))?;
+    Ok(String::from_utf8_lossy(&writer.into_inner()).into_owned())
+}
+
+/// The extensions of an entry.
+///
+/// Two sets of extensions are equal when they write the same XML.
+#[derive(Debug, Clone, Default)]
+pub struct Extensions(Vec<Arc<dyn SitemapExtension>>);
+
+impl Extensions {
+    /// Creates an empty set of extensions.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Adds an extension.
+    pub fn push(&mut self, extension: impl SitemapExtension + 'static) {
+        self.0.push(Arc::new(extension));
+    }
+
+    /// Whether there are no extensions.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The number of extensions.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Iterates over the extensions.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn SitemapExtension> {
+        self.0.iter().map(AsRef::as_ref)
+    }
+
+    /// The XML written by the extensions, used to compare them.
+    fn render(&self) -> Vec<Vec<u8>> {
+        self.iter()
+            .map(|extension| {
+                let mut writer = Writer::new(Cursor::new(Vec::new()));
+                let _ = extension.write(&mut ExtensionWriter::new(&mut writer));
+                let mut xml = writer.into_inner().into_inner();
+                xml.extend_from_slice(extension.namespace().as_bytes());
+                xml
+            })
+            .collect()
+    }
+}
+
+impl PartialEq for Extensions {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && (self.is_empty() || self.render() == other.render())
+    }
+}
+
+impl PartialOrd for Extensions {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.is_empty() && other.is_empty() {
+            return Some(Ordering::Equal);
+        }
+        Some(self.render().cmp(&other.render()))
+    }
+}
+
+/// The namespaces used by the extensions of `entries`, sorted by prefix.
+///
+/// Fails if a prefix is bound to two different namespaces.
+pub(crate) fn namespaces<'a, I>(entries: I) -> Result<Vec<(String, String)>>
+where
+    I: IntoIterator<Item = &'a Extensions>,
+{
+    let mut namespaces: Vec<(String, String)> = Vec::new();
+    for extension in entries.into_iter().flat_map(Extensions::iter) {
+        declare(&mut namespaces, extension.prefix(), extension.namespace())?;
+    }
+    Ok(namespaces)
+}
+
+/// Adds `prefix` to the sorted `namespaces`, unless it is already bound to `namespace`.
+pub(crate) fn declare(
+    namespaces: &mut Vec<(String, String)>,
+    prefix: &str,
+    namespace: &str,
+) -> Result<()> {
+    match namespaces.binary_search_by(|(p, _)| p.as_str().cmp(prefix)) {
+        Ok(i) if namespaces[i].1 == namespace => Ok(()),
+        Ok(_) => Err(Error::NamespaceConflict {
+            prefix: prefix.to_owned(),
+        }),
+        Err(i) => {
+            namespaces.insert(i, (prefix.to_owned(), namespace.to_owned()));
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UrlEntry;
+
+    #[derive(Debug)]
+    struct Tag(&'static str, &'static str);
+
+    impl SitemapExtension for Tag {
+        fn prefix(&self) -> &str {
+            "tag"
+        }
+
+        fn namespace(&self) -> &str {
+            self.0
+        }
+
+        fn write(&self, writer: &mut ExtensionWriter<'_>) -> Result<()> {
+            writer.start("tag:tag", &[("kind", "a&b")])?;
+            writer.text_element("tag:name", self.1)?;
+            writer.end("tag:tag")
+        }
+    }
+
+    fn entry(tag: Tag) -> UrlEntry {
+        let mut entry = UrlEntry::new("https://a.com/".parse().unwrap(), None, None, None);
+        entry.extensions.push(tag);
+        entry
+    }
+
+    #[test]
+    fn writes_and_declares() {
+        let xml = crate::generate_str(&[entry(Tag("urn:tag", "<x>")), entry(Tag("urn:tag", "y"))]);
+        assert!(xml.contains(
+            r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9" xmlns:tag="urn:tag">"#
+        ));
+        assert!(xml.contains(
+            "<tag:tag kind=\"a&amp;b\">\n            <tag:name>&lt;x&gt;</tag:name>\n        </tag:tag>"
+        ));
+        assert_eq!(entry(Tag("urn:tag", "y")), entry(Tag("urn:tag", "y")));
+        assert_ne!(entry(Tag("urn:tag", "y")), entry(Tag("urn:tag", "z")));
+    }
+
+    #[test]
+    fn rejects_conflicting_prefixes() {
+        let urls = [entry(Tag("urn:a", "x")), entry(Tag("urn:b", "x"))];
+        let result = crate::generate(Vec::new(), &urls);
+        assert!(matches!(result, Err(Error::NamespaceConflict { prefix }) if prefix == "tag"));
+    }
+}
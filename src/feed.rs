@@ -0,0 +1,276 @@
+//! RSS 2.0 and Atom feeds of the entries.
+//!
+//! Search engines accept feeds as sitemaps, so the same entries can also be published as
+//! a `feed.xml`. The `lastmod` of each entry becomes its `pubDate` or `updated`.
+//!
+//! ```rust
+//! use sitewriter::{feed::Feed, UrlEntry};
+//!
+//! let urls = vec![UrlEntry::new("https://example.com/blog/post".parse()?, None, None, None)];
+//! let feed = Feed::new("Example blog", "https://example.com/blog".parse()?);
+//! println!("{}", feed.generate_rss_str(&urls));
+//! println!("{}", feed.generate_atom_str(&urls));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::io::Cursor;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use quick_xml::{
+    events::{BytesDecl, BytesEnd, BytesStart, Event},
+    Writer,
+};
+use url::Url;
+
+use crate::{loc_str, write_tag, Result, UrlEntry};
+
+/// The channel information of a feed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Feed {
+    title: String,
+    link: Url,
+    description: String,
+    author: Option<String>,
+    updated: Option<DateTime<Utc>>,
+}
+
+impl Feed {
+    /// Creates a feed with the given title, linking to the given page.
+    #[must_use]
+    pub fn new(title: impl Into<String>, link: Url) -> Self {
+        Self {
+            title: title.into(),
+            link,
+            description: String::new(),
+            author: None,
+            updated: None,
+        }
+    }
+
+    /// Sets the RSS channel description, empty by default.
+    #[must_use]
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Sets the Atom feed author, the title by default.
+    #[must_use]
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    /// Sets when the feed was updated, by default the most recent `lastmod`, or now if no
+    /// entry has one.
+    #[must_use]
+    pub const fn updated(mut self, updated: DateTime<Utc>) -> Self {
+        self.updated = Some(updated);
+        self
+    }
+
+    fn updated_for(&self, urls: &[UrlEntry]) -> DateTime<Utc> {
+        self.updated
+            .or_else(|| urls.iter().filter_map(|u| u.lastmod).max())
+            .unwrap_or_else(Utc::now)
+    }
+
+    /// Generates the RSS 2.0 feed and saves it using the provided writer.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if it fails to write to the writer.
+    pub fn generate_rss<T>(&self, inner_writer: T, urls: &[UrlEntry]) -> Result<T>
+    where
+        T: std::io::Write,
+    {
+        let mut writer = Writer::new_with_indent(inner_writer, b' ', 4);
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+
+        let mut rss = BytesStart::new("rss");
+        rss.push_attribute(("version", "2.0"));
+        writer.write_event(Event::Start(rss))?;
+        writer.write_event(Event::Start(BytesStart::new("channel")))?;
+
+        write_tag(&mut writer, "title", &self.title)?;
+        write_tag(&mut writer, "link", self.link.as_str())?;
+        write_tag(&mut writer, "description", &self.description)?;
+        write_tag(
+            &mut writer,
+            "lastBuildDate",
+            &self.updated_for(urls).to_rfc2822(),
+        )?;
+
+        for entry in urls {
+            writer.write_event(Event::Start(BytesStart::new("item")))?;
+            let loc = loc_str(&entry.loc);
+            write_tag(&mut writer, "link", &loc)?;
+            write_tag(&mut writer, "guid", &loc)?;
+            if let Some(lastmod) = &entry.lastmod {
+                write_tag(&mut writer, "pubDate", &lastmod.to_rfc2822())?;
+            }
+            writer.write_event(Event::End(BytesEnd::new("item")))?;
+        }
+
+        writer.write_event(Event::End(BytesEnd::new("channel")))?;
+        writer.write_event(Event::End(BytesEnd::new("rss")))?;
+
+        Ok(writer.into_inner())
+    }
+
+    /// Generates the Atom feed and saves it using the provided writer.
+    ///
+    /// Atom requires a title and an update time for every entry, the loc is used as the
+    /// title and the feed update time stands in for a missing `lastmod`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if it fails to write to the writer.
+    pub fn generate_atom<T>(&self, inner_writer: T, urls: &[UrlEntry]) -> Result<T>
+    where
+        T: std::io::Write,
+    {
+        let updated = self.updated_for(urls);
+        let format = |date: &DateTime<Utc>| date.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        let mut writer = Writer::new_with_indent(inner_writer, b' ', 4);
+        writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+
+        let mut feed = BytesStart::new("feed");
+        feed.push_attribute(("xmlns", "http://www.w3.org/2005/Atom"));
+        writer.write_event(Event::Start(feed))?;
+
+        write_tag(&mut writer, "id", self.link.as_str())?;
+        write_tag(&mut writer, "title", &self.title)?;
+        write_link(&mut writer, &self.link)?;
+        write_tag(&mut writer, "updated", &format(&updated))?;
+        writer.write_event(Event::Start(BytesStart::new("author")))?;
+        write_tag(
+            &mut writer,
+            "name",
+            self.author.as_deref().unwrap_or(&self.title),
+        )?;
+        writer.write_event(Event::End(BytesEnd::new("author")))?;
+
+        for entry in urls {
+            writer.write_event(Event::Start(BytesStart::new("entry")))?;
+            let loc = loc_str(&entry.loc);
+            write_tag(&mut writer, "id", &loc)?;
+            write_tag(&mut writer, "title", &loc)?;
+            write_link(&mut writer, &entry.loc)?;
+            write_tag(
+                &mut writer,
+                "updated",
+                &format(entry.lastmod.as_ref().unwrap_or(&updated)),
+            )?;
+            writer.write_event(Event::End(BytesEnd::new("entry")))?;
+        }
+
+        writer.write_event(Event::End(BytesEnd::new("feed")))?;
+
+        Ok(writer.into_inner())
+    }
+
+    /// Generates the RSS 2.0 feed returning a string.
+    #[must_use]
+    pub fn generate_rss_str(&self, urls: &[UrlEntry]) -> String {
+        let result = self.generate_rss(Cursor::new(Vec::new()), urls).expect(
+            "it should never error, please report this bug to https://github.com/edg-l/sitewriter/issues",
+        );
+        String::from_utf8(result.into_inner()).expect("to be valid utf8")
+    }
+
+    /// Generates the Atom feed returning a string.
+    #[must_use]
+    pub fn generate_atom_str(&self, urls: &[UrlEntry]) -> String {
+        let result = self.generate_atom(Cursor::new(Vec::new()), urls).expect(
+            "it should never error, please report this bug to https://github.com/edg-l/sitewriter/issues",
+        );
+        String::from_utf8(result.into_inner()).expect("to be valid utf8")
+    }
+}
+
+fn write_link<T>(writer: &mut Writer<T>, href: &Url) -> Result<()>
+where
+    T: std::io::Write,
+{
+    let mut link = BytesStart::new("link");
+    link.push_attribute(("href", &*loc_str(href)));
+    writer.write_event(Event::Empty(link))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn urls() -> Vec<UrlEntry> {
+        vec![
+            UrlEntry::new(
+                "https://a.com/post?a=1&b=2".parse().unwrap(),
+                Some(Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap()),
+                None,
+                None,
+            ),
+            UrlEntry::new("https://a.com/draft".parse().unwrap(), None, None, None),
+        ]
+    }
+
+    #[test]
+    fn rss() {
+        let feed = Feed::new("A & B", "https://a.com/".parse().unwrap());
+        assert_eq!(
+            feed.generate_rss_str(&urls()),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+    <channel>
+        <title>A &amp; B</title>
+        <link>https://a.com/</link>
+        <description></description>
+        <lastBuildDate>Wed, 1 May 2024 08:00:00 +0000</lastBuildDate>
+        <item>
+            <link>https://a.com/post?a=1&amp;b=2</link>
+            <guid>https://a.com/post?a=1&amp;b=2</guid>
+            <pubDate>Wed, 1 May 2024 08:00:00 +0000</pubDate>
+        </item>
+        <item>
+            <link>https://a.com/draft</link>
+            <guid>https://a.com/draft</guid>
+        </item>
+    </channel>
+</rss>"#
+        );
+    }
+
+    #[test]
+    fn atom() {
+        let feed = Feed::new("Blog", "https://a.com/".parse().unwrap()).author("Edgar");
+        assert_eq!(
+            feed.generate_atom_str(&urls()),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+    <id>https://a.com/</id>
+    <title>Blog</title>
+    <link href="https://a.com/"/>
+    <updated>2024-05-01T08:00:00Z</updated>
+    <author>
+        <name>Edgar</name>
+    </author>
+    <entry>
+        <id>https://a.com/post?a=1&amp;b=2</id>
+        <title>https://a.com/post?a=1&amp;b=2</title>
+        <link href="https://a.com/post?a=1&amp;b=2"/>
+        <updated>2024-05-01T08:00:00Z</updated>
+    </entry>
+    <entry>
+        <id>https://a.com/draft</id>
+        <title>https://a.com/draft</title>
+        <link href="https://a.com/draft"/>
+        <updated>2024-05-01T08:00:00Z</updated>
+    </entry>
+</feed>"#
+        );
+    }
+}
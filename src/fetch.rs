@@ -0,0 +1,240 @@
+//! Downloading and parsing remote sitemaps.
+//!
+//! [`fetch_sitemap_with`] downloads one sitemap or sitemap index, gzipped or not, and
+//! [`fetch_index_recursive_with`] follows the sitemaps of indexes down to their entries.
+//! The documents are third-party input, so [`FetchOptions`] bounds their size, how deeply
+//! indexes nest and how many sitemaps are fetched.
+//!
+//! ```rust
+//! use sitewriter::{client::HttpResponse, fetch::{fetch_index_recursive_with, FetchOptions}, Url};
+//!
+//! let client = |url: &Url| {
+//!     Ok(HttpResponse::ok(match url.path() {
+//!         "/sitemap.xml" => {
+//!             "<sitemapindex><sitemap><loc>https://example.com/posts.xml</loc></sitemap></sitemapindex>"
+//!         }
+//!         _ => "<urlset><url><loc>https://example.com/posts/1</loc></url></urlset>",
+//!     }))
+//! };
+//! let urls = fetch_index_recursive_with(
+//!     &client,
+//!     &"https://example.com/sitemap.xml".parse()?,
+//!     &FetchOptions::default(),
+//! )?;
+//! assert_eq!(urls[0].loc.as_str(), "https://example.com/posts/1");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use url::Url;
+
+use crate::{
+    client::HttpClient,
+    expand::{expand, read_document},
+    parse::{Document, Parser},
+    Error, Result, UrlEntry,
+};
+
+/// The limits of fetching remote sitemaps, or of [expanding](crate::expand) indexes.
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    /// The maximum size in bytes of a document, after decompression.
+    pub max_size: usize,
+    /// How many levels of sitemap indexes are followed below the first document.
+    pub max_depth: usize,
+    /// The maximum number of documents fetched by [`fetch_index_recursive_with`], or read
+    /// by [`expand`].
+    pub max_sitemaps: usize,
+    /// The parser of the documents, with its own limits.
+    pub parser: Parser,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            max_size: 64 * 1024 * 1024,
+            max_depth: 2,
+            max_sitemaps: 1000,
+            parser: Parser::new(),
+        }
+    }
+}
+
+/// Fetches and parses the sitemap or sitemap index at `url` with the default options.
+///
+/// See [`fetch_sitemap_with`].
+///
+/// # Errors
+///
+/// Will return `Err` as [`fetch_sitemap_with`].
+#[cfg(feature = "fetch")]
+pub fn fetch_sitemap(url: &Url) -> Result<Document> {
+    fetch_sitemap_with(
+        &crate::client::UreqClient::default(),
+        url,
+        &FetchOptions::default(),
+    )
+}
+
+/// Fetches the sitemap at `url` and the sitemaps of the indexes it leads to, with the
+/// default options.
+///
+/// See [`fetch_index_recursive_with`].
+///
+/// # Errors
+///
+/// Will return `Err` as [`fetch_index_recursive_with`].
+#[cfg(feature = "fetch")]
+pub fn fetch_index_recursive(url: &Url) -> Result<Vec<UrlEntry>> {
+    fetch_index_recursive_with(
+        &crate::client::UreqClient::default(),
+        url,
+        &FetchOptions::default(),
+    )
+}
+
+/// Fetches and parses the sitemap or sitemap index at `url`.
+///
+/// Gzipped documents are recognized by their magic bytes and decompressed, which needs the
+/// `gzip` feature.
+///
+/// # Errors
+///
+/// Will return [`Error::Request`] if no response was received or its status isn't a
+/// success, [`Error::LimitExceeded`] if the document is over `max_size`,
+/// [`Error::FeatureDisabled`] for a gzipped document without the `gzip` feature, or `Err`
+/// if the document doesn't parse.
+pub fn fetch_sitemap_with<C: HttpClient>(
+    client: &C,
+    url: &Url,
+    options: &FetchOptions,
+) -> Result<Document> {
+    read_document(&fetch_body(client, url)?, options)
+}
+
+/// The body of the successful response to a GET of `url`.
+fn fetch_body<C: HttpClient>(client: &C, url: &Url) -> Result<Vec<u8>> {
+    let response = client.get(url)?;
+    if !response.is_success() {
+        return Err(Error::Request {
+            url: url.clone(),
+            source: format!("unexpected status {}", response.status).into(),
+        });
+    }
+    Ok(response.body)
+}
+
+/// Fetches the sitemap at `url`, following the sitemaps of indexes, and returns the
+/// entries of every sitemap in the order they are listed, see [`expand`].
+///
+/// A sitemap listed more than once, or by an index it leads to, is fetched once.
+///
+/// # Errors
+///
+/// Will return `Err` as [`fetch_sitemap_with`] for any of the documents, or
+/// [`Error::LimitExceeded`] if indexes nest deeper than `max_depth` or more than
+/// `max_sitemaps` documents would be fetched.
+pub fn fetch_index_recursive_with<C: HttpClient>(
+    client: &C,
+    url: &Url,
+    options: &FetchOptions,
+) -> Result<Vec<UrlEntry>> {
+    expand(&|url: &Url| fetch_body(client, url), url, options).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::HttpResponse, Limit};
+
+    fn index(locs: &[&str]) -> String {
+        let sitemaps: String = locs
+            .iter()
+            .map(|loc| format!("<sitemap><loc>https://a.com/{loc}</loc></sitemap>"))
+            .collect();
+        format!("<sitemapindex>{sitemaps}</sitemapindex>")
+    }
+
+    #[test]
+    fn follows_indexes() {
+        let client = |url: &Url| {
+            Ok(match url.path() {
+                "/root.xml" => HttpResponse::ok(index(&["nested.xml", "missing.xml"])),
+                "/nested.xml" => HttpResponse::ok(index(&["a.xml"])),
+                "/missing.xml" => HttpResponse::status(404),
+                path => HttpResponse::ok(format!(
+                    "<urlset><url><loc>https://a.com{path}.html</loc></url></urlset>"
+                )),
+            })
+        };
+        let root = "https://a.com/root.xml".parse().unwrap();
+
+        let mut options = FetchOptions::default();
+        assert!(matches!(
+            fetch_index_recursive_with(&client, &root, &options),
+            Err(Error::Request { .. })
+        ));
+
+        options.max_depth = 0;
+        assert!(matches!(
+            fetch_index_recursive_with(&client, &root, &options),
+            Err(Error::LimitExceeded {
+                limit: Limit::IndexDepth,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn collects_entries_once() {
+        let client = |url: &Url| {
+            Ok(match url.path() {
+                "/root.xml" => HttpResponse::ok(index(&["nested.xml", "a.xml"])),
+                "/nested.xml" => HttpResponse::ok(index(&["root.xml", "b.xml", "a.xml"])),
+                path => HttpResponse::ok(format!(
+                    "<urlset><url><loc>https://a.com{path}.html</loc></url></urlset>"
+                )),
+            })
+        };
+        let root = "https://a.com/root.xml".parse().unwrap();
+
+        let mut options = FetchOptions::default();
+        let urls = fetch_index_recursive_with(&client, &root, &options).unwrap();
+        let locs: Vec<&str> = urls.iter().map(|u| u.loc.as_str()).collect();
+        assert_eq!(
+            locs,
+            ["https://a.com/b.xml.html", "https://a.com/a.xml.html"]
+        );
+
+        options.max_sitemaps = 3;
+        assert!(matches!(
+            fetch_index_recursive_with(&client, &root, &options),
+            Err(Error::LimitExceeded {
+                limit: Limit::Sitemaps,
+                ..
+            })
+        ));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn decompresses_gzip() {
+        let xml = "<urlset><url><loc>https://a.com/</loc></url></urlset>";
+        let client = |_: &Url| Ok(HttpResponse::ok(crate::compress::gzip(xml.as_bytes())));
+        let url = "https://a.com/sitemap.xml.gz".parse().unwrap();
+
+        let mut options = FetchOptions::default();
+        assert!(matches!(
+            fetch_sitemap_with(&client, &url, &options),
+            Ok(Document::Urlset(urls)) if urls.len() == 1
+        ));
+
+        options.max_size = 10;
+        assert!(matches!(
+            fetch_sitemap_with(&client, &url, &options),
+            Err(Error::LimitExceeded {
+                limit: Limit::Size,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,177 @@
+//! A C interface, so backends in other languages, such as PHP or Ruby extensions, can use
+//! the generator.
+//!
+//! Build the shared library with
+//! `cargo rustc --release --features ffi --crate-type cdylib` and include
+//! `include/sitewriter.h`, generated from this module with
+//! `cbindgen --config cbindgen.toml --output include/sitewriter.h`.
+//!
+//! The entries are passed as a JSON array, in the [serde representation](crate::profile)
+//! of [`UrlEntry`]:
+//!
+//! ```c
+//! SitewriterBuffer xml;
+//! if (sitewriter_generate("[{\"loc\": \"https://example.com/\"}]", &xml) != 0) {
+//!     fprintf(stderr, "%s\n", sitewriter_last_error());
+//!     return 1;
+//! }
+//! fwrite(xml.data, 1, xml.len, stdout);
+//! sitewriter_buffer_free(&xml);
+//! ```
+//!
+//! A failing function returns a non-zero status, and [`sitewriter_last_error`] describes
+//! the failure.
+
+#![allow(unsafe_code)]
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, CStr, CString},
+    panic, ptr,
+};
+
+use crate::{Sitemap, UrlEntry};
+
+/// Bytes allocated by the library, released with [`sitewriter_buffer_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct SitewriterBuffer {
+    /// The bytes, null when the buffer is empty.
+    pub data: *mut u8,
+    /// The number of bytes.
+    pub len: usize,
+}
+
+impl SitewriterBuffer {
+    const EMPTY: Self = Self {
+        data: ptr::null_mut(),
+        len: 0,
+    };
+
+    fn new(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()).cast::<u8>();
+        Self { data, len }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Keeps `message` for [`sitewriter_last_error`], returning the failure status.
+fn fail(message: impl ToString) -> c_int {
+    let message = CString::new(message.to_string().replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(message));
+    -1
+}
+
+/// Generates the sitemap of the entries.
+fn generate(entries_json: &[u8]) -> Result<Vec<u8>, String> {
+    let urls: Vec<UrlEntry> =
+        serde_json::from_slice(entries_json).map_err(|e| format!("invalid entries: {e}"))?;
+    Sitemap::new()
+        .generate(Vec::new(), &urls)
+        .map_err(|e| e.to_string())
+}
+
+/// Generates the sitemap of `entries_json`, a JSON array of entries.
+///
+/// Returns 0 with the XML stored in `out`, to release with [`sitewriter_buffer_free`].
+/// Returns -1 on failure, with `out` left empty.
+///
+/// # Safety
+///
+/// `entries_json` must be null or a NUL terminated string, and `out` must be null or
+/// point to a [`SitewriterBuffer`] that can be written.
+#[no_mangle]
+pub unsafe extern "C" fn sitewriter_generate(
+    entries_json: *const c_char,
+    out: *mut SitewriterBuffer,
+) -> c_int {
+    if out.is_null() {
+        return fail("out is null");
+    }
+    out.write(SitewriterBuffer::EMPTY);
+    if entries_json.is_null() {
+        return fail("entries_json is null");
+    }
+
+    let json = CStr::from_ptr(entries_json).to_bytes();
+    // Unwinding into C is undefined behavior.
+    match panic::catch_unwind(|| generate(json)) {
+        Ok(Ok(xml)) => {
+            out.write(SitewriterBuffer::new(xml));
+            0
+        }
+        Ok(Err(message)) => fail(message),
+        Err(_) => fail("the generation panicked"),
+    }
+}
+
+/// Releases the bytes of `buffer` and empties it. Releasing an empty buffer does nothing.
+///
+/// # Safety
+///
+/// `buffer` must be null or point to a buffer filled by this library, or emptied by this
+/// function.
+#[no_mangle]
+pub unsafe extern "C" fn sitewriter_buffer_free(buffer: *mut SitewriterBuffer) {
+    if buffer.is_null() {
+        return;
+    }
+    let SitewriterBuffer { data, len } = buffer.replace(SitewriterBuffer::EMPTY);
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
+
+/// The message of the last failure on the calling thread, or null if nothing failed.
+///
+/// The string is valid until the next failure on the same thread.
+#[no_mangle]
+pub extern "C" fn sitewriter_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_through_the_c_interface() {
+        let mut xml = SitewriterBuffer::EMPTY;
+        let json = c"[{\"loc\": \"https://example.com/\", \"priority\": 0.5}]";
+        let status = unsafe { sitewriter_generate(json.as_ptr(), &mut xml) };
+        assert_eq!(status, 0);
+        let bytes = unsafe { std::slice::from_raw_parts(xml.data, xml.len) };
+        assert!(std::str::from_utf8(bytes)
+            .unwrap()
+            .contains("<loc>https://example.com/</loc>"));
+        unsafe { sitewriter_buffer_free(&mut xml) };
+        assert!(xml.data.is_null());
+
+        let status = unsafe { sitewriter_generate(c"{".as_ptr(), &mut xml) };
+        assert_eq!(status, -1);
+        let error = unsafe { CStr::from_ptr(sitewriter_last_error()) };
+        assert!(error.to_str().unwrap().starts_with("invalid entries"));
+    }
+
+    #[test]
+    fn header_declares_every_function() {
+        let header = include_str!("../include/sitewriter.h");
+        for name in [
+            "sitewriter_generate",
+            "sitewriter_buffer_free",
+            "sitewriter_last_error",
+            "SitewriterBuffer",
+        ] {
+            assert!(header.contains(name), "{name} is missing from the header");
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Keeping sections of a site out of its sitemaps.
+//!
+//! The filters given to [`Sitemap::include`](crate::Sitemap::include) and
+//! [`Sitemap::exclude`](crate::Sitemap::exclude) decide which entries are written, so
+//! `noindex` sections are left out wherever the sitemap is generated:
+//!
+//! ```rust
+//! use sitewriter::{filter::UrlFilter, Sitemap, UrlEntry};
+//!
+//! let sitemap = Sitemap::new()
+//!     .exclude("/admin/**")
+//!     .exclude("/search?*")
+//!     .exclude(UrlFilter::predicate(|entry| entry.priority == Some(0.0)));
+//! let urls = ["/", "/admin/users/1", "/search?q=shoes"].map(|path| {
+//!     let loc = format!("https://example.com{path}").parse().unwrap();
+//!     UrlEntry::new(loc, None, None, None)
+//! });
+//! assert_eq!(sitemap.generate_txt(&urls), "https://example.com/\n");
+//! ```
+//!
+//! Glob patterns and regexes are matched against the path of the loc, followed by `?` and
+//! the query when it has one. In globs, `*` matches anything but `/`, `**` anything
+//! including `/`, `**/` zero or more directories and `?` any character but `/`.
+//! Regexes need the `regex` feature.
+
+use std::{fmt, sync::Arc};
+
+use url::Position;
+
+use crate::{glob::Glob, UrlEntry};
+
+type PredicateFn = dyn Fn(&UrlEntry) -> bool + Send + Sync;
+
+#[derive(Clone)]
+enum Matcher {
+    Glob(String, Glob),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+    Predicate(Arc<PredicateFn>),
+}
+
+/// A test of the entries, see the [module docs](self).
+#[derive(Clone)]
+pub struct UrlFilter(Matcher);
+
+impl fmt::Debug for UrlFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Matcher::Glob(pattern, _) => f.debug_tuple("Glob").field(pattern).finish(),
+            #[cfg(feature = "regex")]
+            Matcher::Regex(regex) => f.debug_tuple("Regex").field(&regex.as_str()).finish(),
+            Matcher::Predicate(_) => f.debug_tuple("Predicate").finish_non_exhaustive(),
+        }
+    }
+}
+
+impl UrlFilter {
+    /// Matches the entries whose path and query match the glob `pattern`.
+    #[must_use]
+    pub fn glob(pattern: &str) -> Self {
+        Self(Matcher::Glob(pattern.to_owned(), Glob::new(pattern)))
+    }
+
+    /// Matches the entries whose path and query match `regex`, anywhere unless it is
+    /// anchored.
+    #[cfg(feature = "regex")]
+    #[must_use]
+    pub const fn regex(regex: regex::Regex) -> Self {
+        Self(Matcher::Regex(regex))
+    }
+
+    /// Matches the entries for which `predicate` returns `true`.
+    #[must_use]
+    pub fn predicate<F>(predicate: F) -> Self
+    where
+        F: Fn(&UrlEntry) -> bool + Send + Sync + 'static,
+    {
+        Self(Matcher::Predicate(Arc::new(predicate)))
+    }
+
+    /// Whether the filter matches `entry`.
+    #[must_use]
+    pub fn matches(&self, entry: &UrlEntry) -> bool {
+        match &self.0 {
+            Matcher::Glob(_, glob) => glob.matches(path_and_query(entry)),
+            #[cfg(feature = "regex")]
+            Matcher::Regex(regex) => regex.is_match(path_and_query(entry)),
+            Matcher::Predicate(predicate) => predicate(entry),
+        }
+    }
+}
+
+/// The path of the loc, with the query if it has one.
+fn path_and_query(entry: &UrlEntry) -> &str {
+    &entry.loc[Position::BeforePath..Position::AfterQuery]
+}
+
+impl From<&str> for UrlFilter {
+    fn from(pattern: &str) -> Self {
+        Self::glob(pattern)
+    }
+}
+
+impl From<String> for UrlFilter {
+    fn from(pattern: String) -> Self {
+        Self::glob(&pattern)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl From<regex::Regex> for UrlFilter {
+    fn from(regex: regex::Regex) -> Self {
+        Self::regex(regex)
+    }
+}
+
+impl<F> From<F> for UrlFilter
+where
+    F: Fn(&UrlEntry) -> bool + Send + Sync + 'static,
+{
+    fn from(predicate: F) -> Self {
+        Self::predicate(predicate)
+    }
+}
+
+/// The include and exclude filters of a [`Sitemap`](crate::Sitemap).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Filters {
+    pub(crate) include: Vec<UrlFilter>,
+    pub(crate) exclude: Vec<UrlFilter>,
+}
+
+impl Filters {
+    pub(crate) const fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether `entry` is written: it matches an include filter, if there are any, and no
+    /// exclude filter.
+    pub(crate) fn keeps(&self, entry: &UrlEntry) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|f| f.matches(entry)))
+            && !self.exclude.iter().any(|f| f.matches(entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sitemap;
+
+    fn entry(path: &str) -> UrlEntry {
+        let loc = format!("https://a.com{path}").parse().unwrap();
+        UrlEntry::new(loc, None, None, None)
+    }
+
+    #[test]
+    fn includes_and_excludes() {
+        let urls = ["/", "/blog/a", "/blog/drafts/b", "/search?q=a", "/about"].map(entry);
+        let sitemap = Sitemap::new()
+            .include("/blog/**")
+            .include(|entry: &UrlEntry| entry.loc.path().starts_with("/search"))
+            .exclude("**/drafts/**");
+        assert_eq!(
+            sitemap.generate_txt(&urls),
+            "https://a.com/blog/a\nhttps://a.com/search?q=a\n"
+        );
+
+        let glob = UrlFilter::glob("/search?*");
+        assert!(glob.matches(&entry("/search?q=a")));
+        assert!(!glob.matches(&entry("/search")));
+        assert_eq!(format!("{glob:?}"), "Glob(\"/search?*\")");
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn matches_regexes() {
+        let filter = UrlFilter::from(regex::Regex::new(r"^/p/\d+$").unwrap());
+        assert!(filter.matches(&entry("/p/12")));
+        assert!(!filter.matches(&entry("/p/12?page=2")));
+        let urls = [entry("/p/1"), entry("/p/a")];
+        let sitemap = Sitemap::new().exclude(filter);
+        assert_eq!(sitemap.generate_txt(&urls), "https://a.com/p/a\n");
+    }
+}
@@ -0,0 +1,126 @@
+//! Formatting of the values of the elements without allocating.
+//!
+//! The common cases are written into stack buffers, anything else falls back to the
+//! standard formatting so the output is always the same. Only `core` and `alloc` are
+//! used, like the rest of the serialization in [`urlset`](crate::urlset).
+
+use alloc::{borrow::Cow, format, vec::Vec};
+
+use chrono::{DateTime, Datelike, SecondsFormat, Timelike, Utc};
+
+/// Appends `text` to `out` with `<`, `>`, `&`, `'` and `"` escaped, like the text and
+/// attribute values written by quick-xml.
+pub fn escape(out: &mut Vec<u8>, text: &str) {
+    let mut rest = text.as_bytes();
+    while let Some(i) = rest.iter().position(|b| b"<>&'\"".contains(b)) {
+        out.extend_from_slice(&rest[..i]);
+        out.extend_from_slice(match rest[i] {
+            b'<' => b"&lt;",
+            b'>' => b"&gt;",
+            b'&' => b"&amp;",
+            b'\'' => b"&apos;",
+            _ => b"&quot;",
+        });
+        rest = &rest[i + 1..];
+    }
+    out.extend_from_slice(rest);
+}
+
+/// Formats a lastmod as `YYYY-MM-DDTHH:MM:SSZ`, like
+/// `to_rfc3339_opts(SecondsFormat::Secs, true)`.
+pub fn lastmod<'a>(lastmod: &DateTime<Utc>, buf: &'a mut [u8; 20]) -> Cow<'a, str> {
+    let Ok(year) = u32::try_from(lastmod.year()) else {
+        return lastmod.to_rfc3339_opts(SecondsFormat::Secs, true).into();
+    };
+    // A leap second has extra nanoseconds, which chrono prints as second 60.
+    if year > 9999 || lastmod.nanosecond() >= 1_000_000_000 {
+        return lastmod.to_rfc3339_opts(SecondsFormat::Secs, true).into();
+    }
+
+    digits(&mut buf[0..4], year);
+    buf[4] = b'-';
+    digits(&mut buf[5..7], lastmod.month());
+    buf[7] = b'-';
+    digits(&mut buf[8..10], lastmod.day());
+    buf[10] = b'T';
+    digits(&mut buf[11..13], lastmod.hour());
+    buf[13] = b':';
+    digits(&mut buf[14..16], lastmod.minute());
+    buf[16] = b':';
+    digits(&mut buf[17..19], lastmod.second());
+    buf[19] = b'Z';
+    Cow::Borrowed(core::str::from_utf8(buf).expect("only ascii digits and separators"))
+}
+
+/// Formats a priority with one decimal, like `format!("{priority:.1}")`.
+pub fn priority(priority: f32, buf: &mut [u8; 3]) -> Cow<'_, str> {
+    let scaled = priority * 10.0;
+    let tenths = scaled.round();
+    // Only values that are a whole number of tenths, give or take the float error, are
+    // sure to round the same way as the standard formatting.
+    if priority.is_sign_positive() && tenths <= 10.0 && (scaled - tenths).abs() < 1e-3 {
+        // Truncation is exact, `tenths` is a whole number between 0 and 10.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let tenths = tenths as u8;
+        if tenths < 10 {
+            *buf = [b'0', b'.', b'0' + tenths];
+            return Cow::Borrowed(core::str::from_utf8(buf).expect("only ascii digits"));
+        }
+        return Cow::Borrowed("1.0");
+    }
+    format!("{priority:.1}").into()
+}
+
+/// Writes `value` as decimal digits filling `buf`, with leading zeros.
+fn digits(buf: &mut [u8], mut value: u32) {
+    for byte in buf.iter_mut().rev() {
+        // The remainder is a single digit.
+        #[allow(clippy::cast_possible_truncation)]
+        let digit = (value % 10) as u8;
+        *byte = b'0' + digit;
+        value /= 10;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn same_as_standard_formatting() {
+        for i in 0..=1000 {
+            let p = i as f32 / 1000.0;
+            assert_eq!(priority(p, &mut [0; 3]), format!("{p:.1}"), "{p}");
+        }
+        for p in [-0.0, -0.1, 1.5, 12.0, f32::NAN, f32::INFINITY, 0.25] {
+            assert_eq!(priority(p, &mut [0; 3]), format!("{p:.1}"));
+        }
+
+        for date in [
+            Utc.with_ymd_and_hms(2024, 2, 29, 23, 59, 59).unwrap(),
+            Utc.with_ymd_and_hms(7, 1, 1, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(-1, 1, 1, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(10000, 1, 1, 0, 0, 0).unwrap(),
+            Utc.timestamp_opt(1_700_000_000, 999_999_999).unwrap(),
+            Utc.timestamp_opt(1_483_228_799, 1_500_000_000).unwrap(),
+        ] {
+            assert_eq!(
+                lastmod(&date, &mut [0; 20]),
+                date.to_rfc3339_opts(SecondsFormat::Secs, true)
+            );
+        }
+        let leap = Utc.timestamp_opt(1_483_228_799, 1_000_000_000).unwrap();
+        assert_eq!(lastmod(&leap, &mut [0; 20]), "2016-12-31T23:59:60Z");
+    }
+
+    #[test]
+    fn escapes_like_quick_xml() {
+        for text in ["", "plain", "a<b>&c'd\"e", "&&", "é & ü"] {
+            let mut out = Vec::new();
+            escape(&mut out, text);
+            assert_eq!(out, quick_xml::escape::escape(text).as_bytes(), "{text}");
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Building entries from a directory of static files.
+//!
+//! ```rust,no_run
+//! use sitewriter::fs::{scan_dir, ScanOptions};
+//!
+//! let options = ScanOptions::default().exclude("404.html").exclude("drafts/**");
+//! let urls = scan_dir("public", &"https://example.com/".parse()?, &options)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use crate::{glob::Glob, lastmod::lastmod_from_path, Result, Url, UrlEntry};
+
+/// Options used by [`scan_dir`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScanOptions {
+    /// Only files whose path relative to the root matches one of these globs are included.
+    /// All files are included when empty.
+    pub include: Vec<String>,
+    /// Files whose path relative to the root matches one of these globs are skipped.
+    pub exclude: Vec<String>,
+    /// Extensions of the files that are pages.
+    pub extensions: Vec<String>,
+    /// Files that are served as their directory, such as `index.html` as `/blog/`.
+    pub index_files: Vec<String>,
+    /// Remove the extension from the url, `/about.html` becomes `/about`.
+    pub strip_extension: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            extensions: vec!["html".to_owned(), "htm".to_owned()],
+            index_files: vec!["index.html".to_owned(), "index.htm".to_owned()],
+            strip_extension: false,
+        }
+    }
+}
+
+impl ScanOptions {
+    /// Adds a glob of files to include.
+    #[must_use]
+    pub fn include(mut self, glob: &str) -> Self {
+        self.include.push(glob.to_owned());
+        self
+    }
+
+    /// Adds a glob of files to skip.
+    #[must_use]
+    pub fn exclude(mut self, glob: &str) -> Self {
+        self.exclude.push(glob.to_owned());
+        self
+    }
+
+    /// Sets whether the extension is removed from the urls.
+    #[must_use]
+    pub const fn strip_extension(mut self, strip: bool) -> Self {
+        self.strip_extension = strip;
+        self
+    }
+}
+
+/// Walks the built site in `root` and returns an entry for each page, in path order.
+///
+/// Each file is mapped to a url relative to `base`, index files are mapped to their
+/// directory. The modification time of the file is used as `lastmod`, see
+/// [`lastmod_from_path`].
+///
+/// # Errors
+///
+/// Will return `Err` if a directory or file metadata can't be read.
+pub fn scan_dir(
+    root: impl AsRef<Path>,
+    base: &Url,
+    options: &ScanOptions,
+) -> Result<Vec<UrlEntry>> {
+    let root = root.as_ref();
+    let include: Vec<Glob> = options.include.iter().map(|g| Glob::new(g)).collect();
+    let exclude: Vec<Glob> = options.exclude.iter().map(|g| Glob::new(g)).collect();
+
+    let mut base = base.clone();
+    if !base.path().ends_with('/') {
+        base.set_path(&format!("{}/", base.path()));
+    }
+
+    let mut files = Vec::new();
+    walk(root, &mut files)?;
+
+    let mut urls = Vec::new();
+    for path in files {
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        let relative: Vec<&str> = relative
+            .components()
+            .filter_map(|c| c.as_os_str().to_str())
+            .collect();
+        let relative = relative.join("/");
+
+        let is_page = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| options.extensions.iter().any(|x| x.eq_ignore_ascii_case(e)));
+        if !is_page
+            || (!include.is_empty() && !include.iter().any(|g| g.matches(&relative)))
+            || exclude.iter().any(|g| g.matches(&relative))
+        {
+            continue;
+        }
+
+        let Some(url) = file_url(&base, &relative, options) else {
+            continue;
+        };
+        urls.push(UrlEntry::new(url, lastmod_from_path(&path), None, None));
+    }
+
+    Ok(urls)
+}
+
+fn file_url(base: &Url, relative: &str, options: &ScanOptions) -> Option<Url> {
+    let (dir, name) = relative.rsplit_once('/').unwrap_or(("", relative));
+
+    let path = if options.index_files.iter().any(|i| i == name) {
+        if dir.is_empty() {
+            String::new()
+        } else {
+            format!("{dir}/")
+        }
+    } else if options.strip_extension {
+        let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+        if dir.is_empty() {
+            stem.to_owned()
+        } else {
+            format!("{dir}/{stem}")
+        }
+    } else {
+        relative.to_owned()
+    };
+
+    base.join(&path).ok()
+}
+
+/// Collects the files under `dir`, sorted by path so the output is deterministic.
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let mut entries = std::fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(std::fs::DirEntry::file_name);
+
+    for entry in entries {
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(&entry.path(), files)?;
+        } else if file_type.is_file() {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn scans_static_site() {
+        let root = TempDir::new("fs");
+        for file in [
+            "index.html",
+            "about.html",
+            "style.css",
+            "404.html",
+            "blog/index.html",
+            "blog/first post.html",
+            "drafts/wip.html",
+        ] {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "<html></html>").unwrap();
+        }
+
+        let options = ScanOptions::default()
+            .exclude("404.html")
+            .exclude("drafts/**")
+            .strip_extension(true);
+        let urls = scan_dir(&root, &"https://a.com/site".parse().unwrap(), &options).unwrap();
+
+        let locs: Vec<&str> = urls.iter().map(|u| u.loc.as_str()).collect();
+        assert_eq!(
+            locs,
+            [
+                "https://a.com/site/about",
+                "https://a.com/site/blog/first%20post",
+                "https://a.com/site/blog/",
+                "https://a.com/site/",
+            ]
+        );
+        assert!(urls.iter().all(|u| u.lastmod.is_some()));
+    }
+}
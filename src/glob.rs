@@ -0,0 +1,102 @@
+//! Minimal glob patterns matched against `/` separated paths.
+//!
+//! `*` matches anything but `/`, `**` matches anything including `/`, `**/` matches zero or
+//! more directories and `?` matches a single character other than `/`.
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Token {
+    Char(char),
+    Any,
+    Star,
+    DoubleStar,
+    /// `**/`, zero or more complete directories.
+    Dirs,
+}
+
+/// A compiled glob pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Glob {
+    tokens: Vec<Token>,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '?' => Token::Any,
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        Token::Dirs
+                    } else {
+                        Token::DoubleStar
+                    }
+                }
+                '*' => Token::Star,
+                c => Token::Char(c),
+            };
+            tokens.push(token);
+        }
+        Self { tokens }
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let path: Vec<char> = path.chars().collect();
+        matches(&self.tokens, &path)
+    }
+}
+
+fn matches(tokens: &[Token], path: &[char]) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return path.is_empty();
+    };
+
+    match token {
+        Token::Char(c) => path.first() == Some(c) && matches(rest, &path[1..]),
+        Token::Any => path.first().is_some_and(|&c| c != '/') && matches(rest, &path[1..]),
+        Token::Star => (0..=path.len())
+            .take_while(|&i| i == 0 || path[i - 1] != '/')
+            .any(|i| matches(rest, &path[i..])),
+        Token::DoubleStar => (0..=path.len()).any(|i| matches(rest, &path[i..])),
+        Token::Dirs => {
+            matches(rest, path)
+                || path
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &c)| c == '/')
+                    .any(|(i, _)| matches(rest, &path[i + 1..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        let cases = [
+            ("*.html", "index.html", true),
+            ("*.html", "blog/index.html", false),
+            ("**/*.html", "index.html", true),
+            ("**/*.html", "blog/2024/post.html", true),
+            ("drafts/**", "drafts/a/b.html", true),
+            ("drafts/**", "blog/drafts/a.html", false),
+            ("sitemap?.xml", "sitemap1.xml", true),
+            ("sitemap?.xml", "sitemap.xml", false),
+            ("/admin/*", "/admin/users", true),
+            ("/admin/*", "/admin/users/1", false),
+        ];
+
+        for (pattern, path, expected) in cases {
+            assert_eq!(
+                Glob::new(pattern).matches(path),
+                expected,
+                "{pattern} {path}"
+            );
+        }
+    }
+}
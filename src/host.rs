@@ -0,0 +1,284 @@
+//! Sitemaps of platforms serving several hosts.
+//!
+//! Multi-tenant platforms often read the entries of every site from one table.
+//! [`partition_by_host`] groups them by host, and [`Sitemap::write_per_host`] writes the
+//! sitemap of each host, split as the [limits](Sitemap::limits) require, plus one index
+//! listing them all:
+//!
+//! ```rust,no_run
+//! use sitewriter::{host::HostOutput, Sitemap, UrlEntry};
+//!
+//! let urls = vec![
+//!     UrlEntry::new("https://a.example.com/".parse()?, None, None, None),
+//!     UrlEntry::new("https://b.example.com/".parse()?, None, None, None),
+//! ];
+//! let output = HostOutput::new("public", "https://example.com/maps/".parse()?);
+//! let files = Sitemap::new().write_per_host(&urls, &output)?;
+//! // The index, then sitemap-a.example.com-1.xml and sitemap-b.example.com-1.xml.
+//! assert_eq!(files.len(), 3);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Search engines only accept a sitemap listing urls of another host when that host
+//! declares it, such as with a `Sitemap:` line in its `robots.txt`.
+
+use std::{borrow::Borrow, collections::HashMap, path::PathBuf};
+
+use url::{Host, Url};
+
+use crate::{
+    sink::OutputSink,
+    sort::sort_entries,
+    split::{SplitOutput, WrittenFile, INDEX_PLACEHOLDER},
+    validate::{Issue, IssueKind, ValidationReport},
+    Error, Result, Sitemap, UrlEntry,
+};
+
+/// The placeholder replaced with the host of each sitemap in a name template.
+pub const HOST_PLACEHOLDER: &str = "{host}";
+
+/// Groups the entries by the host of their loc, keeping their order within each host.
+///
+/// Entries without a host, such as `mailto:` locs, are left out.
+#[must_use]
+pub fn partition_by_host(urls: impl IntoIterator<Item = UrlEntry>) -> HashMap<Host, Vec<UrlEntry>> {
+    group(urls).0
+}
+
+/// The entries grouped by host, and the ones without a host.
+fn group<T: Borrow<UrlEntry>>(
+    urls: impl IntoIterator<Item = T>,
+) -> (HashMap<Host, Vec<T>>, Vec<T>) {
+    let mut hosts: HashMap<Host, Vec<T>> = HashMap::new();
+    let mut hostless = Vec::new();
+    for entry in urls {
+        match entry.borrow().loc.host() {
+            Some(host) => hosts.entry(host.to_owned()).or_default().push(entry),
+            None => hostless.push(entry),
+        }
+    }
+    (hosts, hostless)
+}
+
+/// Where [`Sitemap::write_per_host`] writes its files.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HostOutput {
+    dir: PathBuf,
+    base_url: Url,
+    template: String,
+    index_name: String,
+}
+
+impl HostOutput {
+    /// Writes to `dir`, whose files are served under `base_url`.
+    ///
+    /// The sitemaps are named `sitemap-{host}-{index}.xml` and the index
+    /// `sitemap_index.xml` by default.
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>, base_url: Url) -> Self {
+        Self {
+            dir: dir.into(),
+            base_url,
+            template: "sitemap-{host}-{index}.xml".to_owned(),
+            index_name: "sitemap_index.xml".to_owned(),
+        }
+    }
+
+    /// Sets the name template of the sitemaps, which must contain [`HOST_PLACEHOLDER`] and
+    /// [`INDEX_PLACEHOLDER`]. The template may contain `/`, such as
+    /// `{host}/sitemap-{index}.xml`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the template lacks one of the placeholders, the sitemaps of different
+    /// hosts or parts would have the same name.
+    #[must_use]
+    pub fn template(mut self, template: impl Into<String>) -> Self {
+        let template = template.into();
+        for placeholder in [HOST_PLACEHOLDER, INDEX_PLACEHOLDER] {
+            assert!(
+                template.contains(placeholder),
+                "the template '{template}' has no {placeholder} placeholder"
+            );
+        }
+        self.template = template;
+        self
+    }
+
+    /// Sets the file name of the sitemap index.
+    #[must_use]
+    pub fn index_name(mut self, name: impl Into<String>) -> Self {
+        self.index_name = name.into();
+        self
+    }
+
+    /// The output of the sitemaps of `host`.
+    fn split_output(&self, host: &Host) -> SplitOutput {
+        // Keep the brackets and colons of IPv6 hosts out of the file names.
+        let host: String = host
+            .to_string()
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+                _ => '_',
+            })
+            .collect();
+        SplitOutput::new(&self.dir, self.base_url.clone())
+            .template(self.template.replace(HOST_PLACEHOLDER, &host))
+            .index_name(&self.index_name)
+    }
+}
+
+impl Sitemap {
+    /// Writes the sitemap of each host of the entries, split as the
+    /// [limits](Sitemap::limits) require, plus a sitemap index listing every part, see
+    /// [`write_split`](Sitemap::write_split).
+    ///
+    /// The entries are grouped after the [transforms](Sitemap::transform), so each
+    /// sitemap only lists urls of its host even when a transform such as
+    /// [`RewriteHost`](crate::transform::RewriteHost) moves them. The hosts are written in
+    /// order. Returns the index followed by the parts.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::Invalid`] if an entry has no host, or `Err` as
+    /// [`Sitemap::write_split`].
+    pub fn write_per_host(
+        &self,
+        urls: &[UrlEntry],
+        output: &HostOutput,
+    ) -> Result<Vec<WrittenFile>> {
+        self.per_host_into(urls, output, None)
+    }
+
+    /// Like [`write_per_host`](Sitemap::write_per_host), with the files stored in `sink`,
+    /// see [`Sitemap::write_split_to`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`Sitemap::write_per_host`].
+    pub fn write_per_host_to(
+        &self,
+        urls: &[UrlEntry],
+        output: &HostOutput,
+        sink: &dyn OutputSink,
+    ) -> Result<Vec<WrittenFile>> {
+        self.per_host_into(urls, output, Some(sink))
+    }
+
+    fn per_host_into(
+        &self,
+        urls: &[UrlEntry],
+        output: &HostOutput,
+        sink: Option<&dyn OutputSink>,
+    ) -> Result<Vec<WrittenFile>> {
+        self.check_empty(urls)?;
+        if self.skips(urls) {
+            #[cfg(feature = "tracing")]
+            tracing::info!("no entries, skipped writing the per host sitemaps");
+            return Ok(Vec::new());
+        }
+
+        let urls = self.transformed(urls);
+        let (hosts, hostless) = group(urls.iter());
+        if !hostless.is_empty() {
+            let issues = hostless
+                .into_iter()
+                .map(|entry| Issue {
+                    kind: IssueKind::UnsupportedScheme,
+                    loc: entry.loc.clone(),
+                    source: entry.source.clone(),
+                })
+                .collect();
+            return Err(Error::Invalid(ValidationReport { issues }));
+        }
+        let mut hosts: Vec<(Host, Vec<&UrlEntry>)> = hosts.into_iter().collect();
+        hosts.sort_by_cached_key(|(host, _)| host.to_string());
+
+        let mut written = Vec::new();
+        let mut index = Vec::new();
+        for (host, mut urls) in hosts {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!("sitemap_host", %host).entered();
+            let split = output.split_output(&host);
+            if let Some(order) = self.sort {
+                sort_entries(&mut urls, order);
+            }
+            let mut parts = Vec::new();
+            for chunk in urls.chunks(self.limits.max_entries.max(1)) {
+                self.write_part(chunk, &split, sink, &mut parts, &mut index)?;
+            }
+            written.append(&mut parts);
+        }
+
+        let index_output =
+            SplitOutput::new(&output.dir, output.base_url.clone()).index_name(&output.index_name);
+        written.insert(0, self.write_index(&index, &index_output, sink)?);
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sink::MemorySink, transform::RewriteHost, Limits};
+
+    fn entry(loc: &str) -> UrlEntry {
+        UrlEntry::new(loc.parse().unwrap(), None, None, None)
+    }
+
+    #[test]
+    fn partitions() {
+        let hosts = partition_by_host(vec![
+            entry("https://a.com/1"),
+            entry("https://b.com/"),
+            entry("http://a.com/2"),
+            entry("mailto:someone@a.com"),
+        ]);
+        assert_eq!(hosts.len(), 2);
+        let a = &hosts[&Host::Domain("a.com".to_owned())];
+        assert_eq!(a[0].loc.as_str(), "https://a.com/1");
+        assert_eq!(a[1].loc.as_str(), "http://a.com/2");
+    }
+
+    #[test]
+    fn writes_one_sitemap_per_host() {
+        let urls = [
+            entry("https://b.com/1"),
+            entry("https://a.com/1"),
+            entry("https://b.com/2"),
+            entry("https://[::1]/"),
+        ];
+        let sink = MemorySink::new();
+        let output = HostOutput::new("maps", "https://cdn.com/maps/".parse().unwrap());
+        let sitemap = Sitemap::new().limits(Limits::new(1, crate::MAX_SIZE));
+        let files = sitemap.write_per_host_to(&urls, &output, &sink).unwrap();
+        let names: Vec<&str> = files.iter().map(|f| f.url.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "https://cdn.com/maps/sitemap_index.xml",
+                "https://cdn.com/maps/sitemap-___1_-1.xml",
+                "https://cdn.com/maps/sitemap-a.com-1.xml",
+                "https://cdn.com/maps/sitemap-b.com-1.xml",
+                "https://cdn.com/maps/sitemap-b.com-2.xml",
+            ]
+        );
+        let part = String::from_utf8(sink.get("maps/sitemap-b.com-2.xml").unwrap()).unwrap();
+        assert!(part.contains("https://b.com/2"));
+        assert_eq!(files[0].url_count, 4);
+
+        // Transforms apply before grouping.
+        let moved =
+            Sitemap::new().transform(RewriteHost::new("b.com", "https://a.com".parse().unwrap()));
+        let files = moved.write_per_host_to(&urls[..3], &output, &sink).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[1].url_count, 3);
+
+        let hostless = [entry("mailto:someone@a.com")];
+        assert!(matches!(
+            sitemap.write_per_host_to(&hostless, &output, &sink),
+            Err(Error::Invalid(report)) if report.issues[0].kind == IssueKind::UnsupportedScheme
+        ));
+    }
+}
@@ -0,0 +1,434 @@
+//! Sitemap index generation.
+//!
+//! A sitemap index lists other sitemaps, for sites with more urls than fit in one sitemap.
+//!
+//! ```rust
+//! use sitewriter::index::{generate_index_str, IndexEntry};
+//!
+//! let sitemaps = vec![
+//!     IndexEntry::new("https://example.com/sitemap-1.xml".parse()?, None),
+//!     IndexEntry::new("https://example.com/sitemap-2.xml".parse()?, None),
+//! ];
+//! println!("{}", generate_index_str(&sitemaps));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{io::Cursor, path::Path};
+
+use chrono::{DateTime, Utc};
+use quick_xml::{
+    events::{BytesDecl, BytesEnd, BytesStart, Event},
+    Writer,
+};
+use url::Url;
+
+#[cfg(feature = "manifest")]
+use crate::manifest::{Manifest, ManifestFile};
+use crate::{format, lastmod::lastmod_from_path, loc_str, write_tag, Error, Result};
+
+/// A sitemap listed in a sitemap index.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IndexEntry {
+    /// URL of the sitemap.
+    pub loc: Url,
+    /// When the sitemap was last modified.
+    pub lastmod: Option<DateTime<Utc>>,
+}
+
+impl IndexEntry {
+    /// Create a new index entry.
+    #[must_use]
+    pub const fn new(loc: Url, lastmod: Option<DateTime<Utc>>) -> Self {
+        Self { loc, lastmod }
+    }
+}
+
+/// A sitemap listed by a [`SitemapIndexBuilder`], with what is known of its file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IndexChild {
+    /// URL of the sitemap.
+    pub loc: Url,
+    /// When the sitemap was last modified.
+    pub lastmod: Option<DateTime<Utc>>,
+    /// The size in bytes of the sitemap.
+    pub size: Option<u64>,
+    /// How many urls the sitemap lists.
+    pub url_count: Option<usize>,
+    /// The file of the sitemap, reported by [`SitemapIndexBuilder::manifest`].
+    #[cfg(feature = "manifest")]
+    pub file: Option<ManifestFile>,
+}
+
+impl IndexChild {
+    /// A sitemap served at `loc`.
+    #[must_use]
+    pub const fn new(loc: Url) -> Self {
+        Self {
+            loc,
+            lastmod: None,
+            size: None,
+            url_count: None,
+            #[cfg(feature = "manifest")]
+            file: None,
+        }
+    }
+
+    /// Sets when the sitemap was last modified.
+    #[must_use]
+    pub const fn lastmod(mut self, lastmod: DateTime<Utc>) -> Self {
+        self.lastmod = Some(lastmod);
+        self
+    }
+
+    /// Sets the size in bytes of the sitemap.
+    #[must_use]
+    pub const fn size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Sets how many urls the sitemap lists.
+    #[must_use]
+    pub const fn url_count(mut self, url_count: usize) -> Self {
+        self.url_count = Some(url_count);
+        self
+    }
+
+    /// Sets the file of the sitemap, along with its size and number of urls.
+    #[cfg(feature = "manifest")]
+    #[must_use]
+    pub fn file(mut self, file: ManifestFile) -> Self {
+        self.size = Some(file.size);
+        self.url_count = Some(file.url_count);
+        self.file = Some(file);
+        self
+    }
+}
+
+impl From<IndexEntry> for IndexChild {
+    fn from(entry: IndexEntry) -> Self {
+        let child = Self::new(entry.loc);
+        match entry.lastmod {
+            Some(lastmod) => child.lastmod(lastmod),
+            None => child,
+        }
+    }
+}
+
+/// Builds a sitemap index from sitemaps described with their size and number of urls, which
+/// the `manifest` feature turns into the [`Manifest`](crate::manifest::Manifest) of the
+/// files.
+///
+/// ```rust
+/// use sitewriter::index::{IndexChild, SitemapIndexBuilder};
+///
+/// let index = SitemapIndexBuilder::new()
+///     .child(IndexChild::new("https://example.com/sitemap-1.xml".parse()?).url_count(50_000))
+///     .child(IndexChild::new("https://example.com/sitemap-2.xml".parse()?).url_count(12));
+/// assert_eq!(index.url_count(), 50_012);
+/// println!("{}", String::from_utf8(index.build_bytes())?);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// With the files:
+///
+/// ```rust
+/// # #[cfg(feature = "manifest")]
+/// # {
+/// use sitewriter::{
+///     index::{IndexChild, SitemapIndexBuilder},
+///     manifest::ManifestFile,
+///     Sitemap, UrlEntry,
+/// };
+///
+/// let urls = vec![UrlEntry::new("https://example.com/".parse()?, None, None, None)];
+/// let xml = Sitemap::new().generate_bytes(&urls);
+///
+/// let index = SitemapIndexBuilder::new().child(
+///     IndexChild::new("https://example.com/sitemap-1.xml".parse()?)
+///         .file(ManifestFile::new("sitemap-1.xml", &xml, urls.len())),
+/// );
+/// let bytes = index.build_bytes();
+/// let manifest = index.manifest("sitemap_index.xml");
+/// assert_eq!(manifest.files.len(), 2);
+/// assert_eq!(manifest.url_count(), 2);
+/// # }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SitemapIndexBuilder {
+    children: Vec<IndexChild>,
+}
+
+impl SitemapIndexBuilder {
+    /// Creates an index without sitemaps.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a sitemap.
+    #[must_use]
+    pub fn child(mut self, child: impl Into<IndexChild>) -> Self {
+        self.push(child);
+        self
+    }
+
+    /// Adds a sitemap.
+    pub fn push(&mut self, child: impl Into<IndexChild>) {
+        self.children.push(child.into());
+    }
+
+    /// The sitemaps, in the order they were added.
+    #[must_use]
+    pub fn children(&self) -> &[IndexChild] {
+        &self.children
+    }
+
+    /// The number of urls over the sitemaps whose number of urls is known.
+    #[must_use]
+    pub fn url_count(&self) -> usize {
+        self.children
+            .iter()
+            .filter_map(|child| child.url_count)
+            .sum()
+    }
+
+    /// The entries of the index.
+    #[must_use]
+    pub fn entries(&self) -> Vec<IndexEntry> {
+        self.children
+            .iter()
+            .map(|child| IndexEntry::new(child.loc.clone(), child.lastmod))
+            .collect()
+    }
+
+    /// Generates the sitemap index and saves it using the provided writer.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if it fails to write to the writer.
+    pub fn build<T: std::io::Write>(&self, inner_writer: T) -> Result<T> {
+        generate_index(inner_writer, &self.entries())
+    }
+
+    /// Generates the sitemap index.
+    #[must_use]
+    pub fn build_bytes(&self) -> Vec<u8> {
+        generate_index_bytes(&self.entries())
+    }
+
+    /// The manifest of the sitemaps whose file is known, published at their loc, followed
+    /// by the index itself under `index_path`.
+    ///
+    /// The index counts as listing one url per sitemap.
+    #[cfg(feature = "manifest")]
+    #[must_use]
+    pub fn manifest(&self, index_path: &str) -> Manifest {
+        let mut manifest = Manifest::new();
+        for child in &self.children {
+            if let Some(file) = &child.file {
+                let mut file = file.clone();
+                file.destination
+                    .get_or_insert_with(|| child.loc.to_string());
+                manifest.push(file);
+            }
+        }
+        manifest.push(ManifestFile::new(
+            index_path,
+            &self.build_bytes(),
+            self.children.len(),
+        ));
+        manifest
+    }
+}
+
+/// Generates the sitemap index and saves it using the provided writer.
+///
+/// # Errors
+///
+/// Will return `Err` if it fails to write to the writer.
+pub fn generate_index<T>(inner_writer: T, sitemaps: &[IndexEntry]) -> Result<T>
+where
+    T: std::io::Write,
+{
+    let mut writer = Writer::new_with_indent(inner_writer, b' ', 4);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+
+    let root_name = "sitemapindex";
+    let mut root = BytesStart::new(root_name);
+    root.push_attribute(("xmlns", "http://www.sitemaps.org/schemas/sitemap/0.9"));
+    writer.write_event(Event::Start(root))?;
+
+    for entry in sitemaps {
+        writer.write_event(Event::Start(BytesStart::new("sitemap")))?;
+        write_tag(&mut writer, "loc", &loc_str(&entry.loc))?;
+        if let Some(lastmod) = &entry.lastmod {
+            write_tag(
+                &mut writer,
+                "lastmod",
+                &format::lastmod(lastmod, &mut [0; 20]),
+            )?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("sitemap")))?;
+    }
+
+    writer.write_event(Event::End(BytesEnd::new(root_name)))?;
+
+    Ok(writer.into_inner())
+}
+
+/// Generates the sitemap index.
+#[must_use]
+pub fn generate_index_bytes(sitemaps: &[IndexEntry]) -> Vec<u8> {
+    let inner = Cursor::new(Vec::new());
+    let result = generate_index(inner, sitemaps).expect(
+        "it should never error, please report this bug to https://github.com/edg-l/sitewriter/issues",
+    );
+    result.into_inner()
+}
+
+/// Generates the sitemap index returning a string.
+#[must_use]
+pub fn generate_index_str(sitemaps: &[IndexEntry]) -> String {
+    let bytes = generate_index_bytes(sitemaps);
+    let res = std::str::from_utf8(&bytes).expect("to be valid utf8");
+    res.to_owned()
+}
+
+/// Lists the sitemaps in `dir`, for an index of sitemaps written by independent jobs.
+///
+/// The sitemaps are the files named `sitemap*.xml` or `sitemap*.xml.gz`, except the ones
+/// with `index` in their name, which are taken to be sitemap indexes. Subdirectories are
+/// not scanned. Each is expected to be served at `public_base` joined with its file name,
+/// with the modification time of the file as lastmod. The entries are sorted by name.
+///
+/// ```rust,no_run
+/// use sitewriter::index::{generate_index_bytes, index_from_dir};
+///
+/// let sitemaps = index_from_dir("public", &"https://example.com/".parse()?)?;
+/// sitewriter::write_atomic("public/sitemap_index.xml", &generate_index_bytes(&sitemaps))?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if the directory can't be read, or [`Error::InvalidUrl`] if a file
+/// name can't be joined to `public_base`.
+pub fn index_from_dir(dir: impl AsRef<Path>, public_base: &Url) -> Result<Vec<IndexEntry>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+            continue;
+        };
+        let is_sitemap = name.starts_with("sitemap")
+            && (name.ends_with(".xml") || name.ends_with(".xml.gz"))
+            && !name.contains("index");
+        if is_sitemap && entry.file_type()?.is_file() {
+            files.push((name, entry.path()));
+        }
+    }
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|(name, path)| {
+            let loc = public_base
+                .join(&name)
+                .map_err(|source| Error::InvalidUrl {
+                    value: name,
+                    source,
+                })?;
+            Ok(IndexEntry::new(loc, lastmod_from_path(path)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn generates_index() {
+        let xml = generate_index_str(&[
+            IndexEntry::new(
+                "https://a.com/s1.xml".parse().unwrap(),
+                Some(Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap()),
+            ),
+            IndexEntry::new("https://a.com/s2.xml?a=1&b=2".parse().unwrap(), None),
+        ]);
+        assert_eq!(
+            xml,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+    <sitemap>
+        <loc>https://a.com/s1.xml</loc>
+        <lastmod>2024-05-01T08:00:00Z</lastmod>
+    </sitemap>
+    <sitemap>
+        <loc>https://a.com/s2.xml?a=1&amp;b=2</loc>
+    </sitemap>
+</sitemapindex>"#
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "manifest")]
+    fn builds_index_and_manifest() {
+        let lastmod = Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap();
+        let index = SitemapIndexBuilder::new()
+            .child(
+                IndexChild::new("https://a.com/s1.xml".parse().unwrap())
+                    .lastmod(lastmod)
+                    .file(ManifestFile::new("s1.xml", b"<urlset/>", 3)),
+            )
+            .child(IndexEntry::new(
+                "https://a.com/s2.xml".parse().unwrap(),
+                None,
+            ));
+
+        assert_eq!(index.entries()[0].lastmod, Some(lastmod));
+        assert_eq!(index.url_count(), 3);
+        assert_eq!(index.children()[0].size, Some(9));
+        assert_eq!(index.children()[1].size, None);
+
+        let manifest = index.manifest("index.xml");
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["s1.xml", "index.xml"]);
+        assert_eq!(
+            manifest.files[0].destination.as_deref(),
+            Some("https://a.com/s1.xml")
+        );
+        assert!(manifest.files[1].matches(&index.build_bytes()));
+    }
+
+    #[test]
+    fn lists_sitemaps_in_dir() {
+        let dir = TempDir::new("index");
+        std::fs::create_dir_all(dir.join("sitemap-old.xml")).unwrap();
+        for name in [
+            "sitemap-2.xml.gz",
+            "sitemap-1.xml",
+            "sitemap_index.xml",
+            "robots.txt",
+            "sitemap.txt",
+        ] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        let sitemaps = index_from_dir(&dir, &"https://a.com/maps/".parse().unwrap()).unwrap();
+        let locs: Vec<&str> = sitemaps.iter().map(|s| s.loc.as_str()).collect();
+        assert_eq!(
+            locs,
+            [
+                "https://a.com/maps/sitemap-1.xml",
+                "https://a.com/maps/sitemap-2.xml.gz"
+            ]
+        );
+        assert!(sitemaps.iter().all(|s| s.lastmod.is_some()));
+    }
+}
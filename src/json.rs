@@ -0,0 +1,183 @@
+//! Minimal JSON output, enough for the documents this crate writes.
+
+use std::fmt::Write;
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+
+use crate::{extension, SitemapExtension};
+
+/// Appends `value` as a quoted JSON string.
+pub fn string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// An element written by an extension.
+#[derive(Default)]
+struct Element {
+    name: String,
+    namespace: Option<String>,
+    attributes: Vec<(String, String)>,
+    text: String,
+    children: Vec<Self>,
+}
+
+impl Element {
+    fn new(e: &BytesStart<'_>, extension: &dyn SitemapExtension) -> Self {
+        let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+        let attributes: Vec<(String, String)> = e
+            .attributes()
+            .flatten()
+            .map(|attribute| {
+                let value = attribute.unescape_value().map_or_else(
+                    |_| String::from_utf8_lossy(&attribute.value).into(),
+                    Into::into,
+                );
+                (
+                    String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+                    value,
+                )
+            })
+            .collect();
+        let namespace = match name.split_once(':') {
+            Some((prefix, _)) if prefix == extension.prefix() => {
+                Some(extension.namespace().to_owned())
+            }
+            Some((prefix, _)) => {
+                let declaration = format!("xmlns:{prefix}");
+                attributes
+                    .iter()
+                    .find(|(key, _)| *key == declaration)
+                    .map(|(_, value)| value.clone())
+            }
+            None => None,
+        };
+        Self {
+            name,
+            namespace,
+            attributes,
+            ..Self::default()
+        }
+    }
+
+    fn write(&self, out: &mut String) {
+        out.push_str("{\"name\": ");
+        string(out, &self.name);
+        out.push_str(", \"namespace\": ");
+        match &self.namespace {
+            Some(namespace) => string(out, namespace),
+            None => out.push_str("null"),
+        }
+        out.push_str(", \"attributes\": {");
+        for (i, (key, value)) in self.attributes.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            string(out, key);
+            out.push_str(": ");
+            string(out, value);
+        }
+        out.push_str("}, \"text\": ");
+        let text = self.text.trim();
+        if text.is_empty() {
+            out.push_str("null");
+        } else {
+            string(out, text);
+        }
+        out.push_str(", \"children\": [");
+        for (i, child) in self.children.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            child.write(out);
+        }
+        out.push_str("]}");
+    }
+}
+
+/// Appends the elements written by the extensions as an array of objects with their
+/// `name`, `namespace`, `attributes`, `text` and `children`.
+///
+/// The text is unescaped and trimmed, `null` when empty. An extension that fails to write
+/// is left out, as writing the XML fails.
+pub fn extensions<'a>(
+    out: &mut String,
+    extensions: impl Iterator<Item = &'a dyn SitemapExtension>,
+) {
+    let mut elements = Vec::new();
+    for ext in extensions {
+        let Ok(xml) = extension::to_xml(ext) else {
+            continue;
+        };
+        let mut reader = Reader::from_str(&xml);
+        let mut open: Vec<Element> = Vec::new();
+        loop {
+            let closed = match reader.read_event() {
+                Ok(Event::Start(e)) => {
+                    open.push(Element::new(&e, ext));
+                    continue;
+                }
+                Ok(Event::Empty(e)) => Element::new(&e, ext),
+                Ok(Event::End(_)) => match open.pop() {
+                    Some(element) => element,
+                    None => continue,
+                },
+                Ok(Event::Text(e)) => {
+                    if let (Some(element), Ok(text)) = (open.last_mut(), e.unescape()) {
+                        element.text.push_str(&text);
+                    }
+                    continue;
+                }
+                Ok(Event::CData(e)) => {
+                    if let Some(element) = open.last_mut() {
+                        element.text.push_str(&String::from_utf8_lossy(&e));
+                    }
+                    continue;
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                Ok(_) => continue,
+            };
+            match open.last_mut() {
+                Some(parent) => parent.children.push(closed),
+                None => elements.push(closed),
+            }
+        }
+    }
+
+    out.push('[');
+    for (i, element) in elements.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        element.write(out);
+    }
+    out.push(']');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes() {
+        let mut out = String::new();
+        string(&mut out, "a\"b\\c\n\u{1}é");
+        assert_eq!(out, r#""a\"b\\c\n\u0001é""#);
+    }
+}
@@ -0,0 +1,58 @@
+//! Helpers to obtain `lastmod` values.
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+use crate::UrlEntryBuilder;
+
+/// Returns the modification time of the file at `path`, in UTC.
+///
+/// Returns `None` if the file doesn't exist, can't be accessed or the platform doesn't record
+/// modification times. Symbolic links are followed. The time is truncated to whole seconds,
+/// the precision of the `<lastmod>` element, so it doesn't vary between filesystems.
+#[must_use]
+pub fn lastmod_from_path(path: impl AsRef<Path>) -> Option<DateTime<Utc>> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let modified = DateTime::<Utc>::from(modified);
+    DateTime::from_timestamp(modified.timestamp(), 0)
+}
+
+impl UrlEntryBuilder {
+    /// Sets the last modification date to the modification time of the file at `path`.
+    ///
+    /// Leaves the date unset if it can't be read, see [`lastmod_from_path`].
+    pub fn lastmod_from_file(&mut self, path: impl AsRef<Path>) -> &mut Self {
+        if let Some(lastmod) = lastmod_from_path(path) {
+            self.lastmod(lastmod);
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn reads_file_mtime() {
+        let dir = TempDir::new("lastmod");
+        let path = dir.join("lastmod");
+        std::fs::write(&path, "").unwrap();
+
+        let lastmod = lastmod_from_path(&path).unwrap();
+        assert!((Utc::now() - lastmod).num_minutes() < 5);
+        assert_eq!(lastmod.timestamp_subsec_nanos(), 0);
+
+        let entry = UrlEntryBuilder::default()
+            .loc("https://a.com/")
+            .lastmod_from_file(&path)
+            .build()
+            .unwrap();
+        assert_eq!(entry.lastmod, Some(lastmod));
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(lastmod_from_path(&path), None);
+    }
+}
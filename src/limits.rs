@@ -0,0 +1,74 @@
+//! The size limits of a sitemap.
+
+use crate::{error::Limit, Error, Result, MAX_ENTRIES, MAX_SIZE};
+
+/// The number of entries and uncompressed bytes a sitemap must stay within.
+///
+/// Splitting and [`SitemapWriter::limits`](crate::SitemapWriter::limits) use them, the
+/// default is the current limits of the protocol. Some regional engines still enforce the
+/// former limit of 10MB:
+///
+/// ```rust
+/// use sitewriter::{Limits, Sitemap};
+///
+/// let sitemap = Sitemap::new().limits(Limits::legacy_10mb());
+/// assert!(Limits::legacy_10mb().check(50_000, 11 * 1024 * 1024).is_err());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+pub struct Limits {
+    /// The maximum number of entries.
+    pub max_entries: usize,
+    /// The maximum size in bytes of the uncompressed sitemap.
+    pub max_size: usize,
+}
+
+impl Limits {
+    /// Custom limits.
+    #[must_use]
+    pub const fn new(max_entries: usize, max_size: usize) -> Self {
+        Self {
+            max_entries,
+            max_size,
+        }
+    }
+
+    /// [`MAX_ENTRIES`] entries and [`MAX_SIZE`] bytes, the limits of the protocol.
+    #[must_use]
+    pub const fn current_50mb() -> Self {
+        Self::new(MAX_ENTRIES, MAX_SIZE)
+    }
+
+    /// [`MAX_ENTRIES`] entries and 10MB, the limits of the protocol before 2016.
+    #[must_use]
+    pub const fn legacy_10mb() -> Self {
+        Self::new(MAX_ENTRIES, 10 * 1024 * 1024)
+    }
+
+    /// Checks a sitemap of `entries` entries and `size` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::LimitExceeded`] if it is over a limit.
+    pub const fn check(&self, entries: usize, size: usize) -> Result<()> {
+        if entries > self.max_entries {
+            return Err(Error::LimitExceeded {
+                limit: Limit::Entries,
+                max: self.max_entries,
+            });
+        }
+        if size > self.max_size {
+            return Err(Error::LimitExceeded {
+                limit: Limit::Size,
+                max: self.max_size,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::current_50mb()
+    }
+}
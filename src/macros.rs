@@ -0,0 +1,142 @@
+//! The [`sitemap!`](crate::sitemap!) macro.
+
+use crate::validate::is_uri_char;
+
+/// Builds a `Vec<UrlEntry>` from url literals, checking the urls at compile time.
+///
+/// Each url can be followed by `=> { .. }` setting any of `lastmod`, `changefreq` (a
+/// [`ChangeFreq`](crate::ChangeFreq) variant name), `priority` and `mobile`:
+///
+/// ```rust
+/// use sitewriter::{sitemap, ChangeFreq};
+///
+/// let urls = sitemap![
+///     "https://example.com/" => { changefreq: Daily, priority: 1.0 },
+///     "https://example.com/about",
+///     "https://example.com/blog/?page=2" => { changefreq: Weekly },
+/// ];
+/// assert_eq!(urls[0].changefreq, Some(ChangeFreq::Daily));
+/// assert_eq!(urls[1].priority, None);
+/// let xml = sitewriter::generate_str(&urls);
+/// ```
+///
+/// A url that isn't an absolute `http` or `https` url made of ASCII characters allowed in
+/// a URI, with a host, fails to compile:
+///
+/// ```rust,compile_fail
+/// let urls = sitewriter::sitemap!["https://example.com/a page"];
+/// ```
+///
+/// The check is syntactic, the urls are parsed when the entries are built.
+#[macro_export]
+macro_rules! sitemap {
+    (@field $entry:ident, lastmod, $value:expr) => {
+        $entry.lastmod = ::core::option::Option::Some($value);
+    };
+    (@field $entry:ident, changefreq, $value:expr) => {
+        $entry.changefreq = ::core::option::Option::Some({
+            #[allow(unused_imports)]
+            use $crate::ChangeFreq::*;
+            $value
+        });
+    };
+    (@field $entry:ident, priority, $value:expr) => {
+        $entry.priority = ::core::option::Option::Some($value);
+    };
+    (@field $entry:ident, mobile, $value:expr) => {
+        $entry.mobile = $value;
+    };
+    ($($loc:literal $(=> { $($field:ident : $value:expr),* $(,)? })?),* $(,)?) => {{
+        let entries: ::std::vec::Vec<$crate::UrlEntry> = ::std::vec![$({
+            const _: () = ::core::assert!(
+                $crate::macros::is_valid_loc($loc),
+                ::core::concat!("invalid sitemap url: ", $loc),
+            );
+            #[allow(unused_mut)]
+            let mut entry = $crate::UrlEntry::new(
+                $crate::Url::parse($loc).expect("checked at compile time"),
+                ::core::option::Option::None,
+                ::core::option::Option::None,
+                ::core::option::Option::None,
+            );
+            $($($crate::sitemap!(@field entry, $field, $value);)*)?
+            entry
+        }),*];
+        entries
+    }};
+}
+
+/// Whether `loc` is an absolute `http` or `https` url with a host, made of ASCII
+/// characters allowed in a URI, for [`sitemap!`](crate::sitemap!).
+#[doc(hidden)]
+#[must_use]
+pub const fn is_valid_loc(loc: &str) -> bool {
+    let bytes = loc.as_bytes();
+    let start = if starts_with(bytes, b"https://") {
+        8
+    } else if starts_with(bytes, b"http://") {
+        7
+    } else {
+        return false;
+    };
+    if bytes.len() == start || matches!(bytes[start], b'/' | b'?' | b'#') {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii() || !is_uri_char(bytes[i] as char) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Whether `bytes` starts with `prefix`, in a const context.
+const fn starts_with(bytes: &[u8], prefix: &[u8]) -> bool {
+    if bytes.len() < prefix.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < prefix.len() {
+        if bytes[i] != prefix[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChangeFreq;
+
+    #[test]
+    fn checks_and_builds_entries() {
+        assert!(is_valid_loc("https://a.com"));
+        assert!(is_valid_loc("http://a.com/b?c=%20#d"));
+        for invalid in [
+            "a.com/",
+            "ftp://a.com/",
+            "https://",
+            "https:///a",
+            "https://a.com/ b",
+            "https://é.com/",
+        ] {
+            assert!(!is_valid_loc(invalid), "{invalid}");
+        }
+
+        let urls = crate::sitemap![
+            "https://a.com/" => { changefreq: Never, priority: 0.5, mobile: true, },
+            "https://a.com/b",
+        ];
+        assert_eq!(urls.len(), 2);
+        assert_eq!(urls[0].changefreq, Some(ChangeFreq::Never));
+        assert_eq!(urls[0].priority, Some(0.5));
+        assert!(urls[0].mobile);
+        assert_eq!(urls[1].loc.as_str(), "https://a.com/b");
+        assert!(crate::sitemap![].is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+//! A machine-readable record of the files produced by a run.
+//!
+//! Deploy tooling can use the [`Manifest`] to check that what was uploaded is what was
+//! generated:
+//!
+//! ```rust
+//! use sitewriter::{manifest::{Manifest, ManifestFile}, Sitemap, UrlEntry};
+//!
+//! let urls = vec![UrlEntry::new("https://example.com/".parse()?, None, None, None)];
+//! let xml = Sitemap::new().generate_bytes(&urls);
+//!
+//! let mut manifest = Manifest::new();
+//! manifest.push(
+//!     ManifestFile::new("sitemap.xml", &xml, urls.len())
+//!         .destination("s3://bucket/sitemap.xml"),
+//! );
+//! assert!(manifest.files[0].matches(&xml));
+//! println!("{}", manifest.to_json());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{fmt::Write, path::Path};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::{json, Result};
+
+/// A file produced by a run.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ManifestFile {
+    /// The path of the file, relative to the output directory.
+    pub path: String,
+    /// The size in bytes.
+    pub size: u64,
+    /// The lowercase hex SHA-256 of the contents.
+    pub sha256: String,
+    /// How many urls the file lists.
+    pub url_count: usize,
+    /// Where the file is published, if known.
+    pub destination: Option<String>,
+}
+
+impl ManifestFile {
+    /// Describes a file with the given contents.
+    #[must_use]
+    pub fn new(path: impl Into<String>, contents: &[u8], url_count: usize) -> Self {
+        Self {
+            path: path.into(),
+            size: contents.len() as u64,
+            sha256: sha256_hex(contents),
+            url_count,
+            destination: None,
+        }
+    }
+
+    /// Sets where the file is published.
+    #[must_use]
+    pub fn destination(mut self, destination: impl Into<String>) -> Self {
+        self.destination = Some(destination.into());
+        self
+    }
+
+    /// Whether `contents` are the contents this file was described with.
+    #[must_use]
+    pub fn matches(&self, contents: &[u8]) -> bool {
+        contents.len() as u64 == self.size && sha256_hex(contents) == self.sha256
+    }
+}
+
+/// The files produced by a run.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Manifest {
+    /// When the run happened.
+    pub generated_at: DateTime<Utc>,
+    /// The produced files, in the order they were added.
+    pub files: Vec<ManifestFile>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            generated_at: Utc::now(),
+            files: Vec::new(),
+        }
+    }
+}
+
+impl Manifest {
+    /// Creates an empty manifest timestamped now.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file.
+    pub fn push(&mut self, file: ManifestFile) {
+        self.files.push(file);
+    }
+
+    /// The number of urls over all files.
+    #[must_use]
+    pub fn url_count(&self) -> usize {
+        self.files.iter().map(|f| f.url_count).sum()
+    }
+
+    /// Returns the paths of the files in `dir` that are missing or don't match.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a file exists but can't be read.
+    pub fn verify_dir(&self, dir: impl AsRef<Path>) -> Result<Vec<&str>> {
+        let dir = dir.as_ref();
+        let mut mismatched = Vec::new();
+        for file in &self.files {
+            match std::fs::read(dir.join(&file.path)) {
+                Ok(contents) if file.matches(&contents) => {}
+                Ok(_) => mismatched.push(file.path.as_str()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    mismatched.push(file.path.as_str());
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(mismatched)
+    }
+
+    /// Serializes the manifest as pretty-printed JSON.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\n  \"generated_at\": ");
+        json::string(
+            &mut out,
+            &self.generated_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        );
+        let _ = write!(
+            out,
+            ",\n  \"url_count\": {},\n  \"files\": [",
+            self.url_count()
+        );
+
+        for (i, file) in self.files.iter().enumerate() {
+            out.push_str(if i == 0 { "\n" } else { ",\n" });
+            out.push_str("    {\n      \"path\": ");
+            json::string(&mut out, &file.path);
+            let _ = write!(
+                out,
+                ",\n      \"size\": {},\n      \"sha256\": \"{}\",\n      \"url_count\": {},\n      \"destination\": ",
+                file.size, file.sha256, file.url_count
+            );
+            match &file.destination {
+                Some(destination) => json::string(&mut out, destination),
+                None => out.push_str("null"),
+            }
+            out.push_str("\n    }");
+        }
+
+        out.push_str(if self.files.is_empty() {
+            "]\n}\n"
+        } else {
+            "\n  ]\n}\n"
+        });
+        out
+    }
+
+    /// Writes the JSON manifest to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file can't be written.
+    pub fn write_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_json())?;
+        Ok(())
+    }
+}
+
+fn sha256_hex(contents: &[u8]) -> String {
+    Sha256::digest(contents)
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, b| {
+            let _ = write!(hex, "{b:02x}");
+            hex
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn json() {
+        let mut manifest = Manifest {
+            generated_at: Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap(),
+            files: Vec::new(),
+        };
+        assert_eq!(
+            manifest.to_json(),
+            "{\n  \"generated_at\": \"2024-05-01T08:00:00Z\",\n  \"url_count\": 0,\n  \"files\": []\n}\n"
+        );
+
+        manifest.push(ManifestFile::new("sitemap.xml", b"abc", 2).destination("s3://b/\"x\""));
+        manifest.push(ManifestFile::new("sitemap-2.xml", b"", 1));
+        let json = manifest.to_json();
+        assert!(json.contains("\"url_count\": 3"));
+        assert!(json.contains(
+            "\"sha256\": \"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\""
+        ));
+        assert!(json.contains("\"destination\": \"s3://b/\\\"x\\\"\""));
+        assert!(json.contains("\"destination\": null\n    }\n  ]"));
+    }
+
+    #[test]
+    fn verify_dir() {
+        let dir = TempDir::new("manifest");
+        std::fs::write(dir.join("a.xml"), "a").unwrap();
+        std::fs::write(dir.join("b.xml"), "changed").unwrap();
+
+        let mut manifest = Manifest::new();
+        manifest.push(ManifestFile::new("a.xml", b"a", 1));
+        manifest.push(ManifestFile::new("b.xml", b"b", 1));
+        manifest.push(ManifestFile::new("c.xml", b"c", 1));
+
+        assert_eq!(manifest.verify_dir(&dir).unwrap(), ["b.xml", "c.xml"]);
+    }
+}
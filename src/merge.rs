@@ -0,0 +1,119 @@
+//! Combining the entries of several sources.
+
+use std::sync::Arc;
+
+use crate::{
+    dedup::{resolve, ConflictStrategy},
+    Result, UrlEntry,
+};
+
+/// Merges the entries of several sources, such as the blog, shop and docs of a site, into a
+/// single list with one entry per loc.
+///
+/// When the same loc appears more than once, `strategy` picks the entry that is kept. The
+/// order of the first occurrence of each loc is preserved. Locs are compared as they are,
+/// use [`Sitemap::dedup`](crate::Sitemap::dedup) to also normalize them.
+///
+/// ```rust
+/// use sitewriter::{merge, ConflictStrategy, UrlEntry};
+///
+/// let blog = vec![UrlEntry::new("https://example.com/".parse()?, None, None, Some(0.5))];
+/// let shop = vec![UrlEntry::new("https://example.com/".parse()?, None, None, Some(1.0))];
+///
+/// let urls = merge([blog, shop], ConflictStrategy::HighestPriority)?;
+/// assert_eq!(urls[0].priority, Some(1.0));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if there is a duplicate and the strategy is [`ConflictStrategy::Error`].
+pub fn merge<I, S>(sources: I, strategy: ConflictStrategy) -> Result<Vec<UrlEntry>>
+where
+    I: IntoIterator<Item = S>,
+    S: IntoIterator<Item = UrlEntry>,
+{
+    resolve(sources.into_iter().flatten(), strategy)
+}
+
+/// Sets the [`source`](UrlEntry::source) of the entries of a source that don't have one
+/// yet, so the entries kept by [`merge`] tell where they come from.
+///
+/// ```rust
+/// use sitewriter::{merge, merge::tagged, ConflictStrategy, UrlEntry};
+///
+/// let scanned = vec![UrlEntry::new("https://example.com/".parse()?, None, None, None)];
+/// let stored = vec![UrlEntry::new("https://example.com/a".parse()?, None, None, None)];
+///
+/// let urls = merge(
+///     [tagged("scanner", scanned), tagged("database", stored)],
+///     ConflictStrategy::KeepFirst,
+/// )?;
+/// assert_eq!(urls[1].source.as_deref(), Some("database"));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn tagged<I>(source: &str, urls: I) -> impl Iterator<Item = UrlEntry>
+where
+    I: IntoIterator<Item = UrlEntry>,
+{
+    let source: Arc<str> = source.into();
+    urls.into_iter().map(move |mut entry| {
+        entry.source.get_or_insert_with(|| source.clone());
+        entry
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    fn entry(loc: &str, priority: Option<f32>) -> UrlEntry {
+        UrlEntry::new(loc.parse().unwrap(), None, None, priority)
+    }
+
+    #[test]
+    fn merges_sources() {
+        let blog = vec![
+            entry("https://a.com/", None),
+            entry("https://a.com/blog", None),
+        ];
+        let shop = vec![
+            entry("https://a.com/shop", None),
+            entry("https://a.com/", Some(0.8)),
+        ];
+
+        let urls = merge(
+            [blog.clone(), shop.clone()],
+            ConflictStrategy::HighestPriority,
+        )
+        .unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(urls[0].priority, Some(0.8));
+
+        let urls = merge([blog.clone(), shop.clone()], ConflictStrategy::KeepFirst).unwrap();
+        assert_eq!(urls[0].priority, None);
+
+        let err = merge([blog, shop], ConflictStrategy::Error).unwrap_err();
+        assert!(matches!(err, Error::DuplicateLoc(loc) if loc.as_str() == "https://a.com/"));
+    }
+
+    #[test]
+    fn sources_reach_reports() {
+        let scanned = vec![entry("https://a.com/", None), entry("ftp://a.com/", None)];
+        let pushed = vec![entry("https://a.com/", Some(1.0)).with_source("webhook")];
+
+        let urls = merge(
+            [tagged("scanner", scanned), tagged("database", pushed)],
+            ConflictStrategy::HighestPriority,
+        )
+        .unwrap();
+        assert_eq!(urls[0].source.as_deref(), Some("webhook"));
+
+        let report = crate::validate::validate(&urls);
+        assert_eq!(
+            report.to_string(),
+            "ftp://a.com/: loc is not an http or https url (from scanner)"
+        );
+    }
+}
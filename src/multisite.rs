@@ -0,0 +1,263 @@
+//! Sitemaps of many small sites served by one process.
+//!
+//! A [`MultiSiteManager`] keeps the entries of each tenant, usually keyed by its host.
+//! Changing the entries of a tenant marks it dirty, and
+//! [`regenerate`](MultiSiteManager::regenerate) only generates the sitemaps of the dirty
+//! tenants, so a platform with thousands of sites pays for the ones that changed. Handlers
+//! serve `https://{tenant}/sitemap.xml` with [`get`](MultiSiteManager::get):
+//!
+//! ```rust
+//! use sitewriter::{multisite::MultiSiteManager, Sitemap, UrlEntry};
+//!
+//! let mut manager = MultiSiteManager::new(Sitemap::new());
+//! for host in ["a.example.com", "b.example.com"] {
+//!     let loc = format!("https://{host}/").parse()?;
+//!     manager.upsert(host.to_owned(), UrlEntry::new(loc, None, None, None));
+//! }
+//! let report = manager.regenerate();
+//! assert_eq!(report.regenerated.len(), 2);
+//!
+//! // In the handler, with the host of the request.
+//! let response = manager.get("a.example.com").unwrap();
+//! assert!(std::str::from_utf8(response.xml())?.contains("https://a.example.com/"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Mutations take `&mut self`, wrap the manager in a [`RwLock`](std::sync::RwLock) to
+//! share it between handlers. The responses are reference counted, so the lock is only
+//! held to clone one out.
+
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    sync::Arc,
+};
+
+use url::Url;
+
+use crate::{diff::same_values, response::SitemapResponse, Error, Sitemap, UrlEntry};
+
+/// The entries and the generated sitemap of a tenant.
+#[derive(Debug, Default)]
+struct Site {
+    entries: BTreeMap<Url, UrlEntry>,
+    dirty: bool,
+    response: Option<Arc<SitemapResponse>>,
+}
+
+/// The outcome of [`MultiSiteManager::regenerate`].
+#[derive(Debug)]
+pub struct RegenerateReport<K> {
+    /// The tenants whose sitemap was regenerated.
+    pub regenerated: Vec<K>,
+    /// The tenants whose sitemap failed to generate, which still serve their previous
+    /// sitemap and stay dirty.
+    pub failed: Vec<(K, Error)>,
+}
+
+/// The sitemaps of many tenants, see the [module docs](self).
+#[derive(Debug)]
+pub struct MultiSiteManager<K = String> {
+    sitemap: Sitemap,
+    sites: HashMap<K, Site>,
+}
+
+impl<K: Eq + Hash + Clone> MultiSiteManager<K> {
+    /// A manager generating the sitemap of every tenant with `sitemap`.
+    #[must_use]
+    pub fn new(sitemap: Sitemap) -> Self {
+        Self {
+            sitemap,
+            sites: HashMap::new(),
+        }
+    }
+
+    /// The number of tenants.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.sites.len()
+    }
+
+    /// Whether there are no tenants.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.sites.is_empty()
+    }
+
+    /// The tenants, in no particular order.
+    pub fn tenants(&self) -> impl Iterator<Item = &K> {
+        self.sites.keys()
+    }
+
+    /// The entries of `tenant`, sorted by loc.
+    pub fn entries<Q>(&self, tenant: &Q) -> impl Iterator<Item = &UrlEntry>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.sites
+            .get(tenant)
+            .into_iter()
+            .flat_map(|site| site.entries.values())
+    }
+
+    /// Adds the entry to `tenant`, or replaces its entry with the same loc, adding the
+    /// tenant if it is new.
+    ///
+    /// Returns whether anything changed: replacing an entry with the same values doesn't
+    /// mark the tenant dirty.
+    pub fn upsert(&mut self, tenant: K, entry: UrlEntry) -> bool {
+        let site = self.sites.entry(tenant).or_default();
+        if site
+            .entries
+            .get(&entry.loc)
+            .is_some_and(|current| same_values(current, &entry))
+        {
+            return false;
+        }
+        site.entries.insert(entry.loc.clone(), entry);
+        site.dirty = true;
+        true
+    }
+
+    /// Removes the entry of `loc` from `tenant`, returning whether there was one.
+    pub fn remove<Q>(&mut self, tenant: &Q, loc: &Url) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let Some(site) = self.sites.get_mut(tenant) else {
+            return false;
+        };
+        let removed = site.entries.remove(loc).is_some();
+        site.dirty |= removed;
+        removed
+    }
+
+    /// Replaces the entries of `tenant`, adding the tenant if it is new.
+    ///
+    /// The tenant is marked dirty unless the entries are the same.
+    pub fn set_entries(&mut self, tenant: K, entries: impl IntoIterator<Item = UrlEntry>) {
+        let entries: BTreeMap<Url, UrlEntry> = entries
+            .into_iter()
+            .map(|entry| (entry.loc.clone(), entry))
+            .collect();
+        let site = self.sites.entry(tenant).or_default();
+        let same = site.entries.len() == entries.len()
+            && site
+                .entries
+                .values()
+                .zip(entries.values())
+                .all(|(a, b)| same_values(a, b));
+        if !same {
+            site.entries = entries;
+            site.dirty = true;
+        }
+    }
+
+    /// Removes `tenant` and its sitemap, returning whether it existed.
+    pub fn remove_tenant<Q>(&mut self, tenant: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.sites.remove(tenant).is_some()
+    }
+
+    /// Marks `tenant` dirty, so its sitemap is regenerated even if its entries didn't
+    /// change, as after changing what the sitemap depends on outside of the entries.
+    pub fn mark_dirty<Q>(&mut self, tenant: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Some(site) = self.sites.get_mut(tenant) {
+            site.dirty = true;
+        }
+    }
+
+    /// The tenants whose sitemap is out of date.
+    pub fn dirty(&self) -> impl Iterator<Item = &K> {
+        self.sites
+            .iter()
+            .filter(|(_, site)| site.dirty)
+            .map(|(tenant, _)| tenant)
+    }
+
+    /// Regenerates the sitemaps of the dirty tenants.
+    ///
+    /// A tenant failing to generate, for instance because its entries are empty and the
+    /// [`EmptyStrategy`](crate::EmptyStrategy) rejects that, doesn't stop the others.
+    pub fn regenerate(&mut self) -> RegenerateReport<K> {
+        let mut report = RegenerateReport {
+            regenerated: Vec::new(),
+            failed: Vec::new(),
+        };
+        for (tenant, site) in self.sites.iter_mut().filter(|(_, site)| site.dirty) {
+            let urls: Vec<&UrlEntry> = site.entries.values().collect();
+            match self.sitemap.generate_iter(Vec::new(), urls) {
+                Ok(xml) => {
+                    site.response = Some(Arc::new(SitemapResponse::new(xml)));
+                    site.dirty = false;
+                    report.regenerated.push(tenant.clone());
+                }
+                Err(e) => report.failed.push((tenant.clone(), e)),
+            }
+        }
+        report
+    }
+
+    /// The sitemap of `tenant`, as of the last successful regeneration.
+    #[must_use]
+    pub fn get<Q>(&self, tenant: &Q) -> Option<Arc<SitemapResponse>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.sites.get(tenant)?.response.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EmptyStrategy;
+
+    fn entry(host: &str, path: &str) -> UrlEntry {
+        let loc = format!("https://{host}/{path}").parse().unwrap();
+        UrlEntry::new(loc, None, None, None)
+    }
+
+    #[test]
+    fn regenerates_dirty_tenants() {
+        let mut manager = MultiSiteManager::new(Sitemap::new().on_empty(EmptyStrategy::Error));
+        manager.set_entries("a.com".to_owned(), vec![entry("a.com", "1")]);
+        manager.set_entries("b.com".to_owned(), vec![entry("b.com", "1")]);
+        assert_eq!(manager.regenerate().regenerated.len(), 2);
+        assert_eq!(manager.dirty().count(), 0);
+        let a = manager.get("a.com").unwrap();
+
+        assert!(!manager.upsert("a.com".to_owned(), entry("a.com", "1")));
+        manager.set_entries("a.com".to_owned(), vec![entry("a.com", "1")]);
+        assert!(manager.upsert("b.com".to_owned(), entry("b.com", "2")));
+        let report = manager.regenerate();
+        assert_eq!(report.regenerated, ["b.com"]);
+        assert!(Arc::ptr_eq(&manager.get("a.com").unwrap(), &a));
+        let b = manager.get("b.com").unwrap();
+        assert!(std::str::from_utf8(b.xml())
+            .unwrap()
+            .contains("https://b.com/2"));
+
+        // A failed regeneration keeps serving the previous sitemap.
+        assert!(manager.remove("a.com", &entry("a.com", "1").loc));
+        let report = manager.regenerate();
+        assert!(matches!(report.failed[..], [(_, Error::EmptyInput)]));
+        assert_eq!(manager.get("a.com").unwrap(), a);
+        assert_eq!(manager.dirty().collect::<Vec<_>>(), ["a.com"]);
+
+        assert!(manager.remove_tenant("a.com"));
+        assert!(manager.get("a.com").is_none());
+        assert_eq!(manager.len(), 1);
+    }
+}
@@ -0,0 +1,312 @@
+//! Writing generated files safely.
+
+use std::{
+    borrow::Cow,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::{
+    compress::{Compression, Encoder},
+    Result,
+};
+
+/// Where [`write_atomic_using`] stages a file before it replaces the target.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum TempStrategy {
+    /// A hidden temporary file in the directory of the target, renamed over it.
+    ///
+    /// Readers see either the old file or the new one.
+    #[default]
+    Sibling,
+    /// A temporary file in this directory, renamed over the target.
+    ///
+    /// When the rename fails, such as across filesystems, the temporary file is copied over
+    /// the target instead, and readers may see it partially written.
+    Dir(PathBuf),
+    /// The contents are staged in memory and then written over the target, for targets
+    /// such as network mounts where renaming fails.
+    ///
+    /// Nothing touches the target if generating the contents fails, but readers may see
+    /// it partially written while it is written.
+    InMemory,
+}
+
+/// Writes `contents` to `path` so that readers see either the old file or the new one,
+/// never a partially written file.
+///
+/// The contents go to a temporary file in the same directory, which is synced and then
+/// renamed over `path`. Missing parent directories are created.
+///
+/// ```rust,no_run
+/// sitewriter::write_atomic("public/sitemap.xml", b"<urlset/>")?;
+/// # Ok::<(), sitewriter::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if a directory or the file can't be written, the temporary file is
+/// removed in that case.
+pub fn write_atomic(path: impl AsRef<Path>, contents: &[u8]) -> Result<()> {
+    write_atomic_using(path, contents, &TempStrategy::Sibling)
+}
+
+/// Like [`write_atomic`], with the file staged according to `strategy`.
+///
+/// ```rust,no_run
+/// use sitewriter::output::{write_atomic_using, TempStrategy};
+///
+/// let strategy = TempStrategy::Dir("/var/tmp/sitemaps".into());
+/// write_atomic_using("/mnt/share/sitemap.xml", b"<urlset/>", &strategy)?;
+/// # Ok::<(), sitewriter::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` if a directory or the file can't be written, the temporary file is
+/// removed in that case.
+pub fn write_atomic_using(
+    path: impl AsRef<Path>,
+    contents: &[u8],
+    strategy: &TempStrategy,
+) -> Result<()> {
+    write_atomic_with(path.as_ref(), strategy, |writer| {
+        Ok(writer.write_all(contents)?)
+    })
+}
+
+/// Like [`write_atomic_using`], with the contents streamed to the temporary file by `write`.
+pub(crate) fn write_atomic_with<T>(
+    path: &Path,
+    strategy: &TempStrategy,
+    write: impl FnOnce(&mut dyn Write) -> Result<T>,
+) -> Result<T> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temp = match strategy {
+        TempStrategy::Sibling => temp_path(path),
+        TempStrategy::Dir(dir) => {
+            fs::create_dir_all(dir)?;
+            dir.join(temp_path(path).file_name().unwrap_or_default())
+        }
+        TempStrategy::InMemory => {
+            let mut contents = Vec::new();
+            let value = write(&mut contents)?;
+            let mut file = File::create(path)?;
+            file.write_all(&contents)?;
+            file.sync_all()?;
+            return Ok(value);
+        }
+    };
+
+    let result = (|| {
+        let mut file = BufWriter::new(File::create(&temp)?);
+        let value = write(&mut file)?;
+        let file = file.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        if let Err(e) = fs::rename(&temp, path) {
+            if !matches!(strategy, TempStrategy::Dir(_)) {
+                return Err(e.into());
+            }
+            fs::copy(&temp, path)?;
+            fs::remove_file(&temp)?;
+        }
+        Ok(value)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Writes `xml` with [`write_atomic_using`], compressed as the extension of `path` says.
+pub(crate) fn write_xml(path: &Path, xml: &[u8], strategy: &TempStrategy) -> Result<()> {
+    write_atomic_using(path, &xml_contents(path, xml)?, strategy)
+}
+
+/// The contents of a file holding `xml` at `path`, compressed as its extension says.
+pub(crate) fn xml_contents<'a>(path: &Path, xml: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+    Compression::from_path(path).compress(xml)
+}
+
+/// Like [`write_xml`], with the XML streamed by `write` instead of buffered.
+pub(crate) fn write_xml_with<T>(
+    path: &Path,
+    strategy: &TempStrategy,
+    write: impl FnOnce(&mut dyn Write) -> Result<T>,
+) -> Result<T> {
+    let compression = Compression::from_path(path);
+    if compression == Compression::None {
+        return write_atomic_with(path, strategy, write);
+    }
+    compression.check_enabled()?;
+    write_atomic_with(path, strategy, |writer| {
+        let mut encoder = Encoder::new(compression, writer)?;
+        let value = write(&mut encoder)?;
+        encoder.finish()?;
+        Ok(value)
+    })
+}
+
+/// Counts the bytes written through it.
+pub(crate) struct CountingWriter<W> {
+    inner: W,
+    count: usize,
+}
+
+impl<W> CountingWriter<W> {
+    pub(crate) const fn new(inner: W) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    pub(crate) const fn count(&self) -> usize {
+        self.count
+    }
+
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+
+    #[cfg(feature = "body")]
+    pub(crate) const fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes UTF-8 bytes to a [`std::fmt::Write`].
+///
+/// The XML writer writes whole strings, but a character split between two writes is
+/// still put back together.
+pub(crate) struct FmtWriter<'a, W: ?Sized> {
+    inner: &'a mut W,
+    /// The start of a character split by the last write.
+    pending: Vec<u8>,
+}
+
+impl<'a, W: std::fmt::Write + ?Sized> FmtWriter<'a, W> {
+    pub(crate) const fn new(inner: &'a mut W) -> Self {
+        Self {
+            inner,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Fails if the last write ended in the middle of a character.
+    pub(crate) fn finish(self) -> std::io::Result<()> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "incomplete utf-8 sequence",
+            ))
+        }
+    }
+}
+
+impl<W: std::fmt::Write + ?Sized> Write for FmtWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut joined = std::mem::take(&mut self.pending);
+        let bytes = if joined.is_empty() {
+            buf
+        } else {
+            joined.extend_from_slice(buf);
+            &joined
+        };
+        let (text, rest) = match std::str::from_utf8(bytes) {
+            Ok(text) => (text, &[][..]),
+            Err(e) if e.error_len().is_none() => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                (std::str::from_utf8(valid).expect("valid up to here"), rest)
+            }
+            Err(e) => return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+        };
+        self.inner.write_str(text).map_err(std::io::Error::other)?;
+        self.pending = rest.to_vec();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A unique hidden path next to `path`.
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(
+        ".{name}.{}-{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn fmt_writer_joins_split_characters() {
+        let mut out = String::new();
+        let mut writer = FmtWriter::new(&mut out);
+        let bytes = "a·b".as_bytes();
+        writer.write_all(&bytes[..2]).unwrap();
+        writer.write_all(&bytes[2..]).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(out, "a·b");
+
+        let mut writer = FmtWriter::new(&mut out);
+        writer.write_all(&bytes[..2]).unwrap();
+        assert!(writer.finish().is_err());
+        assert!(FmtWriter::new(&mut out).write(&[0xff]).is_err());
+    }
+
+    #[test]
+    fn replaces_and_creates_directories() {
+        let dir = TempDir::new("output");
+        let path = dir.join("nested/sitemap.xml");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn stages_elsewhere() {
+        let dir = TempDir::new("staged");
+        let path = dir.join("out/sitemap.xml");
+
+        let staging = TempStrategy::Dir(dir.join("tmp"));
+        write_atomic_using(&path, b"first", &staging).unwrap();
+        assert_eq!(fs::read_dir(dir.join("tmp")).unwrap().count(), 0);
+        write_atomic_using(&path, b"second", &TempStrategy::InMemory).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+}
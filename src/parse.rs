@@ -0,0 +1,1606 @@
+//! Reading existing sitemaps.
+//!
+//! The parser is meant to be fed third-party files, so it enforces [`ParseLimits`] and fails
+//! with [`Error::LimitExceeded`] instead of growing without bounds.
+//!
+//! The character encoding is detected from the byte order mark and the XML declaration.
+//! UTF-8, UTF-16, ISO-8859-1 and windows-1252 documents are transcoded, other encodings fail
+//! with [`Error::UnsupportedEncoding`].
+//!
+//! Entities are never resolved: only the predefined XML entities and character references
+//! are expanded, so external entities (XXE) and entity expansion attacks can't happen. On top
+//! of that documents with a `<!DOCTYPE>` are rejected by default, see [`ParseSecurity`].
+//!
+//! ```rust
+//! use sitewriter::parse::{ParseLimits, Parser};
+//!
+//! let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+//! <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+//!     <url>
+//!         <loc>https://example.com/</loc>
+//!         <changefreq>daily</changefreq>
+//!     </url>
+//! </urlset>"#;
+//!
+//! let parser = Parser::new().limits(ParseLimits {
+//!     max_entries: 1000,
+//!     ..Default::default()
+//! });
+//! let urls = parser.parse_str(xml).unwrap();
+//! assert_eq!(urls[0].loc.as_str(), "https://example.com/");
+//! ```
+
+use std::{
+    io::{BufRead, Cursor, Read},
+    ops::ControlFlow,
+};
+#[cfg(feature = "tokio")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use chrono::{DateTime, NaiveDate, Utc};
+#[cfg(feature = "tokio")]
+use futures_util::{Stream, StreamExt};
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+#[cfg(feature = "tokio")]
+use tokio::io::AsyncBufRead;
+
+use crate::{
+    charset::{self, Charset},
+    error::Limit,
+    index::generate_index_bytes,
+    schema::SITEMAP_NAMESPACE,
+    ChangeFreq, Error, Extensions, IndexEntry, RawExtension, Result, Sitemap, SortOrder, Url,
+    UrlEntry, MOBILE_NAMESPACE,
+};
+
+/// Resource limits enforced while parsing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ParseLimits {
+    /// Maximum number of `<url>` entries.
+    pub max_entries: usize,
+    /// Maximum element nesting depth, the root element has a depth of 1.
+    pub max_depth: usize,
+    /// Maximum length in bytes of the text of a single element.
+    pub max_text_len: usize,
+    /// Maximum size in bytes of the document, including the documents that aren't in
+    /// UTF-8 and are read into memory to be transcoded.
+    pub max_bytes: usize,
+}
+
+impl Default for ParseLimits {
+    /// The limits of the sitemap protocol, with some slack for the text length.
+    fn default() -> Self {
+        Self {
+            max_entries: 50_000,
+            max_depth: 16,
+            max_text_len: 8192,
+            max_bytes: crate::MAX_SIZE,
+        }
+    }
+}
+
+/// How the parser treats document type declarations.
+///
+/// Sitemaps never need one, a `<!DOCTYPE>` in a sitemap is either a mistake or an attempt
+/// to use entities.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum ParseSecurity {
+    /// Fail with [`Error::DoctypeNotAllowed`] when the document has a `<!DOCTYPE>`.
+    #[default]
+    RejectDoctype,
+    /// Skip the `<!DOCTYPE>`. The entities it declares are still never expanded.
+    IgnoreDoctype,
+}
+
+/// A configurable sitemap parser.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Parser {
+    limits: ParseLimits,
+    security: ParseSecurity,
+    keep_extensions: bool,
+}
+
+impl Parser {
+    /// Creates a parser with the default limits.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the resource limits.
+    #[must_use]
+    pub const fn limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets how document type declarations are treated.
+    #[must_use]
+    pub const fn security(mut self, security: ParseSecurity) -> Self {
+        self.security = security;
+        self
+    }
+
+    /// Keeps the namespaced children of the entries that the crate doesn't understand, such
+    /// as image or vendor extensions, as [`RawExtension`]s of their entry.
+    ///
+    /// Generating the parsed entries writes them back, so editing an existing sitemap
+    /// doesn't drop them. Their prefix must be declared on the root element or on the child
+    /// itself, children with an undeclared prefix are still left out.
+    ///
+    /// ```rust
+    /// use sitewriter::parse::Parser;
+    ///
+    /// let xml = r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+    ///         xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+    ///     <url>
+    ///         <loc>https://example.com/</loc>
+    ///         <image:image><image:loc>https://example.com/a.png</image:loc></image:image>
+    ///     </url>
+    /// </urlset>"#;
+    /// let urls = Parser::new().keep_extensions(true).parse_str(xml)?;
+    /// assert_eq!(urls[0].extensions.len(), 1);
+    /// assert!(sitewriter::generate_str(&urls).contains("<image:loc>https://example.com/a.png</image:loc>"));
+    /// # Ok::<(), sitewriter::Error>(())
+    /// ```
+    #[must_use]
+    pub const fn keep_extensions(mut self, keep: bool) -> Self {
+        self.keep_extensions = keep;
+        self
+    }
+
+    /// Returns an iterator over the entries of the sitemap read from `reader`.
+    ///
+    /// UTF-8 documents are parsed as they are read, documents in other encodings are read
+    /// into memory, up to [`ParseLimits::max_bytes`], and transcoded first. The iterator
+    /// stops after the first error.
+    pub fn entries<R: BufRead>(&self, reader: R) -> Entries<R> {
+        match Input::detect(reader, self.limits.max_bytes) {
+            Ok(input) => self.entries_from(input, None),
+            Err(e) => self.entries_from(Input::Decoded(Cursor::default()), Some(e)),
+        }
+    }
+
+    /// Returns a stream of the entries of the sitemap read from `reader`, the async
+    /// counterpart of [`Parser::entries`].
+    ///
+    /// UTF-8 documents are parsed as they are read, so only the entry being read of a large
+    /// remote sitemap is held in memory. Documents in other encodings are read into memory,
+    /// up to [`ParseLimits::max_bytes`], and transcoded first. The stream stops after the
+    /// first error.
+    ///
+    /// ```rust
+    /// use futures_util::StreamExt;
+    /// use sitewriter::parse::Parser;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build()?.block_on(async {
+    /// let xml = b"<urlset><url><loc>https://example.com/</loc></url></urlset>";
+    /// let mut entries = std::pin::pin!(Parser::new().entries_stream(&xml[..]));
+    /// while let Some(entry) = entries.next().await {
+    ///     println!("{}", entry?.loc);
+    /// }
+    /// # Ok::<(), sitewriter::Error>(())
+    /// # })?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn entries_stream<R: AsyncBufRead + Unpin>(
+        &self,
+        reader: R,
+    ) -> impl Stream<Item = Result<UrlEntry>> {
+        let parser = self.clone();
+        let entries = async move {
+            match Input::detect_async(reader, parser.limits.max_bytes).await {
+                Ok(input) => parser.entries_from(input, None),
+                Err(e) => parser.entries_from(Input::Decoded(Cursor::default()), Some(e)),
+            }
+        };
+        futures_util::stream::once(entries).flat_map(|entries| {
+            futures_util::stream::unfold(entries, |mut entries| async move {
+                let item = entries.next_async().await?;
+                Some((item, entries))
+            })
+        })
+    }
+
+    fn entries_from<R>(&self, input: Input<R>, error: Option<Error>) -> Entries<R> {
+        self.items_from(input, error, false)
+    }
+
+    /// The items of a sitemap, or of a sitemap index too with `accept_index`.
+    fn items_from<R>(
+        &self,
+        input: Input<R>,
+        error: Option<Error>,
+        accept_index: bool,
+    ) -> Entries<R> {
+        let mut reader = Reader::from_reader(input);
+        reader.trim_text(true);
+        Entries {
+            lenient: false,
+            problems: Vec::new(),
+            qname: String::new(),
+            extra: Extra::default(),
+            reader,
+            error,
+            buf: Vec::new(),
+            limits: self.limits,
+            security: self.security,
+            keep_extensions: self.keep_extensions,
+            namespaces: Vec::new(),
+            extension: None,
+            depth: 0,
+            root: false,
+            accept_index,
+            index: false,
+            count: 0,
+            current: None,
+            field: None,
+            text: String::new(),
+            done: false,
+        }
+    }
+
+    /// Parses the sitemap read from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the input is not a valid sitemap or a limit is exceeded.
+    pub fn parse<R: BufRead>(&self, reader: R) -> Result<Vec<UrlEntry>> {
+        self.entries(reader).collect()
+    }
+
+    /// Parses the sitemap contained in the bytes.
+    ///
+    /// Unlike [`Parser::parse`], a document that claims to be UTF-8 but isn't is decoded as
+    /// windows-1252, the most common mismatch in the wild.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the input is not a valid sitemap or a limit is exceeded.
+    pub fn parse_bytes(&self, bytes: &[u8]) -> Result<Vec<UrlEntry>> {
+        self.entries_from(Input::from_bytes(bytes)?, None).collect()
+    }
+
+    /// Parses the sitemap or sitemap index contained in the bytes, decoded as with
+    /// [`Parser::parse_bytes`].
+    ///
+    /// ```rust
+    /// use sitewriter::parse::{Document, Parser};
+    ///
+    /// let xml = br#"<sitemapindex><sitemap><loc>https://example.com/a.xml</loc></sitemap></sitemapindex>"#;
+    /// let Document::Index(sitemaps) = Parser::new().parse_document(xml)? else {
+    ///     panic!("not an index");
+    /// };
+    /// assert_eq!(sitemaps[0].loc.as_str(), "https://example.com/a.xml");
+    /// # Ok::<(), sitewriter::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the input is neither a valid sitemap nor a valid sitemap index,
+    /// or a limit is exceeded. The limit on the number of entries applies to the sitemaps
+    /// of an index too.
+    pub fn parse_document(&self, bytes: &[u8]) -> Result<Document> {
+        let mut items = self.items_from(Input::from_bytes(bytes)?, None, true);
+        let entries = items.by_ref().collect::<Result<Vec<_>>>()?;
+        Ok(if items.index {
+            Document::Index(
+                entries
+                    .into_iter()
+                    .map(|entry| IndexEntry::new(entry.loc, entry.lastmod))
+                    .collect(),
+            )
+        } else {
+            Document::Urlset(entries)
+        })
+    }
+
+    /// Parses the sitemap contained in the bytes, recovering from the problems common in
+    /// sitemaps found in the wild instead of failing on the first one.
+    ///
+    /// The problems are collected in the report: tags left unclosed, values that don't parse,
+    /// entries without a loc, which are left out. A lastmod that doesn't parse is kept as
+    /// text, and the children of an entry that aren't part of the protocol are kept with
+    /// their text. Limits are still enforced, and a document that isn't a sitemap at all
+    /// yields no entries.
+    ///
+    /// ```rust
+    /// use sitewriter::parse::Parser;
+    ///
+    /// let xml = br#"<urlset>
+    ///     <url><loc>https://example.com/</loc><lastmod>yesterday</lastmod></url>
+    ///     <url><loc>not a url</loc></url>
+    ///     <url><loc>https://example.com/about</loc><rank>3</rank>
+    /// </urlset>"#;
+    /// let report = Parser::new().parse_lenient(xml);
+    /// assert_eq!(report.entries.len(), 2);
+    /// assert_eq!(report.entries[0].raw_lastmod.as_deref(), Some("yesterday"));
+    /// assert_eq!(report.entries[1].unknown, [("rank".to_owned(), "3".to_owned())]);
+    /// assert_eq!(report.problems.len(), 3);
+    /// ```
+    #[must_use]
+    pub fn parse_lenient(&self, bytes: &[u8]) -> LenientReport {
+        let mut report = LenientReport::default();
+        let mut entries = match Input::from_bytes(bytes) {
+            Ok(input) => self.entries_from(input, None),
+            Err(e) => {
+                report.problems.push(ParseProblem::new(0, &e));
+                return report;
+            }
+        };
+        entries.lenient = true;
+        entries.reader.check_end_names(false);
+
+        while let Some(item) = entries.next() {
+            match item {
+                Ok(entry) => {
+                    let Extra {
+                        raw_lastmod,
+                        unknown,
+                    } = std::mem::take(&mut entries.extra);
+                    report.entries.push(LenientEntry {
+                        entry,
+                        raw_lastmod,
+                        unknown,
+                    });
+                }
+                Err(e) => {
+                    let position = entries.reader.buffer_position();
+                    entries.problems.push(ParseProblem::new(position, &e));
+                }
+            }
+        }
+        report.problems = entries.problems;
+        report
+    }
+
+    /// Parses the sitemap contained in the string.
+    ///
+    /// The string is already decoded, so the encoding in the XML declaration is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the input is not a valid sitemap or a limit is exceeded.
+    pub fn parse_str(&self, xml: &str) -> Result<Vec<UrlEntry>> {
+        let xml = xml.strip_prefix('\u{feff}').unwrap_or(xml);
+        self.entries_from(Input::stream(xml.as_bytes()), None)
+            .collect()
+    }
+}
+
+/// The document, either read as is or transcoded to UTF-8.
+enum Input<R> {
+    /// The bytes read to detect the encoding, then the rest of the reader, of which only
+    /// the given number of bytes is read.
+    Stream(Cursor<Vec<u8>>, R, usize),
+    Decoded(Cursor<Vec<u8>>),
+}
+
+/// How many bytes are read at most to find the end of the XML declaration.
+const SNIFF_LEN: usize = 1024;
+
+impl<'a> Input<&'a [u8]> {
+    /// The input of a whole document, UTF-8 documents that aren't valid UTF-8 being
+    /// read as windows-1252.
+    fn from_bytes(bytes: &'a [u8]) -> Result<Self> {
+        let (charset, bom) = charset::sniff(bytes)?;
+        let bytes = &bytes[bom..];
+        Ok(match charset {
+            Charset::Utf8 if std::str::from_utf8(bytes).is_ok() => Self::stream(bytes),
+            Charset::Utf8 => Self::decoded(bytes, Charset::Windows1252),
+            charset => Self::decoded(bytes, charset),
+        })
+    }
+}
+
+impl<R: BufRead> Input<R> {
+    /// Detects the encoding from the start of the document, up to the end of the XML
+    /// declaration. Documents in UTF-8 are read up to one byte past `max_bytes` as they are
+    /// parsed, the others are read up to `max_bytes` to be transcoded.
+    fn detect(mut reader: R, max_bytes: usize) -> Result<Self> {
+        let mut head = Vec::new();
+        while !is_sniffed(&head) {
+            let buf = reader.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            let len = buf.len().min(SNIFF_LEN - head.len());
+            head.extend_from_slice(&buf[..len]);
+            reader.consume(len);
+        }
+        let (charset, bom) = charset::sniff(&head)?;
+        let allowed = max_bytes.saturating_add(1).saturating_sub(head.len());
+        head.drain(..bom);
+
+        if charset == Charset::Utf8 {
+            return Ok(Self::Stream(Cursor::new(head), reader, allowed));
+        }
+        let rest = (max_bytes + 1).saturating_sub(head.len()) as u64;
+        (&mut reader).take(rest).read_to_end(&mut head)?;
+        check_size(head.len(), max_bytes)?;
+        Ok(Self::decoded(&head, charset))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: AsyncBufRead + Unpin> Input<R> {
+    /// The async counterpart of [`Input::detect`].
+    async fn detect_async(mut reader: R, max_bytes: usize) -> Result<Self> {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+        let mut head = Vec::new();
+        while !is_sniffed(&head) {
+            let buf = reader.fill_buf().await?;
+            if buf.is_empty() {
+                break;
+            }
+            let len = buf.len().min(SNIFF_LEN - head.len());
+            head.extend_from_slice(&buf[..len]);
+            reader.consume(len);
+        }
+        let (charset, bom) = charset::sniff(&head)?;
+        let allowed = max_bytes.saturating_add(1).saturating_sub(head.len());
+        head.drain(..bom);
+
+        if charset == Charset::Utf8 {
+            return Ok(Self::Stream(Cursor::new(head), reader, allowed));
+        }
+        let rest = (max_bytes + 1).saturating_sub(head.len()) as u64;
+        (&mut reader).take(rest).read_to_end(&mut head).await?;
+        check_size(head.len(), max_bytes)?;
+        Ok(Self::decoded(&head, charset))
+    }
+}
+
+/// Whether enough of the document is read to detect its encoding.
+fn is_sniffed(head: &[u8]) -> bool {
+    head.len() >= SNIFF_LEN || head.windows(2).any(|w| w == b"?>")
+}
+
+const fn check_size(len: usize, max_bytes: usize) -> Result<()> {
+    if len > max_bytes {
+        return Err(Error::LimitExceeded {
+            limit: Limit::Size,
+            max: max_bytes,
+        });
+    }
+    Ok(())
+}
+
+impl<R> Input<R> {
+    /// A document already in memory, which there is no point in reading partially.
+    const fn stream(reader: R) -> Self {
+        Self::Stream(Cursor::new(Vec::new()), reader, usize::MAX)
+    }
+
+    /// Fails with [`Limit::Size`] once more than `max_bytes` were read from the reader.
+    const fn check_read(&self, max_bytes: usize) -> Result<()> {
+        match self {
+            Self::Stream(_, _, 0) => Err(Error::LimitExceeded {
+                limit: Limit::Size,
+                max: max_bytes,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn decoded(bytes: &[u8], charset: Charset) -> Self {
+        Self::Decoded(Cursor::new(charset::decode(bytes, charset).into_bytes()))
+    }
+}
+
+/// Whether the bytes read to detect the encoding aren't all parsed yet.
+const fn has_remaining(head: &Cursor<Vec<u8>>) -> bool {
+    head.position() < head.get_ref().len() as u64
+}
+
+impl<R: BufRead> Read for Input<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for Input<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        match self {
+            Self::Stream(head, _, _) if has_remaining(head) => head.fill_buf(),
+            Self::Stream(_, r, allowed) => {
+                let buf = r.fill_buf()?;
+                Ok(&buf[..buf.len().min(*allowed)])
+            }
+            Self::Decoded(r) => r.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Self::Stream(head, _, _) if has_remaining(head) => head.consume(amt),
+            Self::Stream(_, r, allowed) => {
+                *allowed -= amt;
+                r.consume(amt);
+            }
+            Self::Decoded(r) => r.consume(amt),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: AsyncBufRead + Unpin> tokio::io::AsyncRead for Input<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let available = std::task::ready!(self.as_mut().poll_fill_buf(cx))?;
+        let len = available.len().min(buf.remaining());
+        buf.put_slice(&available[..len]);
+        self.consume(len);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: AsyncBufRead + Unpin> AsyncBufRead for Input<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>> {
+        match self.get_mut() {
+            Self::Stream(head, _, _) if has_remaining(head) => Pin::new(head).poll_fill_buf(cx),
+            Self::Stream(_, r, allowed) => Pin::new(r)
+                .poll_fill_buf(cx)
+                .map_ok(|buf| &buf[..buf.len().min(*allowed)]),
+            Self::Decoded(r) => Pin::new(r).poll_fill_buf(cx),
+        }
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        match self.get_mut() {
+            Self::Stream(head, _, _) if has_remaining(head) => Pin::new(head).consume(amt),
+            Self::Stream(_, r, allowed) => {
+                *allowed -= amt;
+                Pin::new(r).consume(amt);
+            }
+            Self::Decoded(r) => Pin::new(r).consume(amt),
+        }
+    }
+}
+
+/// The outcome of [`Parser::parse_lenient`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LenientReport {
+    /// The entries that have a valid loc.
+    pub entries: Vec<LenientEntry>,
+    /// The problems found, in document order.
+    pub problems: Vec<ParseProblem>,
+}
+
+/// An entry read by [`Parser::parse_lenient`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LenientEntry {
+    /// The entry, without the values that didn't parse.
+    pub entry: UrlEntry,
+    /// The text of the lastmod, when it didn't parse.
+    pub raw_lastmod: Option<String>,
+    /// The qualified names and text of the children that aren't part of the protocol, and
+    /// aren't [kept as extensions](Parser::keep_extensions).
+    pub unknown: Vec<(String, String)>,
+}
+
+/// A problem [`Parser::parse_lenient`] recovered from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParseProblem {
+    /// The byte offset in the document where the problem was noticed.
+    pub position: usize,
+    /// What is wrong.
+    pub message: String,
+}
+
+impl ParseProblem {
+    fn new(position: usize, message: &impl std::fmt::Display) -> Self {
+        Self {
+            position,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at byte {}: {}", self.position, self.message)
+    }
+}
+
+/// A parsed document, see [`Parser::parse_document`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Document {
+    /// A sitemap, with its entries.
+    Urlset(Vec<UrlEntry>),
+    /// A sitemap index, with the sitemaps it lists.
+    Index(Vec<IndexEntry>),
+}
+
+/// Parses the sitemap read from `reader` with the default limits.
+///
+/// # Errors
+///
+/// Will return `Err` if the input is not a valid sitemap or a limit is exceeded.
+pub fn parse<R: BufRead>(reader: R) -> Result<Vec<UrlEntry>> {
+    Parser::new().parse(reader)
+}
+
+/// Parses the sitemap contained in the string with the default limits.
+///
+/// # Errors
+///
+/// Will return `Err` if the input is not a valid sitemap or a limit is exceeded.
+pub fn parse_str(xml: &str) -> Result<Vec<UrlEntry>> {
+    Parser::new().parse_str(xml)
+}
+
+/// Parses a sitemap or sitemap index and generates it again in a canonical form: the
+/// entries sorted by loc, written with the default options of [`Sitemap`].
+///
+/// Documents listing the same entries canonicalize to the same bytes, whatever their
+/// order, formatting or encoding. Extensions aren't kept, so they are left out.
+///
+/// ```rust
+/// let a = b"<urlset><url><loc>https://example.com/b</loc></url>\
+///           <url><loc>https://example.com/a</loc></url></urlset>";
+/// let b = b"<urlset>\n  <url><loc>https://example.com/a</loc></url>\n\
+///           <url><loc>https://example.com/b</loc></url>\n</urlset>";
+/// assert_eq!(sitewriter::canonicalize(a)?, sitewriter::canonicalize(b)?);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Errors
+///
+/// Will return `Err` as [`Parser::parse_document`].
+pub fn canonicalize(xml: &[u8]) -> Result<Vec<u8>> {
+    match Parser::new().parse_document(xml)? {
+        Document::Urlset(mut urls) => {
+            urls.sort_by(|a, b| {
+                SortOrder::Loc
+                    .compare(a, b)
+                    .then_with(|| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            });
+            Ok(Sitemap::new().generate_bytes(&urls))
+        }
+        Document::Index(mut sitemaps) => {
+            sitemaps.sort();
+            Ok(generate_index_bytes(&sitemaps))
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Field {
+    Loc,
+    Lastmod,
+    ChangeFreq,
+    Priority,
+    Mobile,
+    /// A child outside of the protocol, kept by the lenient parser.
+    Unknown,
+}
+
+#[derive(Debug, Default)]
+struct Partial {
+    loc: Option<Url>,
+    lastmod: Option<DateTime<Utc>>,
+    changefreq: Option<ChangeFreq>,
+    priority: Option<f32>,
+    mobile: bool,
+    /// Whether the loc didn't parse, which the lenient parser already reported.
+    invalid_loc: bool,
+    extensions: Extensions,
+    extra: Extra,
+}
+
+/// What the lenient parser keeps on top of the entry.
+#[derive(Debug, Default)]
+struct Extra {
+    raw_lastmod: Option<String>,
+    unknown: Vec<(String, String)>,
+}
+
+/// An iterator over the entries of a sitemap, created by [`Parser::entries`].
+pub struct Entries<R> {
+    reader: Reader<Input<R>>,
+    error: Option<Error>,
+    buf: Vec<u8>,
+    limits: ParseLimits,
+    security: ParseSecurity,
+    /// Whether to keep the unknown children, see [`Parser::keep_extensions`].
+    keep_extensions: bool,
+    /// The prefixes declared on the root element, with their namespace.
+    namespaces: Vec<(String, String)>,
+    /// The unknown child being read.
+    extension: Option<RawExtension>,
+    depth: usize,
+    root: bool,
+    /// Whether a `<sitemapindex>` root is accepted.
+    accept_index: bool,
+    /// Whether the root is a `<sitemapindex>`.
+    index: bool,
+    count: usize,
+    current: Option<Partial>,
+    field: Option<Field>,
+    text: String,
+    done: bool,
+    /// Whether to recover from problems, see [`Parser::parse_lenient`].
+    lenient: bool,
+    problems: Vec<ParseProblem>,
+    /// The qualified name of the element being entered, kept when lenient.
+    qname: String,
+    /// The extra values of the last entry returned.
+    extra: Extra,
+}
+
+impl<R> std::fmt::Debug for Entries<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Entries")
+            .field("limits", &self.limits)
+            .field("depth", &self.depth)
+            .field("count", &self.count)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R> Entries<R> {
+    fn enter(&mut self, name: &[u8]) -> Result<()> {
+        self.depth += 1;
+        if self.depth > self.limits.max_depth {
+            return Err(Error::LimitExceeded {
+                limit: Limit::Depth,
+                max: self.limits.max_depth,
+            });
+        }
+
+        match (self.depth, name) {
+            (1, b"urlset") => self.root = true,
+            (1, b"sitemapindex") if self.accept_index => {
+                self.root = true;
+                self.index = true;
+            }
+            (1, _) => {
+                return Err(Error::UnexpectedRoot(
+                    String::from_utf8_lossy(name).into_owned(),
+                ))
+            }
+            (2, b"url") if !self.index => self.start_entry()?,
+            (2, b"sitemap") if self.index => self.start_entry()?,
+            (3, _) if self.extension.is_some() => self.field = None,
+            (3, _) if self.current.is_some() => {
+                self.field = match name {
+                    b"loc" => Some(Field::Loc),
+                    b"lastmod" => Some(Field::Lastmod),
+                    b"changefreq" => Some(Field::ChangeFreq),
+                    b"priority" => Some(Field::Priority),
+                    b"mobile" => Some(Field::Mobile),
+                    _ if self.lenient => {
+                        if let Some(current) = &mut self.current {
+                            let name = std::mem::take(&mut self.qname);
+                            current.extra.unknown.push((name, String::new()));
+                        }
+                        Some(Field::Unknown)
+                    }
+                    _ => None,
+                };
+                self.text.clear();
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// The prefix and namespace of `e` if it is a child of an entry to keep as an extension.
+    fn extension_of(&self, e: &BytesStart) -> Option<(String, String)> {
+        if !self.keep_extensions || self.depth != 2 || self.current.is_none() {
+            return None;
+        }
+        let name = e.name();
+        let prefix = std::str::from_utf8(name.prefix()?.into_inner()).ok()?;
+        let declaration = format!("xmlns:{prefix}");
+        let namespace = e
+            .attributes()
+            .flatten()
+            .find(|attribute| attribute.key.as_ref() == declaration.as_bytes())
+            .and_then(|attribute| attribute.unescape_value().ok())
+            .map(Into::into)
+            .or_else(|| {
+                self.namespaces
+                    .iter()
+                    .find(|(p, _)| p == prefix)
+                    .map(|(_, namespace)| namespace.clone())
+            })?;
+        (namespace != SITEMAP_NAMESPACE && namespace != MOBILE_NAMESPACE)
+            .then(|| (prefix.to_owned(), namespace))
+    }
+
+    /// Records the prefixes declared on the root element.
+    fn declare_namespaces(&mut self, e: &BytesStart) {
+        for attribute in e.attributes().flatten() {
+            let Some(prefix) = attribute.key.as_ref().strip_prefix(b"xmlns:") else {
+                continue;
+            };
+            if let (Ok(prefix), Ok(namespace)) =
+                (std::str::from_utf8(prefix), attribute.unescape_value())
+            {
+                self.namespaces
+                    .push((prefix.to_owned(), namespace.into_owned()));
+            }
+        }
+    }
+
+    /// Starts reading `e` as an extension, or adds it to the one being read.
+    fn capture(&mut self, event: Event<'static>) {
+        if let Event::Start(e) | Event::Empty(e) = &event {
+            if self.depth == 0 && self.keep_extensions {
+                self.declare_namespaces(e);
+            }
+            if let Some((prefix, namespace)) = self.extension_of(e) {
+                self.extension = Some(RawExtension::new(prefix, namespace));
+            }
+        }
+        if let Some(extension) = &mut self.extension {
+            extension.push(event);
+        }
+    }
+
+    /// Attaches the extension to its entry once its element is closed.
+    fn finish_extension(&mut self) {
+        if self.depth > 2 {
+            return;
+        }
+        if let (Some(extension), Some(current)) = (self.extension.take(), &mut self.current) {
+            current.extensions.push(extension);
+        }
+    }
+
+    /// Starts a `<url>`, or a `<sitemap>` of an index.
+    fn start_entry(&mut self) -> Result<()> {
+        if self.count >= self.limits.max_entries {
+            return Err(Error::LimitExceeded {
+                limit: Limit::Entries,
+                max: self.limits.max_entries,
+            });
+        }
+        self.current = Some(Partial::default());
+        Ok(())
+    }
+
+    fn push_text(&mut self, text: &str) -> Result<()> {
+        let len = if self.field.is_some() {
+            self.text.push_str(text);
+            self.text.len()
+        } else {
+            text.len()
+        };
+
+        if len > self.limits.max_text_len {
+            return Err(Error::LimitExceeded {
+                limit: Limit::TextLength,
+                max: self.limits.max_text_len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Records a problem and carries on when lenient, fails otherwise.
+    fn recover(&mut self, error: Error) -> Result<()> {
+        if !self.lenient {
+            return Err(error);
+        }
+        self.note(&error);
+        Ok(())
+    }
+
+    /// Records a problem at the current position.
+    fn note(&mut self, problem: &impl std::fmt::Display) {
+        let position = self.reader.buffer_position();
+        self.problems.push(ParseProblem::new(position, problem));
+    }
+
+    /// Closes the entry being read, whose closing tag is missing.
+    fn close_entry(&mut self) -> Result<Option<UrlEntry>> {
+        let tag = if self.index { "sitemap" } else { "url" };
+        self.note(&format!("unclosed <{tag}>"));
+        self.extension = None;
+        while self.depth > 2 {
+            self.leave()?;
+        }
+        self.leave()
+    }
+
+    fn leave(&mut self) -> Result<Option<UrlEntry>> {
+        let depth = self.depth;
+        self.depth = self.depth.saturating_sub(1);
+
+        match depth {
+            3 => {
+                if let (Some(field), Some(current)) = (self.field.take(), &mut self.current) {
+                    let text = self.text.trim();
+                    let result = match field {
+                        Field::Unknown => {
+                            if let Some((_, value)) = current.extra.unknown.last_mut() {
+                                text.clone_into(value);
+                            }
+                            Ok(())
+                        }
+                        Field::Lastmod if self.lenient && parse_lastmod(text).is_none() => {
+                            current.extra.raw_lastmod = Some(text.to_owned());
+                            Err(Error::InvalidValue {
+                                element: "lastmod",
+                                value: text.to_owned(),
+                            })
+                        }
+                        field => set_field(current, field, text),
+                    };
+                    if let Err(e) = result {
+                        current.invalid_loc |= field == Field::Loc;
+                        self.recover(e)?;
+                    }
+                }
+                Ok(None)
+            }
+            2 => match self.current.take() {
+                Some(partial) => {
+                    let Some(loc) = partial.loc else {
+                        if !partial.invalid_loc {
+                            self.recover(Error::MissingElement("loc"))?;
+                        }
+                        return Ok(None);
+                    };
+                    self.count += 1;
+                    self.extra = partial.extra;
+                    Ok(Some(UrlEntry {
+                        loc,
+                        lastmod: partial.lastmod,
+                        changefreq: partial.changefreq,
+                        priority: partial.priority,
+                        extensions: partial.extensions,
+                        mobile: partial.mobile,
+                        source: None,
+                    }))
+                }
+                None => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Handles an event, breaking with the entry it completes or at the end of the document.
+    fn handle(&mut self, event: Event<'_>) -> Result<ControlFlow<Option<UrlEntry>>> {
+        check_size(self.reader.buffer_position(), self.limits.max_bytes)?;
+        let entry = match event {
+            Event::Start(e) => {
+                let name = e.local_name().as_ref().to_vec();
+                if self.lenient {
+                    self.qname = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                }
+                let entry_tag: &[u8] = if self.index { b"sitemap" } else { b"url" };
+                let implicit_close = self.lenient
+                    && self.current.is_some()
+                    && self.extension.is_none()
+                    && name == entry_tag;
+                if implicit_close {
+                    let entry = self.close_entry()?;
+                    self.enter(&name)?;
+                    entry
+                } else {
+                    if self.keep_extensions {
+                        let event = Event::Start(e.into_owned());
+                        self.capture(event);
+                    }
+                    self.enter(&name)?;
+                    None
+                }
+            }
+            Event::Empty(e) => {
+                let name = e.local_name().as_ref().to_vec();
+                if self.lenient {
+                    self.qname = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                }
+                if self.keep_extensions {
+                    let event = Event::Empty(e.into_owned());
+                    self.capture(event);
+                }
+                self.enter(&name)?;
+                let entry = self.leave()?;
+                self.finish_extension();
+                entry
+            }
+            Event::End(e) => {
+                if self.extension.is_some() {
+                    let event = Event::End(e.into_owned());
+                    self.capture(event);
+                }
+                let entry = self.leave()?;
+                self.finish_extension();
+                entry
+            }
+            Event::Text(e) if self.extension.is_some() => {
+                let e = e.into_owned();
+                self.push_text(&String::from_utf8_lossy(&e))?;
+                self.capture(Event::Text(e));
+                None
+            }
+            Event::CData(e) if self.extension.is_some() => {
+                let e = e.into_owned();
+                self.push_text(&String::from_utf8_lossy(&e))?;
+                self.capture(Event::CData(e));
+                None
+            }
+            Event::Text(e) => {
+                let text = match e.unescape() {
+                    Ok(text) => text.into_owned(),
+                    Err(error) => {
+                        let raw = String::from_utf8_lossy(&e).into_owned();
+                        self.recover(error.into())?;
+                        raw
+                    }
+                };
+                self.push_text(&text)?;
+                None
+            }
+            Event::CData(e) => {
+                let text = String::from_utf8_lossy(&e).into_owned();
+                self.push_text(&text)?;
+                None
+            }
+            Event::DocType(_) if self.security == ParseSecurity::RejectDoctype => {
+                return Err(Error::DoctypeNotAllowed);
+            }
+            Event::Eof if self.lenient && self.depth > 0 => {
+                let entry = if self.current.is_some() {
+                    self.close_entry()?
+                } else {
+                    None
+                };
+                if self.depth > 0 {
+                    self.note(&"the document ends before its root element is closed");
+                    self.depth = 0;
+                }
+                return Ok(ControlFlow::Break(entry));
+            }
+            Event::Eof => {
+                return match (self.depth, self.root) {
+                    (0, true) => Ok(ControlFlow::Break(None)),
+                    (0, false) => Err(Error::MissingElement("urlset")),
+                    _ => Err(quick_xml::Error::UnexpectedEof("urlset".to_owned()).into()),
+                };
+            }
+            _ => None,
+        };
+
+        Ok(if entry.is_some() {
+            ControlFlow::Break(entry)
+        } else {
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Ends the iteration after the last entry or the first error.
+    const fn outcome(&mut self, result: Result<Option<UrlEntry>>) -> Option<Result<UrlEntry>> {
+        let item = result.transpose();
+        self.done = !matches!(item, Some(Ok(_)));
+        item
+    }
+}
+
+impl<R: BufRead> Entries<R> {
+    fn next_entry(&mut self) -> Result<Option<UrlEntry>> {
+        let mut buf = std::mem::take(&mut self.buf);
+        loop {
+            buf.clear();
+            let event = self.reader.read_event_into(&mut buf);
+            self.reader.get_ref().check_read(self.limits.max_bytes)?;
+            if let ControlFlow::Break(entry) = self.handle(event?)? {
+                self.buf = buf;
+                return Ok(entry);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: AsyncBufRead + Unpin> Entries<R> {
+    async fn next_entry_async(&mut self) -> Result<Option<UrlEntry>> {
+        let mut buf = std::mem::take(&mut self.buf);
+        loop {
+            buf.clear();
+            let event = self.reader.read_event_into_async(&mut buf).await;
+            self.reader.get_ref().check_read(self.limits.max_bytes)?;
+            if let ControlFlow::Break(entry) = self.handle(event?)? {
+                self.buf = buf;
+                return Ok(entry);
+            }
+        }
+    }
+
+    async fn next_async(&mut self) -> Option<Result<UrlEntry>> {
+        if self.done {
+            return None;
+        }
+        let result = match self.error.take() {
+            Some(e) => Err(e),
+            None => self.next_entry_async().await,
+        };
+        self.outcome(result)
+    }
+}
+
+impl<R: BufRead> Iterator for Entries<R> {
+    type Item = Result<UrlEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.error.take().map_or_else(|| self.next_entry(), Err);
+        self.outcome(result)
+    }
+}
+
+fn set_field(partial: &mut Partial, field: Field, text: &str) -> Result<()> {
+    match field {
+        Field::Loc => {
+            partial.loc = Some(text.parse().map_err(|source| Error::InvalidUrl {
+                value: text.to_owned(),
+                source,
+            })?);
+        }
+        Field::Lastmod => {
+            partial.lastmod = Some(parse_lastmod(text).ok_or_else(|| Error::InvalidValue {
+                element: "lastmod",
+                value: text.to_owned(),
+            })?);
+        }
+        Field::ChangeFreq => partial.changefreq = Some(text.parse()?),
+        Field::Priority => {
+            partial.priority = Some(
+                text.parse::<f32>()
+                    .ok()
+                    .filter(|p| p.is_finite())
+                    .ok_or_else(|| Error::InvalidValue {
+                        element: "priority",
+                        value: text.to_owned(),
+                    })?,
+            );
+        }
+        Field::Mobile => partial.mobile = true,
+        // Kept by the caller.
+        Field::Unknown => {}
+    }
+    Ok(())
+}
+
+/// Parses a date in one of the [W3C Datetime](https://www.w3.org/TR/NOTE-datetime) formats
+/// allowed by the protocol, such as `2024-05-01` or `2024-05-01T08:00:00+02:00`.
+///
+/// Returns `None` if it isn't in one of them.
+#[must_use]
+pub fn parse_lastmod(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Some(date.with_timezone(&Utc));
+    }
+    // Hours and minutes without seconds.
+    for format in ["%Y-%m-%dT%H:%M%:z", "%Y-%m-%dT%H:%MZ"] {
+        if let Ok(date) = DateTime::parse_from_str(text, format) {
+            return Some(date.with_timezone(&Utc));
+        }
+        if let Ok(date) = chrono::NaiveDateTime::parse_from_str(text, format) {
+            return Some(date.and_utc());
+        }
+    }
+
+    let date = match text.len() {
+        4 => NaiveDate::parse_from_str(&format!("{text}-01-01"), "%Y-%m-%d"),
+        7 => NaiveDate::parse_from_str(&format!("{text}-01"), "%Y-%m-%d"),
+        _ => NaiveDate::parse_from_str(text, "%Y-%m-%d"),
+    };
+    date.ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| d.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let urls = vec![
+            UrlEntry {
+                loc: "https://a.com/?q=1&p='2'".parse().unwrap(),
+                lastmod: Some(Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap()),
+                changefreq: Some(ChangeFreq::Weekly),
+                priority: Some(0.5),
+                extensions: Default::default(),
+                mobile: false,
+                source: None,
+            },
+            UrlEntry::new("https://a.com/b".parse().unwrap(), None, None, None),
+        ];
+
+        let parsed = parse_str(&crate::generate_str(&urls)).unwrap();
+        assert_eq!(parsed, urls);
+    }
+
+    #[test]
+    fn lastmod_formats() {
+        let day = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        assert_eq!(parse_lastmod("2024-03-01"), Some(day));
+        assert_eq!(parse_lastmod("2024-03"), Some(day));
+        assert_eq!(parse_lastmod("2024-03-01T01:00+01:00"), Some(day));
+        assert_eq!(parse_lastmod("2024-03-01T00:00:00Z"), Some(day));
+        assert_eq!(parse_lastmod("yesterday"), None);
+    }
+
+    #[test]
+    fn enforces_limits() {
+        let xml = "<urlset><url><loc>https://a.com/1</loc></url><url><loc>https://a.com/2</loc></url></urlset>";
+        let parser = |limits| Parser::new().limits(limits);
+
+        let err = parser(ParseLimits {
+            max_entries: 1,
+            ..Default::default()
+        })
+        .parse_str(xml)
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LimitExceeded {
+                limit: Limit::Entries,
+                max: 1
+            }
+        ));
+
+        let err = parser(ParseLimits {
+            max_text_len: 10,
+            ..Default::default()
+        })
+        .parse_str(xml)
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LimitExceeded {
+                limit: Limit::TextLength,
+                ..
+            }
+        ));
+
+        // An endless loc is read no further than the size limit.
+        struct Endless(usize);
+
+        impl Read for Endless {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let head = b"<urlset><url><loc>https://a.com/";
+                for (i, byte) in buf.iter_mut().enumerate() {
+                    *byte = head.get(self.0 + i).copied().unwrap_or(b'a');
+                }
+                self.0 += buf.len();
+                Ok(buf.len())
+            }
+        }
+        let mut endless = Endless(0);
+        let err = parser(ParseLimits {
+            max_bytes: 1000,
+            max_text_len: 100,
+            ..Default::default()
+        })
+        .parse(std::io::BufReader::with_capacity(64, &mut endless))
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LimitExceeded {
+                limit: Limit::Size,
+                max: 1000
+            }
+        ));
+        assert!(endless.0 <= 1064, "read {} bytes", endless.0);
+
+        let nested = format!("<urlset>{}{}</urlset>", "<a>".repeat(20), "</a>".repeat(20));
+        let err = Parser::new().parse_str(&nested).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LimitExceeded {
+                limit: Limit::Depth,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        assert!(matches!(
+            parse_str("<sitemapindex/>"),
+            Err(Error::UnexpectedRoot(_))
+        ));
+        assert!(matches!(
+            parse_str("<urlset><url><priority>1</priority></url></urlset>"),
+            Err(Error::MissingElement("loc"))
+        ));
+        assert!(matches!(
+            parse_str("<urlset><url><loc>nope</loc></url></urlset>"),
+            Err(Error::InvalidUrl { .. })
+        ));
+        assert!(parse_str("<urlset><url>").is_err());
+        assert_eq!(parse_str("<urlset/>").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn parses_documents() {
+        let parser = Parser::new();
+        assert_eq!(
+            parser
+                .parse_document(b"<urlset><url><loc>https://a.com/</loc></url></urlset>")
+                .unwrap(),
+            Document::Urlset(vec![UrlEntry::new(
+                "https://a.com/".parse().unwrap(),
+                None,
+                None,
+                None
+            )])
+        );
+        assert_eq!(
+            parser.parse_document(b"<sitemapindex/>").unwrap(),
+            Document::Index(vec![])
+        );
+        assert!(matches!(
+            parser.parse_document(b"<sitemapindex><url><loc>https://a.com/</loc></url></sitemapindex>"),
+            Ok(Document::Index(sitemaps)) if sitemaps.is_empty()
+        ));
+    }
+
+    #[test]
+    fn canonicalizes() {
+        let urls = b"<urlset><url><loc>https://a.com/b</loc><priority>0.50</priority></url>\
+<url><loc>https://a.com/a</loc></url></urlset>";
+        let canonical = canonicalize(urls).unwrap();
+        assert_eq!(canonicalize(&canonical).unwrap(), canonical);
+        let parsed = parse(&canonical[..]).unwrap();
+        assert_eq!(parsed[0].loc.as_str(), "https://a.com/a");
+        assert_eq!(parsed[1].priority, Some(0.5));
+
+        let index = b"<sitemapindex><sitemap><loc>https://a.com/2.xml</loc></sitemap>\
+<sitemap><loc>https://a.com/1.xml</loc></sitemap></sitemapindex>";
+        let canonical = String::from_utf8(canonicalize(index).unwrap()).unwrap();
+        assert!(canonical.find("1.xml").unwrap() < canonical.find("2.xml").unwrap());
+        assert!(canonical.contains("<sitemapindex"));
+    }
+
+    #[test]
+    fn transcodes() {
+        let latin1 = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\
+<urlset><url><loc>https://a.com/caf\xE9</loc></url></urlset>";
+        let expected = "https://a.com/caf%C3%A9";
+
+        assert_eq!(parse(&latin1[..]).unwrap()[0].loc.as_str(), expected);
+
+        let utf16: Vec<u8> = "\u{feff}<urlset><url><loc>https://a.com/café</loc></url></urlset>"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(parse(&utf16[..]).unwrap()[0].loc.as_str(), expected);
+
+        // The declaration is found past the first read of a small buffer.
+        let small = std::io::BufReader::with_capacity(8, &latin1[..]);
+        let parser = Parser::new();
+        assert_eq!(parser.parse(small).unwrap()[0].loc.as_str(), expected);
+        let limited = Parser::new().limits(ParseLimits {
+            max_bytes: 40,
+            ..Default::default()
+        });
+        for doc in [
+            &latin1[..],
+            &utf16[..],
+            b"<urlset><url><loc>https://a.com/</loc></url></urlset>",
+        ] {
+            assert!(matches!(
+                limited.parse(doc),
+                Err(Error::LimitExceeded {
+                    limit: Limit::Size,
+                    max: 40
+                })
+            ));
+        }
+
+        // Declared as UTF-8 but actually windows-1252.
+        let mismatch = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+<urlset><url><loc>https://a.com/caf\xE9</loc></url></urlset>";
+        assert_eq!(
+            parser.parse_bytes(mismatch).unwrap()[0].loc.as_str(),
+            expected
+        );
+        assert!(parser.parse(&mismatch[..]).is_err());
+
+        assert!(matches!(
+            parse(&b"<?xml version=\"1.0\" encoding=\"EUC-JP\"?><urlset/>"[..]),
+            Err(Error::UnsupportedEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn doctype_and_entities() {
+        let xxe = r#"<?xml version="1.0"?>
+<!DOCTYPE urlset [<!ENTITY xxe SYSTEM "file:///etc/passwd">]>
+<urlset><url><loc>https://a.com/&xxe;</loc></url></urlset>"#;
+
+        assert!(matches!(parse_str(xxe), Err(Error::DoctypeNotAllowed)));
+
+        // Even when the doctype is skipped, the entity is never resolved.
+        let parser = Parser::new().security(ParseSecurity::IgnoreDoctype);
+        assert!(matches!(parser.parse_str(xxe), Err(Error::Xml(_))));
+
+        let plain =
+            "<!DOCTYPE urlset><urlset><url><loc>https://a.com/?a=1&amp;b=2</loc></url></urlset>";
+        let urls = parser.parse_str(plain).unwrap();
+        assert_eq!(urls[0].loc.query(), Some("a=1&b=2"));
+    }
+
+    #[test]
+    fn recovers_leniently() {
+        let xml = b"<urlset>\
+            <url><loc>https://a.com/1</loc><changefreq>often</changefreq>\
+            <url><loc>https://a.com/2?a=1&b</loc><news:news><news:title>T</news:title></news:news></url>\
+            <url><lastmod>2024-05-01</lastmod></url>\
+            <url><loc>https://a.com/3";
+        assert!(parse(&xml[..]).is_err());
+
+        let report = Parser::new().parse_lenient(xml);
+        let locs: Vec<&str> = report
+            .entries
+            .iter()
+            .map(|e| e.entry.loc.as_str())
+            .collect();
+        assert_eq!(
+            locs,
+            [
+                "https://a.com/1",
+                "https://a.com/2?a=1&b",
+                "https://a.com/3"
+            ]
+        );
+        assert_eq!(report.entries[0].entry.changefreq, None);
+        assert_eq!(
+            report.entries[1].unknown,
+            [("news:news".to_owned(), "T".to_owned())]
+        );
+        let messages: Vec<&str> = report.problems.iter().map(|p| p.message.as_str()).collect();
+        assert_eq!(messages.len(), 6, "{messages:?}");
+        assert!(messages[1].starts_with("unclosed <url>"));
+        assert!(report
+            .problems
+            .windows(2)
+            .all(|w| w[0].position <= w[1].position));
+
+        let report = Parser::new().parse_lenient(b"<html></html>");
+        assert!(report.entries.is_empty());
+        assert_eq!(report.problems.len(), 1);
+    }
+
+    #[test]
+    fn keeps_extensions() {
+        let xml = r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+                xmlns:image="http://www.google.com/schemas/sitemap-image/1.1"
+                xmlns:mobile="http://www.google.com/schemas/sitemap-mobile/1.0">
+            <url>
+                <loc>https://a.com/</loc>
+                <image:image><image:loc>https://a.com/a.png?a=1&amp;b=2</image:loc></image:image>
+                <vendor:rank xmlns:vendor="urn:vendor" level="2"/>
+                <mobile:mobile/>
+                <undeclared:thing>x</undeclared:thing>
+            </url>
+        </urlset>"#;
+        assert!(parse_str(xml).unwrap()[0].extensions.is_empty());
+
+        let urls = Parser::new().keep_extensions(true).parse_str(xml).unwrap();
+        let extensions: Vec<(&str, &str)> = urls[0]
+            .extensions
+            .iter()
+            .map(|extension| (extension.prefix(), extension.namespace()))
+            .collect();
+        assert_eq!(
+            extensions,
+            [
+                ("image", "http://www.google.com/schemas/sitemap-image/1.1"),
+                ("vendor", "urn:vendor"),
+            ]
+        );
+        assert!(urls[0].mobile);
+
+        let generated = crate::generate_str(&urls);
+        assert!(generated.contains(
+            "<image:image>\n            <image:loc>https://a.com/a.png?a=1&amp;b=2</image:loc>\n        </image:image>"
+        ));
+        assert!(generated.contains(r#"<vendor:rank xmlns:vendor="urn:vendor" level="2"/>"#));
+        let again = Parser::new()
+            .keep_extensions(true)
+            .parse_str(&generated)
+            .unwrap();
+        assert_eq!(again, urls);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn streams_from_async_readers() {
+        use futures_util::StreamExt;
+
+        let xml = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\
+            <urlset><url><loc>https://a.com/caf\xe9</loc><priority>0.5</priority></url>\
+            <url><loc>https://a.com/2</loc></url><url><lastmod>2024</lastmod></url></urlset>";
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let parser = Parser::new();
+        let entries: Vec<Result<UrlEntry>> = runtime.block_on(
+            parser
+                .entries_stream(tokio::io::BufReader::new(&xml[..]))
+                .collect(),
+        );
+        let expected: Vec<Result<UrlEntry>> = parser.entries(&xml[..]).collect();
+        assert_eq!(entries.len(), 3);
+        for (entry, expected) in entries.iter().zip(&expected).take(2) {
+            assert_eq!(entry.as_ref().unwrap(), expected.as_ref().unwrap());
+        }
+        assert_eq!(entries[0].as_ref().unwrap().loc.path(), "/caf%C3%A9");
+        assert!(matches!(entries[2], Err(Error::MissingElement("loc"))));
+
+        let utf8 = b"<urlset><url><loc>https://a.com/</loc></url>";
+        let entries: Vec<Result<UrlEntry>> = runtime.block_on(
+            parser
+                .entries_stream(tokio::io::BufReader::with_capacity(4, &utf8[..]))
+                .collect(),
+        );
+        assert!(entries[0].is_ok());
+        assert!(matches!(entries[1], Err(Error::Xml(_))));
+
+        // The declaration spans several reads, and the transcoded document is capped.
+        let small = || tokio::io::BufReader::with_capacity(8, &xml[..]);
+        let entries: Vec<Result<UrlEntry>> =
+            runtime.block_on(parser.entries_stream(small()).collect());
+        assert_eq!(entries[0].as_ref().unwrap().loc.path(), "/caf%C3%A9");
+        let limited = Parser::new().limits(ParseLimits {
+            max_bytes: 100,
+            ..Default::default()
+        });
+        let entries: Vec<Result<UrlEntry>> =
+            runtime.block_on(limited.entries_stream(small()).collect());
+        let endless = tokio::io::AsyncReadExt::chain(
+            &b"<urlset><url><loc>https://a.com/"[..],
+            tokio::io::repeat(b'a'),
+        );
+        let endless: Vec<Result<UrlEntry>> = runtime.block_on(
+            limited
+                .entries_stream(tokio::io::BufReader::new(endless))
+                .collect(),
+        );
+        assert!(matches!(
+            endless[..],
+            [Err(Error::LimitExceeded {
+                limit: Limit::Size,
+                max: 100
+            })]
+        ));
+        assert!(matches!(
+            entries[..],
+            [Err(Error::LimitExceeded {
+                limit: Limit::Size,
+                max: 100
+            })]
+        ));
+    }
+}
@@ -0,0 +1,47 @@
+//! Notifying search engines of a new sitemap.
+//!
+//! A ping is a `GET` request to an endpoint with the url of the sitemap in its `sitemap`
+//! query parameter. Google and Bing retired their public endpoints, submit sitemaps through
+//! their webmaster tools or `robots.txt` instead, but other engines and internal indexers
+//! still take pings.
+//!
+//! ```rust
+//! use sitewriter::{client::HttpResponse, ping::ping, Url};
+//!
+//! let client = |url: &Url| {
+//!     assert_eq!(url.as_str(), "https://search.example/ping?sitemap=https%3A%2F%2Fexample.com%2Fsitemap.xml");
+//!     Ok(HttpResponse::status(200))
+//! };
+//! let response = ping(
+//!     &client,
+//!     &"https://search.example/ping".parse()?,
+//!     &"https://example.com/sitemap.xml".parse()?,
+//! )?;
+//! assert!(response.is_success());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use url::Url;
+
+use crate::{
+    client::{HttpClient, HttpResponse},
+    Result,
+};
+
+/// The url requested to ping `endpoint` with `sitemap`.
+#[must_use]
+pub fn ping_url(endpoint: &Url, sitemap: &Url) -> Url {
+    let mut url = endpoint.clone();
+    url.query_pairs_mut()
+        .append_pair("sitemap", sitemap.as_str());
+    url
+}
+
+/// Pings `endpoint` with the url of `sitemap`, returning the response whatever its status.
+///
+/// # Errors
+///
+/// Will return [`Error::Request`](crate::Error::Request) if no response was received.
+pub fn ping<C: HttpClient>(client: &C, endpoint: &Url, sitemap: &Url) -> Result<HttpResponse> {
+    client.get(&ping_url(endpoint, sitemap))
+}
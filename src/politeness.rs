@@ -0,0 +1,306 @@
+//! Rate limiting of requests to remote hosts.
+//!
+//! [`Throttle`] hands out permits so that each host sees a limited number of concurrent
+//! requests, spaced by a minimum delay, while the total number of requests in flight stays
+//! under a global cap. Code that sends requests for many entries, such as an audit, should
+//! hold a permit for the duration of each request:
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use sitewriter::politeness::{PolitenessOptions, Throttle};
+//!
+//! let throttle = Throttle::new(PolitenessOptions {
+//!     per_host_delay: Duration::from_millis(10),
+//!     ..Default::default()
+//! });
+//!
+//! let url = "https://example.com/".parse()?;
+//! {
+//!     let _permit = throttle.acquire(&url);
+//!     // send the request
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, VecDeque},
+    sync::{Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use url::Url;
+
+use crate::UrlEntry;
+
+/// Limits applied by a [`Throttle`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PolitenessOptions {
+    /// How many requests to the same host may be in flight at once.
+    pub per_host_concurrency: usize,
+    /// The minimum time between the start of two requests to the same host.
+    pub per_host_delay: Duration,
+    /// How many requests may be in flight at once, over all hosts.
+    pub max_concurrency: usize,
+}
+
+impl Default for PolitenessOptions {
+    fn default() -> Self {
+        Self {
+            per_host_concurrency: 2,
+            per_host_delay: Duration::from_millis(250),
+            max_concurrency: 16,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    active: usize,
+    hosts: HashMap<String, HostState>,
+}
+
+enum Admission {
+    Granted,
+    /// Until the per-host delay has passed.
+    WaitFor(Duration),
+    /// Until another request finishes.
+    WaitForRelease,
+}
+
+#[derive(Debug)]
+struct HostState {
+    active: usize,
+    next_start: Instant,
+}
+
+/// Hands out permits to send requests, see the [module docs](self).
+///
+/// It can be shared between threads.
+#[derive(Debug, Default)]
+pub struct Throttle {
+    options: PolitenessOptions,
+    state: Mutex<State>,
+    released: Condvar,
+}
+
+impl Throttle {
+    /// Creates a throttle with the given limits.
+    #[must_use]
+    pub fn new(options: PolitenessOptions) -> Self {
+        Self {
+            options,
+            ..Default::default()
+        }
+    }
+
+    /// The limits of this throttle.
+    #[must_use]
+    pub const fn options(&self) -> &PolitenessOptions {
+        &self.options
+    }
+
+    /// Blocks until a request to the host of `url` is allowed.
+    ///
+    /// The request counts as in flight until the returned permit is dropped.
+    pub fn acquire(&self, url: &Url) -> Permit<'_> {
+        let host = host_key(url);
+        let mut state = self.lock();
+
+        loop {
+            let now = Instant::now();
+            state = match self.admit(&mut state, &host, now) {
+                Admission::Granted => {
+                    return Permit {
+                        throttle: self,
+                        host,
+                    }
+                }
+                Admission::WaitFor(delay) => {
+                    self.released
+                        .wait_timeout(state, delay)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+                Admission::WaitForRelease => {
+                    self.released.wait(state).unwrap_or_else(|e| e.into_inner())
+                }
+            };
+        }
+    }
+
+    /// Takes a permit if a request is allowed right now.
+    pub fn try_acquire(&self, url: &Url) -> Option<Permit<'_>> {
+        let host = host_key(url);
+        let mut state = self.lock();
+        match self.admit(&mut state, &host, Instant::now()) {
+            Admission::Granted => Some(Permit {
+                throttle: self,
+                host,
+            }),
+            Admission::WaitFor(_) | Admission::WaitForRelease => None,
+        }
+    }
+
+    /// Reserves a slot for `host` if a request is allowed at `now`.
+    fn admit(&self, state: &mut State, host: &str, now: Instant) -> Admission {
+        if state.active >= self.options.max_concurrency.max(1) {
+            return Admission::WaitForRelease;
+        }
+
+        let entry = state.hosts.entry(host.to_owned()).or_insert(HostState {
+            active: 0,
+            next_start: now,
+        });
+        if entry.active >= self.options.per_host_concurrency.max(1) {
+            return Admission::WaitForRelease;
+        }
+        if entry.next_start > now {
+            return Admission::WaitFor(entry.next_start - now);
+        }
+
+        entry.active += 1;
+        entry.next_start = now + self.options.per_host_delay;
+        state.active += 1;
+        Admission::Granted
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn release(&self, host: &str) {
+        let mut state = self.lock();
+        state.active -= 1;
+        if let Some(entry) = state.hosts.get_mut(host) {
+            entry.active -= 1;
+        }
+        drop(state);
+        self.released.notify_all();
+    }
+}
+
+/// Permission to send one request, released when dropped.
+#[derive(Debug)]
+pub struct Permit<'a> {
+    throttle: &'a Throttle,
+    host: String,
+}
+
+impl Permit<'_> {
+    /// The host the permit was taken for.
+    #[must_use]
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.throttle.release(&self.host);
+    }
+}
+
+fn host_key(url: &Url) -> String {
+    match (url.host_str(), url.port_or_known_default()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_owned(),
+        (None, _) => String::new(),
+    }
+}
+
+/// Reorders entries so consecutive entries alternate between hosts, taking one entry
+/// per host in turn. The order within each host is preserved.
+///
+/// Processing entries in this order keeps workers busy on other hosts while one host
+/// is being waited on.
+pub fn interleave_by_host<T, I>(entries: I) -> Vec<T>
+where
+    T: Borrow<UrlEntry>,
+    I: IntoIterator<Item = T>,
+{
+    let mut order: Vec<String> = Vec::new();
+    let mut queues: HashMap<String, VecDeque<T>> = HashMap::new();
+    let mut len = 0;
+
+    for entry in entries {
+        let host = host_key(&entry.borrow().loc);
+        queues
+            .entry(host.clone())
+            .or_insert_with(|| {
+                order.push(host);
+                VecDeque::new()
+            })
+            .push_back(entry);
+        len += 1;
+    }
+
+    let mut result = Vec::with_capacity(len);
+    while result.len() < len {
+        for host in &order {
+            if let Some(entry) = queues.get_mut(host).and_then(VecDeque::pop_front) {
+                result.push(entry);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn limits_per_host_and_globally() {
+        let throttle = Throttle::new(PolitenessOptions {
+            per_host_concurrency: 1,
+            per_host_delay: Duration::ZERO,
+            max_concurrency: 2,
+        });
+
+        let a = throttle.try_acquire(&url("https://a.com/1")).unwrap();
+        assert!(throttle.try_acquire(&url("https://a.com/2")).is_none());
+        let _b = throttle.try_acquire(&url("https://b.com/")).unwrap();
+        assert!(throttle.try_acquire(&url("https://c.com/")).is_none());
+
+        drop(a);
+        assert!(throttle.try_acquire(&url("https://c.com/")).is_some());
+    }
+
+    #[test]
+    fn spaces_requests_to_the_same_host() {
+        let delay = Duration::from_millis(50);
+        let throttle = Throttle::new(PolitenessOptions {
+            per_host_delay: delay,
+            ..Default::default()
+        });
+
+        let start = Instant::now();
+        drop(throttle.acquire(&url("https://a.com/1")));
+        drop(throttle.acquire(&url("https://b.com/1")));
+        drop(throttle.acquire(&url("https://a.com/2")));
+        assert!(start.elapsed() >= delay);
+    }
+
+    #[test]
+    fn interleaves_hosts() {
+        let entries: Vec<UrlEntry> = ["https://a.com/1", "https://a.com/2", "https://b.com/1"]
+            .iter()
+            .map(|loc| UrlEntry::new(url(loc), None, None, None))
+            .collect();
+
+        let locs: Vec<String> = interleave_by_host(&entries)
+            .iter()
+            .map(|e| e.loc.to_string())
+            .collect();
+        assert_eq!(
+            locs,
+            ["https://a.com/1", "https://b.com/1", "https://a.com/2"]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Computed priorities for the entries that don't set one.
+//!
+//! A [`PriorityStrategy`] given to [`Sitemap::priority_strategy`](crate::Sitemap::priority_strategy)
+//! fills in the priority of each entry written without one. The entries keep their own
+//! priority when they have one.
+//!
+//! ```rust
+//! use sitewriter::{priority::ByDepth, Sitemap, UrlEntry};
+//!
+//! let urls = vec![
+//!     UrlEntry::new("https://example.com/".parse()?, None, None, None),
+//!     UrlEntry::new("https://example.com/blog/post".parse()?, None, None, None),
+//! ];
+//! let xml = Sitemap::new().priority_strategy(ByDepth::default()).generate_str(&urls);
+//! assert!(xml.contains("<priority>1.0</priority>"));
+//! assert!(xml.contains("<priority>0.6</priority>"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{fmt::Debug, sync::Arc};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::UrlEntryRef;
+
+/// Computes the priority of an entry.
+pub trait PriorityStrategy: Debug + Send + Sync {
+    /// The priority of `entry`, between 0.0 and 1.0, or `None` to leave it unset.
+    fn priority(&self, entry: UrlEntryRef<'_>) -> Option<f32>;
+}
+
+impl<S: PriorityStrategy + ?Sized> PriorityStrategy for Arc<S> {
+    fn priority(&self, entry: UrlEntryRef<'_>) -> Option<f32> {
+        (**self).priority(entry)
+    }
+}
+
+/// The same priority for every entry.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Constant(pub f32);
+
+impl PriorityStrategy for Constant {
+    fn priority(&self, _: UrlEntryRef<'_>) -> Option<f32> {
+        Some(self.0)
+    }
+}
+
+/// A priority decaying with the depth of the path: the homepage gets 1.0 and each path
+/// segment multiplies it by `factor`, down to `min`.
+///
+/// `/blog/` and `/blog` both have a depth of 1.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ByDepth {
+    /// The factor applied per path segment.
+    pub factor: f32,
+    /// The lowest priority given.
+    pub min: f32,
+}
+
+impl Default for ByDepth {
+    /// A factor of 0.8 down to 0.1.
+    fn default() -> Self {
+        Self {
+            factor: 0.8,
+            min: 0.1,
+        }
+    }
+}
+
+impl PriorityStrategy for ByDepth {
+    fn priority(&self, entry: UrlEntryRef<'_>) -> Option<f32> {
+        let path = entry
+            .loc
+            .split_once("://")
+            .map_or(entry.loc, |(_, rest)| rest);
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let depth = path
+            .split('/')
+            .skip(1)
+            .filter(|segment| !segment.is_empty())
+            .count();
+        let depth = i32::try_from(depth).unwrap_or(i32::MAX);
+        Some(self.factor.powi(depth).max(self.min))
+    }
+}
+
+/// A priority halving every `half_life` since the lastmod of the entry, down to `min`.
+///
+/// Entries modified after `now` get 1.0, entries without lastmod are left unset.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ByRecency {
+    /// The time the age of the entries is computed at.
+    pub now: DateTime<Utc>,
+    /// The age at which the priority is halved.
+    pub half_life: Duration,
+    /// The lowest priority given.
+    pub min: f32,
+}
+
+impl ByRecency {
+    /// Halves the priority every `half_life` from now, down to 0.1.
+    #[must_use]
+    pub fn new(half_life: Duration) -> Self {
+        Self {
+            now: Utc::now(),
+            half_life,
+            min: 0.1,
+        }
+    }
+}
+
+impl PriorityStrategy for ByRecency {
+    fn priority(&self, entry: UrlEntryRef<'_>) -> Option<f32> {
+        let age = (self.now - entry.lastmod?).num_seconds().max(0);
+        let half_life = self.half_life.num_seconds().max(1);
+        // Precision loss doesn't matter at the scale of the priorities.
+        #[allow(clippy::cast_precision_loss)]
+        let halvings = age as f32 / half_life as f32;
+        Some(0.5_f32.powf(halvings).max(self.min))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strategies() {
+        let depth = ByDepth::default();
+        let priority = |loc| depth.priority(UrlEntryRef::new(loc)).unwrap();
+        assert!((priority("https://a.com") - 1.0).abs() < f32::EPSILON);
+        assert!((priority("https://a.com/?page=/a/b") - 1.0).abs() < f32::EPSILON);
+        assert!((priority("https://a.com/blog/") - 0.8).abs() < 1e-6);
+        assert!((priority("https://a.com/a/b/c/d/e/f/g/h/i/j/k") - 0.1).abs() < 1e-6);
+
+        let now = Utc::now();
+        let recency = ByRecency {
+            now,
+            half_life: Duration::days(30),
+            min: 0.2,
+        };
+        let priority =
+            |age| recency.priority(UrlEntryRef::new("https://a.com/").lastmod(now - age));
+        assert_eq!(priority(Duration::zero()), Some(1.0));
+        assert_eq!(priority(Duration::days(30)), Some(0.5));
+        assert_eq!(priority(Duration::days(3000)), Some(0.2));
+        assert_eq!(recency.priority(UrlEntryRef::new("https://a.com/")), None);
+    }
+}
@@ -0,0 +1,242 @@
+//! Serde support and adapters for the JSON shapes of other systems.
+//!
+//! [`UrlEntry`] and [`ChangeFreq`] implement `Serialize` and `Deserialize`, using the
+//! element names of the protocol: `loc`, `lastmod`, `changefreq` and `priority`.
+//!
+//! Deserialization also accepts the key spellings commonly found in existing url
+//! inventories, such as `url`, `lastModified`, `last_modified`, `updatedAt` or
+//! `changeFrequency`, and lastmods in any of the W3C formats the protocol allows. The
+//! [`CamelCase`] and [`SnakeCase`] adapters deserialize the same way and serialize with
+//! their own key names, so no parallel DTO struct is needed on either side:
+//!
+//! ```rust
+//! use sitewriter::{profile::CamelCase, UrlEntry};
+//!
+//! let json = r#"[{"url": "https://example.com/", "lastModified": "2024-05-01", "changeFrequency": "Daily"}]"#;
+//! let entries: Vec<CamelCase> = serde_json::from_str(json)?;
+//! let urls: Vec<UrlEntry> = entries.into_iter().map(UrlEntry::from).collect();
+//! assert_eq!(urls[0].loc.as_str(), "https://example.com/");
+//!
+//! let json = serde_json::to_string(&CamelCase(urls[0].clone()))?;
+//! assert!(json.contains(r#""lastModified":"2024-05-01T00:00:00Z""#));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Extensions are neither serialized nor deserialized.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{
+    de::Error as _, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{parse::parse_lastmod, ChangeFreq, Url, UrlEntry};
+
+impl Serialize for ChangeFreq {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ChangeFreq {
+    /// Accepts the protocol values in any case.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.to_ascii_lowercase().parse().map_err(D::Error::custom)
+    }
+}
+
+/// Key names used when serializing an entry.
+struct Keys {
+    name: &'static str,
+    lastmod: &'static str,
+    changefreq: &'static str,
+}
+
+const PROTOCOL: Keys = Keys {
+    name: "UrlEntry",
+    lastmod: "lastmod",
+    changefreq: "changefreq",
+};
+
+fn serialize_entry<S: Serializer>(
+    entry: &UrlEntry,
+    keys: &Keys,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut state = serializer.serialize_struct(keys.name, 4)?;
+    state.serialize_field("loc", entry.loc.as_str())?;
+    state.serialize_field(
+        keys.lastmod,
+        &entry
+            .lastmod
+            .map(|lastmod| lastmod.to_rfc3339_opts(SecondsFormat::Secs, true)),
+    )?;
+    state.serialize_field(keys.changefreq, &entry.changefreq)?;
+    state.serialize_field("priority", &entry.priority)?;
+    state.end()
+}
+
+/// The fields of an entry under all the accepted spellings.
+#[derive(Deserialize)]
+struct LenientEntry {
+    #[serde(alias = "url", alias = "location", alias = "href")]
+    loc: String,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_lastmod",
+        alias = "lastModified",
+        alias = "last_modified",
+        alias = "lastmodified",
+        alias = "updatedAt",
+        alias = "updated_at",
+        alias = "modified"
+    )]
+    lastmod: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        alias = "changeFrequency",
+        alias = "change_frequency",
+        alias = "changeFreq",
+        alias = "change_freq"
+    )]
+    changefreq: Option<ChangeFreq>,
+    #[serde(default)]
+    priority: Option<f32>,
+}
+
+pub(crate) fn deserialize_lastmod<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|text| {
+            parse_lastmod(&text)
+                .ok_or_else(|| D::Error::custom(format!("invalid lastmod '{text}'")))
+        })
+        .transpose()
+}
+
+/// Deserializes a url from its text.
+pub(crate) fn deserialize_url<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Url, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    text.parse()
+        .map_err(|e| D::Error::custom(format!("invalid url '{text}': {e}")))
+}
+
+fn deserialize_entry<'de, D: Deserializer<'de>>(deserializer: D) -> Result<UrlEntry, D::Error> {
+    let entry = LenientEntry::deserialize(deserializer)?;
+    let loc = entry
+        .loc
+        .parse()
+        .map_err(|e| D::Error::custom(format!("invalid url '{}': {e}", entry.loc)))?;
+    Ok(UrlEntry::new(
+        loc,
+        entry.lastmod,
+        entry.changefreq,
+        entry.priority,
+    ))
+}
+
+impl Serialize for UrlEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_entry(self, &PROTOCOL, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for UrlEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_entry(deserializer)
+    }
+}
+
+macro_rules! profile {
+    ($(#[$doc:meta])* $name:ident, $lastmod:literal, $changefreq:literal) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, PartialEq, PartialOrd)]
+        pub struct $name(pub UrlEntry);
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let keys = Keys {
+                    name: stringify!($name),
+                    lastmod: $lastmod,
+                    changefreq: $changefreq,
+                };
+                serialize_entry(&self.0, &keys, serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserialize_entry(deserializer).map(Self)
+            }
+        }
+
+        impl From<$name> for UrlEntry {
+            fn from(entry: $name) -> Self {
+                entry.0
+            }
+        }
+
+        impl From<UrlEntry> for $name {
+            fn from(entry: UrlEntry) -> Self {
+                Self(entry)
+            }
+        }
+    };
+}
+
+profile!(
+    /// An entry serialized with `loc`, `lastModified`, `changeFrequency` and `priority` keys.
+    CamelCase,
+    "lastModified",
+    "changeFrequency"
+);
+
+profile!(
+    /// An entry serialized with `loc`, `last_modified`, `change_frequency` and `priority`
+    /// keys.
+    SnakeCase,
+    "last_modified",
+    "change_frequency"
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_common_shapes() {
+        let json = r#"[
+            {"loc": "https://a.com/", "lastmod": "2024-05-01T08:00:00+02:00", "changefreq": "daily", "priority": 0.5},
+            {"url": "https://a.com/b", "last_modified": "2024-05", "change_frequency": "WEEKLY"},
+            {"location": "https://a.com/c", "updatedAt": null}
+        ]"#;
+
+        let urls: Vec<UrlEntry> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            urls[0].lastmod.unwrap().to_rfc3339(),
+            "2024-05-01T06:00:00+00:00"
+        );
+        assert_eq!(urls[0].priority, Some(0.5));
+        assert_eq!(urls[1].changefreq, Some(ChangeFreq::Weekly));
+        assert_eq!(urls[2].lastmod, None);
+
+        let invalid = r#"{"loc": "https://a.com/", "lastmod": "yesterday"}"#;
+        assert!(serde_json::from_str::<UrlEntry>(invalid).is_err());
+    }
+
+    #[test]
+    fn serializes_with_profile_keys() {
+        let entry: UrlEntry =
+            serde_json::from_str(r#"{"loc": "https://a.com/", "changefreq": "never"}"#).unwrap();
+
+        assert_eq!(
+            serde_json::to_string(&entry).unwrap(),
+            r#"{"loc":"https://a.com/","lastmod":null,"changefreq":"never","priority":null}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&SnakeCase(entry)).unwrap(),
+            r#"{"loc":"https://a.com/","last_modified":null,"change_frequency":"never","priority":null}"#
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Progress of long generations.
+//!
+//! A hook given to [`Sitemap::on_progress`](crate::Sitemap::on_progress) or
+//! [`SitemapWriter::on_progress`](crate::SitemapWriter::on_progress) is called as the
+//! entries and files are written, to drive a progress bar or metrics:
+//!
+//! ```rust
+//! use std::sync::{
+//!     atomic::{AtomicUsize, Ordering},
+//!     Arc,
+//! };
+//!
+//! use sitewriter::{progress::ProgressEvent, Sitemap, UrlEntry};
+//!
+//! let written = Arc::new(AtomicUsize::new(0));
+//! let counter = Arc::clone(&written);
+//! let sitemap = Sitemap::new().on_progress(move |event| {
+//!     if let ProgressEvent::EntriesWritten { entries, .. } = event {
+//!         counter.store(entries, Ordering::Relaxed);
+//!     }
+//! });
+//! let urls = vec![UrlEntry::new("https://example.com/".parse()?, None, None, None)];
+//! sitemap.generate_str(&urls);
+//! assert_eq!(written.load(Ordering::Relaxed), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Without a hook, the cost is a branch per entry.
+
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// A step of a generation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProgressEvent {
+    /// Entries were written to the current sitemap.
+    EntriesWritten {
+        /// The entries written to the sitemap so far.
+        entries: usize,
+        /// The bytes of XML written to the sitemap so far.
+        bytes: usize,
+    },
+    /// A file was written.
+    ///
+    /// A part over the [size limit](crate::Limits) is generated again as two halves, their
+    /// entries are reported again before the files are completed.
+    FileCompleted {
+        /// Where the file was written.
+        path: PathBuf,
+        /// The entries of the file, the sitemaps of an index.
+        entries: usize,
+        /// The bytes of XML of the file, before any compression.
+        bytes: usize,
+    },
+}
+
+/// A progress hook shared by the clones of a [`Sitemap`](crate::Sitemap).
+#[derive(Clone)]
+pub(crate) struct ProgressHook(Arc<Mutex<dyn FnMut(ProgressEvent) + Send>>);
+
+impl ProgressHook {
+    pub(crate) fn new(hook: impl FnMut(ProgressEvent) + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(hook)))
+    }
+
+    /// Calls the hook with `event`.
+    pub(crate) fn report(&self, event: ProgressEvent) {
+        // A hook that panicked once can still be called.
+        let mut hook = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        hook(event);
+    }
+}
+
+impl fmt::Debug for ProgressHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressHook").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::{sink::MemorySink, split::SplitOutput, Limits, Sitemap, UrlEntry};
+
+    #[test]
+    fn reports_entries_and_files() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&log);
+        let sitemap = Sitemap::new()
+            .limits(Limits::new(2, crate::MAX_SIZE))
+            .on_progress(move |event| recorded.lock().unwrap().push(event));
+        let urls: Vec<UrlEntry> = (0..3)
+            .map(|i| {
+                let loc = format!("https://a.com/{i}").parse().unwrap();
+                UrlEntry::new(loc, None, None, None)
+            })
+            .collect();
+        let output = SplitOutput::new("maps", "https://a.com/".parse().unwrap());
+        let sink = MemorySink::new();
+        sitemap.write_split_to(&urls, &output, &sink).unwrap();
+
+        let events = std::mem::take(&mut *log.lock().unwrap());
+        let entries: Vec<usize> = events
+            .iter()
+            .filter_map(|event| match event {
+                ProgressEvent::EntriesWritten { entries, .. } => Some(*entries),
+                ProgressEvent::FileCompleted { .. } => None,
+            })
+            .collect();
+        assert_eq!(entries, [1, 2, 1]);
+        let files: Vec<(String, usize, usize)> = events
+            .iter()
+            .filter_map(|event| match event {
+                ProgressEvent::FileCompleted {
+                    path,
+                    entries,
+                    bytes,
+                } => Some((path.display().to_string(), *entries, *bytes)),
+                ProgressEvent::EntriesWritten { .. } => None,
+            })
+            .collect();
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].1, 2);
+        assert_eq!(files[2].1, 2);
+        let part = sink.get("maps/sitemap-1.xml").unwrap();
+        assert_eq!(files[0].2, part.len());
+
+        // The files report the entries the filters keep.
+        let dir = TempDir::new("progress");
+        let path = dir.join("progress.xml");
+        sitemap
+            .exclude("/1")
+            .generate_to_file(&path, &urls)
+            .unwrap();
+        let events = std::mem::take(&mut *log.lock().unwrap());
+        assert!(matches!(
+            events.last(),
+            Some(ProgressEvent::FileCompleted { entries: 2, .. })
+        ));
+    }
+}
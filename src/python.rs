@@ -0,0 +1,176 @@
+//! Python bindings, for sitemap pipelines written in Python.
+//!
+//! Build the `sitewriter` Python module with [maturin](https://www.maturin.rs), which
+//! reads `pyproject.toml`: `maturin build --release`. It exposes `UrlEntry`,
+//! `ChangeFreq`, `generate_str` and `generate_file`:
+//!
+//! ```python
+//! from datetime import datetime, timezone
+//!
+//! import sitewriter
+//!
+//! urls = [
+//!     sitewriter.UrlEntry(
+//!         "https://example.com/",
+//!         lastmod=datetime.now(timezone.utc),
+//!         changefreq=sitewriter.ChangeFreq.Daily,
+//!         priority=1.0,
+//!     ),
+//!     sitewriter.UrlEntry("https://example.com/about"),
+//! ]
+//! xml = sitewriter.generate_str(urls)
+//! sitewriter.generate_file("public/sitemap.xml.gz", urls)
+//! ```
+//!
+//! The lastmods are timezone aware datetimes. The generation releases the GIL, so other
+//! Python threads keep running meanwhile.
+
+use std::path::PathBuf;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use pyo3::{
+    exceptions::{PyOSError, PyValueError},
+    prelude::*,
+};
+
+use crate::{ChangeFreq, Error, Sitemap, UrlEntry};
+
+/// A [`UrlEntry`], as the `UrlEntry` Python class.
+#[pyclass(name = "UrlEntry", module = "sitewriter")]
+#[derive(Debug, Clone)]
+pub struct PyUrlEntry(pub UrlEntry);
+
+#[pymethods]
+impl PyUrlEntry {
+    #[new]
+    #[pyo3(signature = (loc, lastmod = None, changefreq = None, priority = None))]
+    fn new(
+        loc: &str,
+        lastmod: Option<DateTime<FixedOffset>>,
+        changefreq: Option<ChangeFreq>,
+        priority: Option<f32>,
+    ) -> PyResult<Self> {
+        let url = loc
+            .parse()
+            .map_err(|e| PyValueError::new_err(format!("invalid loc '{loc}': {e}")))?;
+        let lastmod = lastmod.map(|lastmod| lastmod.with_timezone(&Utc));
+        Ok(Self(UrlEntry::new(url, lastmod, changefreq, priority)))
+    }
+
+    #[getter]
+    fn loc(&self) -> &str {
+        self.0.loc.as_str()
+    }
+
+    #[getter]
+    const fn lastmod(&self) -> Option<DateTime<Utc>> {
+        self.0.lastmod
+    }
+
+    #[getter]
+    const fn changefreq(&self) -> Option<ChangeFreq> {
+        self.0.changefreq
+    }
+
+    #[getter]
+    const fn priority(&self) -> Option<f32> {
+        self.0.priority
+    }
+
+    fn __repr__(&self) -> String {
+        format!("UrlEntry('{}')", self.0.loc)
+    }
+}
+
+/// The error raised for `error`: `OSError` for IO errors, `ValueError` otherwise.
+fn py_err(error: Error) -> PyErr {
+    match error {
+        Error::Io(e) => e.into(),
+        error => PyValueError::new_err(error.to_string()),
+    }
+}
+
+fn entries(urls: Vec<PyUrlEntry>) -> Vec<UrlEntry> {
+    urls.into_iter().map(|entry| entry.0).collect()
+}
+
+/// Generates the sitemap of `urls`.
+#[pyfunction]
+fn generate_str(py: Python<'_>, urls: Vec<PyUrlEntry>) -> String {
+    let urls = entries(urls);
+    py.allow_threads(|| Sitemap::new().generate_str(&urls))
+}
+
+/// Writes the sitemap of `urls` to `path`, gzipped for a `.gz` path.
+#[pyfunction]
+fn generate_file(py: Python<'_>, path: PathBuf, urls: Vec<PyUrlEntry>) -> PyResult<()> {
+    let urls = entries(urls);
+    py.allow_threads(|| Sitemap::new().generate_to_file(&path, &urls))
+        .map_err(|e| match e {
+            Error::FeatureDisabled(feature) => {
+                PyOSError::new_err(format!("built without the {feature} feature"))
+            }
+            e => py_err(e),
+        })
+}
+
+/// The `sitewriter` Python module.
+#[pymodule]
+#[pyo3(name = "sitewriter")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyUrlEntry>()?;
+    m.add_class::<ChangeFreq>()?;
+    m.add_function(wrap_pyfunction!(generate_str, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_file, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::types::IntoPyDict;
+
+    use super::*;
+
+    #[test]
+    fn generates_from_python() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = pyo3::wrap_pymodule!(python_module)(py);
+            let locals = [("sitewriter", module)].into_py_dict(py).unwrap();
+            py.run(
+                cr#"
+from datetime import datetime, timedelta, timezone
+
+paris = timezone(timedelta(hours=2))
+urls = [
+    sitewriter.UrlEntry(
+        "https://example.com/",
+        lastmod=datetime(2024, 5, 1, 10, tzinfo=paris),
+        changefreq=sitewriter.ChangeFreq.Daily,
+        priority=0.5,
+    ),
+    sitewriter.UrlEntry("https://example.com/about"),
+]
+assert urls[0].changefreq == sitewriter.ChangeFreq.Daily
+xml = sitewriter.generate_str(urls)
+assert "<lastmod>2024-05-01T08:00:00Z</lastmod>" in xml, xml
+assert "<loc>https://example.com/about</loc>" in xml, xml
+
+try:
+    sitewriter.UrlEntry("/relative")
+    raise AssertionError("a relative loc was accepted")
+except ValueError:
+    pass
+try:
+    sitewriter.generate_file("/dev/null/sitemap.xml", urls)
+    raise AssertionError("the file was written")
+except OSError:
+    pass
+"#,
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+        });
+    }
+}
@@ -0,0 +1,131 @@
+//! Sitemaps of the recently changed urls.
+//!
+//! Search engines re-read a small sitemap more often than the parts of a large one.
+//! Publishing the urls changed since a cutoff next to the full sitemap gets them crawled
+//! sooner:
+//!
+//! ```rust,no_run
+//! use chrono::{TimeDelta, Utc};
+//! use sitewriter::{Sitemap, UrlEntry};
+//!
+//! # let urls: Vec<UrlEntry> = Vec::new();
+//! let since = Utc::now() - TimeDelta::days(2);
+//! // Writes public/sitemap.xml and public/sitemap-recent.xml.
+//! let recent = Sitemap::new().generate_with_recent("public/sitemap.xml", &urls, since)?;
+//! assert!(recent.ends_with("sitemap-recent.xml"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Both files must be listed, by a sitemap index or the `robots.txt`, to be read.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use crate::{compress::Compression, EmptyStrategy, Result, Sitemap, UrlEntry};
+
+/// The file name of the sitemap of the recent urls, next to the full sitemap.
+pub const RECENT_NAME: &str = "sitemap-recent.xml";
+
+impl Sitemap {
+    /// The entries whose lastmod is after `since`, in their order.
+    ///
+    /// The lastmod is the one written, after the [transforms](Sitemap::transform). Entries
+    /// without one are left out, there is no telling when they changed, and so are the
+    /// entries the [filters](Sitemap::include) leave out.
+    #[must_use]
+    pub fn recent(&self, urls: &[UrlEntry], since: DateTime<Utc>) -> Vec<UrlEntry> {
+        urls.iter()
+            .filter(|entry| {
+                self.transform_entry((*entry).clone())
+                    .and_then(|written| written.lastmod)
+                    .is_some_and(|lastmod| lastmod > since)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Writes the sitemap of `urls` to `path` as [`Sitemap::generate_to_file`], then the
+    /// sitemap of the [recent](Sitemap::recent) ones to [`RECENT_NAME`] in the same
+    /// directory. Returns the path of the recent sitemap.
+    ///
+    /// The recent sitemap is compressed as `path` is, `sitemap-recent.xml.gz` next to a
+    /// `sitemap.xml.gz`. It is written even when no entry is recent, so the changes of a
+    /// previous run aren't listed again.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`Sitemap::generate_to_file`].
+    pub fn generate_with_recent(
+        &self,
+        path: impl AsRef<Path>,
+        urls: &[UrlEntry],
+        since: DateTime<Utc>,
+    ) -> Result<PathBuf> {
+        let path = path.as_ref();
+        self.generate_to_file(path, urls)?;
+
+        let name = Compression::from_path(path).extension().map_or_else(
+            || RECENT_NAME.to_owned(),
+            |extension| format!("{RECENT_NAME}.{extension}"),
+        );
+        let recent_path = path.with_file_name(name);
+        self.clone()
+            .on_empty(EmptyStrategy::Write)
+            .generate_to_file(&recent_path, &self.recent(urls, since))?;
+        Ok(recent_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::transform::LastmodPolicy;
+
+    #[test]
+    fn keeps_recent_entries() {
+        let now = Utc::now();
+        let entry = |path: &str, lastmod| {
+            let loc = format!("https://a.com/{path}").parse().unwrap();
+            UrlEntry::new(loc, lastmod, None, None)
+        };
+        let urls = [
+            entry("old", Some(now - TimeDelta::days(3))),
+            entry("new", Some(now - TimeDelta::hours(1))),
+            entry("unknown", None),
+            entry("future", Some(now + TimeDelta::days(1))),
+        ];
+        let since = now - TimeDelta::days(1);
+        let recent = Sitemap::new().recent(&urls, since);
+        let locs: Vec<&str> = recent.iter().map(|e| e.loc.path()).collect();
+        assert_eq!(locs, ["/new", "/future"]);
+
+        let clamped = Sitemap::new().transform(LastmodPolicy::new().clamp_future());
+        assert_eq!(clamped.recent(&urls, now + TimeDelta::hours(1)).len(), 0);
+    }
+
+    #[test]
+    fn writes_both_sitemaps() {
+        let dir = TempDir::new("recent");
+        let old = Utc::now() - TimeDelta::days(30);
+        let urls = [UrlEntry::new(
+            "https://a.com/".parse().unwrap(),
+            Some(old),
+            None,
+            None,
+        )];
+
+        let sitemap = Sitemap::new().on_empty(EmptyStrategy::Error);
+        let recent = sitemap
+            .generate_with_recent(dir.join("sitemap.xml"), &urls, Utc::now())
+            .unwrap();
+        assert_eq!(recent, dir.join(RECENT_NAME));
+        let full = std::fs::read_to_string(dir.join("sitemap.xml")).unwrap();
+        assert!(full.contains("<loc>https://a.com/</loc>"));
+        let recent = std::fs::read_to_string(recent).unwrap();
+        assert!(!recent.contains("<loc>"));
+    }
+}
@@ -0,0 +1,261 @@
+//! A sitemap kept in memory and regenerated in the background.
+//!
+//! [`SitemapRefresher::start`] generates the sitemap from a closure producing the entries,
+//! then spawns a tokio task regenerating it on an interval, or sooner when
+//! [`refresh`](SitemapRefresher::refresh) is called. Handlers serve the latest sitemap
+//! with [`current`](SitemapRefresher::current), which never waits for a regeneration:
+//!
+//! ```rust
+//! # tokio::runtime::Builder::new_current_thread().enable_time().build()?.block_on(async {
+//! use std::time::Duration;
+//!
+//! use sitewriter::{refresh::SitemapRefresher, Sitemap, UrlEntry};
+//!
+//! let refresher = SitemapRefresher::start(Sitemap::new(), Duration::from_secs(600), || async {
+//!     // Query the database.
+//!     Ok(vec![UrlEntry::new("https://example.com/".parse().unwrap(), None, None, None)])
+//! })
+//! .await?;
+//!
+//! let response = refresher.current();
+//! assert!(std::str::from_utf8(response.xml())?.contains("https://example.com/"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! # })?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The task stops once every clone of the refresher is dropped.
+
+use std::{any::Any, future::Future, panic::AssertUnwindSafe, sync::Arc, time::Duration};
+
+use arc_swap::{ArcSwap, ArcSwapOption};
+use futures_util::FutureExt;
+use tokio::{sync::Notify, task::JoinHandle};
+
+use crate::{response::SitemapResponse, Error, Result, Sitemap, UrlEntry};
+
+/// The latest sitemap and what the task shares with the handles.
+#[derive(Debug)]
+struct Shared {
+    current: ArcSwap<SitemapResponse>,
+    last_error: ArcSwapOption<Error>,
+    notify: Notify,
+}
+
+/// Aborts the task when the last handle is dropped.
+#[derive(Debug)]
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Serves a sitemap regenerated in the background, see the [module docs](self).
+///
+/// Clones share the same sitemap and task.
+#[derive(Debug, Clone)]
+pub struct SitemapRefresher {
+    shared: Arc<Shared>,
+    _task: Arc<AbortOnDrop>,
+}
+
+impl SitemapRefresher {
+    /// Generates the sitemap of the entries returned by `entries`, then spawns a task on
+    /// the current tokio runtime regenerating it every `interval`.
+    ///
+    /// When a later regeneration fails, or `entries` panics, the previous sitemap keeps
+    /// being served and the error is reported by
+    /// [`last_error`](SitemapRefresher::last_error). The sitemap is generated on the
+    /// blocking threads of the runtime, so a large one doesn't stall the other tasks.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the first call of `entries` fails, or the sitemap can't be
+    /// generated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub async fn start<F, Fut>(sitemap: Sitemap, interval: Duration, entries: F) -> Result<Self>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Vec<UrlEntry>>> + Send,
+    {
+        let sitemap = Arc::new(sitemap);
+        let first = regenerate(&sitemap, &entries).await?;
+        let shared = Arc::new(Shared {
+            current: ArcSwap::from_pointee(first),
+            last_error: ArcSwapOption::empty(),
+            notify: Notify::new(),
+        });
+
+        let task_shared = Arc::clone(&shared);
+        let task = tokio::spawn(async move {
+            loop {
+                // Either the interval elapsed or a refresh was requested.
+                let _ = tokio::time::timeout(interval, task_shared.notify.notified()).await;
+                match regenerate(&sitemap, &entries).await {
+                    Ok(response) => {
+                        task_shared.current.store(Arc::new(response));
+                        task_shared.last_error.store(None);
+                    }
+                    Err(e) => task_shared.last_error.store(Some(Arc::new(e))),
+                }
+            }
+        });
+
+        Ok(Self {
+            shared,
+            _task: Arc::new(AbortOnDrop(task)),
+        })
+    }
+
+    /// The latest sitemap.
+    #[must_use]
+    pub fn current(&self) -> Arc<SitemapResponse> {
+        self.shared.current.load_full()
+    }
+
+    /// Regenerates the sitemap now instead of at the end of the interval, which then
+    /// starts over.
+    ///
+    /// Returns without waiting for the regeneration. Requests made during a regeneration
+    /// are merged into one more regeneration.
+    pub fn refresh(&self) {
+        self.shared.notify.notify_one();
+    }
+
+    /// The error of the last regeneration, if it failed.
+    #[must_use]
+    pub fn last_error(&self) -> Option<Arc<Error>> {
+        self.shared.last_error.load_full()
+    }
+}
+
+async fn regenerate<F, Fut>(sitemap: &Arc<Sitemap>, entries: &F) -> Result<SitemapResponse>
+where
+    F: Fn() -> Fut + Sync,
+    Fut: Future<Output = Result<Vec<UrlEntry>>> + Send,
+{
+    // A panic would end the task, leaving the sitemap stale without any error.
+    let urls = AssertUnwindSafe(async { entries().await })
+        .catch_unwind()
+        .await
+        .map_err(panicked)??;
+    let sitemap = Arc::clone(sitemap);
+    let xml = tokio::task::spawn_blocking(move || sitemap.generate(Vec::new(), &urls))
+        .await
+        .map_err(|e| Error::Source(Box::new(e)))??;
+    Ok(SitemapResponse::new(xml))
+}
+
+/// The error reporting a panic of the entries closure.
+fn panicked(payload: Box<dyn Any + Send>) -> Error {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown payload");
+    Error::Source(format!("the entries panicked: {message}").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn refreshes_on_request() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let calls = Arc::new(AtomicUsize::new(0));
+            let counter = Arc::clone(&calls);
+            let refresher =
+                SitemapRefresher::start(Sitemap::new(), Duration::from_secs(3600), move || {
+                    let call = counter.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        if call == 1 {
+                            return Err(Error::EmptyInput);
+                        }
+                        let loc = format!("https://a.com/{call}").parse().unwrap();
+                        Ok(vec![UrlEntry::new(loc, None, None, None)])
+                    }
+                })
+                .await
+                .unwrap();
+            let first = refresher.current();
+            assert!(std::str::from_utf8(first.xml())
+                .unwrap()
+                .contains("a.com/0"));
+
+            // A failed regeneration keeps the previous sitemap.
+            refresher.refresh();
+            while calls.load(Ordering::SeqCst) < 2 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            assert!(matches!(
+                refresher.last_error().as_deref(),
+                Some(Error::EmptyInput)
+            ));
+            assert_eq!(refresher.current(), first);
+
+            refresher.refresh();
+            while refresher.current() == first {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            assert!(std::str::from_utf8(refresher.current().xml())
+                .unwrap()
+                .contains("a.com/2"));
+            assert!(refresher.last_error().is_none());
+        });
+    }
+
+    #[test]
+    fn reports_panics() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let calls = Arc::new(AtomicUsize::new(0));
+            let counter = Arc::clone(&calls);
+            let refresher =
+                SitemapRefresher::start(Sitemap::new(), Duration::from_secs(3600), move || {
+                    let call = counter.fetch_add(1, Ordering::SeqCst);
+                    assert!(call != 1, "database is gone");
+                    async move {
+                        let loc = format!("https://a.com/{call}").parse().unwrap();
+                        Ok(vec![UrlEntry::new(loc, None, None, None)])
+                    }
+                })
+                .await
+                .unwrap();
+            let first = refresher.current();
+
+            refresher.refresh();
+            while refresher.last_error().is_none() {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            let error = refresher.last_error().unwrap().to_string();
+            assert!(
+                error.contains("the entries panicked: database is gone"),
+                "{error}"
+            );
+            assert_eq!(refresher.current(), first);
+
+            // The task keeps running.
+            refresher.refresh();
+            while refresher.current() == first {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            assert!(refresher.last_error().is_none());
+        });
+    }
+}
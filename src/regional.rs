@@ -0,0 +1,86 @@
+//! Extensions of regional search engines.
+//!
+//! Yandex reads the Google `<mobile:mobile/>` element, set with [`UrlEntry::mobile`].
+//! Baidu has its own mobile namespace, with a `type` attribute telling which devices a
+//! page is for, added as a [`BaiduMobile`] extension:
+//!
+//! ```rust
+//! use sitewriter::{regional::BaiduMobile, UrlEntry};
+//!
+//! let mut entry = UrlEntry::new("https://m.example.com/".parse()?, None, None, None);
+//! entry.extensions.push(BaiduMobile::PcMobile);
+//!
+//! let xml = sitewriter::generate_str(&[entry]);
+//! assert!(xml.contains(r#"xmlns:mobile="http://www.baidu.com/schemas/sitemap-mobile/1/""#));
+//! assert!(xml.contains(r#"<mobile:mobile type="pc,mobile"/>"#));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Both use the `mobile` prefix, so a sitemap can't mix [`BaiduMobile`] extensions with
+//! [`UrlEntry::mobile`], generating it fails with
+//! [`Error::NamespaceConflict`](crate::Error::NamespaceConflict). Baidu expects its own
+//! sitemap anyway.
+//!
+//! [`UrlEntry::mobile`]: crate::UrlEntry::mobile
+
+use crate::{ExtensionWriter, Result, SitemapExtension};
+
+/// The namespace of the Baidu mobile sitemap.
+pub const BAIDU_MOBILE_NAMESPACE: &str = "http://www.baidu.com/schemas/sitemap-mobile/1/";
+
+/// The devices a page is meant for, in a Baidu mobile sitemap.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BaiduMobile {
+    /// A page for mobile devices only.
+    Mobile,
+    /// A page with a single url for both desktop and mobile devices.
+    PcMobile,
+    /// A page that adapts its markup to the device.
+    HtmlAdapt,
+}
+
+impl BaiduMobile {
+    /// The value of the `type` attribute.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Mobile => "mobile",
+            Self::PcMobile => "pc,mobile",
+            Self::HtmlAdapt => "htmladapt",
+        }
+    }
+}
+
+impl SitemapExtension for BaiduMobile {
+    fn prefix(&self) -> &str {
+        "mobile"
+    }
+
+    fn namespace(&self) -> &str {
+        BAIDU_MOBILE_NAMESPACE
+    }
+
+    fn write(&self, writer: &mut ExtensionWriter<'_>) -> Result<()> {
+        writer.empty("mobile:mobile", &[("type", self.as_str())])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Error, UrlEntry};
+
+    #[test]
+    fn conflicts_with_google_mobile() {
+        let mut baidu = UrlEntry::new("https://a.com/".parse().unwrap(), None, None, None);
+        baidu.extensions.push(BaiduMobile::Mobile);
+        let mut google = UrlEntry::new("https://a.com/m".parse().unwrap(), None, None, None);
+        google.mobile = true;
+
+        assert!(crate::generate(Vec::new(), &[baidu.clone()]).is_ok());
+        assert!(matches!(
+            crate::generate(Vec::new(), &[baidu, google]),
+            Err(Error::NamespaceConflict { prefix }) if prefix == "mobile"
+        ));
+    }
+}
@@ -0,0 +1,233 @@
+//! Serving generated sitemaps over HTTP.
+//!
+//! [`SitemapResponse`] holds the generated XML and computes the headers to serve it with.
+//! With the `axum` or the `actix` feature it can be returned straight from a handler:
+//!
+//! ```rust
+//! # #[cfg(feature = "axum")]
+//! # {
+//! use sitewriter::{response::SitemapResponse, Sitemap, UrlEntry};
+//!
+//! async fn sitemap() -> SitemapResponse {
+//!     let urls = vec![UrlEntry::new("https://example.com/".parse().unwrap(), None, None, None)];
+//!     SitemapResponse::generate(&Sitemap::new(), &urls).cache_control("public, max-age=600")
+//! }
+//! # }
+//! ```
+
+use std::borrow::Cow;
+
+use crate::{compress::Compression, hash::fnv1a, Sitemap, UrlEntry};
+
+/// The default `Cache-Control` header value.
+pub const DEFAULT_CACHE_CONTROL: &str = "public, max-age=3600";
+
+/// A generated sitemap ready to be served.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SitemapResponse {
+    body: Vec<u8>,
+    cache_control: Option<String>,
+    compression: Compression,
+}
+
+impl SitemapResponse {
+    /// Serves already generated XML, such as a sitemap or a sitemap index.
+    #[must_use]
+    pub fn new(xml: impl Into<Vec<u8>>) -> Self {
+        Self {
+            body: xml.into(),
+            cache_control: Some(DEFAULT_CACHE_CONTROL.to_owned()),
+            compression: Compression::None,
+        }
+    }
+
+    /// Generates the sitemap of the given entries.
+    #[must_use]
+    pub fn generate(sitemap: &Sitemap, urls: &[UrlEntry]) -> Self {
+        Self::new(sitemap.generate_bytes(urls))
+    }
+
+    /// Sets the `Cache-Control` header, [`DEFAULT_CACHE_CONTROL`] by default.
+    #[must_use]
+    pub fn cache_control(mut self, value: impl Into<String>) -> Self {
+        self.cache_control = Some(value.into());
+        self
+    }
+
+    /// Omits the `Cache-Control` header.
+    #[must_use]
+    pub fn no_cache_control(mut self) -> Self {
+        self.cache_control = None;
+        self
+    }
+
+    /// Sets whether the body is sent compressed with `Content-Encoding: gzip`.
+    ///
+    /// Only enable it for clients that sent `Accept-Encoding: gzip`.
+    #[cfg(feature = "gzip")]
+    #[must_use]
+    pub const fn gzip(mut self, gzip: bool) -> Self {
+        self.compression = if gzip {
+            Compression::Gzip
+        } else {
+            Compression::None
+        };
+        self
+    }
+
+    /// Sets the encoding the body is sent with, in the `Content-Encoding` header.
+    ///
+    /// Only pick an encoding the client accepts. One whose feature is disabled is sent
+    /// uncompressed.
+    #[must_use]
+    pub const fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// The uncompressed XML.
+    #[must_use]
+    pub fn xml(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Returns the headers and the body to send.
+    ///
+    /// The `ETag` is derived from the body that is sent, so the compressed and uncompressed
+    /// representations have different tags.
+    #[must_use]
+    pub fn into_parts(self) -> (Vec<(&'static str, String)>, Vec<u8>) {
+        let encoding = self.compression.content_encoding();
+        let (body, encoding) = match self.compression.compress(&self.body) {
+            Ok(Cow::Owned(compressed)) => (compressed, encoding),
+            _ => (self.body, None),
+        };
+
+        let mut headers = vec![
+            ("content-type", "application/xml".to_owned()),
+            ("etag", format!("\"{:016x}\"", fnv1a(&body))),
+        ];
+        if let Some(encoding) = encoding {
+            headers.push(("content-encoding", encoding.to_owned()));
+            headers.push(("vary", "accept-encoding".to_owned()));
+        }
+        if let Some(cache_control) = self.cache_control {
+            headers.push(("cache-control", cache_control));
+        }
+
+        (headers, body)
+    }
+}
+
+#[cfg(feature = "axum")]
+impl axum_core::response::IntoResponse for SitemapResponse {
+    fn into_response(self) -> axum_core::response::Response {
+        let (headers, body) = self.into_parts();
+
+        let mut response = axum_core::response::Response::new(axum_core::body::Body::from(body));
+        for (name, value) in headers {
+            if let Ok(value) = http::HeaderValue::from_str(&value) {
+                response.headers_mut().insert(name, value);
+            }
+        }
+        response
+    }
+}
+
+/// The name [`SitemapResponse`] goes by in actix-web handlers.
+#[cfg(feature = "actix")]
+pub type SitemapResponder = SitemapResponse;
+
+#[cfg(feature = "actix")]
+impl actix_web::Responder for SitemapResponse {
+    type Body = actix_web::body::BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> actix_web::HttpResponse {
+        let (headers, body) = self.into_parts();
+
+        let mut response = actix_web::HttpResponse::Ok();
+        for header in headers {
+            response.insert_header(header);
+        }
+        response.body(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers() {
+        let (headers, body) = SitemapResponse::new("<urlset/>").into_parts();
+        assert_eq!(body, b"<urlset/>");
+        assert_eq!(headers[0], ("content-type", "application/xml".to_owned()));
+        assert_eq!(headers[1].0, "etag");
+        assert_eq!(
+            headers[2],
+            ("cache-control", DEFAULT_CACHE_CONTROL.to_owned())
+        );
+
+        let (headers, _) = SitemapResponse::new("<urlset/>")
+            .no_cache_control()
+            .into_parts();
+        assert_eq!(headers.len(), 2);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip() {
+        let plain = SitemapResponse::new("<urlset/>");
+        let (plain_headers, _) = plain.clone().into_parts();
+        let (headers, body) = plain.gzip(true).into_parts();
+
+        assert_eq!(&body[..2], [0x1f, 0x8b]);
+        assert!(headers.contains(&("content-encoding", "gzip".to_owned())));
+        assert_ne!(headers[1], plain_headers[1]);
+    }
+
+    #[test]
+    fn compression() {
+        for &compression in Compression::ENABLED {
+            let response = SitemapResponse::new("<urlset/>").compression(compression);
+            let (headers, body) = response.into_parts();
+            assert_ne!(body, b"<urlset/>");
+            let encoding = compression.content_encoding().unwrap().to_owned();
+            assert!(headers.contains(&("content-encoding", encoding)));
+        }
+        #[cfg(not(feature = "zstd"))]
+        {
+            let response = SitemapResponse::new("<urlset/>").compression(Compression::Zstd);
+            let (headers, body) = response.into_parts();
+            assert_eq!(body, b"<urlset/>");
+            assert_eq!(headers.len(), 3);
+        }
+    }
+
+    #[cfg(feature = "axum")]
+    #[test]
+    fn axum_response() {
+        use axum_core::response::IntoResponse;
+
+        let response = SitemapResponse::new("<urlset/>").into_response();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "application/xml");
+        assert!(response.headers().contains_key("etag"));
+    }
+
+    #[cfg(feature = "actix")]
+    #[test]
+    fn actix_responder() {
+        use actix_web::{test::TestRequest, Responder};
+
+        let request = TestRequest::default().to_http_request();
+        let response: SitemapResponder = SitemapResponse::new("<sitemapindex/>");
+        let response = response.respond_to(&request);
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/xml"
+        );
+        assert!(response.headers().contains_key("etag"));
+    }
+}
@@ -0,0 +1,258 @@
+//! Finding the sitemaps a site declares in its `robots.txt`.
+//!
+//! ```rust
+//! use sitewriter::robots::discover_sitemaps;
+//!
+//! let robots = "User-agent: *\nDisallow: /admin\n\nSitemap: https://example.com/sitemap.xml\n";
+//! let sitemaps = discover_sitemaps(robots);
+//! assert_eq!(sitemaps[0].as_str(), "https://example.com/sitemap.xml");
+//! ```
+//!
+//! With [`fetch_robots_sitemaps_with`] the `robots.txt` is fetched from the site, and the
+//! sitemaps found can then be read with [`crate::fetch`].
+
+use url::Url;
+
+use crate::{client::HttpClient, Error, Result};
+
+/// The urls of the `Sitemap:` directives of a `robots.txt`, in order and without duplicates.
+///
+/// The directive name is case insensitive and may appear anywhere in the file, as it isn't
+/// tied to a user agent group. Comments are ignored, and so are values that aren't
+/// absolute urls.
+#[must_use]
+pub fn discover_sitemaps(robots_txt: &str) -> Vec<Url> {
+    let mut sitemaps: Vec<Url> = Vec::new();
+    for line in robots_txt.trim_start_matches('\u{feff}').lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if !name.trim().eq_ignore_ascii_case("sitemap") {
+            continue;
+        }
+        if let Ok(url) = value.trim().parse::<Url>() {
+            if !sitemaps.contains(&url) {
+                sitemaps.push(url);
+            }
+        }
+    }
+    sitemaps
+}
+
+/// The `Allow` and `Disallow` rules of a `robots.txt` that apply to one crawler.
+///
+/// ```rust
+/// use sitewriter::robots::RobotsRules;
+///
+/// let robots = "User-agent: *\nDisallow: /admin\nAllow: /admin/public\nDisallow: /*.pdf$\n";
+/// let rules = RobotsRules::parse(robots, "sitewriter");
+/// assert!(!rules.is_allowed(&"https://example.com/admin/users".parse()?));
+/// assert!(rules.is_allowed(&"https://example.com/admin/public/faq".parse()?));
+/// assert!(!rules.is_allowed(&"https://example.com/files/a.pdf".parse()?));
+/// assert!(rules.is_allowed(&"https://example.com/files/a.pdf?v=2".parse()?));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RobotsRules {
+    /// Whether each rule allows, and its path pattern.
+    rules: Vec<(bool, String)>,
+}
+
+impl RobotsRules {
+    /// The rules of the groups whose `User-agent` is `user_agent`, compared case
+    /// insensitively, or of the `*` groups if there are none.
+    #[must_use]
+    pub fn parse(robots_txt: &str, user_agent: &str) -> Self {
+        let mut specific = Vec::new();
+        let mut any = Vec::new();
+        let mut found_specific = false;
+        // The agents of the current group, and whether its rules started.
+        let mut agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+
+        for line in robots_txt.trim_start_matches('\u{feff}').lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        agents.clear();
+                        in_rules = false;
+                    }
+                    let agent = value.to_ascii_lowercase();
+                    found_specific |= agent.eq_ignore_ascii_case(user_agent);
+                    agents.push(agent);
+                }
+                directive @ ("allow" | "disallow") => {
+                    in_rules = true;
+                    // An empty `Disallow` allows everything, like no rule.
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let rule = (directive == "allow", value.to_owned());
+                    if agents.iter().any(|a| a.eq_ignore_ascii_case(user_agent)) {
+                        specific.push(rule);
+                    } else if agents.iter().any(|a| a == "*") {
+                        any.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            rules: if found_specific { specific } else { any },
+        }
+    }
+
+    /// Whether the crawler may fetch `url`.
+    ///
+    /// The rule with the longest pattern matching the path and query wins, `Allow` winning
+    /// a tie. Patterns may contain `*` wildcards and end with `$` to match the end.
+    #[must_use]
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        let path = url.query().map_or_else(
+            || url.path().to_owned(),
+            |query| format!("{}?{query}", url.path()),
+        );
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| pattern_matches(pattern, &path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+/// Whether a robots.txt path pattern matches `path`.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = pattern
+        .strip_suffix('$')
+        .map_or((pattern, false), |pattern| (pattern, true));
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return !anchored || rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    if anchored {
+        rest.ends_with(last)
+    } else {
+        rest.contains(last)
+    }
+}
+
+/// Fetches the `robots.txt` of the site of `site` and returns the sitemaps it declares.
+///
+/// See [`fetch_robots_sitemaps_with`].
+///
+/// # Errors
+///
+/// Will return `Err` as [`fetch_robots_sitemaps_with`].
+#[cfg(feature = "fetch")]
+pub fn fetch_robots_sitemaps(site: &Url) -> Result<Vec<Url>> {
+    fetch_robots_sitemaps_with(&crate::client::UreqClient::default(), site)
+}
+
+/// Fetches `/robots.txt` from the origin of `site` and returns the sitemaps it declares, see
+/// [`discover_sitemaps`].
+///
+/// A site without a `robots.txt`, answering with a `404` or `410`, declares no sitemaps.
+///
+/// # Errors
+///
+/// Will return [`Error::Request`] if no response was received or its status is another
+/// error.
+pub fn fetch_robots_sitemaps_with<C: HttpClient>(client: &C, site: &Url) -> Result<Vec<Url>> {
+    let mut url = site
+        .join("/robots.txt")
+        .map_err(|source| Error::InvalidUrl {
+            value: site.to_string(),
+            source,
+        })?;
+    url.set_query(None);
+    let response = client.get(&url)?;
+    match response.status {
+        404 | 410 => Ok(Vec::new()),
+        _ if response.is_success() => {
+            Ok(discover_sitemaps(&String::from_utf8_lossy(&response.body)))
+        }
+        status => Err(Error::Request {
+            url,
+            source: format!("unexpected status {status}").into(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::HttpResponse;
+
+    #[test]
+    fn finds_directives() {
+        let robots = "\u{feff}# Sitemap: https://a.com/commented.xml\r\n\
+User-agent: *\r\n\
+sitemap:https://a.com/a.xml\r\n\
+  SITEMAP : https://a.com/b.xml # the news\r\n\
+Sitemap: /relative.xml\r\n\
+Sitemap: https://a.com/a.xml\r\n";
+        let locs: Vec<String> = discover_sitemaps(robots)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(locs, ["https://a.com/a.xml", "https://a.com/b.xml"]);
+    }
+
+    #[test]
+    fn applies_the_matching_group() {
+        let robots = "User-agent: *\nDisallow: /\n\n\
+User-agent: Other\nUser-agent: sitewriter\nDisallow: /private\nAllow: /private/*.html$\n";
+        let rules = RobotsRules::parse(robots, "SiteWriter");
+        let allowed =
+            |path: &str| rules.is_allowed(&format!("https://a.com{path}").parse().unwrap());
+        assert!(allowed("/"));
+        assert!(!allowed("/private/a.png"));
+        assert!(allowed("/private/a.html"));
+        assert!(!allowed("/private/a.html?x"));
+
+        let rules = RobotsRules::parse(robots, "unknown");
+        assert!(!rules.is_allowed(&"https://a.com/".parse().unwrap()));
+        assert!(RobotsRules::default().is_allowed(&"https://a.com/".parse().unwrap()));
+    }
+
+    #[test]
+    fn fetches_robots() {
+        let client = |url: &Url| {
+            Ok(match url.host_str() {
+                Some("a.com") => {
+                    assert_eq!(url.as_str(), "https://a.com/robots.txt");
+                    HttpResponse::ok("Sitemap: https://a.com/sitemap.xml")
+                }
+                Some("b.com") => HttpResponse::status(404),
+                _ => HttpResponse::status(500),
+            })
+        };
+        let sitemaps =
+            fetch_robots_sitemaps_with(&client, &"https://a.com/blog/?p=1".parse().unwrap());
+        assert_eq!(sitemaps.unwrap().len(), 1);
+        assert!(
+            fetch_robots_sitemaps_with(&client, &"https://b.com/".parse().unwrap())
+                .unwrap()
+                .is_empty()
+        );
+        assert!(fetch_robots_sitemaps_with(&client, &"https://c.com/".parse().unwrap()).is_err());
+    }
+}
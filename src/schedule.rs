@@ -0,0 +1,181 @@
+//! Recrawl schedules derived from the entries.
+//!
+//! The `changefreq` and `lastmod` of each entry give a suggested time to crawl it again,
+//! which can be exported as JSON for a crawler:
+//!
+//! ```rust
+//! use chrono::Utc;
+//! use sitewriter::{schedule::{to_json, Scheduler}, ChangeFreq, UrlEntry};
+//!
+//! let urls = vec![UrlEntry::new(
+//!     "https://example.com/".parse()?,
+//!     Some(Utc::now()),
+//!     Some(ChangeFreq::Daily),
+//!     None,
+//! )];
+//! let hints = Scheduler::new().schedule(&urls, Utc::now());
+//! println!("{}", to_json(&hints));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt::Write;
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use url::Url;
+
+use crate::{json, ChangeFreq, UrlEntry};
+
+impl ChangeFreq {
+    /// The time between two changes, `None` for [`ChangeFreq::Never`].
+    ///
+    /// Months are 30 days and years 365 days.
+    #[must_use]
+    pub const fn interval(self) -> Option<Duration> {
+        Some(match self {
+            Self::Always => Duration::zero(),
+            Self::Hourly => Duration::hours(1),
+            Self::Daily => Duration::days(1),
+            Self::Weekly => Duration::weeks(1),
+            Self::Monthly => Duration::days(30),
+            Self::Yearly => Duration::days(365),
+            Self::Never => return None,
+        })
+    }
+}
+
+/// When to crawl a url next.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CrawlHint {
+    /// The url.
+    pub loc: Url,
+    /// The suggested time of the next crawl, `None` if it never needs to be crawled again.
+    pub next_crawl: Option<DateTime<Utc>>,
+}
+
+/// Computes [`CrawlHint`]s from entries.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Scheduler {
+    default_interval: Duration,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            default_interval: Duration::weeks(1),
+        }
+    }
+}
+
+impl Scheduler {
+    /// Creates a scheduler with a default interval of one week.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the interval used for entries without a `changefreq`.
+    #[must_use]
+    pub const fn default_interval(mut self, interval: Duration) -> Self {
+        self.default_interval = interval;
+        self
+    }
+
+    /// Returns the hint of each entry, the soonest first.
+    ///
+    /// The next crawl is one interval after the `lastmod`, or after `now` for entries
+    /// without one. Overdue entries are scheduled at `now`. Entries that never change are
+    /// last.
+    #[must_use]
+    pub fn schedule(&self, urls: &[UrlEntry], now: DateTime<Utc>) -> Vec<CrawlHint> {
+        let mut hints: Vec<CrawlHint> = urls
+            .iter()
+            .map(|entry| {
+                let interval = entry
+                    .changefreq
+                    .map_or(Some(self.default_interval), ChangeFreq::interval);
+                let next_crawl = interval.map(|interval| {
+                    let base = entry.lastmod.unwrap_or(now);
+                    (base + interval).max(now)
+                });
+                CrawlHint {
+                    loc: entry.loc.clone(),
+                    next_crawl,
+                }
+            })
+            .collect();
+
+        hints.sort_by(|a, b| match (a.next_crawl, b.next_crawl) {
+            (Some(a_next), Some(b_next)) => a_next.cmp(&b_next).then_with(|| a.loc.cmp(&b.loc)),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.loc.cmp(&b.loc),
+        });
+        hints
+    }
+}
+
+/// Serializes the hints as a JSON array of `{"loc": .., "next_crawl": ..}` objects, one per
+/// line, with RFC 3339 times and `null` for urls that never need crawling.
+#[must_use]
+pub fn to_json(hints: &[CrawlHint]) -> String {
+    let mut out = String::from("[");
+    for (i, hint) in hints.iter().enumerate() {
+        out.push_str(if i == 0 { "\n  " } else { ",\n  " });
+        out.push_str("{\"loc\": ");
+        json::string(&mut out, hint.loc.as_str());
+        out.push_str(", \"next_crawl\": ");
+        match hint.next_crawl {
+            Some(next) => {
+                let _ = write!(
+                    out,
+                    "\"{}\"",
+                    next.to_rfc3339_opts(SecondsFormat::Secs, true)
+                );
+            }
+            None => out.push_str("null"),
+        }
+        out.push('}');
+    }
+    out.push_str(if hints.is_empty() { "]\n" } else { "\n]\n" });
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn schedules_from_changefreq_and_lastmod() {
+        let now = Utc.with_ymd_and_hms(2024, 5, 10, 0, 0, 0).unwrap();
+        let entry = |loc: &str, lastmod: Option<u32>, changefreq| {
+            UrlEntry::new(
+                loc.parse().unwrap(),
+                lastmod.map(|d| Utc.with_ymd_and_hms(2024, 5, d, 0, 0, 0).unwrap()),
+                changefreq,
+                None,
+            )
+        };
+        let urls = vec![
+            entry("https://a.com/archive", None, Some(ChangeFreq::Never)),
+            entry("https://a.com/weekly", Some(9), None),
+            entry("https://a.com/daily", Some(9), Some(ChangeFreq::Daily)),
+            entry("https://a.com/stale", Some(1), Some(ChangeFreq::Daily)),
+        ];
+
+        let hints = Scheduler::new().schedule(&urls, now);
+        let json = to_json(&hints);
+        assert_eq!(
+            json,
+            r#"[
+  {"loc": "https://a.com/daily", "next_crawl": "2024-05-10T00:00:00Z"},
+  {"loc": "https://a.com/stale", "next_crawl": "2024-05-10T00:00:00Z"},
+  {"loc": "https://a.com/weekly", "next_crawl": "2024-05-16T00:00:00Z"},
+  {"loc": "https://a.com/archive", "next_crawl": null}
+]
+"#
+        );
+        assert_eq!(to_json(&[]), "[]\n");
+    }
+}
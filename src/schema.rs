@@ -0,0 +1,393 @@
+//! Checking XML against the sitemap schemas.
+//!
+//! [`check`] is a small embedded checker of the rules of
+//! [sitemap.xsd](https://www.sitemaps.org/schemas/sitemap/0.9/sitemap.xsd) and
+//! [siteindex.xsd](https://www.sitemaps.org/schemas/sitemap/0.9/siteindex.xsd): the
+//! elements, their order and the format of their values. Elements of extensions must be in
+//! a namespace of their own, and the mobile extension must be an empty `<mobile:mobile/>`.
+//! It catches in tests what Search Console would report after the sitemap is submitted:
+//!
+//! ```rust
+//! use sitewriter::{Sitemap, UrlEntry};
+//!
+//! let urls = vec![UrlEntry::new("https://example.com/".parse()?, None, None, None)];
+//! let xml = Sitemap::new().generate_bytes(&urls);
+//! assert!(Sitemap::validate_xml(&xml).is_empty());
+//!
+//! let violations = Sitemap::validate_xml(
+//!     br#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+//!         <url><priority>2</priority></url>
+//!     </urlset>"#,
+//! );
+//! assert_eq!(violations.len(), 2);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt::Display;
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    name::{Namespace, ResolveResult},
+    NsReader,
+};
+use url::Url;
+
+use crate::{parse::parse_lastmod, validate::MAX_LOC_LEN, ChangeFreq, MOBILE_NAMESPACE};
+
+/// The namespace of sitemaps and sitemap indexes.
+pub const SITEMAP_NAMESPACE: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
+/// The shortest loc the schema allows.
+const MIN_LOC_LEN: usize = 12;
+
+/// A place where the XML breaks the schema.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SchemaViolation {
+    /// The byte offset in the XML where it was found.
+    pub position: usize,
+    /// What is wrong.
+    pub message: String,
+}
+
+impl Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at byte {}: {}", self.position, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Urlset,
+    Index,
+}
+
+impl Kind {
+    /// The children of the root and their own children, in the order of the schema.
+    const fn entry(self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            Self::Urlset => ("url", &["loc", "lastmod", "changefreq", "priority"]),
+            Self::Index => ("sitemap", &["loc", "lastmod"]),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Frame {
+    Root(Kind),
+    /// A `<url>` or `<sitemap>`, with the position of the next child allowed.
+    Entry {
+        kind: Kind,
+        next: usize,
+        has_loc: bool,
+    },
+    /// A child of an entry, with its text.
+    Field {
+        name: &'static str,
+        text: String,
+    },
+    /// An element outside the sitemap namespace, whose content isn't checked.
+    Foreign {
+        mobile: bool,
+    },
+}
+
+/// Checks the XML against the sitemap or sitemap index schema, returning the violations
+/// in document order.
+///
+/// Checking stops at the first malformed part of the XML, reported as a violation.
+#[must_use]
+pub fn check(xml: &[u8]) -> Vec<SchemaViolation> {
+    let mut checker = Checker::default();
+    let mut reader = NsReader::from_reader(xml);
+    let mut malformed = false;
+    loop {
+        let position = reader.buffer_position();
+        let event = reader.read_resolved_event();
+        checker.position = position;
+        match event {
+            Ok((ns, Event::Start(e))) => checker.open(&ns, &e),
+            Ok((ns, Event::Empty(e))) => {
+                checker.open(&ns, &e);
+                checker.close();
+            }
+            Ok((_, Event::End(_))) => checker.close(),
+            Ok((_, Event::Text(e))) => match e.unescape() {
+                Ok(text) => checker.text(&text),
+                Err(e) => {
+                    checker.violation(format!("malformed xml: {e}"));
+                    malformed = true;
+                    break;
+                }
+            },
+            Ok((_, Event::CData(e))) => checker.text(&String::from_utf8_lossy(&e)),
+            Ok((_, Event::Eof)) => break,
+            Ok(_) => {}
+            Err(e) => {
+                checker.violation(format!("malformed xml: {e}"));
+                malformed = true;
+                break;
+            }
+        }
+    }
+    if !checker.seen_root {
+        checker.violation("there is no root element".to_owned());
+    } else if !checker.stack.is_empty() && !malformed {
+        checker.violation("the document ends before the root element is closed".to_owned());
+    }
+    checker.violations
+}
+
+#[derive(Debug, Default)]
+struct Checker {
+    stack: Vec<Frame>,
+    violations: Vec<SchemaViolation>,
+    position: usize,
+    seen_root: bool,
+}
+
+impl Checker {
+    fn violation(&mut self, message: String) {
+        self.violations.push(SchemaViolation {
+            position: self.position,
+            message,
+        });
+    }
+
+    fn open(&mut self, ns: &ResolveResult<'_>, e: &BytesStart<'_>) {
+        let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+        let namespace = match ns {
+            ResolveResult::Bound(Namespace(ns)) => Some(*ns),
+            _ => None,
+        };
+        let in_sitemap = namespace == Some(SITEMAP_NAMESPACE.as_bytes());
+
+        let frame = match self.stack.last_mut() {
+            None if self.seen_root => {
+                self.violation(format!("<{name}> is after the root element"));
+                Frame::Foreign { mobile: false }
+            }
+            None => {
+                self.seen_root = true;
+                match name.as_str() {
+                    "urlset" if in_sitemap => Frame::Root(Kind::Urlset),
+                    "sitemapindex" if in_sitemap => Frame::Root(Kind::Index),
+                    _ => {
+                        self.violation(format!(
+                            "the root element must be <urlset> or <sitemapindex> in the \
+                             namespace {SITEMAP_NAMESPACE}, not <{name}>"
+                        ));
+                        Frame::Foreign { mobile: false }
+                    }
+                }
+            }
+            Some(&mut Frame::Root(kind)) => {
+                let (entry, _) = kind.entry();
+                if in_sitemap && name == entry {
+                    Frame::Entry {
+                        kind,
+                        next: 0,
+                        has_loc: false,
+                    }
+                } else {
+                    self.violation(format!("unexpected <{name}>, expected <{entry}>"));
+                    Frame::Foreign { mobile: false }
+                }
+            }
+            Some(Frame::Entry {
+                kind,
+                next,
+                has_loc,
+            }) => {
+                let (entry, fields) = kind.entry();
+                if in_sitemap {
+                    match fields.iter().position(|&field| field == name) {
+                        Some(i) if i >= *next => {
+                            *next = i + 1;
+                            *has_loc |= i == 0;
+                            Frame::Field {
+                                name: fields[i],
+                                text: String::new(),
+                            }
+                        }
+                        Some(_) => {
+                            self.violation(format!("<{name}> is repeated or out of order"));
+                            Frame::Foreign { mobile: false }
+                        }
+                        None => {
+                            self.violation(format!("unexpected <{name}> in <{entry}>"));
+                            Frame::Foreign { mobile: false }
+                        }
+                    }
+                } else {
+                    // Extensions come after the standard elements.
+                    *next = fields.len();
+                    self.foreign(ns, namespace, &name)
+                }
+            }
+            Some(Frame::Field { name: field, .. }) => {
+                let field = *field;
+                self.violation(format!("<{field}> must only contain text, not <{name}>"));
+                Frame::Foreign { mobile: false }
+            }
+            Some(&mut Frame::Foreign { mobile }) => {
+                if mobile {
+                    self.violation("<mobile:mobile> must be empty".to_owned());
+                }
+                Frame::Foreign { mobile: false }
+            }
+        };
+        self.stack.push(frame);
+    }
+
+    /// The frame of an extension element.
+    fn foreign(&mut self, ns: &ResolveResult<'_>, namespace: Option<&[u8]>, name: &str) -> Frame {
+        match namespace {
+            Some(ns) if ns == MOBILE_NAMESPACE.as_bytes() => {
+                if name != "mobile" {
+                    self.violation(format!("unexpected <{name}> of the mobile extension"));
+                }
+                Frame::Foreign { mobile: true }
+            }
+            Some(_) => Frame::Foreign { mobile: false },
+            None => {
+                match ns {
+                    ResolveResult::Unknown(prefix) => self.violation(format!(
+                        "the prefix of <{name}>, {}, isn't bound to a namespace",
+                        String::from_utf8_lossy(prefix)
+                    )),
+                    _ => self.violation(format!(
+                        "<{name}> must be in the sitemap namespace or the namespace of an \
+                         extension"
+                    )),
+                }
+                Frame::Foreign { mobile: false }
+            }
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        match self.stack.last_mut() {
+            Some(Frame::Field { text: field, .. }) => field.push_str(text),
+            Some(Frame::Foreign { .. }) => {}
+            _ if text.trim().is_empty() => {}
+            _ => self.violation(format!("unexpected text {:?}", text.trim())),
+        }
+    }
+
+    fn close(&mut self) {
+        match self.stack.pop() {
+            Some(Frame::Field { name, text }) => {
+                if let Some(problem) = check_value(name, text.trim()) {
+                    self.violation(problem);
+                }
+            }
+            Some(Frame::Entry { kind, has_loc, .. }) if !has_loc => {
+                let (entry, _) = kind.entry();
+                self.violation(format!("<{entry}> has no <loc>"));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// What is wrong with the value of a standard element, if anything.
+fn check_value(name: &str, value: &str) -> Option<String> {
+    let valid = match name {
+        "loc" => {
+            if !(MIN_LOC_LEN..=MAX_LOC_LEN).contains(&value.len()) {
+                return Some(format!(
+                    "<loc> must be between {MIN_LOC_LEN} and {MAX_LOC_LEN} characters long"
+                ));
+            }
+            Url::parse(value).is_ok()
+        }
+        "lastmod" => parse_lastmod(value).is_some(),
+        "changefreq" => value.parse::<ChangeFreq>().is_ok(),
+        "priority" => {
+            // A decimal, without the exponents and special values Rust would parse.
+            value.bytes().all(|b| b.is_ascii_digit() || b == b'.')
+                && value
+                    .parse::<f32>()
+                    .is_ok_and(|priority| (0.0..=1.0).contains(&priority))
+        }
+        _ => true,
+    };
+    (!valid).then(|| format!("<{name}> has an invalid value {value:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(xml: &str) -> Vec<String> {
+        check(xml.as_bytes())
+            .into_iter()
+            .map(|v| v.message)
+            .collect()
+    }
+
+    #[test]
+    fn checks_urlsets() {
+        let valid = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="{SITEMAP_NAMESPACE}" xmlns:mobile="{MOBILE_NAMESPACE}" xmlns:x="urn:x">
+    <url>
+        <loc>https://example.com/a&amp;b</loc>
+        <lastmod>2024-05-01</lastmod>
+        <changefreq>daily</changefreq>
+        <priority>0.5</priority>
+        <mobile:mobile/>
+        <x:thing><x:nested>anything</x:nested></x:thing>
+    </url>
+</urlset>"#
+        );
+        assert_eq!(messages(&valid), Vec::<String>::new());
+
+        let invalid = format!(
+            r#"<urlset xmlns="{SITEMAP_NAMESPACE}" xmlns:mobile="{MOBILE_NAMESPACE}">
+    <url><lastmod>yesterday</lastmod><loc>https://example.com/</loc></url>
+    <url><loc>https://example.com/</loc><mobile:mobile><x/></mobile:mobile><priority>1.0</priority></url>
+    <url><loc>https://example.com/</loc><other>text</other><y:z/></url>
+    <page/>
+</urlset>"#
+        );
+        assert_eq!(
+            messages(&invalid),
+            [
+                "<lastmod> has an invalid value \"yesterday\"",
+                "<loc> is repeated or out of order",
+                "<url> has no <loc>",
+                "<mobile:mobile> must be empty",
+                "<priority> is repeated or out of order",
+                "unexpected <other> in <url>",
+                "the prefix of <z>, y, isn't bound to a namespace",
+                "unexpected <page>, expected <url>",
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_indexes_and_roots() {
+        let index = format!(
+            r#"<sitemapindex xmlns="{SITEMAP_NAMESPACE}">
+    <sitemap><loc>https://example.com/1.xml</loc><lastmod>2024-05-01T08:00:00Z</lastmod></sitemap>
+    <sitemap><loc>short</loc><changefreq>daily</changefreq></sitemap>
+</sitemapindex>"#
+        );
+        assert_eq!(
+            messages(&index),
+            [
+                "<loc> must be between 12 and 2048 characters long",
+                "unexpected <changefreq> in <sitemap>",
+            ]
+        );
+
+        assert_eq!(messages("<urlset/>").len(), 1);
+        assert_eq!(messages(""), ["there is no root element"]);
+        assert_eq!(
+            messages(&format!("<urlset xmlns=\"{SITEMAP_NAMESPACE}\"><url>")),
+            ["the document ends before the root element is closed"]
+        );
+        assert!(messages("<urlset></url>")[1].starts_with("malformed xml"));
+    }
+}
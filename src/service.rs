@@ -0,0 +1,217 @@
+//! A [`tower_service::Service`] serving sitemaps.
+//!
+//! [`SitemapService`] maps request paths to [`SitemapResponse`]s. Clones share the same
+//! routes, so a background task can regenerate a sitemap while the server keeps serving:
+//!
+//! ```rust
+//! use sitewriter::{response::SitemapResponse, service::SitemapService, Sitemap, UrlEntry};
+//!
+//! let service = SitemapService::new()
+//!     .route("/sitemap.xml", SitemapResponse::new("<urlset/>"));
+//!
+//! let updater = service.clone();
+//! let urls = vec![UrlEntry::new("https://example.com/".parse()?, None, None, None)];
+//! updater.set("/sitemap.xml", SitemapResponse::generate(&Sitemap::new(), &urls));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    future::{ready, Ready},
+    sync::{Arc, RwLock},
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use http::{header, HeaderValue, Method, Request, Response, StatusCode};
+use http_body_util::Full;
+
+use crate::{compress::Compression, response::SitemapResponse};
+
+/// Serves sitemaps and sitemap indexes under configured paths, see the [module docs](self).
+///
+/// Requests to other paths get a `404 Not Found`, and requests with a method other than
+/// `GET` or `HEAD` get a `405 Method Not Allowed`.
+#[derive(Debug, Clone, Default)]
+pub struct SitemapService {
+    routes: Arc<RwLock<HashMap<String, SitemapResponse>>>,
+}
+
+impl SitemapService {
+    /// Creates a service without routes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves `response` at `path`.
+    #[must_use]
+    pub fn route(self, path: impl Into<String>, response: SitemapResponse) -> Self {
+        self.set(path, response);
+        self
+    }
+
+    /// Replaces what is served at `path`, for this service and all its clones.
+    pub fn set(&self, path: impl Into<String>, response: SitemapResponse) {
+        self.routes
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.into(), response);
+    }
+
+    /// Stops serving `path`.
+    pub fn remove(&self, path: &str) {
+        self.routes
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(path);
+    }
+
+    fn respond<B>(&self, request: &Request<B>) -> Response<Full<Bytes>> {
+        let found = self
+            .routes
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(request.uri().path())
+            .cloned();
+        let Some(found) = found else {
+            return status(StatusCode::NOT_FOUND);
+        };
+
+        let head = request.method() == Method::HEAD;
+        if !head && request.method() != Method::GET {
+            let mut response = status(StatusCode::METHOD_NOT_ALLOWED);
+            response
+                .headers_mut()
+                .insert(header::ALLOW, HeaderValue::from_static("GET, HEAD"));
+            return response;
+        }
+
+        let found = found.compression(negotiate(request));
+
+        let (headers, body) = found.into_parts();
+        let mut response = Response::new(if head {
+            Full::default()
+        } else {
+            Full::new(Bytes::from(body))
+        });
+        for (name, value) in headers {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                response.headers_mut().insert(name, value);
+            }
+        }
+        response
+    }
+}
+
+fn status(status: StatusCode) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::default());
+    *response.status_mut() = status;
+    response
+}
+
+/// The enabled encoding the client prefers by the q-values of its `Accept-Encoding`,
+/// ties going to the best one of [`Compression::ENABLED`].
+fn negotiate<B>(request: &Request<B>) -> Compression {
+    let accepted: Vec<(&str, f32)> = request
+        .headers()
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|coding| {
+            let mut parts = coding.split(';');
+            let name = parts.next().unwrap_or_default().trim();
+            let q = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(1.0, |q| q.trim().parse().unwrap_or(0.0));
+            (name, q)
+        })
+        .collect();
+
+    let mut best = (Compression::None, 0.0);
+    for &compression in Compression::ENABLED {
+        let name = compression.content_encoding().unwrap_or_default();
+        let q = accepted
+            .iter()
+            .find(|(coding, _)| coding.eq_ignore_ascii_case(name))
+            .map_or(0.0, |&(_, q)| q);
+        if q > best.1 {
+            best = (compression, q);
+        }
+    }
+    best.0
+}
+
+impl<B> tower_service::Service<Request<B>> for SitemapService {
+    type Response = Response<Full<Bytes>>;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        ready(Ok(self.respond(&request)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_service::Service;
+
+    use super::*;
+
+    fn call(service: &mut SitemapService, request: Request<()>) -> Response<Full<Bytes>> {
+        service.call(request).into_inner().unwrap()
+    }
+
+    fn get(path: &str) -> Request<()> {
+        Request::get(path).body(()).unwrap()
+    }
+
+    #[test]
+    fn routes() {
+        let mut service =
+            SitemapService::new().route("/sitemap.xml", SitemapResponse::new("<urlset/>"));
+
+        let response = call(&mut service, get("/sitemap.xml"));
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/xml");
+
+        assert_eq!(call(&mut service, get("/other.xml")).status(), 404);
+        let post = Request::post("/sitemap.xml").body(()).unwrap();
+        assert_eq!(call(&mut service, post).status(), 405);
+
+        service.clone().remove("/sitemap.xml");
+        assert_eq!(call(&mut service, get("/sitemap.xml")).status(), 404);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn negotiates_gzip() {
+        let mut service =
+            SitemapService::new().route("/sitemap.xml", SitemapResponse::new("<urlset/>"));
+
+        let request = Request::get("/sitemap.xml")
+            .header("accept-encoding", "br, gzip;q=0.5")
+            .body(())
+            .unwrap();
+        let response = call(&mut service, request);
+        let preferred = if cfg!(feature = "brotli") {
+            "br"
+        } else {
+            "gzip"
+        };
+        assert_eq!(response.headers()["content-encoding"], preferred);
+
+        let request = Request::get("/sitemap.xml")
+            .header("accept-encoding", "gzip;q=0")
+            .body(())
+            .unwrap();
+        let response = call(&mut service, request);
+        assert!(!response.headers().contains_key("content-encoding"));
+    }
+}
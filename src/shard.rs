@@ -0,0 +1,236 @@
+//! Sitemaps split into shards by a stable hash of the locs.
+//!
+//! [`Sitemap::write_split`] fills each part with the next entries, so adding or removing
+//! one url shifts every part after it. [`Sitemap::write_sharded`] assigns each url to a
+//! shard by the hash of its loc instead: when a url changes, only the bytes of its shard
+//! change, and CDNs and search engines re-fetch that file alone.
+//!
+//! ```rust,no_run
+//! use sitewriter::{shard::ShardOptions, split::SplitOutput, Sitemap, UrlEntry};
+//!
+//! # let urls: Vec<UrlEntry> = Vec::new();
+//! let output = SplitOutput::new("public", "https://example.com/".parse()?);
+//! let files = Sitemap::new().write_sharded(&urls, &output, &ShardOptions::new(16))?;
+//! // The index, then the non empty ones of sitemap-1.xml to sitemap-16.xml.
+//! assert!(files.len() <= 17);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The assignment only depends on the loc, the shard count and the seed, so it holds
+//! across runs, platforms and releases. Changing the count or the seed moves most urls.
+
+use std::io::Cursor;
+
+use url::Url;
+
+use crate::{
+    hash::{fnv1a, fnv1a_from},
+    index::IndexEntry,
+    sink::OutputSink,
+    sort::sort_entries,
+    split::{SplitOutput, WrittenFile},
+    write_urlset, Error, Limit, Result, Sitemap, UrlEntry,
+};
+
+/// How [`Sitemap::write_sharded`] assigns the entries to shards.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ShardOptions {
+    count: usize,
+    seed: u64,
+}
+
+impl ShardOptions {
+    /// Assigns the entries to `count` shards, at least one, with a seed of 0.
+    ///
+    /// Pick a count that leaves every shard well under the [limits](Sitemap::limits) as
+    /// the site grows, changing it later moves most urls to another shard.
+    #[must_use]
+    pub fn new(count: usize) -> Self {
+        Self {
+            count: count.max(1),
+            seed: 0,
+        }
+    }
+
+    /// Sets the seed of the hash, to assign the urls differently with the same count.
+    #[must_use]
+    pub const fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// The 0-based shard of `loc`, written to the part numbered one more.
+    #[must_use]
+    pub fn shard_of(&self, loc: &Url) -> usize {
+        let hash = fnv1a_from(fnv1a(&self.seed.to_le_bytes()), loc.as_str().as_bytes());
+        // The count fits in a u64 and the remainder is below it.
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            (hash % self.count as u64) as usize
+        }
+    }
+}
+
+impl Sitemap {
+    /// Writes the sitemap split into the shards of `shards`, plus a sitemap index listing
+    /// them, see the [module docs](crate::shard).
+    ///
+    /// The shard numbered `n`, from 1, is the part `n` of `output`. Empty shards aren't
+    /// written nor listed by the index. The entries of a shard are in the
+    /// [sort order](Sitemap::sort), or in the order they are given. Each shard is
+    /// generated in memory. Returns the index followed by the shards.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::LimitExceeded`] if a shard is over the
+    /// [limits](Sitemap::limits), which calls for more shards, or `Err` as
+    /// [`Sitemap::write_split`].
+    pub fn write_sharded(
+        &self,
+        urls: &[UrlEntry],
+        output: &SplitOutput,
+        shards: &ShardOptions,
+    ) -> Result<Vec<WrittenFile>> {
+        self.shard_into(urls, output, shards, None)
+    }
+
+    /// Like [`write_sharded`](Sitemap::write_sharded), with the files stored in `sink`,
+    /// see [`Sitemap::write_split_to`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`Sitemap::write_sharded`].
+    pub fn write_sharded_to(
+        &self,
+        urls: &[UrlEntry],
+        output: &SplitOutput,
+        shards: &ShardOptions,
+        sink: &dyn OutputSink,
+    ) -> Result<Vec<WrittenFile>> {
+        self.shard_into(urls, output, shards, Some(sink))
+    }
+
+    fn shard_into(
+        &self,
+        urls: &[UrlEntry],
+        output: &SplitOutput,
+        shards: &ShardOptions,
+        sink: Option<&dyn OutputSink>,
+    ) -> Result<Vec<WrittenFile>> {
+        self.check_empty(urls)?;
+        if self.skips(urls) {
+            #[cfg(feature = "tracing")]
+            tracing::info!("no entries, skipped writing the sharded sitemap");
+            return Ok(Vec::new());
+        }
+
+        let urls = self.transformed(urls);
+        let mut parts: Vec<Vec<&UrlEntry>> = vec![Vec::new(); shards.count];
+        for entry in urls.iter() {
+            parts[shards.shard_of(&entry.loc)].push(entry);
+        }
+
+        let mut written = Vec::new();
+        let mut index = Vec::new();
+        for (shard, mut part) in parts.into_iter().enumerate() {
+            if part.is_empty() {
+                continue;
+            }
+            let mut file = output.part(shard + 1)?;
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!("sitemap_shard", path = %file.path.display()).entered();
+            if part.len() > self.limits.max_entries {
+                return Err(Error::LimitExceeded {
+                    limit: Limit::Entries,
+                    max: self.limits.max_entries,
+                });
+            }
+            if let Some(order) = self.sort {
+                sort_entries(&mut part, order);
+            }
+
+            let xml = write_urlset(self, Cursor::new(Vec::new()), part.iter().copied())?;
+            let xml = xml.into_inner();
+            if xml.len() > self.limits.max_size {
+                return Err(Error::LimitExceeded {
+                    limit: Limit::Size,
+                    max: self.limits.max_size,
+                });
+            }
+            self.store(&file.path, &xml, sink)?;
+            file.url_count = part.len();
+            self.file_completed(&file.path, part.len(), xml.len());
+            index.push(IndexEntry::new(
+                file.url.clone(),
+                part.iter().filter_map(|u| u.lastmod).max(),
+            ));
+            written.push(file);
+        }
+        written.insert(0, self.write_index(&index, output, sink)?);
+
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sink::MemorySink, Limits};
+
+    fn entries(locs: impl IntoIterator<Item = usize>) -> Vec<UrlEntry> {
+        locs.into_iter()
+            .map(|i| {
+                let loc = format!("https://a.com/{i}").parse().unwrap();
+                UrlEntry::new(loc, None, None, None)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn changes_only_the_shard_of_a_changed_url() {
+        let shards = ShardOptions::new(8);
+        let output = SplitOutput::new("maps", "https://a.com/".parse().unwrap());
+        let before = MemorySink::new();
+        let urls = entries(0..200);
+        let files = Sitemap::new()
+            .write_sharded_to(&urls, &output, &shards, &before)
+            .unwrap();
+        assert_eq!(files.len(), 9);
+        assert_eq!(files[1..].iter().map(|f| f.url_count).sum::<usize>(), 200);
+
+        let after = MemorySink::new();
+        let added = entries(0..201);
+        Sitemap::new()
+            .write_sharded_to(&added, &output, &shards, &after)
+            .unwrap();
+        let shard = format!("maps/sitemap-{}.xml", shards.shard_of(&added[200].loc) + 1);
+        let changed: Vec<String> = after
+            .names()
+            .into_iter()
+            .filter(|name| before.get(name) != after.get(name))
+            .collect();
+        assert_eq!(changed, [shard]);
+
+        let seeded = shards.seed(1);
+        assert!((0..200).any(|i| seeded.shard_of(&urls[i].loc) != shards.shard_of(&urls[i].loc)));
+    }
+
+    #[test]
+    fn rejects_shards_over_the_limits() {
+        let output = SplitOutput::new("maps", "https://a.com/".parse().unwrap());
+        let sitemap = Sitemap::new().limits(Limits::new(10, crate::MAX_SIZE));
+        let sharded = sitemap.write_sharded_to(
+            &entries(0..11),
+            &output,
+            &ShardOptions::new(1),
+            &MemorySink::new(),
+        );
+        assert!(matches!(
+            sharded,
+            Err(Error::LimitExceeded {
+                limit: Limit::Entries,
+                max: 10
+            })
+        ));
+    }
+}
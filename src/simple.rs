@@ -0,0 +1,188 @@
+//! A one-call API for the common case.
+//!
+//! [`write_sitemap`] turns a list of paths into sitemap files with good defaults:
+//!
+//! - the urls are normalized, deduplicated and sorted,
+//! - the entries are validated, invalid entries are an error,
+//! - with more than [`MAX_ENTRIES`](crate::MAX_ENTRIES) entries or
+//!   [`MAX_SIZE`](crate::MAX_SIZE) bytes the sitemap is split and a sitemap index is
+//!   written at the output path,
+//! - with the `gzip` feature, files over [`GZIP_THRESHOLD`] bytes are compressed.
+//!
+//! ```rust,no_run
+//! use chrono::Utc;
+//!
+//! let pages = vec![("/", Utc::now()), ("/about", Utc::now())];
+//! let files = sitewriter::simple::write_sitemap(
+//!     &"https://example.com/".parse()?,
+//!     pages,
+//!     "public/sitemap.xml",
+//! )?;
+//! println!("submit {}", files[0].display());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use url::Url;
+
+use crate::{
+    index::{generate_index_bytes, IndexEntry},
+    sort::{sort_entries, SortOrder},
+    validate::validate,
+    write_atomic, Error, Result, Sitemap, UrlEntry,
+};
+
+/// The size in bytes over which [`write_sitemap`] compresses a file, with the `gzip` feature.
+pub const GZIP_THRESHOLD: usize = 10 * 1024 * 1024;
+
+/// Writes the sitemap of the pages at the given paths, each with an optional lastmod.
+///
+/// Paths are joined to `base_url`. When the sitemap has to be split, the parts are named
+/// after the output file, `sitemap.xml` gives `sitemap-1.xml`, `sitemap-2.xml` and so on,
+/// and are expected to be served next to it: the index links to `base_url` joined with
+/// each part's file name.
+///
+/// Returns the paths of the written files, the first one is the file to submit to search
+/// engines. It has a `.gz` extension appended when it was compressed.
+///
+/// # Errors
+///
+/// Will return [`Error::InvalidUrl`] if a path can't be joined to `base_url`,
+/// [`Error::Invalid`] if an entry fails validation, or an I/O error if a file can't be
+/// written.
+pub fn write_sitemap<I, P, D>(
+    base_url: &Url,
+    pages: I,
+    output_path: impl AsRef<Path>,
+) -> Result<Vec<PathBuf>>
+where
+    I: IntoIterator<Item = (P, D)>,
+    P: AsRef<str>,
+    D: Into<Option<DateTime<Utc>>>,
+{
+    let output_path = output_path.as_ref();
+
+    let urls = pages
+        .into_iter()
+        .map(|(path, lastmod)| {
+            let path = path.as_ref();
+            let loc = base_url.join(path).map_err(|source| Error::InvalidUrl {
+                value: path.to_owned(),
+                source,
+            })?;
+            Ok(UrlEntry::new(loc, lastmod.into(), None, None))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let sitemap = Sitemap::new();
+    let mut urls = sitemap.dedup(urls)?;
+    sort_entries(&mut urls, SortOrder::Loc);
+
+    let report = validate(&urls);
+    if report.has_errors() {
+        return Err(Error::Invalid(report));
+    }
+
+    let parts = sitemap.split(&urls)?;
+
+    match parts.as_slice() {
+        [] => return Ok(vec![write_file(output_path, &sitemap.generate_bytes(&[]))?]),
+        [(_, xml)] => return Ok(vec![write_file(output_path, xml)?]),
+        _ => {}
+    }
+
+    let stem = output_path
+        .file_stem()
+        .map_or_else(|| "sitemap".into(), |s| s.to_string_lossy());
+    let extension = output_path
+        .extension()
+        .map_or_else(|| "xml".into(), |s| s.to_string_lossy());
+
+    let mut written = Vec::with_capacity(parts.len() + 1);
+    let mut index = Vec::with_capacity(parts.len());
+    for (i, (urls, xml)) in parts.iter().enumerate() {
+        let path = write_file(
+            &output_path.with_file_name(format!("{stem}-{}.{extension}", i + 1)),
+            xml,
+        )?;
+        let name = path
+            .file_name()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let loc = base_url.join(&name).map_err(|source| Error::InvalidUrl {
+            value: name.clone(),
+            source,
+        })?;
+        index.push(IndexEntry::new(
+            loc,
+            urls.iter().filter_map(|u| u.lastmod).max(),
+        ));
+        written.push(path);
+    }
+
+    written.insert(0, write_file(output_path, &generate_index_bytes(&index))?);
+    Ok(written)
+}
+
+/// Writes the file atomically, compressed if it is large, returning the path it was written to.
+fn write_file(path: &Path, xml: &[u8]) -> Result<PathBuf> {
+    #[cfg(feature = "gzip")]
+    if xml.len() > GZIP_THRESHOLD {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".gz");
+        let path = PathBuf::from(name);
+        write_atomic(&path, &crate::compress::gzip(xml))?;
+        return Ok(path);
+    }
+
+    write_atomic(path, xml)?;
+    Ok(path.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn writes_a_single_sitemap() {
+        let dir = TempDir::new("simple-single");
+        let base: Url = "https://a.com/".parse().unwrap();
+
+        let files = write_sitemap(
+            &base,
+            [("/b", None), ("/a#top", None), ("/b", None)],
+            dir.join("sitemap.xml"),
+        )
+        .unwrap();
+
+        assert_eq!(files, [dir.join("sitemap.xml")]);
+        let xml = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(xml.find("https://a.com/a<").unwrap() < xml.find("https://a.com/b<").unwrap());
+        assert_eq!(xml.matches("<url>").count(), 2);
+    }
+
+    #[test]
+    fn splits_large_sitemaps() {
+        let dir = TempDir::new("simple-split");
+        let base: Url = "https://a.com/".parse().unwrap();
+        let pages = (0..=crate::MAX_ENTRIES).map(|i| (format!("/p/{i}"), None));
+
+        let files = write_sitemap(&base, pages, dir.join("sitemap.xml")).unwrap();
+
+        assert_eq!(files.len(), 3);
+        let index = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(index.contains("<loc>https://a.com/sitemap-1.xml</loc>"));
+        assert!(index.contains("<loc>https://a.com/sitemap-2.xml</loc>"));
+        assert_eq!(files[2], dir.join("sitemap-2.xml"));
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        let base: Url = "https://a.com/".parse().unwrap();
+        let result = write_sitemap(&base, [("ftp://a.com/x", None)], "unused.xml");
+        assert!(matches!(result, Err(Error::Invalid(_))));
+    }
+}
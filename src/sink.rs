@@ -0,0 +1,282 @@
+//! Destinations of the generated files.
+//!
+//! An [`OutputSink`] stores files by name, so the files of
+//! [`Sitemap::write_split_to`](crate::Sitemap::write_split_to) can go to any storage: a
+//! directory, memory, or a third party sink for an FTP server or a blob store. Sinks
+//! written for an async client implement [`AsyncOutputSink`] instead, and the adapters
+//! [`SyncSink`] and [`BlockingSink`] turn one kind into the other.
+//!
+//! Sinks uploading to an object store such as S3, GCS or Azure should set the headers
+//! given by [`FileMetadata::of`], so the gzipped files are served as such. With the
+//! `object_store` feature, `upload::ObjectStoreSink` does it for the stores of the
+//! `object_store` crate.
+//!
+//! ```rust
+//! use sitewriter::{
+//!     sink::{MemorySink, OutputSink},
+//!     split::SplitOutput,
+//!     Sitemap, UrlEntry,
+//! };
+//!
+//! let urls = vec![UrlEntry::new("https://example.com/".parse()?, None, None, None)];
+//! let sink = MemorySink::new();
+//! let output = SplitOutput::new("maps", "https://example.com/maps/".parse()?);
+//! Sitemap::new().write_split_to(&urls, &output, &sink)?;
+//!
+//! assert_eq!(sink.names(), ["maps/sitemap-1.xml", "maps/sitemap_index.xml"]);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    path::PathBuf,
+    pin::{pin, Pin},
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+use crate::{compress::Compression, write_atomic, Result};
+
+/// A future returned by an [`AsyncOutputSink`].
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Stores generated files.
+pub trait OutputSink: Send + Sync {
+    /// Stores `contents` under `name`, replacing any previous file of that name.
+    ///
+    /// Names are relative paths separated by `/`. Readers should never see a partially
+    /// stored file, if the storage allows it.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file can't be stored.
+    fn put(&self, name: &str, contents: &[u8]) -> Result<()>;
+}
+
+/// Stores generated files asynchronously, see [`OutputSink`].
+pub trait AsyncOutputSink: Send + Sync {
+    /// Stores `contents` under `name`, see [`OutputSink::put`].
+    fn put<'a>(&'a self, name: &'a str, contents: &'a [u8]) -> BoxFuture<'a, Result<()>>;
+}
+
+impl<S: OutputSink + ?Sized> OutputSink for &S {
+    fn put(&self, name: &str, contents: &[u8]) -> Result<()> {
+        (**self).put(name, contents)
+    }
+}
+
+impl<S: OutputSink + ?Sized> OutputSink for Box<S> {
+    fn put(&self, name: &str, contents: &[u8]) -> Result<()> {
+        (**self).put(name, contents)
+    }
+}
+
+impl<S: OutputSink + ?Sized> OutputSink for Arc<S> {
+    fn put(&self, name: &str, contents: &[u8]) -> Result<()> {
+        (**self).put(name, contents)
+    }
+}
+
+impl<S: AsyncOutputSink + ?Sized> AsyncOutputSink for Box<S> {
+    fn put<'a>(&'a self, name: &'a str, contents: &'a [u8]) -> BoxFuture<'a, Result<()>> {
+        (**self).put(name, contents)
+    }
+}
+
+impl<S: AsyncOutputSink + ?Sized> AsyncOutputSink for Arc<S> {
+    fn put<'a>(&'a self, name: &'a str, contents: &'a [u8]) -> BoxFuture<'a, Result<()>> {
+        (**self).put(name, contents)
+    }
+}
+
+/// The headers a stored file should be served with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileMetadata {
+    /// The `Content-Type`.
+    pub content_type: &'static str,
+    /// The `Content-Encoding`, if the file is compressed.
+    pub content_encoding: Option<&'static str>,
+}
+
+impl FileMetadata {
+    /// The metadata of the file named `name`, judging by its extension: `.gz`, `.br` and
+    /// `.zst` files are encoded as their [`Compression`], `.txt` files are plain text and
+    /// the others XML.
+    #[must_use]
+    pub fn of(name: &str) -> Self {
+        let compression = Compression::from_path(name);
+        let content_encoding = compression.content_encoding();
+        let name = compression
+            .extension()
+            .and_then(|ext| name.strip_suffix(ext)?.strip_suffix('.'))
+            .unwrap_or(name);
+        let content_type = if name.ends_with(".txt") {
+            "text/plain; charset=utf-8"
+        } else {
+            "application/xml"
+        };
+        Self {
+            content_type,
+            content_encoding,
+        }
+    }
+}
+
+/// Writes the files under a directory with [`write_atomic`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DirSink {
+    root: PathBuf,
+}
+
+impl DirSink {
+    /// Writes under `root`.
+    #[must_use]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl OutputSink for DirSink {
+    fn put(&self, name: &str, contents: &[u8]) -> Result<()> {
+        write_atomic(self.root.join(name), contents)
+    }
+}
+
+/// Keeps the files in memory, for tests or to serve them directly.
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    files: Mutex<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemorySink {
+    /// Creates an empty sink.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The contents of the file stored under `name`.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<Vec<u8>> {
+        self.lock().get(name).cloned()
+    }
+
+    /// The names of the stored files, sorted.
+    #[must_use]
+    pub fn names(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Vec<u8>>> {
+        self.files.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn put(&self, name: &str, contents: &[u8]) -> Result<()> {
+        self.lock().insert(name.to_owned(), contents.to_vec());
+        Ok(())
+    }
+}
+
+/// Makes an [`OutputSink`] usable as an [`AsyncOutputSink`].
+///
+/// The files are stored when the future is first polled, blocking the thread polling it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SyncSink<S>(pub S);
+
+impl<S: OutputSink> AsyncOutputSink for SyncSink<S> {
+    fn put<'a>(&'a self, name: &'a str, contents: &'a [u8]) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { self.0.put(name, contents) })
+    }
+}
+
+/// Makes an [`AsyncOutputSink`] usable as an [`OutputSink`], by waiting for its futures on
+/// the current thread.
+///
+/// The futures are polled outside of any async runtime, so this only works with sinks whose
+/// futures don't need one, such as the ones of runtime agnostic clients. Sinks built on a
+/// runtime's I/O should be driven by that runtime instead.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlockingSink<S>(pub S);
+
+impl<S: AsyncOutputSink> OutputSink for BlockingSink<S> {
+    fn put(&self, name: &str, contents: &[u8]) -> Result<()> {
+        block_on(self.0.put(name, contents))
+    }
+}
+
+/// Polls `future` to completion, parking the thread while it is pending.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    struct Unpark(Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A sink whose futures are pending once, woken from another thread.
+    struct Deferred(MemorySink);
+
+    impl AsyncOutputSink for Deferred {
+        fn put<'a>(&'a self, name: &'a str, contents: &'a [u8]) -> BoxFuture<'a, Result<()>> {
+            let mut woken = false;
+            Box::pin(std::future::poll_fn(move |cx| {
+                if woken {
+                    return Poll::Ready(self.0.put(name, contents));
+                }
+                woken = true;
+                let waker = cx.waker().clone();
+                thread::spawn(move || waker.wake());
+                Poll::Pending
+            }))
+        }
+    }
+
+    #[test]
+    fn metadata() {
+        let gzip = FileMetadata::of("maps/sitemap-1.xml.gz");
+        assert_eq!(gzip.content_type, "application/xml");
+        assert_eq!(gzip.content_encoding, Some("gzip"));
+        assert_eq!(
+            FileMetadata::of("sitemap_index.xml"),
+            FileMetadata {
+                content_type: "application/xml",
+                content_encoding: None
+            }
+        );
+        assert_eq!(
+            FileMetadata::of("urls.txt").content_type,
+            "text/plain; charset=utf-8"
+        );
+    }
+
+    #[test]
+    fn adapters() {
+        let sink = BlockingSink(Deferred(MemorySink::new()));
+        sink.put("a.xml", b"a").unwrap();
+        assert_eq!(sink.0 .0.get("a.xml").unwrap(), b"a");
+
+        let sink = BlockingSink(SyncSink(MemorySink::new()));
+        sink.put("b.xml", b"b").unwrap();
+        assert_eq!(sink.0 .0.names(), ["b.xml"]);
+    }
+}
@@ -0,0 +1,1076 @@
+use std::{
+    borrow::{Borrow, Cow},
+    io::Cursor,
+    path::Path,
+    sync::Arc,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    dedup::{dedup, ConflictStrategy, DedupOptions},
+    filter::{Filters, UrlFilter},
+    format, json, loc_str,
+    normalize::NormalizeOptions,
+    output::{write_xml, write_xml_with, CountingWriter, FmtWriter, TempStrategy},
+    priority::PriorityStrategy,
+    progress::{ProgressEvent, ProgressHook},
+    sort::{sort_entries, SortOrder},
+    transform::EntryTransform,
+    urlset_writer, write_urlset, ChangeFreq, Error, Limits, Result, UrlEntry, UrlEntryRef,
+};
+
+/// What to do when there are no entries to write.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum EmptyStrategy {
+    /// Write a valid sitemap with an empty `<urlset>`.
+    #[default]
+    Write,
+    /// Fail with [`Error::EmptyInput`].
+    Error,
+    /// Leave the files untouched: [`Sitemap::generate_to_file`] writes nothing and
+    /// [`Sitemap::write_split`] writes no index, returning no files. The methods with a
+    /// writer write an empty `<urlset>`, as with [`EmptyStrategy::Write`].
+    ///
+    /// An empty sitemap replacing a published one can get its pages dropped from search
+    /// results, skipping keeps the last published sitemap instead.
+    Skip,
+}
+
+/// Values of the entries whose field is `None`, see [`Sitemap::defaults`].
+///
+/// ```rust
+/// use sitewriter::{ChangeFreq, EntryDefaults, Sitemap, UrlEntry};
+///
+/// let sitemap = Sitemap::new().defaults(EntryDefaults {
+///     changefreq: Some(ChangeFreq::Weekly),
+///     ..Default::default()
+/// });
+/// let urls = vec![
+///     UrlEntry::new("https://example.com/".parse()?, None, Some(ChangeFreq::Daily), None),
+///     UrlEntry::new("https://example.com/about".parse()?, None, None, None),
+/// ];
+/// let xml = sitemap.generate_str(&urls);
+/// assert_eq!(xml.matches("<changefreq>weekly</changefreq>").count(), 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+pub struct EntryDefaults {
+    /// The lastmod of the entries without one.
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::profile::deserialize_lastmod")
+    )]
+    pub lastmod: Option<DateTime<Utc>>,
+    /// The changefreq of the entries without one.
+    pub changefreq: Option<ChangeFreq>,
+    /// The priority of the entries without one.
+    pub priority: Option<f32>,
+}
+
+impl EntryDefaults {
+    /// Fills the fields of `entry` that are `None` with the defaults.
+    #[must_use]
+    pub const fn apply<'a>(&self, mut entry: UrlEntryRef<'a>) -> UrlEntryRef<'a> {
+        if entry.lastmod.is_none() {
+            entry.lastmod = self.lastmod;
+        }
+        if entry.changefreq.is_none() {
+            entry.changefreq = self.changefreq;
+        }
+        if entry.priority.is_none() {
+            entry.priority = self.priority;
+        }
+        entry
+    }
+}
+
+/// A configurable sitemap generator.
+///
+/// ```rust
+/// use sitewriter::{ConflictStrategy, Sitemap, UrlEntry};
+///
+/// let sitemap = Sitemap::new().on_conflict(ConflictStrategy::KeepFirst);
+/// let urls = sitemap.dedup(vec![
+///     UrlEntry::new("https://example.com/a".parse().unwrap(), None, None, None),
+///     UrlEntry::new("https://EXAMPLE.com/a#top".parse().unwrap(), None, None, None),
+/// ])?;
+/// assert_eq!(urls.len(), 1);
+/// # Ok::<(), sitewriter::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Sitemap {
+    dedup: DedupOptions,
+    pub(crate) sort: Option<SortOrder>,
+    empty: EmptyStrategy,
+    max_memory: Option<usize>,
+    pub(crate) temp: TempStrategy,
+    pub(crate) limits: Limits,
+    pub(crate) ascii_locs: bool,
+    pub(crate) defaults: EntryDefaults,
+    pub(crate) priority: Option<Arc<dyn PriorityStrategy>>,
+    /// The namespaces declared on every urlset, see [`Sitemap::namespace`].
+    pub(crate) namespaces: Vec<(String, String)>,
+    transforms: Vec<Arc<dyn EntryTransform>>,
+    filters: Filters,
+    pub(crate) progress: Option<ProgressHook>,
+    #[cfg(feature = "rayon")]
+    parallel: bool,
+}
+
+impl Sitemap {
+    /// Creates a sitemap generator with the default options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the normalizations applied to each loc by [`Sitemap::dedup`].
+    #[must_use]
+    pub const fn normalize(mut self, options: NormalizeOptions) -> Self {
+        self.dedup.normalize = options;
+        self
+    }
+
+    /// Sets which entry [`Sitemap::dedup`] keeps when a url appears more than once.
+    #[must_use]
+    pub const fn on_conflict(mut self, strategy: ConflictStrategy) -> Self {
+        self.dedup.conflict = strategy;
+        self
+    }
+
+    /// Normalizes the loc of every entry and removes the duplicates.
+    ///
+    /// See [`dedup`](crate::dedup::dedup).
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if there is a duplicate and the conflict strategy is
+    /// [`ConflictStrategy::Error`].
+    pub fn dedup<I>(&self, urls: I) -> Result<Vec<UrlEntry>>
+    where
+        I: IntoIterator<Item = UrlEntry>,
+    {
+        dedup(urls, &self.dedup)
+    }
+
+    /// Sorts the entries before writing them, so the output is stable across runs.
+    ///
+    /// The entries passed to the generate methods are left untouched.
+    #[must_use]
+    pub const fn sort(mut self, order: SortOrder) -> Self {
+        self.sort = Some(order);
+        self
+    }
+
+    /// Sets what [`Sitemap::generate`], [`Sitemap::generate_to_file`] and
+    /// [`Sitemap::write_split`] do when there are no entries.
+    ///
+    /// The methods that can't fail always write an empty sitemap.
+    #[must_use]
+    pub const fn on_empty(mut self, strategy: EmptyStrategy) -> Self {
+        self.empty = strategy;
+        self
+    }
+
+    /// Sets the estimated output size in bytes over which the methods writing files stream
+    /// the XML to disk instead of building it in memory first.
+    ///
+    /// Buffering is faster, streaming keeps the memory flat whatever the number of
+    /// entries. Without a watermark everything is buffered.
+    #[must_use]
+    pub const fn max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory = Some(bytes);
+        self
+    }
+
+    /// Sets the limits each sitemap is kept within when the entries are split, the
+    /// [current limits](Limits::current_50mb) of the protocol by default.
+    #[must_use]
+    pub const fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the values written for the entries whose lastmod, changefreq or priority is
+    /// `None`.
+    ///
+    /// They apply to every generated sitemap, the entries themselves are left untouched.
+    #[must_use]
+    pub const fn defaults(mut self, defaults: EntryDefaults) -> Self {
+        self.defaults = defaults;
+        self
+    }
+
+    /// Declares a namespace on every urlset, for the extensions of the entries that aren't
+    /// known when the document starts, those of [`Sitemap::generate_stream`] and
+    /// [`Sitemap::body`].
+    ///
+    /// The namespaces of the entries given up front are declared without it. Entries with
+    /// [`UrlEntry::mobile`] need the `mobile` prefix bound to
+    /// [`MOBILE_NAMESPACE`](crate::MOBILE_NAMESPACE).
+    #[must_use]
+    pub fn namespace(mut self, prefix: impl Into<String>, namespace: impl Into<String>) -> Self {
+        self.namespaces.push((prefix.into(), namespace.into()));
+        self
+    }
+
+    /// Adds a change applied to a copy of every entry before it is written, after the
+    /// changes added before it, see [`transform`](crate::transform).
+    ///
+    /// The transforms don't apply to the borrowed entries of [`Sitemap::generate_refs`].
+    #[must_use]
+    pub fn transform(mut self, transform: impl EntryTransform + 'static) -> Self {
+        self.transforms.push(Arc::new(transform));
+        self
+    }
+
+    /// Adds a filter, a glob pattern, a regex or a closure, see [`filter`](crate::filter).
+    /// Once there is one, only the entries matching an include filter are written.
+    ///
+    /// The filters see the entries after the transforms. They don't apply to the borrowed
+    /// entries of [`Sitemap::generate_refs`], and the [`EmptyStrategy`] looks at the
+    /// entries before they are filtered.
+    #[must_use]
+    pub fn include(mut self, filter: impl Into<UrlFilter>) -> Self {
+        self.filters.include.push(filter.into());
+        self
+    }
+
+    /// Adds a filter whose matching entries aren't written, whatever the include filters,
+    /// see [`Sitemap::include`].
+    #[must_use]
+    pub fn exclude(mut self, filter: impl Into<UrlFilter>) -> Self {
+        self.filters.exclude.push(filter.into());
+        self
+    }
+
+    /// Computes the priority of the entries without one, see [`priority`](crate::priority).
+    ///
+    /// The strategy applies before the [defaults](Sitemap::defaults).
+    #[must_use]
+    pub fn priority_strategy(mut self, strategy: impl PriorityStrategy + 'static) -> Self {
+        self.priority = Some(Arc::new(strategy));
+        self
+    }
+
+    /// Sets the hook told of the entries and files written, see
+    /// [`progress`](crate::progress).
+    ///
+    /// The clones of the sitemap share the hook.
+    #[must_use]
+    pub fn on_progress(mut self, hook: impl FnMut(ProgressEvent) + Send + 'static) -> Self {
+        self.progress = Some(ProgressHook::new(hook));
+        self
+    }
+
+    /// Tells the progress hook that the file at `path` was written.
+    pub(crate) fn file_completed(&self, path: &Path, entries: usize, bytes: usize) {
+        if let Some(hook) = &self.progress {
+            hook.report(ProgressEvent::FileCompleted {
+                path: path.to_owned(),
+                entries,
+                bytes,
+            });
+        }
+    }
+
+    /// Writes the locs of [`Sitemap::generate_refs`] in ASCII, see
+    /// [`SitemapWriter::ascii_locs`].
+    #[must_use]
+    pub const fn ascii_locs(mut self, ascii: bool) -> Self {
+        self.ascii_locs = ascii;
+        self
+    }
+
+    /// Sets where the methods writing files stage them before they replace the targets.
+    ///
+    /// See [`write_atomic_using`](crate::output::write_atomic_using).
+    #[must_use]
+    pub fn temp_strategy(mut self, strategy: TempStrategy) -> Self {
+        self.temp = strategy;
+        self
+    }
+
+    /// Renders the entries on the threads of the rayon pool, which pays off for sitemaps of
+    /// many thousands of entries.
+    ///
+    /// The output is the same as without it.
+    #[cfg(feature = "rayon")]
+    #[must_use]
+    pub const fn parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Whether the sitemap of `urls` should be built in memory, according to `max_memory`.
+    pub(crate) fn buffers<'a>(&self, urls: impl IntoIterator<Item = &'a UrlEntry>) -> bool {
+        self.max_memory
+            .is_none_or(|max| estimated_size(urls) <= max)
+    }
+
+    /// The entries after the transforms and the filters, borrowed when there are none.
+    pub(crate) fn transformed<'a>(&self, urls: &'a [UrlEntry]) -> Cow<'a, [UrlEntry]> {
+        if self.transforms.is_empty() && self.filters.is_empty() {
+            return Cow::Borrowed(urls);
+        }
+        urls.iter()
+            .filter_map(|entry| self.transform_entry(entry.clone()))
+            .collect()
+    }
+
+    /// Applies the transforms to `entry`, `None` if the filters leave it out.
+    pub(crate) fn transform_entry(&self, mut entry: UrlEntry) -> Option<UrlEntry> {
+        for transform in &self.transforms {
+            transform.transform(&mut entry);
+        }
+        self.filters.keeps(&entry).then_some(entry)
+    }
+
+    /// Fails with [`Error::EmptyInput`] if there are no entries and that is rejected.
+    pub(crate) const fn check_empty(&self, urls: &[UrlEntry]) -> Result<()> {
+        match self.empty {
+            EmptyStrategy::Error if urls.is_empty() => Err(Error::EmptyInput),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the files of `urls` aren't written, per [`EmptyStrategy::Skip`].
+    pub(crate) const fn skips(&self, urls: &[UrlEntry]) -> bool {
+        matches!(self.empty, EmptyStrategy::Skip) && urls.is_empty()
+    }
+
+    /// Generates the sitemap and saves it using the provided writer.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if it fails to write to the writer, or [`Error::EmptyInput`] if
+    /// there are no entries and the [`EmptyStrategy`] is [`EmptyStrategy::Error`].
+    pub fn generate<T>(&self, inner_writer: T, urls: &[UrlEntry]) -> Result<T>
+    where
+        T: std::io::Write,
+    {
+        self.check_empty(urls)?;
+        self.write(inner_writer, urls)
+    }
+
+    /// Generates the sitemap into a borrowed writer, which the caller keeps whether it
+    /// succeeds or fails.
+    ///
+    /// ```rust
+    /// use sitewriter::{EmptyStrategy, Sitemap};
+    ///
+    /// let mut out = Vec::new();
+    /// let strict = Sitemap::new().on_empty(EmptyStrategy::Error);
+    /// assert!(strict.generate_to_writer(&mut out, &[]).is_err());
+    ///
+    /// Sitemap::new().generate_to_writer(&mut out, &[])?;
+    /// assert!(out.starts_with(b"<?xml"));
+    /// # Ok::<(), sitewriter::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`Sitemap::generate`]. Part of the sitemap may have been
+    /// written by then.
+    pub fn generate_to_writer<W>(&self, writer: &mut W, urls: &[UrlEntry]) -> Result<()>
+    where
+        W: std::io::Write + ?Sized,
+    {
+        self.generate(writer, urls)?;
+        Ok(())
+    }
+
+    /// Checks a generated sitemap or sitemap index against the sitemap schemas, see
+    /// [`schema::check`](crate::schema::check).
+    ///
+    /// Returns the violations, none if the XML is valid.
+    #[must_use]
+    pub fn validate_xml(xml: &[u8]) -> Vec<crate::schema::SchemaViolation> {
+        crate::schema::check(xml)
+    }
+
+    /// Generates the sitemap into a [`std::fmt::Write`], such as a `String` or the output
+    /// of a template engine, without building a byte buffer first.
+    ///
+    /// ```rust
+    /// use sitewriter::{Sitemap, UrlEntry};
+    ///
+    /// let urls = vec![UrlEntry::new("https://example.com/".parse()?, None, None, None)];
+    /// let mut page = String::from("<!-- generated -->\n");
+    /// Sitemap::new().generate_fmt(&mut page, &urls)?;
+    /// assert!(page.contains("<loc>https://example.com/</loc>"));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`Sitemap::generate`], a failure of the writer being reported
+    /// as [`Error::Io`].
+    pub fn generate_fmt<W>(&self, writer: &mut W, urls: &[UrlEntry]) -> Result<()>
+    where
+        W: std::fmt::Write + ?Sized,
+    {
+        self.check_empty(urls)?;
+        self.write(FmtWriter::new(writer), urls)?.finish()?;
+        Ok(())
+    }
+
+    /// Generates the sitemap of borrowed entries, whose locs are written without being
+    /// parsed.
+    ///
+    /// ```rust
+    /// use sitewriter::{Sitemap, UrlEntryRef};
+    ///
+    /// let paths = vec![String::from("https://example.com/a"), String::from("https://example.com/b")];
+    /// let urls: Vec<UrlEntryRef> = paths.iter().map(|loc| UrlEntryRef::new(loc)).collect();
+    /// let xml = Sitemap::new().generate_refs(Vec::new(), &urls)?;
+    /// # assert_eq!(xml, sitewriter::generate_bytes(&[
+    /// #     sitewriter::UrlEntry::try_from(urls[0])?,
+    /// #     sitewriter::UrlEntry::try_from(urls[1])?,
+    /// # ]));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`Sitemap::generate`].
+    pub fn generate_refs<T>(&self, inner_writer: T, urls: &[UrlEntryRef<'_>]) -> Result<T>
+    where
+        T: std::io::Write,
+    {
+        if urls.is_empty() {
+            self.check_empty(&[])?;
+        }
+        let mut urls = Cow::Borrowed(urls);
+        if let Some(order) = self.sort {
+            urls.to_mut().sort_by(|a, b| order.compare_refs(a, b));
+        }
+
+        let mut writer = urlset_writer(self, inner_writer, urls.iter().copied())?;
+        for &entry in urls.iter() {
+            writer.write_ref(entry)?;
+        }
+        writer.finish()
+    }
+
+    /// Generates the sitemap of owned or borrowed entries, such as the values of a map,
+    /// without cloning them into a slice first.
+    ///
+    /// ```rust
+    /// use std::collections::BTreeMap;
+    ///
+    /// use sitewriter::{Sitemap, UrlEntry};
+    ///
+    /// let mut pages = BTreeMap::new();
+    /// for id in [1, 2] {
+    ///     let loc = format!("https://example.com/{id}").parse()?;
+    ///     pages.insert(id, UrlEntry::new(loc, None, None, None));
+    /// }
+    /// let xml = Sitemap::new().generate_iter(Vec::new(), pages.values())?;
+    /// # assert!(String::from_utf8(xml)?.contains("https://example.com/2"));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`Sitemap::generate`].
+    pub fn generate_iter<T, I>(&self, inner_writer: T, urls: I) -> Result<T>
+    where
+        T: std::io::Write,
+        I: IntoIterator,
+        I::Item: Borrow<UrlEntry>,
+    {
+        let items: Vec<I::Item> = urls.into_iter().collect();
+        if items.is_empty() {
+            self.check_empty(&[])?;
+        }
+        let transformed: Vec<UrlEntry>;
+        let mut urls: Vec<&UrlEntry> = if self.transforms.is_empty() && self.filters.is_empty() {
+            items.iter().map(Borrow::borrow).collect()
+        } else {
+            transformed = items
+                .iter()
+                .filter_map(|entry| self.transform_entry(entry.borrow().clone()))
+                .collect();
+            transformed.iter().collect()
+        };
+        if let Some(order) = self.sort {
+            sort_entries(&mut urls, order);
+        }
+
+        #[cfg(feature = "rayon")]
+        if self.parallel {
+            return crate::write_urlset_par(self, inner_writer, &urls);
+        }
+        write_urlset(self, inner_writer, urls)
+    }
+
+    /// Generates the sitemap of the entries read from `entries`, writing each one as soon as
+    /// it is read so the memory stays flat however many there are.
+    ///
+    /// The entries are written in the order they are read, the sort order doesn't apply.
+    /// The document starts before the entries are read, so the namespaces of their
+    /// extensions and of the mobile flag are declared up front with [`Sitemap::namespace`].
+    ///
+    /// ```rust
+    /// use sitewriter::{Sitemap, UrlEntry, MOBILE_NAMESPACE};
+    ///
+    /// let rows = ["/a", "/b"].iter().map(|path| {
+    ///     let loc = "https://example.com/".parse::<sitewriter::Url>()?.join(path)?;
+    ///     let mut entry = UrlEntry::new(loc, None, None, None);
+    ///     entry.mobile = true;
+    ///     Ok::<_, url::ParseError>(entry)
+    /// });
+    /// let sitemap = Sitemap::new().namespace("mobile", MOBILE_NAMESPACE);
+    /// let xml = String::from_utf8(sitemap.generate_stream(Vec::new(), rows)?)?;
+    /// # assert!(xml.contains("https://example.com/b"));
+    /// assert!(xml.contains(r#"xmlns:mobile="http://www.google.com/schemas/sitemap-mobile/1.0""#));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::Source`] as soon as the source fails, `Err` if it fails to write
+    /// to the writer, or [`Error::EmptyInput`] as [`Sitemap::generate`].
+    pub fn generate_stream<T, I, E>(&self, inner_writer: T, entries: I) -> Result<T>
+    where
+        T: std::io::Write,
+        I: IntoIterator<Item = std::result::Result<UrlEntry, E>>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let mut writer = urlset_writer(self, inner_writer, std::iter::empty())?;
+        writer.write_all(entries.into_iter().filter_map(|entry| match entry {
+            Ok(entry) => self.transform_entry(entry).map(Ok),
+            Err(e) => Some(Err(e)),
+        }))?;
+        if writer.written() == 0 {
+            self.check_empty(&[])?;
+        }
+        writer.finish()
+    }
+
+    fn write<T>(&self, inner_writer: T, urls: &[UrlEntry]) -> Result<T>
+    where
+        T: std::io::Write,
+    {
+        Ok(self.write_counted(inner_writer, urls)?.0)
+    }
+
+    /// Like [`write`](Sitemap::write), also returning the number of entries written,
+    /// those the filters keep.
+    fn write_counted<T>(&self, inner_writer: T, urls: &[UrlEntry]) -> Result<(T, usize)>
+    where
+        T: std::io::Write,
+    {
+        let urls = &*self.transformed(urls);
+        #[cfg(feature = "rayon")]
+        if self.parallel {
+            let mut sorted: Vec<&UrlEntry> = urls.iter().collect();
+            if let Some(order) = self.sort {
+                sort_entries(&mut sorted, order);
+            }
+            return Ok((
+                crate::write_urlset_par(self, inner_writer, &sorted)?,
+                urls.len(),
+            ));
+        }
+
+        let writer = match self.sort {
+            Some(order) => {
+                let mut sorted: Vec<&UrlEntry> = urls.iter().collect();
+                sort_entries(&mut sorted, order);
+                write_urlset(self, inner_writer, sorted)
+            }
+            None => write_urlset(self, inner_writer, urls),
+        }?;
+        Ok((writer, urls.len()))
+    }
+
+    /// Generates the sitemap and writes it to `path` with [`write_atomic`], so that
+    /// clients serving the file never see it half written.
+    ///
+    /// The output is compressed when `path` has the extension of a
+    /// [`Compression`](crate::compress::Compression), such as `.gz`, which needs the
+    /// feature of the encoding.
+    ///
+    /// Nothing is written without entries when the [`EmptyStrategy`] is
+    /// [`EmptyStrategy::Skip`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file can't be written, [`Error::FeatureDisabled`] for a
+    /// compressed path without the feature of its encoding, or [`Error::EmptyInput`] as
+    /// [`Sitemap::generate`].
+    ///
+    /// [`write_atomic`]: crate::write_atomic
+    pub fn generate_to_file(&self, path: impl AsRef<Path>, urls: &[UrlEntry]) -> Result<()> {
+        self.check_empty(urls)?;
+        if self.skips(urls) {
+            #[cfg(feature = "tracing")]
+            tracing::info!(path = %path.as_ref().display(), "no entries, skipped writing the sitemap");
+            return Ok(());
+        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("sitemap_file", path = %path.as_ref().display()).entered();
+        let path = path.as_ref();
+        let (size, entries) = if self.buffers(urls) {
+            let (xml, entries) = self.write_counted(Vec::new(), urls)?;
+            write_xml(path, &xml, &self.temp)?;
+            (xml.len(), entries)
+        } else {
+            write_xml_with(path, &self.temp, |writer| {
+                let (writer, entries) = self.write_counted(CountingWriter::new(writer), urls)?;
+                Ok((writer.count(), entries))
+            })?
+        };
+        self.file_completed(path, entries, size);
+        Ok(())
+    }
+
+    /// Generates the sitemap in the text format: the locs, one per line.
+    ///
+    /// Only the locs are kept, the format has no room for the other fields.
+    #[must_use]
+    pub fn generate_txt(&self, urls: &[UrlEntry]) -> String {
+        let urls = &*self.transformed(urls);
+        let mut sorted: Vec<&UrlEntry> = urls.iter().collect();
+        if let Some(order) = self.sort {
+            sort_entries(&mut sorted, order);
+        }
+
+        let mut txt = String::with_capacity(sorted.iter().map(|u| u.loc.as_str().len() + 1).sum());
+        for entry in sorted {
+            txt.push_str(&loc_str(&entry.loc));
+            txt.push('\n');
+        }
+        txt
+    }
+
+    /// Generates a JSON array of the entries, one object per line with the `loc`,
+    /// `lastmod`, `changefreq`, `priority` and `extensions` keys, in that order, `null`
+    /// when unset.
+    ///
+    /// The values are written as in the XML output, so the same entries always give the
+    /// same JSON. The extensions are an array of the elements they write, each an object
+    /// with its `name`, `namespace`, `attributes`, `text` and `children`.
+    #[must_use]
+    pub fn generate_json(&self, urls: &[UrlEntry]) -> String {
+        let urls = &*self.transformed(urls);
+        let mut sorted: Vec<&UrlEntry> = urls.iter().collect();
+        if let Some(order) = self.sort {
+            sort_entries(&mut sorted, order);
+        }
+
+        let mut out = String::from("[");
+        for (i, entry) in sorted.into_iter().enumerate() {
+            out.push_str(if i == 0 { "\n  " } else { ",\n  " });
+            out.push_str("{\"loc\": ");
+            json::string(&mut out, &loc_str(&entry.loc));
+            let mut fields = UrlEntryRef::from(entry);
+            if let (None, Some(strategy)) = (fields.priority, &self.priority) {
+                fields.priority = strategy.priority(fields);
+            }
+            let fields = self.defaults.apply(fields);
+            out.push_str(", \"lastmod\": ");
+            match &fields.lastmod {
+                Some(lastmod) => json::string(&mut out, &format::lastmod(lastmod, &mut [0; 20])),
+                None => out.push_str("null"),
+            }
+            out.push_str(", \"changefreq\": ");
+            match fields.changefreq {
+                Some(changefreq) => json::string(&mut out, &changefreq.to_string()),
+                None => out.push_str("null"),
+            }
+            out.push_str(", \"priority\": ");
+            match fields.priority {
+                Some(priority) if priority.is_finite() => {
+                    out.push_str(&format::priority(priority, &mut [0; 3]));
+                }
+                _ => out.push_str("null"),
+            }
+            out.push_str(", \"extensions\": ");
+            json::extensions(&mut out, fields.extensions.iter());
+            out.push('}');
+        }
+        out.push_str(if urls.is_empty() { "]\n" } else { "\n]\n" });
+        out
+    }
+
+    /// Generates the sitemaps of the entries, split so that each is within the
+    /// [limits](Sitemap::limits), along with the entries of each.
+    ///
+    /// With a sort order the entries are sorted before splitting. There are no sitemaps
+    /// when there are no entries.
+    pub(crate) fn split<'a>(
+        &self,
+        urls: &'a [UrlEntry],
+    ) -> Result<Vec<(Vec<&'a UrlEntry>, Vec<u8>)>> {
+        let mut urls: Vec<&UrlEntry> = urls.iter().collect();
+        if let Some(order) = self.sort {
+            sort_entries(&mut urls, order);
+        }
+
+        let mut parts = Vec::new();
+        for chunk in urls.chunks(self.limits.max_entries.max(1)) {
+            split_chunk(self, chunk, &mut parts)?;
+        }
+        Ok(parts)
+    }
+
+    /// Generates the sitemap, even when there are no entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two extensions bind the same prefix to different namespaces.
+    #[must_use]
+    pub fn generate_bytes(&self, urls: &[UrlEntry]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.generate_into(&mut buf, urls);
+        buf
+    }
+
+    /// Generates the sitemap into `buf`, which is cleared first, reusing its allocation
+    /// when the sitemap is generated over and over.
+    ///
+    /// ```rust
+    /// use sitewriter::{Sitemap, UrlEntry};
+    ///
+    /// let urls = vec![UrlEntry::new("https://example.com/".parse()?, None, None, None)];
+    /// let sitemap = Sitemap::new();
+    /// let mut buf = Vec::new();
+    /// for _ in 0..3 {
+    ///     sitemap.generate_into(&mut buf, &urls);
+    ///     # assert_eq!(buf, sitemap.generate_bytes(&urls));
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if two extensions bind the same prefix to different namespaces.
+    pub fn generate_into(&self, buf: &mut Vec<u8>, urls: &[UrlEntry]) {
+        buf.clear();
+        match self.write(buf, urls) {
+            Ok(_) => {}
+            Err(e @ Error::NamespaceConflict { .. }) => panic!("{e}"),
+            Err(e) => panic!(
+                "it should never error, please report this bug to https://github.com/edg-l/sitewriter/issues: {e}"
+            ),
+        }
+    }
+
+    /// Generates the sitemap returning a string, even when there are no entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two extensions bind the same prefix to different namespaces.
+    #[must_use]
+    pub fn generate_str(&self, urls: &[UrlEntry]) -> String {
+        String::from_utf8(self.generate_bytes(urls)).expect("to be valid utf8")
+    }
+}
+
+/// The bytes written for an entry besides its loc and extensions, an upper bound with the
+/// default indentation.
+const ENTRY_OVERHEAD: usize = 224;
+
+/// The bytes written for an extension, a guess.
+const EXTENSION_OVERHEAD: usize = 128;
+
+/// An estimate of the size of the sitemap of `urls`, without generating it.
+fn estimated_size<'a>(urls: impl IntoIterator<Item = &'a UrlEntry>) -> usize {
+    urls.into_iter().fold(256, |size, entry| {
+        size + ENTRY_OVERHEAD
+            + entry.loc.as_str().len()
+            + entry.extensions.len() * EXTENSION_OVERHEAD
+    })
+}
+
+/// Generates `chunk`, halving it until each half fits in the size limit of `sitemap`.
+fn split_chunk<'a>(
+    sitemap: &Sitemap,
+    chunk: &[&'a UrlEntry],
+    parts: &mut Vec<(Vec<&'a UrlEntry>, Vec<u8>)>,
+) -> Result<()> {
+    let xml = write_urlset(sitemap, Cursor::new(Vec::new()), chunk.iter().copied())?.into_inner();
+    if xml.len() > sitemap.limits.max_size && chunk.len() > 1 {
+        let (first, second) = chunk.split_at(chunk.len() / 2);
+        split_chunk(sitemap, first, parts)?;
+        split_chunk(sitemap, second, parts)?;
+    } else {
+        parts.push((chunk.to_vec(), xml));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn generate_txt() {
+        let urls: Vec<UrlEntry> = ["https://a.com/b", "https://a.com/a?x=1&y=<2>"]
+            .iter()
+            .map(|loc| UrlEntry::new(loc.parse().unwrap(), None, None, None))
+            .collect();
+
+        assert_eq!(
+            Sitemap::new().sort(SortOrder::Loc).generate_txt(&urls),
+            "https://a.com/a?x=1&y=%3C2%3E\nhttps://a.com/b\n"
+        );
+        assert_eq!(Sitemap::new().generate_txt(&[]), "");
+    }
+
+    #[test]
+    fn generate_json() {
+        use chrono::{TimeZone, Utc};
+
+        let urls = vec![
+            UrlEntry::new(
+                "https://a.com/\"q\"".parse().unwrap(),
+                Some(Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap()),
+                Some(crate::ChangeFreq::Daily),
+                Some(0.3),
+            ),
+            UrlEntry::new("https://a.com/".parse().unwrap(), None, None, None),
+        ];
+
+        assert_eq!(
+            Sitemap::new().generate_json(&urls),
+            r#"[
+  {"loc": "https://a.com/%22q%22", "lastmod": "2024-05-01T08:00:00Z", "changefreq": "daily", "priority": 0.3, "extensions": []},
+  {"loc": "https://a.com/", "lastmod": null, "changefreq": null, "priority": null, "extensions": []}
+]
+"#
+        );
+        assert_eq!(Sitemap::new().generate_json(&[]), "[]\n");
+    }
+
+    #[test]
+    fn json_round_trip() {
+        use serde_json::Value;
+
+        fn escape(text: &str) -> String {
+            text.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('"', "&quot;")
+        }
+        fn element(value: &Value, namespaces: &mut Vec<(String, String)>) -> String {
+            let name = value["name"].as_str().unwrap();
+            if let (Some((prefix, _)), Some(namespace)) =
+                (name.split_once(':'), value["namespace"].as_str())
+            {
+                namespaces.push((prefix.to_owned(), namespace.to_owned()));
+            }
+            let mut xml = format!("<{name}");
+            for (key, attribute) in value["attributes"].as_object().unwrap() {
+                xml += &format!(" {key}=\"{}\"", escape(attribute.as_str().unwrap()));
+            }
+            xml.push('>');
+            xml += &escape(value["text"].as_str().unwrap_or_default());
+            for child in value["children"].as_array().unwrap() {
+                xml += &element(child, namespaces);
+            }
+            xml + &format!("</{name}>")
+        }
+
+        let xml = r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+                xmlns:image="http://www.google.com/schemas/sitemap-image/1.1"
+                xmlns:vendor="urn:vendor">
+            <url>
+                <loc>https://a.com/</loc>
+                <lastmod>2024-05-01T08:00:00.250+02:00</lastmod>
+                <priority>0.30000001</priority>
+                <image:image><image:loc>https://a.com/a.png?a=1&amp;b=2</image:loc></image:image>
+                <vendor:rank level="2">top &lt;10</vendor:rank>
+            </url>
+        </urlset>"#;
+        let urls = crate::Parser::new()
+            .keep_extensions(true)
+            .parse_str(xml)
+            .unwrap();
+        let json: Value = serde_json::from_str(&Sitemap::new().generate_json(&urls)).unwrap();
+
+        let mut namespaces = Vec::new();
+        let mut rebuilt = String::new();
+        for entry in json.as_array().unwrap() {
+            rebuilt += &format!("<url><loc>{}</loc>", escape(entry["loc"].as_str().unwrap()));
+            rebuilt += &format!("<lastmod>{}</lastmod>", entry["lastmod"].as_str().unwrap());
+            rebuilt += &format!("<priority>{}</priority>", entry["priority"]);
+            for extension in entry["extensions"].as_array().unwrap() {
+                rebuilt += &element(extension, &mut namespaces);
+            }
+            rebuilt += "</url>";
+        }
+        let declarations: String = namespaces
+            .iter()
+            .map(|(prefix, namespace)| format!(" xmlns:{prefix}=\"{namespace}\""))
+            .collect();
+        let rebuilt = format!(
+            r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"{declarations}>{rebuilt}</urlset>"#
+        );
+        let again = crate::Parser::new()
+            .keep_extensions(true)
+            .parse_str(&rebuilt)
+            .unwrap();
+
+        let generated = Sitemap::new().generate_str(&urls);
+        assert!(generated.contains("<lastmod>2024-05-01T06:00:00Z</lastmod>"));
+        assert_eq!(Sitemap::new().generate_str(&again), generated);
+    }
+
+    #[test]
+    fn entry_defaults() {
+        let urls = vec![
+            UrlEntry::new("https://a.com/".parse().unwrap(), None, None, Some(1.0)),
+            UrlEntry::new("https://a.com/b".parse().unwrap(), None, None, None),
+        ];
+        let sitemap = Sitemap::new().defaults(EntryDefaults {
+            changefreq: Some(ChangeFreq::Weekly),
+            priority: Some(0.5),
+            ..Default::default()
+        });
+
+        let xml = sitemap.generate_str(&urls);
+        assert_eq!(xml.matches("<changefreq>weekly</changefreq>").count(), 2);
+        assert!(xml.contains("<priority>1.0</priority>"));
+        assert!(xml.contains("<priority>0.5</priority>"));
+        assert!(!xml.contains("lastmod"));
+        assert!(sitemap
+            .generate_json(&urls)
+            .contains(r#""changefreq": "weekly", "priority": 0.5"#));
+        assert_eq!(sitemap.split(&urls).unwrap()[0].1, xml.into_bytes());
+    }
+
+    #[test]
+    fn empty_input() {
+        let sitemap = Sitemap::new().on_empty(EmptyStrategy::Error);
+        assert!(matches!(
+            sitemap.generate(Vec::new(), &[]),
+            Err(Error::EmptyInput)
+        ));
+        assert!(sitemap.split(&[]).unwrap().is_empty());
+
+        let xml = Sitemap::new().generate_str(&[]);
+        assert_eq!(crate::parse_str(&xml).unwrap(), []);
+        assert_eq!(sitemap.generate_str(&[]), xml);
+    }
+
+    #[test]
+    fn generate_iter_matches_generate() {
+        let urls: Vec<UrlEntry> = ["b", "a"]
+            .iter()
+            .map(|path| {
+                let loc = format!("https://a.com/{path}").parse().unwrap();
+                UrlEntry::new(loc, None, None, None)
+            })
+            .collect();
+        let sitemap = Sitemap::new().sort(SortOrder::Loc);
+        let expected = sitemap.generate(Vec::new(), &urls).unwrap();
+        assert_eq!(sitemap.generate_iter(Vec::new(), &urls).unwrap(), expected);
+        assert_eq!(
+            sitemap.generate_iter(Vec::new(), urls.clone()).unwrap(),
+            expected
+        );
+        assert_eq!(
+            sitemap
+                .generate_iter(Vec::new(), urls.iter().rev())
+                .unwrap(),
+            expected
+        );
+        assert!(matches!(
+            Sitemap::new()
+                .on_empty(EmptyStrategy::Error)
+                .generate_iter(Vec::new(), Vec::<UrlEntry>::new()),
+            Err(Error::EmptyInput)
+        ));
+    }
+
+    #[test]
+    fn skips_empty_files() {
+        let dir = TempDir::new("skip");
+        let path = dir.join("sitemap.xml");
+        std::fs::write(&path, "published").unwrap();
+
+        let sitemap = Sitemap::new().on_empty(EmptyStrategy::Skip);
+        sitemap.generate_to_file(&path, &[]).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "published");
+        let output = crate::split::SplitOutput::new(&dir, "https://a.com/".parse().unwrap());
+        assert!(sitemap.write_split(&[], &output).unwrap().is_empty());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        assert!(sitemap.generate_str(&[]).contains("<urlset"));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_output_is_the_same() {
+        use crate::synthetic::{generate_entries, Profile};
+
+        let urls: Vec<UrlEntry> = generate_entries(5_000, &Profile::default()).collect();
+        let sitemap = Sitemap::new().sort(SortOrder::Loc);
+        assert_eq!(
+            sitemap.clone().parallel(true).generate_bytes(&urls),
+            sitemap.generate_bytes(&urls)
+        );
+    }
+
+    #[test]
+    fn estimated_size_is_an_upper_bound() {
+        let urls = vec![UrlEntry::new(
+            "https://a.com/".parse().unwrap(),
+            Some(chrono::Utc::now()),
+            Some(crate::ChangeFreq::Monthly),
+            Some(0.5),
+        )];
+        let size = Sitemap::new().generate_bytes(&urls).len();
+        assert!(estimated_size(&urls) >= size);
+        assert!(estimated_size(&urls) < 2 * size);
+    }
+
+    #[test]
+    fn generate_to_file() {
+        let dir = TempDir::new("sitemap");
+        let urls = vec![UrlEntry::new(
+            "https://a.com/".parse().unwrap(),
+            None,
+            None,
+            None,
+        )];
+        let sitemap = Sitemap::new();
+
+        sitemap
+            .generate_to_file(dir.join("sitemap.xml"), &urls)
+            .unwrap();
+        assert_eq!(
+            std::fs::read(dir.join("sitemap.xml")).unwrap(),
+            sitemap.generate_bytes(&urls)
+        );
+        sitemap
+            .clone()
+            .max_memory(0)
+            .generate_to_file(dir.join("streamed.xml"), &urls)
+            .unwrap();
+        assert_eq!(
+            std::fs::read(dir.join("streamed.xml")).unwrap(),
+            sitemap.generate_bytes(&urls)
+        );
+
+        let gz = sitemap.generate_to_file(dir.join("sitemap.xml.gz"), &urls);
+        #[cfg(feature = "gzip")]
+        {
+            gz.unwrap();
+            let bytes = std::fs::read(dir.join("sitemap.xml.gz")).unwrap();
+            assert_eq!(bytes[..2], [0x1f, 0x8b]);
+        }
+        #[cfg(not(feature = "gzip"))]
+        assert!(matches!(gz, Err(crate::Error::FeatureDisabled("gzip"))));
+    }
+}
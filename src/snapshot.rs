@@ -0,0 +1,184 @@
+//! Golden-file snapshot testing.
+//!
+//! A snapshot compares generated output with a file checked into the repository. When the
+//! output changes on purpose, run the tests with the [`BLESS_VAR`] environment variable set
+//! to `1` to overwrite the files with the new output.
+//!
+//! ```rust,no_run
+//! use sitewriter::{snapshot::Snapshot, Sitemap, UrlEntry};
+//!
+//! let urls = vec![UrlEntry::new("https://example.com/".parse().unwrap(), None, None, None)];
+//!
+//! Snapshot::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"))
+//!     .assert_sitemap("home.xml", &Sitemap::new(), &urls);
+//! ```
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{Sitemap, UrlEntry};
+
+/// The environment variable that makes snapshots overwrite the golden files.
+pub const BLESS_VAR: &str = "SITEWRITER_BLESS";
+
+/// The result of comparing output against a golden file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The output is the same as the golden file.
+    Matched,
+    /// The golden file was written with the output, because blessing is enabled.
+    Blessed,
+    /// There is no golden file yet.
+    Missing,
+    /// The output differs from the golden file.
+    Mismatch {
+        /// The contents of the golden file.
+        expected: String,
+        /// The output.
+        actual: String,
+    },
+}
+
+/// Compares output against golden files stored in a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    dir: PathBuf,
+    bless: bool,
+}
+
+impl Snapshot {
+    /// Uses the golden files stored in `dir`.
+    ///
+    /// Blessing is enabled when the [`BLESS_VAR`] environment variable is set to `1`.
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            bless: std::env::var(BLESS_VAR).is_ok_and(|v| v == "1"),
+        }
+    }
+
+    /// Overrides whether golden files are overwritten with the output.
+    #[must_use]
+    pub const fn bless(mut self, bless: bool) -> Self {
+        self.bless = bless;
+        self
+    }
+
+    /// The path of the golden file with the given name.
+    #[must_use]
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Compares the canonicalized output with the golden file called `name`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the golden file can't be read or written.
+    pub fn check(&self, name: &str, actual: &str) -> io::Result<Outcome> {
+        let path = self.path(name);
+        let actual = canonicalize(actual);
+
+        if self.bless {
+            write(&path, &actual)?;
+            return Ok(Outcome::Blessed);
+        }
+
+        match fs::read_to_string(&path) {
+            Ok(expected) => {
+                let expected = canonicalize(&expected);
+                if expected == actual {
+                    Ok(Outcome::Matched)
+                } else {
+                    Ok(Outcome::Mismatch { expected, actual })
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Outcome::Missing),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Asserts the output matches the golden file called `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the golden file is missing, differs, or can't be accessed.
+    pub fn assert(&self, name: &str, actual: &str) {
+        let path = self.path(name);
+        match self.check(name, actual) {
+            Ok(Outcome::Matched | Outcome::Blessed) => {}
+            Ok(Outcome::Missing) => panic!(
+                "golden file {} is missing, run with {BLESS_VAR}=1 to create it",
+                path.display()
+            ),
+            Ok(Outcome::Mismatch { expected, actual }) => panic!(
+                "output differs from golden file {}, run with {BLESS_VAR}=1 to update it\n\
+                 --- expected\n{expected}\n--- actual\n{actual}",
+                path.display()
+            ),
+            Err(e) => panic!("failed to access golden file {}: {e}", path.display()),
+        }
+    }
+
+    /// Generates the sitemap with the given configuration and asserts it matches the golden
+    /// file called `name`.
+    ///
+    /// # Panics
+    ///
+    /// Same as [`Snapshot::assert`].
+    pub fn assert_sitemap(&self, name: &str, sitemap: &Sitemap, urls: &[UrlEntry]) {
+        self.assert(name, &sitemap.generate_str(urls));
+    }
+}
+
+/// Normalizes line endings and trailing whitespace, so golden files survive editors and
+/// git's line ending conversion.
+fn canonicalize(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() + 1);
+    for line in text.lines() {
+        result.push_str(line.trim_end());
+        result.push('\n');
+    }
+    result
+}
+
+fn write(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn canonicalizes_line_endings() {
+        assert_eq!(canonicalize("<a>  \r\n<b/>"), "<a>\n<b/>\n");
+    }
+
+    #[test]
+    fn check_outcomes() {
+        let dir = TempDir::new("snapshot");
+        let snapshot = Snapshot::new(&dir).bless(false);
+
+        assert_eq!(snapshot.check("a.xml", "<a/>").unwrap(), Outcome::Missing);
+        assert_eq!(
+            snapshot.clone().bless(true).check("a.xml", "<a/>").unwrap(),
+            Outcome::Blessed
+        );
+        assert_eq!(
+            snapshot.check("a.xml", "<a/>\r\n").unwrap(),
+            Outcome::Matched
+        );
+        assert!(matches!(
+            snapshot.check("a.xml", "<b/>").unwrap(),
+            Outcome::Mismatch { .. }
+        ));
+    }
+}
@@ -0,0 +1,112 @@
+//! Deterministic ordering of entries.
+
+use std::{borrow::Borrow, cmp::Ordering};
+
+use crate::{UrlEntry, UrlEntryRef};
+
+/// The order in which entries are written.
+///
+/// Ties are always broken by loc, so the resulting order doesn't depend on the input order.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SortOrder {
+    /// Lexicographic order of the loc.
+    Loc,
+    /// Highest priority first, entries without priority go last.
+    PriorityDesc,
+    /// Most recently modified first, entries without lastmod go last.
+    LastmodDesc,
+}
+
+impl SortOrder {
+    /// Compares two entries according to this order.
+    #[must_use]
+    pub fn compare(self, a: &UrlEntry, b: &UrlEntry) -> Ordering {
+        self.compare_refs(&a.into(), &b.into())
+    }
+
+    /// Compares two borrowed entries according to this order.
+    #[must_use]
+    pub fn compare_refs(self, a: &UrlEntryRef<'_>, b: &UrlEntryRef<'_>) -> Ordering {
+        let by_loc = || a.loc.cmp(b.loc);
+        match self {
+            Self::Loc => by_loc(),
+            Self::PriorityDesc => match (a.priority, b.priority) {
+                (Some(a), Some(b)) => b.total_cmp(&a),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            }
+            .then_with(by_loc),
+            Self::LastmodDesc => match (a.lastmod, b.lastmod) {
+                (Some(a), Some(b)) => b.cmp(&a),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            }
+            .then_with(by_loc),
+        }
+    }
+}
+
+/// Sorts the entries in place.
+///
+/// Works both on owned entries and on references to them.
+pub fn sort_entries<T: Borrow<UrlEntry>>(entries: &mut [T], order: SortOrder) {
+    entries.sort_by(|a, b| order.compare(a.borrow(), b.borrow()));
+}
+
+/// Sorts the entries by loc, see [`SortOrder::Loc`].
+pub fn sort_by_loc<T: Borrow<UrlEntry>>(entries: &mut [T]) {
+    sort_entries(entries, SortOrder::Loc);
+}
+
+/// Sorts the entries by descending priority, see [`SortOrder::PriorityDesc`].
+pub fn sort_by_priority<T: Borrow<UrlEntry>>(entries: &mut [T]) {
+    sort_entries(entries, SortOrder::PriorityDesc);
+}
+
+/// Sorts the entries by descending lastmod, see [`SortOrder::LastmodDesc`].
+pub fn sort_by_lastmod<T: Borrow<UrlEntry>>(entries: &mut [T]) {
+    sort_entries(entries, SortOrder::LastmodDesc);
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn entry(loc: &str, priority: Option<f32>, day: Option<u32>) -> UrlEntry {
+        UrlEntry::new(
+            loc.parse().unwrap(),
+            day.map(|d| Utc.with_ymd_and_hms(2024, 1, d, 0, 0, 0).unwrap()),
+            None,
+            priority,
+        )
+    }
+
+    fn locs<'a>(entries: &[&'a UrlEntry]) -> Vec<&'a str> {
+        entries.iter().map(|e| e.loc.path()).collect()
+    }
+
+    #[test]
+    fn sort_orders() {
+        let urls = [
+            entry("https://a.com/c", None, Some(2)),
+            entry("https://a.com/a", Some(0.5), None),
+            entry("https://a.com/d", Some(0.5), Some(1)),
+            entry("https://a.com/b", Some(0.9), Some(2)),
+        ];
+        let mut refs: Vec<&UrlEntry> = urls.iter().collect();
+
+        sort_by_loc(&mut refs);
+        assert_eq!(locs(&refs), ["/a", "/b", "/c", "/d"]);
+
+        sort_by_priority(&mut refs);
+        assert_eq!(locs(&refs), ["/b", "/a", "/d", "/c"]);
+
+        sort_by_lastmod(&mut refs);
+        assert_eq!(locs(&refs), ["/b", "/c", "/d", "/a"]);
+    }
+}
@@ -0,0 +1,442 @@
+//! Writing sitemaps split over several files, with a sitemap index.
+//!
+//! ```rust,no_run
+//! use sitewriter::{split::SplitOutput, Sitemap, UrlEntry};
+//!
+//! # let urls: Vec<UrlEntry> = Vec::new();
+//! let output = SplitOutput::new("public", "https://example.com/".parse()?)
+//!     .template("sitemap-{index}.xml.gz");
+//! let files = Sitemap::new().write_split(&urls, &output)?;
+//! for file in &files {
+//!     println!("{} -> {}", file.path.display(), file.url);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use url::Url;
+
+use crate::{
+    index::{generate_index_bytes, IndexEntry},
+    output::{write_xml, write_xml_with, xml_contents, CountingWriter},
+    sink::OutputSink,
+    sort::sort_entries,
+    write_urlset, Error, Result, Sitemap, UrlEntry,
+};
+
+/// The placeholder replaced with the 1-based number of each part in a name template.
+pub const INDEX_PLACEHOLDER: &str = "{index}";
+
+/// Where [`Sitemap::write_split`] writes its files.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SplitOutput {
+    dir: PathBuf,
+    base_url: Url,
+    template: String,
+    index_name: String,
+}
+
+impl SplitOutput {
+    /// Writes to `dir`, whose files are served under `base_url`.
+    ///
+    /// The parts are named `sitemap-{index}.xml` and the index `sitemap_index.xml` by
+    /// default.
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>, base_url: Url) -> Self {
+        Self {
+            dir: dir.into(),
+            base_url,
+            template: "sitemap-{index}.xml".to_owned(),
+            index_name: "sitemap_index.xml".to_owned(),
+        }
+    }
+
+    /// Sets the name template of the parts, which must contain [`INDEX_PLACEHOLDER`].
+    ///
+    /// Parts whose name ends in `.gz` are gzipped, which needs the `gzip` feature, and the
+    /// same goes for the other [`Compression`](crate::compress::Compression)s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the template doesn't contain [`INDEX_PLACEHOLDER`], the parts would all
+    /// have the same name.
+    #[must_use]
+    pub fn template(mut self, template: impl Into<String>) -> Self {
+        let template = template.into();
+        assert!(
+            template.contains(INDEX_PLACEHOLDER),
+            "the template '{template}' has no {INDEX_PLACEHOLDER} placeholder"
+        );
+        self.template = template;
+        self
+    }
+
+    /// Sets the file name of the sitemap index.
+    #[must_use]
+    pub fn index_name(mut self, name: impl Into<String>) -> Self {
+        self.index_name = name.into();
+        self
+    }
+
+    /// The file of the part numbered `number`.
+    pub(crate) fn part(&self, number: usize) -> Result<WrittenFile> {
+        self.file(
+            &self
+                .template
+                .replace(INDEX_PLACEHOLDER, &number.to_string()),
+        )
+    }
+
+    /// The file of the sitemap index.
+    pub(crate) fn index(&self) -> Result<WrittenFile> {
+        self.file(&self.index_name)
+    }
+
+    fn file(&self, name: &str) -> Result<WrittenFile> {
+        let url = self
+            .base_url
+            .join(name)
+            .map_err(|source| Error::InvalidUrl {
+                value: name.to_owned(),
+                source,
+            })?;
+        Ok(WrittenFile {
+            path: self.dir.join(name),
+            url,
+            url_count: 0,
+        })
+    }
+}
+
+/// A file written by [`Sitemap::write_split`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WrittenFile {
+    /// Where it was written, the name it was stored under for
+    /// [`Sitemap::write_split_to`].
+    pub path: PathBuf,
+    /// Where it is served.
+    pub url: Url,
+    /// How many entries it lists, sitemaps for the index.
+    pub url_count: usize,
+}
+
+impl Sitemap {
+    /// Writes the sitemap split into as many parts as the [limits](Sitemap::limits) require, plus a
+    /// sitemap index listing them, even when there is a single part.
+    ///
+    /// Without entries no part is written, only an empty index, unless the
+    /// [`EmptyStrategy`](crate::EmptyStrategy) is to fail or to skip, which writes nothing
+    /// and returns no files.
+    ///
+    /// Every file is written with [`write_atomic`](crate::write_atomic), the parts first
+    /// so the index never links to a missing part. Returns the index followed by the parts.
+    ///
+    /// Only one part is generated at a time. A part estimated over the
+    /// [`max_memory`](Sitemap::max_memory) watermark is streamed to its file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a file name can't be joined to the base url or a file can't be
+    /// written, or [`Error::EmptyInput`] if there are no entries and that is rejected.
+    pub fn write_split(&self, urls: &[UrlEntry], output: &SplitOutput) -> Result<Vec<WrittenFile>> {
+        self.split_into(urls, output, None)
+    }
+
+    /// Like [`write_split`](Sitemap::write_split), with the files stored in `sink` instead
+    /// of written to disk.
+    ///
+    /// The name of each file is its path in `output`, with `/` separators. Every part is
+    /// generated in memory before it is stored, whatever the
+    /// [`max_memory`](Sitemap::max_memory) watermark.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a file name can't be joined to the base url or `sink` fails to
+    /// store a file, or [`Error::EmptyInput`] if there are no entries and that is rejected.
+    pub fn write_split_to(
+        &self,
+        urls: &[UrlEntry],
+        output: &SplitOutput,
+        sink: &dyn OutputSink,
+    ) -> Result<Vec<WrittenFile>> {
+        self.split_into(urls, output, Some(sink))
+    }
+
+    /// Writes the split sitemap to `sink`, or to disk without one.
+    fn split_into(
+        &self,
+        urls: &[UrlEntry],
+        output: &SplitOutput,
+        sink: Option<&dyn OutputSink>,
+    ) -> Result<Vec<WrittenFile>> {
+        self.check_empty(urls)?;
+        if self.skips(urls) {
+            #[cfg(feature = "tracing")]
+            tracing::info!("no entries, skipped writing the split sitemap");
+            return Ok(Vec::new());
+        }
+        let urls = self.transformed(urls);
+        let mut urls: Vec<&UrlEntry> = urls.iter().collect();
+        if let Some(order) = self.sort {
+            sort_entries(&mut urls, order);
+        }
+
+        let mut written = Vec::new();
+        let mut index = Vec::new();
+        for chunk in urls.chunks(self.limits.max_entries.max(1)) {
+            self.write_part(chunk, output, sink, &mut written, &mut index)?;
+        }
+        written.insert(0, self.write_index(&index, output, sink)?);
+
+        Ok(written)
+    }
+
+    /// Writes the sitemap index listing the written parts.
+    pub(crate) fn write_index(
+        &self,
+        index: &[IndexEntry],
+        output: &SplitOutput,
+        sink: Option<&dyn OutputSink>,
+    ) -> Result<WrittenFile> {
+        let mut file = output.index()?;
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("sitemap_index", path = %file.path.display()).entered();
+        let xml = generate_index_bytes(index);
+        self.store(&file.path, &xml, sink)?;
+        file.url_count = index.len();
+        self.file_completed(&file.path, index.len(), xml.len());
+        Ok(file)
+    }
+
+    /// Writes `chunk` as the next part, halving it until each half fits in the size limit.
+    pub(crate) fn write_part(
+        &self,
+        chunk: &[&UrlEntry],
+        output: &SplitOutput,
+        sink: Option<&dyn OutputSink>,
+        written: &mut Vec<WrittenFile>,
+        index: &mut Vec<IndexEntry>,
+    ) -> Result<()> {
+        let mut file = output.part(written.len() + 1)?;
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("sitemap_part", path = %file.path.display()).entered();
+
+        let size = if sink.is_some() || self.buffers(chunk.iter().copied()) {
+            let xml =
+                write_urlset(self, Cursor::new(Vec::new()), chunk.iter().copied())?.into_inner();
+            if xml.len() <= self.limits.max_size || chunk.len() == 1 {
+                self.store(&file.path, &xml, sink)?;
+            }
+            xml.len()
+        } else {
+            // An oversized part is replaced by its first half.
+            write_xml_with(&file.path, &self.temp, |writer| {
+                let mut writer = CountingWriter::new(writer);
+                write_urlset(self, &mut writer, chunk.iter().copied())?;
+                Ok(writer.count())
+            })?
+        };
+
+        if size > self.limits.max_size && chunk.len() > 1 {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                size,
+                entries = chunk.len(),
+                "the part is over the size limit, halving it"
+            );
+            let (first, second) = chunk.split_at(chunk.len() / 2);
+            self.write_part(first, output, sink, written, index)?;
+            return self.write_part(second, output, sink, written, index);
+        }
+
+        file.url_count = chunk.len();
+        self.file_completed(&file.path, chunk.len(), size);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(size, entries = chunk.len(), "wrote the part");
+        index.push(IndexEntry::new(
+            file.url.clone(),
+            chunk.iter().filter_map(|u| u.lastmod).max(),
+        ));
+        written.push(file);
+        Ok(())
+    }
+
+    /// Writes `xml` to `path`, or stores it in `sink` under that path.
+    pub(crate) fn store(
+        &self,
+        path: &Path,
+        xml: &[u8],
+        sink: Option<&dyn OutputSink>,
+    ) -> Result<()> {
+        let Some(sink) = sink else {
+            return write_xml(path, xml, &self.temp);
+        };
+        sink.put(&sink_name(path), &xml_contents(path, xml)?)
+    }
+}
+
+/// The name of the file at `path` in an [`OutputSink`], its parts joined with `/`.
+pub(crate) fn sink_name(path: &Path) -> String {
+    let name: Vec<_> = path.iter().map(|part| part.to_string_lossy()).collect();
+    name.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn writes_parts_and_index() {
+        let dir = TempDir::new("split");
+        let urls: Vec<UrlEntry> = (0..=crate::MAX_ENTRIES)
+            .map(|i| {
+                let loc = format!("https://a.com/{i}").parse().unwrap();
+                UrlEntry::new(loc, None, None, None)
+            })
+            .collect();
+
+        let output = SplitOutput::new(&dir, "https://a.com/maps/".parse().unwrap())
+            .template("part-{index}.xml");
+        let files = Sitemap::new().write_split(&urls, &output).unwrap();
+        let streamed = Sitemap::new()
+            .max_memory(1024 * 1024)
+            .write_split(&urls, &output)
+            .unwrap();
+        assert_eq!(streamed, files);
+
+        let names: Vec<&str> = files.iter().map(|f| f.url.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "https://a.com/maps/sitemap_index.xml",
+                "https://a.com/maps/part-1.xml",
+                "https://a.com/maps/part-2.xml",
+            ]
+        );
+        assert_eq!(files[1].url_count, crate::MAX_ENTRIES);
+        assert_eq!(files[2].url_count, 1);
+        let xml = std::fs::read_to_string(&files[0].path).unwrap();
+        assert!(xml.contains("<loc>https://a.com/maps/part-2.xml</loc>"));
+    }
+
+    #[test]
+    fn empty_writes_no_parts() {
+        let dir = TempDir::new("split-empty");
+        let output = SplitOutput::new(&dir, "https://a.com/".parse().unwrap());
+
+        let files = Sitemap::new().write_split(&[], &output).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].url_count, 0);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let rejected = Sitemap::new()
+            .on_empty(crate::EmptyStrategy::Error)
+            .write_split(&[], &output);
+        assert!(matches!(rejected, Err(Error::EmptyInput)));
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn stores_in_sink() {
+        let urls = [UrlEntry::new(
+            "https://a.com/".parse().unwrap(),
+            None,
+            None,
+            None,
+        )];
+        let sink = crate::sink::MemorySink::new();
+        let output = SplitOutput::new("maps/v1", "https://a.com/maps/".parse().unwrap());
+
+        let files = Sitemap::new()
+            .write_split_to(&urls, &output, &sink)
+            .unwrap();
+        assert_eq!(files[1].path, Path::new("maps/v1/sitemap-1.xml"));
+        assert_eq!(
+            sink.names(),
+            ["maps/v1/sitemap-1.xml", "maps/v1/sitemap_index.xml"]
+        );
+        let xml = sink.get("maps/v1/sitemap-1.xml").unwrap();
+        assert!(String::from_utf8(xml)
+            .unwrap()
+            .contains("<loc>https://a.com/</loc>"));
+
+        let urls = vec![urls[0].clone(); 5];
+        let files = Sitemap::new()
+            .limits(crate::Limits::new(2, crate::MAX_SIZE))
+            .write_split_to(&urls, &output, &sink)
+            .unwrap();
+        let counts: Vec<usize> = files.iter().map(|f| f.url_count).collect();
+        assert_eq!(counts, [3, 2, 2, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "placeholder")]
+    fn template_needs_placeholder() {
+        let _ = SplitOutput::new("out", "https://a.com/".parse().unwrap()).template("s.xml");
+    }
+
+    /// Records the names of the spans and the levels of the events.
+    #[cfg(feature = "tracing")]
+    #[derive(Default)]
+    struct Recorder(std::sync::Mutex<Vec<String>>);
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for Recorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let mut seen = self.0.lock().unwrap();
+            seen.push(span.metadata().name().to_owned());
+            tracing::span::Id::from_u64(seen.len() as u64)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, event: &tracing::Event<'_>) {
+            let level = event.metadata().level().to_string();
+            self.0.lock().unwrap().push(level);
+        }
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn traces_the_written_files() {
+        use std::sync::Arc;
+
+        use crate::{sink::MemorySink, transform::LastmodPolicy};
+
+        let tomorrow = chrono::Utc::now() + chrono::TimeDelta::days(1);
+        let urls = [UrlEntry::new(
+            "https://a.com/".parse().unwrap(),
+            Some(tomorrow),
+            None,
+            None,
+        )];
+        let sitemap = Sitemap::new().transform(LastmodPolicy::new().clamp_future());
+        let output = SplitOutput::new("maps", "https://a.com/".parse().unwrap());
+        let recorder = Arc::new(Recorder::default());
+        tracing::subscriber::with_default(recorder.clone(), || {
+            sitemap
+                .write_split_to(&urls, &output, &MemorySink::new())
+                .unwrap();
+        });
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            ["WARN", "sitemap_part", "DEBUG", "sitemap_index"]
+        );
+    }
+}
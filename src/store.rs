@@ -0,0 +1,562 @@
+//! Incremental regeneration of a split sitemap.
+//!
+//! A [`SitemapStore`] keeps the entries of a site and the part each one was written to,
+//! persisted by a [`StoreBackend`]. Entries are upserted and removed as the site changes,
+//! and [`SitemapStore::flush`] rewrites only the parts whose entries changed, plus the
+//! index, instead of the whole sitemap:
+//!
+//! ```rust,no_run
+//! use sitewriter::{
+//!     split::SplitOutput,
+//!     store::{JsonFile, SitemapStore},
+//!     Sitemap, UrlEntry,
+//! };
+//!
+//! let output = SplitOutput::new("public", "https://example.com/".parse()?);
+//! let mut store = SitemapStore::open(JsonFile::new("sitemap-store.json"), Sitemap::new(), output)?;
+//!
+//! store.upsert(UrlEntry::new("https://example.com/posts/new".parse()?, None, None, None));
+//! store.remove(&"https://example.com/posts/old".parse()?);
+//! for file in store.flush()? {
+//!     println!("rewrote {}", file.path.display());
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! New entries fill the parts with room left, so the entries of the other parts stay
+//! where crawlers already found them. The state is saved once the files are written, so
+//! an interrupted flush is done again by the next one.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::ErrorKind,
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use serde::{ser::Error as _, Deserialize, Serialize, Serializer};
+use url::Url;
+
+use crate::{
+    diff::same_values,
+    extension::{self, RawExtension},
+    index::{generate_index_bytes, IndexEntry},
+    sink::OutputSink,
+    sort::sort_entries,
+    split::{SplitOutput, WrittenFile},
+    write_atomic, write_urlset, Error, Result, Sitemap, UrlEntry,
+};
+
+/// An entry and the number of the part it is written to.
+///
+/// The extensions of the entry are persisted as the XML they write, and read back as
+/// [`RawExtension`]s, so a part rewritten after reopening the store keeps them.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "Persisted")]
+pub struct StoredEntry {
+    /// The 1-based number of the part.
+    pub part: usize,
+    /// The entry.
+    pub entry: UrlEntry,
+}
+
+/// The serialized form of a [`StoredEntry`], with what the serde impl of [`UrlEntry`]
+/// leaves out.
+#[derive(Serialize, Deserialize)]
+struct Persisted {
+    part: usize,
+    entry: UrlEntry,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extensions: Vec<PersistedExtension>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    mobile: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PersistedExtension {
+    prefix: String,
+    namespace: String,
+    xml: String,
+}
+
+impl Serialize for StoredEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let extensions = self
+            .entry
+            .extensions
+            .iter()
+            .map(|ext| {
+                Ok(PersistedExtension {
+                    prefix: ext.prefix().to_owned(),
+                    namespace: ext.namespace().to_owned(),
+                    xml: extension::to_xml(ext)?,
+                })
+            })
+            .collect::<Result<_>>()
+            .map_err(S::Error::custom)?;
+        Persisted {
+            part: self.part,
+            entry: self.entry.clone(),
+            extensions,
+            mobile: self.entry.mobile,
+            source: self.entry.source.as_deref().map(str::to_owned),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl TryFrom<Persisted> for StoredEntry {
+    type Error = Error;
+
+    fn try_from(persisted: Persisted) -> Result<Self> {
+        let mut entry = persisted.entry;
+        for ext in persisted.extensions {
+            entry
+                .extensions
+                .push(RawExtension::from_xml(ext.prefix, ext.namespace, &ext.xml)?);
+        }
+        entry.mobile = persisted.mobile;
+        entry.source = persisted.source.map(Into::into);
+        Ok(Self {
+            part: persisted.part,
+            entry,
+        })
+    }
+}
+
+/// Persists the state of a [`SitemapStore`].
+pub trait StoreBackend {
+    /// Loads the saved entries, none if nothing was saved yet.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the state exists but can't be read.
+    fn load(&self) -> Result<Vec<StoredEntry>>;
+
+    /// Saves the entries, replacing the previous state.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the state can't be written.
+    fn save(&self, entries: &[StoredEntry]) -> Result<()>;
+}
+
+impl<B: StoreBackend + ?Sized> StoreBackend for &B {
+    fn load(&self) -> Result<Vec<StoredEntry>> {
+        (**self).load()
+    }
+
+    fn save(&self, entries: &[StoredEntry]) -> Result<()> {
+        (**self).save(entries)
+    }
+}
+
+/// A [`StoreBackend`] saving the state to a JSON file, written atomically.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JsonFile {
+    path: PathBuf,
+}
+
+impl JsonFile {
+    /// Saves the state to `path`.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl StoreBackend for JsonFile {
+    fn load(&self) -> Result<Vec<StoredEntry>> {
+        let json = match std::fs::read(&self.path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_slice(&json).map_err(|e| Error::Source(Box::new(e)))
+    }
+
+    fn save(&self, entries: &[StoredEntry]) -> Result<()> {
+        let json = serde_json::to_vec(entries).map_err(|e| Error::Source(Box::new(e)))?;
+        write_atomic(&self.path, &json)
+    }
+}
+
+/// A [`StoreBackend`] keeping the state in memory, for tests and short-lived stores.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    entries: Mutex<Vec<StoredEntry>>,
+}
+
+impl MemoryBackend {
+    /// An empty state.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StoreBackend for MemoryBackend {
+    fn load(&self) -> Result<Vec<StoredEntry>> {
+        Ok(self.entries.lock().expect("poisoned").clone())
+    }
+
+    fn save(&self, entries: &[StoredEntry]) -> Result<()> {
+        *self.entries.lock().expect("poisoned") = entries.to_vec();
+        Ok(())
+    }
+}
+
+/// The entries of a split sitemap, regenerated incrementally.
+#[derive(Debug)]
+pub struct SitemapStore<B> {
+    backend: B,
+    sitemap: Sitemap,
+    output: SplitOutput,
+    entries: HashMap<Url, UrlEntry>,
+    part_of: HashMap<Url, usize>,
+    parts: BTreeMap<usize, BTreeSet<Url>>,
+    /// Entries not assigned to a part yet.
+    pending: BTreeSet<Url>,
+    /// Parts to rewrite.
+    dirty: BTreeSet<usize>,
+    changed: BTreeSet<Url>,
+}
+
+impl<B: StoreBackend> SitemapStore<B> {
+    /// Opens the store saved in `backend`, writing its parts with `sitemap` to `output`.
+    ///
+    /// The [limits](Sitemap::limits) of `sitemap` bound each part.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the backend fails to load the state.
+    pub fn open(backend: B, sitemap: Sitemap, output: SplitOutput) -> Result<Self> {
+        let mut store = Self {
+            backend,
+            sitemap,
+            output,
+            entries: HashMap::new(),
+            part_of: HashMap::new(),
+            parts: BTreeMap::new(),
+            pending: BTreeSet::new(),
+            dirty: BTreeSet::new(),
+            changed: BTreeSet::new(),
+        };
+        for StoredEntry { part, entry } in store.backend.load()? {
+            store.assign(entry.loc.clone(), part);
+            store.entries.insert(entry.loc.clone(), entry);
+        }
+        Ok(store)
+    }
+
+    /// The number of entries.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry of `loc`.
+    #[must_use]
+    pub fn get(&self, loc: &Url) -> Option<&UrlEntry> {
+        self.entries.get(loc)
+    }
+
+    /// The locs upserted with other values or removed since the last flush, in order.
+    pub fn changed(&self) -> impl Iterator<Item = &Url> {
+        self.changed.iter()
+    }
+
+    /// Adds the entry, or replaces the entry with the same loc.
+    ///
+    /// Returns whether anything changed: replacing an entry with the same values doesn't
+    /// rewrite its part.
+    pub fn upsert(&mut self, entry: UrlEntry) -> bool {
+        let loc = entry.loc.clone();
+        if self
+            .entries
+            .get(&loc)
+            .is_some_and(|current| same_values(current, &entry))
+        {
+            return false;
+        }
+        match self.part_of.get(&loc) {
+            Some(&part) => {
+                self.dirty.insert(part);
+            }
+            None => {
+                self.pending.insert(loc.clone());
+            }
+        }
+        self.entries.insert(loc.clone(), entry);
+        self.changed.insert(loc);
+        true
+    }
+
+    /// Removes the entry of `loc`, returning whether there was one.
+    pub fn remove(&mut self, loc: &Url) -> bool {
+        if self.entries.remove(loc).is_none() {
+            return false;
+        }
+        if let Some(part) = self.part_of.remove(loc) {
+            self.parts.entry(part).or_default().remove(loc);
+            self.dirty.insert(part);
+        }
+        self.pending.remove(loc);
+        self.changed.insert(loc.clone());
+        true
+    }
+
+    /// Marks every part to be rewritten by the next flush, as after losing the files.
+    pub fn rewrite_all(&mut self) {
+        self.dirty.extend(self.parts.keys().copied());
+        self.changed.extend(self.entries.keys().cloned());
+    }
+
+    /// Rewrites the parts with changed entries and the index, then saves the state.
+    ///
+    /// Returns the index followed by the rewritten parts, nothing if nothing changed. A
+    /// part whose entries were all removed is written empty so crawlers drop its urls, and
+    /// left out of the index.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a file can't be written or the backend fails to save the
+    /// state.
+    pub fn flush(&mut self) -> Result<Vec<WrittenFile>> {
+        self.flush_into(None)
+    }
+
+    /// Like [`flush`](SitemapStore::flush), with the files stored in `sink`, see
+    /// [`Sitemap::write_split_to`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `sink` fails to store a file or the backend fails to save the
+    /// state.
+    pub fn flush_to(&mut self, sink: &dyn OutputSink) -> Result<Vec<WrittenFile>> {
+        self.flush_into(Some(sink))
+    }
+
+    fn flush_into(&mut self, sink: Option<&dyn OutputSink>) -> Result<Vec<WrittenFile>> {
+        if self.dirty.is_empty() && self.pending.is_empty() {
+            self.changed.clear();
+            return Ok(Vec::new());
+        }
+
+        let max_entries = self.sitemap.limits.max_entries.max(1);
+        // Parts over the size limit, which take no new entries in this flush.
+        let mut full = BTreeSet::new();
+        let mut written = Vec::new();
+        loop {
+            for loc in std::mem::take(&mut self.pending) {
+                let part = (1..)
+                    .find(|part| {
+                        !full.contains(part)
+                            && self.parts.get(part).map_or(0, BTreeSet::len) < max_entries
+                    })
+                    .expect("there is always a part with room");
+                self.assign(loc, part);
+                self.dirty.insert(part);
+            }
+            let Some(part) = self.dirty.pop_first() else {
+                break;
+            };
+
+            let urls: Vec<UrlEntry> = self.parts.get(&part).map_or_else(Vec::new, |locs| {
+                locs.iter().map(|loc| self.entries[loc].clone()).collect()
+            });
+            let xml = part_bytes(&self.sitemap, &urls)?;
+            if xml.len() > self.sitemap.limits.max_size && urls.len() > 1 {
+                // Move the second half to other parts and try again.
+                for entry in &urls[urls.len() / 2..] {
+                    self.part_of.remove(&entry.loc);
+                    self.parts.entry(part).or_default().remove(&entry.loc);
+                    self.pending.insert(entry.loc.clone());
+                }
+                full.insert(part);
+                self.dirty.insert(part);
+                continue;
+            }
+
+            let mut file = self.output.part(part)?;
+            self.sitemap.store(&file.path, &xml, sink)?;
+            file.url_count = urls.len();
+            written.push(file);
+        }
+
+        let mut index = Vec::new();
+        for (&part, locs) in &self.parts {
+            if locs.is_empty() {
+                continue;
+            }
+            let lastmod = locs
+                .iter()
+                .filter_map(|loc| self.entries[loc].lastmod)
+                .max();
+            index.push(IndexEntry::new(self.output.part(part)?.url, lastmod));
+        }
+        let mut file = self.output.index()?;
+        self.sitemap
+            .store(&file.path, &generate_index_bytes(&index), sink)?;
+        file.url_count = index.len();
+        written.insert(0, file);
+
+        let state: Vec<StoredEntry> = self
+            .parts
+            .iter()
+            .flat_map(|(&part, locs)| locs.iter().map(move |loc| (part, loc)))
+            .map(|(part, loc)| StoredEntry {
+                part,
+                entry: self.entries[loc].clone(),
+            })
+            .collect();
+        self.backend.save(&state)?;
+        self.changed.clear();
+        Ok(written)
+    }
+
+    fn assign(&mut self, loc: Url, part: usize) {
+        self.part_of.insert(loc.clone(), part);
+        self.parts.entry(part).or_default().insert(loc);
+    }
+}
+
+/// The XML of a part listing `urls`, transformed and sorted.
+fn part_bytes(sitemap: &Sitemap, urls: &[UrlEntry]) -> Result<Vec<u8>> {
+    let urls = sitemap.transformed(urls);
+    let mut urls: Vec<&UrlEntry> = urls.iter().collect();
+    if let Some(order) = sitemap.sort {
+        sort_entries(&mut urls, order);
+    }
+    write_urlset(sitemap, Vec::new(), urls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::{
+        extension::{ExtensionWriter, SitemapExtension},
+        sink::MemorySink,
+        Limits,
+    };
+
+    #[derive(Debug)]
+    struct Tag;
+
+    impl SitemapExtension for Tag {
+        fn prefix(&self) -> &str {
+            "t"
+        }
+
+        fn namespace(&self) -> &str {
+            "urn:tag"
+        }
+
+        fn write(&self, writer: &mut ExtensionWriter<'_>) -> Result<()> {
+            writer.text_element("t:tag", "a & b")
+        }
+    }
+
+    fn entry(path: &str) -> UrlEntry {
+        UrlEntry::new(
+            format!("https://a.com/{path}").parse().unwrap(),
+            None,
+            None,
+            None,
+        )
+    }
+
+    fn open<B: StoreBackend>(backend: B) -> SitemapStore<B> {
+        let sitemap = Sitemap::new().limits(Limits::new(2, crate::MAX_SIZE));
+        let output = SplitOutput::new("maps", "https://a.com/maps/".parse().unwrap());
+        SitemapStore::open(backend, sitemap, output).unwrap()
+    }
+
+    fn names(files: &[WrittenFile]) -> Vec<String> {
+        files
+            .iter()
+            .map(|f| f.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn rewrites_changed_parts() {
+        let backend = MemoryBackend::new();
+        let sink = MemorySink::new();
+        let mut store = open(&backend);
+        for path in ["a", "b", "c", "d", "e"] {
+            assert!(store.upsert(entry(path)));
+        }
+        let files = store.flush_to(&sink).unwrap();
+        assert_eq!(
+            names(&files),
+            [
+                "sitemap_index.xml",
+                "sitemap-1.xml",
+                "sitemap-2.xml",
+                "sitemap-3.xml"
+            ]
+        );
+        assert!(store.flush_to(&sink).unwrap().is_empty());
+
+        let mut store = open(&backend);
+        assert_eq!(store.len(), 5);
+        assert!(!store.upsert(entry("c")));
+        let mut edited = entry("c");
+        edited.priority = Some(0.5);
+        assert!(store.upsert(edited));
+        let changed: Vec<&str> = store.changed().map(Url::as_str).collect();
+        assert_eq!(changed, ["https://a.com/c"]);
+        let files = store.flush_to(&sink).unwrap();
+        assert_eq!(names(&files), ["sitemap_index.xml", "sitemap-2.xml"]);
+
+        // The room left by removed entries is filled before adding parts.
+        assert!(store.remove(&entry("a").loc));
+        assert!(store.remove(&entry("b").loc));
+        let files = store.flush_to(&sink).unwrap();
+        assert_eq!(names(&files), ["sitemap_index.xml", "sitemap-1.xml"]);
+        assert_eq!(files[0].url_count, 2);
+        store.upsert(entry("f"));
+        let files = store.flush_to(&sink).unwrap();
+        assert_eq!(names(&files), ["sitemap_index.xml", "sitemap-1.xml"]);
+        assert_eq!(files[0].url_count, 3);
+    }
+
+    #[test]
+    fn keeps_extensions_after_reopening() {
+        let dir = TempDir::new("store");
+        let path = dir.join("store.json");
+        let sink = MemorySink::new();
+        let mut store = open(JsonFile::new(&path));
+        let mut a = entry("a");
+        a.extensions.push(Tag);
+        a.mobile = true;
+        a.source = Some("posts".into());
+        store.upsert(a.clone());
+        store.upsert(entry("b"));
+        store.flush_to(&sink).unwrap();
+
+        let mut store = open(JsonFile::new(&path));
+        assert!(!store.upsert(a));
+        let mut edited = entry("b");
+        edited.priority = Some(0.5);
+        assert!(store.upsert(edited));
+        let files = store.flush_to(&sink).unwrap();
+        assert_eq!(names(&files), ["sitemap_index.xml", "sitemap-1.xml"]);
+        let xml = String::from_utf8(sink.get("maps/sitemap-1.xml").unwrap()).unwrap();
+        assert!(xml.contains("<t:tag>a &amp; b</t:tag>"));
+        assert!(xml.contains("<priority>0.5</priority>"));
+        let stored = store.backend.load().unwrap();
+        assert!(stored[0].entry.mobile);
+        assert_eq!(stored[0].entry.source.as_deref(), Some("posts"));
+    }
+}
@@ -0,0 +1,195 @@
+//! Generation of realistic synthetic entries, for load testing and benchmarks.
+//!
+//! The entries are generated from a seed, the same seed and profile always give the same
+//! entries:
+//!
+//! ```rust
+//! use sitewriter::synthetic::{generate_entries, Profile};
+//!
+//! let urls: Vec<_> = generate_entries(10_000, &Profile::shop()).collect();
+//! assert_eq!(urls.len(), 10_000);
+//! let xml = sitewriter::generate_bytes(&urls);
+//! # assert!(!xml.is_empty());
+//! ```
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use url::Url;
+
+use crate::{ChangeFreq, UrlEntry};
+
+const WORDS: &[&str] = &[
+    "about", "archive", "blog", "category", "docs", "guide", "help", "news", "post", "product",
+    "release", "review", "shop", "tag", "team", "tutorial", "user", "video",
+];
+
+const QUERY_KEYS: &[&str] = &["page", "sort", "lang", "ref", "color", "size"];
+
+/// The shape of the generated entries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    /// The url every loc is joined to.
+    pub base: Url,
+    /// The maximum number of path segments, at least 1.
+    pub max_depth: u32,
+    /// The fraction of the locs, between 0 and 1, with a query string.
+    pub query_ratio: f64,
+    /// The fraction of the entries, between 0 and 1, with a lastmod.
+    pub lastmod_ratio: f64,
+    /// The lastmods are spread over this period before `anchor`, skewed towards `anchor`.
+    pub lastmod_spread: Duration,
+    /// The most recent possible lastmod.
+    pub anchor: DateTime<Utc>,
+    /// The seed of the generator.
+    pub seed: u64,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            base: Url::parse("https://example.com/").expect("a valid url"),
+            max_depth: 3,
+            query_ratio: 0.1,
+            lastmod_ratio: 0.8,
+            lastmod_spread: Duration::days(365),
+            anchor: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            seed: 0x5173_3717_e2a1,
+        }
+    }
+}
+
+impl Profile {
+    /// A blog: shallow paths, few query strings and lastmods spread over years.
+    #[must_use]
+    pub fn blog() -> Self {
+        Self {
+            max_depth: 2,
+            query_ratio: 0.02,
+            lastmod_ratio: 1.0,
+            lastmod_spread: Duration::days(5 * 365),
+            ..Self::default()
+        }
+    }
+
+    /// A shop: deep category trees, many faceted query strings and recent lastmods.
+    #[must_use]
+    pub fn shop() -> Self {
+        Self {
+            max_depth: 5,
+            query_ratio: 0.4,
+            lastmod_ratio: 0.9,
+            lastmod_spread: Duration::days(90),
+            ..Self::default()
+        }
+    }
+
+    /// Documentation: deep paths, no query strings and no lastmods on a fifth of the pages.
+    #[must_use]
+    pub fn docs() -> Self {
+        Self {
+            max_depth: 6,
+            query_ratio: 0.0,
+            lastmod_ratio: 0.8,
+            lastmod_spread: Duration::days(2 * 365),
+            ..Self::default()
+        }
+    }
+}
+
+/// Generates `n` entries with the shape of `profile`.
+///
+/// Every loc is unique. Deeper pages get a lower priority and change less often.
+pub fn generate_entries(n: usize, profile: &Profile) -> impl Iterator<Item = UrlEntry> {
+    let profile = profile.clone();
+    let mut rng = Rng(profile.seed | 1);
+    (0..n).map(move |i| entry(i, &profile, &mut rng))
+}
+
+fn entry(i: usize, profile: &Profile, rng: &mut Rng) -> UrlEntry {
+    let depth = 1 + rng.below(u64::from(profile.max_depth.max(1)));
+
+    let mut path = String::new();
+    for _ in 1..depth {
+        path.push_str(WORDS[rng.below(WORDS.len() as u64) as usize]);
+        path.push('/');
+    }
+    path.push_str(WORDS[rng.below(WORDS.len() as u64) as usize]);
+    path.push_str(&format!("-{i}"));
+
+    if rng.chance(profile.query_ratio) {
+        let key = QUERY_KEYS[rng.below(QUERY_KEYS.len() as u64) as usize];
+        path.push_str(&format!("?{key}={}", 1 + rng.below(20)));
+    }
+
+    let lastmod = rng.chance(profile.lastmod_ratio).then(|| {
+        let spread = profile.lastmod_spread.num_seconds().max(0) as f64;
+        // Squaring makes recent changes more likely than old ones.
+        let age = spread * rng.unit() * rng.unit();
+        profile.anchor - Duration::seconds(age as i64)
+    });
+
+    let changefreq = match depth {
+        1 => ChangeFreq::Daily,
+        2 => ChangeFreq::Weekly,
+        3 => ChangeFreq::Monthly,
+        _ => ChangeFreq::Yearly,
+    };
+    let priority = (10 - (2 * depth).min(9)) as f32 / 10.0;
+
+    UrlEntry::new(
+        profile.base.join(&path).expect("a valid path"),
+        lastmod,
+        Some(changefreq),
+        Some(priority),
+    )
+}
+
+/// A xorshift64* generator, good enough for test data.
+struct Rng(u64);
+
+impl Rng {
+    const fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    const fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    const fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    const fn chance(&mut self, ratio: f64) -> bool {
+        self.unit() < ratio
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn deterministic_and_unique() {
+        let profile = Profile::shop();
+        let first: Vec<_> = generate_entries(2_000, &profile).collect();
+        let second: Vec<_> = generate_entries(2_000, &profile).collect();
+        assert_eq!(first, second);
+
+        let locs: HashSet<_> = first.iter().map(|u| u.loc.as_str()).collect();
+        assert_eq!(locs.len(), first.len());
+        assert!(first.iter().any(|u| u.loc.query().is_some()));
+        assert!(first
+            .iter()
+            .filter_map(|u| u.lastmod)
+            .all(|lastmod| lastmod <= profile.anchor
+                && lastmod >= profile.anchor - profile.lastmod_spread));
+
+        let other: Vec<_> = generate_entries(10, &Profile { seed: 7, ..profile }).collect();
+        assert_ne!(other, first[..10]);
+    }
+}
@@ -0,0 +1,47 @@
+use url::Url;
+
+use crate::UrlEntry;
+
+/// Converts an application type into a sitemap entry, so collections of it can be given
+/// to [`SitemapWriter::add_all`](crate::SitemapWriter::add_all) directly.
+///
+/// ```rust
+/// use chrono::{DateTime, Utc};
+/// use sitewriter::{SitemapWriter, ToUrlEntry, Url, UrlEntry};
+///
+/// struct BlogPost {
+///     slug: String,
+///     updated: DateTime<Utc>,
+/// }
+///
+/// impl ToUrlEntry for BlogPost {
+///     fn to_url_entry(&self, base: &Url) -> UrlEntry {
+///         let loc = base.join(&format!("blog/{}", self.slug)).expect("valid slug");
+///         UrlEntry::new(loc, Some(self.updated), None, None)
+///     }
+/// }
+///
+/// let posts = vec![BlogPost { slug: "hello".into(), updated: Utc::now() }];
+/// let mut writer = SitemapWriter::new(Vec::new());
+/// writer.add_all(&"https://example.com/".parse()?, &posts)?;
+/// let xml = String::from_utf8(writer.finish()?)?;
+/// assert!(xml.contains("<loc>https://example.com/blog/hello</loc>"));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub trait ToUrlEntry {
+    /// The entry of `self`, with its loc resolved against `base`, the root of the site.
+    fn to_url_entry(&self, base: &Url) -> UrlEntry;
+}
+
+impl<T: ToUrlEntry + ?Sized> ToUrlEntry for &T {
+    fn to_url_entry(&self, base: &Url) -> UrlEntry {
+        (**self).to_url_entry(base)
+    }
+}
+
+impl ToUrlEntry for UrlEntry {
+    /// A copy of the entry, its loc is already absolute.
+    fn to_url_entry(&self, _: &Url) -> UrlEntry {
+        self.clone()
+    }
+}
@@ -0,0 +1,642 @@
+//! Changes applied to every entry before it is written.
+//!
+//! The [`EntryTransform`]s given to [`Sitemap::transform`](crate::Sitemap::transform) run
+//! in order on a copy of each entry, the entries passed to the generate methods are left
+//! untouched:
+//!
+//! ```rust
+//! use sitewriter::{
+//!     transform::{ForceHttps, StripQueryParams},
+//!     Sitemap, UrlEntry,
+//! };
+//!
+//! let sitemap = Sitemap::new()
+//!     .transform(StripQueryParams::tracking())
+//!     .transform(ForceHttps);
+//! let urls = vec![UrlEntry::new(
+//!     "http://example.com/?utm_source=feed&page=2".parse()?,
+//!     None,
+//!     None,
+//!     None,
+//! )];
+//! assert_eq!(sitemap.generate_txt(&urls), "https://example.com/?page=2\n");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use url::Url;
+
+use crate::{
+    normalize::{normalize_url, NormalizeOptions, TrailingSlash},
+    UrlEntry,
+};
+
+/// A change applied to every entry before it is written.
+pub trait EntryTransform: Debug + Send + Sync {
+    /// Changes `entry` in place.
+    fn transform(&self, entry: &mut UrlEntry);
+}
+
+impl<T: EntryTransform + ?Sized> EntryTransform for Arc<T> {
+    fn transform(&self, entry: &mut UrlEntry) {
+        (**self).transform(entry);
+    }
+}
+
+/// Upgrades `http` locs to `https`, dropping the port 80.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ForceHttps;
+
+impl EntryTransform for ForceHttps {
+    fn transform(&self, entry: &mut UrlEntry) {
+        if entry.loc.scheme() == "http" {
+            // Both schemes are special, the change can't fail.
+            let _ = entry.loc.set_scheme("https");
+            if entry.loc.port() == Some(80) {
+                let _ = entry.loc.set_port(None);
+            }
+        }
+    }
+}
+
+/// Applies a trailing slash policy to the path of the locs.
+///
+/// See [`PathPolicy`] to also collapse slashes and remove index files.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TrailingSlashPolicy(pub TrailingSlash);
+
+impl EntryTransform for TrailingSlashPolicy {
+    fn transform(&self, entry: &mut UrlEntry) {
+        let options = NormalizeOptions {
+            lowercase_host: false,
+            strip_fragment: false,
+            trailing_slash: self.0,
+            strip_userinfo: false,
+            collapse_slashes: false,
+            strip_index_files: false,
+        };
+        entry.loc = normalize_url(&entry.loc, &options);
+    }
+}
+
+/// Rules for the path of the locs, so the pages of every content source share one
+/// canonical form.
+///
+/// Every rule is off by default. They apply in order: runs of slashes are collapsed,
+/// then the [index files](crate::normalize::INDEX_FILES) are removed, then the trailing
+/// slash policy applies:
+///
+/// ```rust
+/// use sitewriter::{transform::PathPolicy, Sitemap, TrailingSlash, UrlEntry};
+///
+/// let policy = PathPolicy::new()
+///     .collapse_slashes()
+///     .strip_index_files()
+///     .trailing_slash(TrailingSlash::Add);
+/// let urls = ["//docs/index.html", "/docs/intro"].map(|path| {
+///     let loc = format!("https://example.com{path}").parse().unwrap();
+///     UrlEntry::new(loc, None, None, None)
+/// });
+/// assert_eq!(
+///     Sitemap::new().transform(policy).generate_txt(&urls),
+///     "https://example.com/docs/\nhttps://example.com/docs/intro/\n"
+/// );
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PathPolicy {
+    trailing_slash: TrailingSlash,
+    collapse_slashes: bool,
+    strip_index_files: bool,
+}
+
+impl PathPolicy {
+    /// A policy leaving the paths as they are.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or removes the trailing slash of the paths.
+    #[must_use]
+    pub const fn trailing_slash(mut self, policy: TrailingSlash) -> Self {
+        self.trailing_slash = policy;
+        self
+    }
+
+    /// Collapses the runs of slashes, `/a//b` becoming `/a/b`.
+    #[must_use]
+    pub const fn collapse_slashes(mut self) -> Self {
+        self.collapse_slashes = true;
+        self
+    }
+
+    /// Removes the index files ending the paths, `/page/index.html` becoming `/page/`.
+    #[must_use]
+    pub const fn strip_index_files(mut self) -> Self {
+        self.strip_index_files = true;
+        self
+    }
+}
+
+impl EntryTransform for PathPolicy {
+    fn transform(&self, entry: &mut UrlEntry) {
+        let options = NormalizeOptions {
+            lowercase_host: false,
+            strip_fragment: false,
+            trailing_slash: self.trailing_slash,
+            strip_userinfo: false,
+            collapse_slashes: self.collapse_slashes,
+            strip_index_files: self.strip_index_files,
+        };
+        entry.loc = normalize_url(&entry.loc, &options);
+    }
+}
+
+/// Removes query parameters from the locs, and the query itself once empty.
+///
+/// See [`QueryPolicy`] to keep a list of parameters or sort them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StripQueryParams {
+    /// The names of the removed parameters, every parameter when `None`.
+    names: Option<Vec<String>>,
+}
+
+impl StripQueryParams {
+    /// Removes every parameter.
+    #[must_use]
+    pub const fn all() -> Self {
+        Self { names: None }
+    }
+
+    /// Removes the parameters with these names, a trailing `*` matches any suffix.
+    #[must_use]
+    pub fn named<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            names: Some(names.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    /// Removes the common campaign tracking parameters, the [`TRACKING_PARAMS`].
+    #[must_use]
+    pub fn tracking() -> Self {
+        Self::named(TRACKING_PARAMS.iter().copied())
+    }
+}
+
+impl EntryTransform for StripQueryParams {
+    fn transform(&self, entry: &mut UrlEntry) {
+        let Some(names) = &self.names else {
+            entry.loc.set_query(None);
+            return;
+        };
+        if entry.loc.query().is_none() {
+            return;
+        }
+
+        let kept: Vec<(String, String)> = entry
+            .loc
+            .query_pairs()
+            .filter(|(name, _)| !names.iter().any(|n| matches_param(n, name)))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        if kept.is_empty() {
+            entry.loc.set_query(None);
+        } else {
+            entry.loc.query_pairs_mut().clear().extend_pairs(kept);
+        }
+    }
+}
+
+/// The tracking parameters dropped by a [`QueryPolicy`] and
+/// [`StripQueryParams::tracking`], a trailing `*` matches any suffix.
+pub const TRACKING_PARAMS: &[&str] = &[
+    "utm_*", "gclid", "gbraid", "wbraid", "dclid", "fbclid", "msclkid", "mc_cid", "mc_eid",
+    "_hsenc", "_hsmi", "yclid", "igshid",
+];
+
+/// The query string rules of the locs: which parameters are kept and in which order.
+///
+/// URLs differing only by tracking parameters are the same page to search engines, and
+/// listing each of them wastes crawls. The policy drops the [`TRACKING_PARAMS`] by
+/// default, and the query itself once it is empty:
+///
+/// ```rust
+/// use sitewriter::{transform::QueryPolicy, Sitemap, UrlEntry};
+///
+/// let sitemap = Sitemap::new().transform(QueryPolicy::new().sort_params());
+/// let urls = vec![UrlEntry::new(
+///     "https://example.com/shoes?utm_source=feed&size=42&color=red".parse()?,
+///     None,
+///     None,
+///     None,
+/// )];
+/// assert_eq!(
+///     sitemap.generate_txt(&urls),
+///     "https://example.com/shoes?color=red&size=42\n"
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// Parameter names in the lists may end with `*` to match any suffix, as in `utm_*`.
+/// Queries are left as they are unless a parameter is dropped or moved.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryPolicy {
+    /// The kept parameters, every one when `None`.
+    keep: Option<Vec<String>>,
+    drop: Vec<String>,
+    drop_tracking: bool,
+    sort: bool,
+}
+
+impl Default for QueryPolicy {
+    fn default() -> Self {
+        Self {
+            keep: None,
+            drop: Vec::new(),
+            drop_tracking: true,
+            sort: false,
+        }
+    }
+}
+
+impl QueryPolicy {
+    /// Drops the [`TRACKING_PARAMS`] and keeps the other parameters in their order.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes every query string.
+    #[must_use]
+    pub fn strip_all(mut self) -> Self {
+        self.keep = Some(Vec::new());
+        self
+    }
+
+    /// Keeps only the parameters with these names, such as the page of a listing.
+    #[must_use]
+    pub fn keep_only<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.keep = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Also drops the parameters with these names, such as a session id.
+    #[must_use]
+    pub fn drop<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.drop.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Keeps the [`TRACKING_PARAMS`], which are dropped by default.
+    #[must_use]
+    pub const fn keep_tracking(mut self) -> Self {
+        self.drop_tracking = false;
+        self
+    }
+
+    /// Sorts the parameters by name, so the same page always has the same loc. The values
+    /// of a repeated parameter keep their order.
+    #[must_use]
+    pub const fn sort_params(mut self) -> Self {
+        self.sort = true;
+        self
+    }
+
+    /// Whether the parameter named `name` is kept.
+    fn keeps(&self, name: &str) -> bool {
+        let matches = |pattern: &str| matches_param(pattern, name);
+        self.keep
+            .as_ref()
+            .is_none_or(|keep| keep.iter().any(|p| matches(p)))
+            && !self.drop.iter().any(|p| matches(p))
+            && !(self.drop_tracking && TRACKING_PARAMS.iter().any(|p| matches(p)))
+    }
+}
+
+/// Whether the parameter `name` matches `pattern`, where a trailing `*` matches any suffix.
+fn matches_param(pattern: &str, name: &str) -> bool {
+    pattern
+        .strip_suffix('*')
+        .map_or(pattern == name, |prefix| name.starts_with(prefix))
+}
+
+impl EntryTransform for QueryPolicy {
+    fn transform(&self, entry: &mut UrlEntry) {
+        if entry.loc.query().is_none() {
+            return;
+        }
+        if self.keep.as_ref().is_some_and(Vec::is_empty) {
+            entry.loc.set_query(None);
+            return;
+        }
+
+        let pairs: Vec<(String, String)> = entry
+            .loc
+            .query_pairs()
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        let mut kept: Vec<(String, String)> = pairs
+            .iter()
+            .filter(|(name, _)| self.keeps(name))
+            .cloned()
+            .collect();
+        if self.sort {
+            kept.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+
+        if kept.is_empty() {
+            entry.loc.set_query(None);
+        } else if kept != pairs {
+            entry.loc.query_pairs_mut().clear().extend_pairs(kept);
+        }
+    }
+}
+
+/// Moves the locs of a host to another origin, such as from a staging host to production.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RewriteHost {
+    from: String,
+    to: Url,
+}
+
+impl RewriteHost {
+    /// Moves the locs on the host `from` to the scheme, host and port of `to`.
+    #[must_use]
+    pub fn new(from: impl Into<String>, to: Url) -> Self {
+        Self {
+            from: from.into().to_ascii_lowercase(),
+            to,
+        }
+    }
+}
+
+impl EntryTransform for RewriteHost {
+    fn transform(&self, entry: &mut UrlEntry) {
+        if entry.loc.host_str() != Some(self.from.as_str()) {
+            return;
+        }
+        let mut loc = self.to.clone();
+        loc.set_path(entry.loc.path());
+        loc.set_query(entry.loc.query());
+        loc.set_fragment(entry.loc.fragment());
+        entry.loc = loc;
+    }
+}
+
+/// Rules for the lastmod of the entries.
+///
+/// Every rule is off by default. They apply in order: future lastmods are clamped, then
+/// floored to their date, then omitted if too old. The rules comparing with the current
+/// time read the clock on every entry, pin the time with [`at`](LastmodPolicy::at) for
+/// [reproducible output](crate#reproducible-output).
+///
+/// The policy counts the lastmods it changed. Keep it in an [`Arc`] to read the counts
+/// after generating:
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use chrono::{TimeDelta, Utc};
+/// use sitewriter::{transform::LastmodPolicy, Sitemap, UrlEntry};
+///
+/// let policy = Arc::new(LastmodPolicy::new().clamp_future().floor_to_date());
+/// let sitemap = Sitemap::new().transform(policy.clone());
+/// let tomorrow = Utc::now() + TimeDelta::days(1);
+/// let urls = vec![UrlEntry::new("https://example.com/".parse()?, Some(tomorrow), None, None)];
+/// sitemap.generate_str(&urls);
+/// assert_eq!(policy.clamped(), 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct LastmodPolicy {
+    max_age: Option<TimeDelta>,
+    floor_to_date: bool,
+    clamp_future: bool,
+    now: Option<DateTime<Utc>>,
+    clamped: AtomicUsize,
+    omitted: AtomicUsize,
+}
+
+impl LastmodPolicy {
+    /// A policy leaving the lastmods untouched.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Omits the lastmods older than `age`, such as `TimeDelta::days(2 * 365)`.
+    #[must_use]
+    pub const fn omit_older_than(mut self, age: TimeDelta) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Floors the lastmods to midnight UTC, for sources only precise to the day.
+    #[must_use]
+    pub const fn floor_to_date(mut self) -> Self {
+        self.floor_to_date = true;
+        self
+    }
+
+    /// Clamps the lastmods in the future to the current time, counting them in
+    /// [`clamped`](LastmodPolicy::clamped). Search engines may ignore the lastmods of a
+    /// sitemap with future dates, which usually come from a clock or time zone mistake.
+    #[must_use]
+    pub const fn clamp_future(mut self) -> Self {
+        self.clamp_future = true;
+        self
+    }
+
+    /// Uses `now` as the current time instead of reading the clock.
+    #[must_use]
+    pub const fn at(mut self, now: DateTime<Utc>) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    /// The number of lastmods clamped because they were in the future.
+    #[must_use]
+    pub fn clamped(&self) -> usize {
+        self.clamped.load(Ordering::Relaxed)
+    }
+
+    /// The number of lastmods omitted because they were too old.
+    #[must_use]
+    pub fn omitted(&self) -> usize {
+        self.omitted.load(Ordering::Relaxed)
+    }
+}
+
+impl EntryTransform for LastmodPolicy {
+    fn transform(&self, entry: &mut UrlEntry) {
+        let Some(mut lastmod) = entry.lastmod else {
+            return;
+        };
+        let now = self.now.unwrap_or_else(Utc::now);
+        if self.clamp_future && lastmod > now {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(loc = %entry.loc, %lastmod, "clamped a lastmod in the future");
+            lastmod = now;
+            self.clamped.fetch_add(1, Ordering::Relaxed);
+        }
+        if self.floor_to_date {
+            lastmod = lastmod
+                .date_naive()
+                .and_time(chrono::NaiveTime::MIN)
+                .and_utc();
+        }
+        if self.max_age.is_some_and(|age| now - lastmod > age) {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(loc = %entry.loc, %lastmod, "omitted an old lastmod");
+            self.omitted.fetch_add(1, Ordering::Relaxed);
+            entry.lastmod = None;
+        } else {
+            entry.lastmod = Some(lastmod);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(transform: &dyn EntryTransform, loc: &str) -> String {
+        let mut entry = UrlEntry::new(loc.parse().unwrap(), None, None, None);
+        transform.transform(&mut entry);
+        entry.loc.into()
+    }
+
+    #[test]
+    fn builtin_transforms() {
+        assert_eq!(apply(&ForceHttps, "http://a.com:80/x"), "https://a.com/x");
+        assert_eq!(
+            apply(&ForceHttps, "http://a.com:8080/"),
+            "https://a.com:8080/"
+        );
+        assert_eq!(
+            apply(
+                &TrailingSlashPolicy(TrailingSlash::Add),
+                "https://a.com/x?q"
+            ),
+            "https://a.com/x/?q"
+        );
+        assert_eq!(
+            apply(
+                &StripQueryParams::tracking(),
+                "https://a.com/?gclid=1&utm_id=2&a=b+c"
+            ),
+            "https://a.com/?a=b+c"
+        );
+        assert_eq!(
+            apply(&StripQueryParams::named(["a"]), "https://a.com/?a=1"),
+            "https://a.com/"
+        );
+        assert_eq!(
+            apply(&StripQueryParams::all(), "https://a.com/?a=1"),
+            "https://a.com/"
+        );
+        assert_eq!(
+            apply(&QueryPolicy::new(), "https://a.com/?utm_term=x&fbclid=1"),
+            "https://a.com/"
+        );
+
+        let rewrite = RewriteHost::new("staging.a.com", "https://a.com".parse().unwrap());
+        assert_eq!(
+            apply(&rewrite, "http://staging.a.com:8080/x?y#z"),
+            "https://a.com/x?y#z"
+        );
+        assert_eq!(apply(&rewrite, "http://b.com/"), "http://b.com/");
+    }
+
+    #[test]
+    fn query_policy() {
+        let sorted = QueryPolicy::new().sort_params().drop(["session*"]);
+        assert_eq!(
+            apply(
+                &sorted,
+                "https://a.com/?b=2&sessionid=x&a=1&b=1&utm_medium=mail"
+            ),
+            "https://a.com/?a=1&b=2&b=1"
+        );
+        // Untouched queries keep their encoding.
+        assert_eq!(
+            apply(&sorted, "https://a.com/?a=b%20c"),
+            "https://a.com/?a=b%20c"
+        );
+
+        let listing = QueryPolicy::new().keep_only(["page"]);
+        assert_eq!(
+            apply(&listing, "https://a.com/shoes?page=2&sort=price"),
+            "https://a.com/shoes?page=2"
+        );
+        let tracked = QueryPolicy::new().keep_tracking();
+        assert_eq!(
+            apply(&tracked, "https://a.com/?utm_source=x"),
+            "https://a.com/?utm_source=x"
+        );
+        assert_eq!(
+            apply(&QueryPolicy::new().strip_all(), "https://a.com/?a=1"),
+            "https://a.com/"
+        );
+    }
+
+    #[test]
+    fn lastmod_policy() {
+        let now = "2024-05-10T12:00:00Z".parse().unwrap();
+        let policy = LastmodPolicy::new()
+            .clamp_future()
+            .floor_to_date()
+            .omit_older_than(TimeDelta::days(365))
+            .at(now);
+        let lastmod = |date: &str| {
+            let mut entry = UrlEntry::new(
+                "https://a.com/".parse().unwrap(),
+                Some(date.parse().unwrap()),
+                None,
+                None,
+            );
+            policy.transform(&mut entry);
+            entry.lastmod.map(|lastmod| lastmod.to_rfc3339())
+        };
+        assert_eq!(
+            lastmod("2024-05-01T08:30:00Z").as_deref(),
+            Some("2024-05-01T00:00:00+00:00")
+        );
+        assert_eq!(
+            lastmod("2025-01-01T00:00:00Z").as_deref(),
+            Some("2024-05-10T00:00:00+00:00")
+        );
+        assert_eq!(lastmod("2020-01-01T00:00:00Z"), None);
+        assert_eq!((policy.clamped(), policy.omitted()), (1, 1));
+
+        let untouched = LastmodPolicy::new();
+        let mut entry = UrlEntry::new(
+            "https://a.com/".parse().unwrap(),
+            Some("2099-01-01T00:00:00Z".parse().unwrap()),
+            None,
+            None,
+        );
+        untouched.transform(&mut entry);
+        assert!(entry.lastmod.is_some());
+    }
+}
@@ -0,0 +1,176 @@
+//! Uploading the generated files to object stores such as S3, GCS or Azure.
+//!
+//! An [`ObjectStoreSink`] stores the files through an [`ObjectStore`] of the
+//! [`object_store`](https://docs.rs/object_store) crate, with the `Content-Type` and
+//! `Content-Encoding` given by [`FileMetadata::of`], so gzipped sitemaps are served as
+//! such. [`Sitemap::upload_split`] writes a split sitemap to it without touching the disk:
+//!
+//! ```rust
+//! use std::sync::Arc;
+//!
+//! use object_store::ObjectStore;
+//! use sitewriter::{split::SplitOutput, upload::ObjectStoreSink, Sitemap, UrlEntry};
+//!
+//! async fn publish(store: Arc<dyn ObjectStore>, urls: &[UrlEntry]) -> sitewriter::Result<()> {
+//!     let sink = ObjectStoreSink::new(store).prefix("sitemaps");
+//!     let base = "https://example.com/sitemaps/".parse().unwrap();
+//!     let output = SplitOutput::new("", base).template("sitemap-{index}.xml.gz");
+//!     Sitemap::new().upload_split(urls, &output, &sink).await?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::sync::Arc;
+
+use object_store::{path::Path as ObjectPath, Attribute, Attributes, ObjectStore, PutPayload};
+
+use crate::{
+    sink::{AsyncOutputSink, BoxFuture, FileMetadata, MemorySink},
+    split::{sink_name, SplitOutput, WrittenFile},
+    Error, Result, Sitemap, UrlEntry,
+};
+
+/// Stores the files in an [`ObjectStore`], see the [module docs](self).
+///
+/// It is an [`AsyncOutputSink`], its futures are those of the store and need the runtime
+/// the store needs, tokio for the cloud stores.
+#[derive(Debug, Clone)]
+pub struct ObjectStoreSink {
+    store: Arc<dyn ObjectStore>,
+    prefix: ObjectPath,
+}
+
+impl ObjectStoreSink {
+    /// Stores the files at the root of `store`.
+    #[must_use]
+    pub fn new(store: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            store,
+            prefix: ObjectPath::default(),
+        }
+    }
+
+    /// Stores the files under `prefix`, a path separated by `/`.
+    #[must_use]
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = ObjectPath::from(prefix);
+        self
+    }
+
+    /// The location of the file named `name`.
+    fn location(&self, name: &str) -> ObjectPath {
+        self.prefix
+            .parts()
+            .chain(ObjectPath::from(name).parts())
+            .collect()
+    }
+}
+
+impl AsyncOutputSink for ObjectStoreSink {
+    fn put<'a>(&'a self, name: &'a str, contents: &'a [u8]) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let metadata = FileMetadata::of(name);
+            let mut attributes = Attributes::new();
+            attributes.insert(Attribute::ContentType, metadata.content_type.into());
+            if let Some(encoding) = metadata.content_encoding {
+                attributes.insert(Attribute::ContentEncoding, encoding.into());
+            }
+            let payload = PutPayload::from(contents.to_vec());
+            self.store
+                .put_opts(&self.location(name), payload, attributes.into())
+                .await
+                .map_err(|e| Error::Source(Box::new(e)))?;
+            Ok(())
+        })
+    }
+}
+
+impl Sitemap {
+    /// Writes the sitemap split as [`Sitemap::write_split_to`], then stores the files in
+    /// `sink`, the index last so it never lists a part that isn't stored yet.
+    ///
+    /// The files are generated in memory before they are stored. Returns the index
+    /// followed by the parts.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`Sitemap::write_split_to`], or if `sink` fails to store a file,
+    /// [`Error::Source`] for an [`ObjectStoreSink`].
+    pub async fn upload_split(
+        &self,
+        urls: &[UrlEntry],
+        output: &SplitOutput,
+        sink: &dyn AsyncOutputSink,
+    ) -> Result<Vec<WrittenFile>> {
+        let memory = MemorySink::new();
+        let files = self.write_split_to(urls, output, &memory)?;
+        for path in files.iter().skip(1).chain(files.first()).map(|f| &f.path) {
+            let name = sink_name(path);
+            let contents = memory.get(&name).unwrap_or_default();
+            sink.put(&name, &contents).await?;
+        }
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use object_store::memory::InMemory;
+
+    use super::*;
+    use crate::{sink::block_on, Limits};
+
+    /// Records the order the files are stored in.
+    struct Recorder(ObjectStoreSink, Mutex<Vec<String>>);
+
+    impl AsyncOutputSink for Recorder {
+        fn put<'a>(&'a self, name: &'a str, contents: &'a [u8]) -> BoxFuture<'a, Result<()>> {
+            self.1.lock().unwrap().push(name.to_owned());
+            self.0.put(name, contents)
+        }
+    }
+
+    #[test]
+    fn uploads_with_metadata() {
+        let store = Arc::new(InMemory::new());
+        let sink = ObjectStoreSink::new(store.clone()).prefix("site/maps");
+        let recorder = Recorder(sink, Mutex::new(Vec::new()));
+        let urls: Vec<UrlEntry> = (0..3)
+            .map(|i| {
+                let loc = format!("https://a.com/{i}").parse().unwrap();
+                UrlEntry::new(loc, None, None, None)
+            })
+            .collect();
+        let output = SplitOutput::new("v1", "https://a.com/maps/".parse().unwrap())
+            .template("sitemap-{index}.xml.gz");
+        let sitemap = Sitemap::new().limits(Limits::new(2, crate::MAX_SIZE));
+
+        let files = block_on(sitemap.upload_split(&urls, &output, &recorder)).unwrap();
+        assert_eq!(files.len(), 3);
+        assert_eq!(
+            *recorder.1.lock().unwrap(),
+            [
+                "v1/sitemap-1.xml.gz",
+                "v1/sitemap-2.xml.gz",
+                "v1/sitemap_index.xml"
+            ]
+        );
+
+        let part = block_on(store.get(&"site/maps/v1/sitemap-1.xml.gz".into())).unwrap();
+        assert_eq!(
+            part.attributes
+                .get(&Attribute::ContentEncoding)
+                .map(|v| &**v),
+            Some("gzip")
+        );
+        assert_eq!(
+            part.attributes.get(&Attribute::ContentType).map(|v| &**v),
+            Some("application/xml")
+        );
+        let listed = block_on(store.get(&"site/maps/v1/sitemap_index.xml".into())).unwrap();
+        let listed = block_on(listed.bytes()).unwrap();
+        assert!(String::from_utf8_lossy(&listed).contains("https://a.com/maps/sitemap-2.xml.gz"));
+    }
+}
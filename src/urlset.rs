@@ -0,0 +1,241 @@
+//! The serialization of a `<urlset>` into a `Vec<u8>`.
+//!
+//! The document is built from bytes without going through [`std::io::Write`], which is
+//! left to [`SitemapWriter`](crate::SitemapWriter). Only `core` and `alloc` are used, apart
+//! from the extensions, which are written through quick-xml by their [`ExtensionWriter`].
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Writer,
+};
+
+use crate::{
+    extension::ExtensionWriter,
+    format,
+    priority::PriorityStrategy,
+    validate::{validate_entry, ValidateOptions},
+    EntryDefaults, Error, Extensions, Result, UrlEntry, UrlEntryRef, MOBILE_NAMESPACE,
+};
+
+/// The bytes closing the document after the last entry.
+pub const TAIL: &[u8] = b"\n</urlset>";
+
+/// Appends the XML declaration and the opening `<urlset>`, declaring `namespaces`.
+pub fn head(out: &mut Vec<u8>, namespaces: &[(String, String)]) {
+    out.extend_from_slice(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    out.extend_from_slice(b"\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\"");
+    for (prefix, namespace) in namespaces {
+        out.extend_from_slice(b" xmlns:");
+        out.extend_from_slice(prefix.as_bytes());
+        out.extend_from_slice(b"=\"");
+        format::escape(out, namespace);
+        out.push(b'"');
+    }
+    out.push(b'>');
+}
+
+/// Appends a `<name>text</name>` child of `<url>`.
+fn element(out: &mut Vec<u8>, name: &str, text: &str) {
+    out.extend_from_slice(b"\n        <");
+    out.extend_from_slice(name.as_bytes());
+    out.push(b'>');
+    format::escape(out, text);
+    out.extend_from_slice(b"</");
+    out.extend_from_slice(name.as_bytes());
+    out.push(b'>');
+}
+
+/// Checks and renders entries, shared by the threads of a parallel generation.
+#[derive(Debug, Default)]
+pub struct Renderer {
+    /// Sorted by prefix once the writer started.
+    pub namespaces: Vec<(String, String)>,
+    pub validate: Option<ValidateOptions>,
+    pub defaults: EntryDefaults,
+    pub priority: Option<Box<dyn PriorityStrategy>>,
+}
+
+impl Renderer {
+    /// Fails if `prefix` isn't declared, or is bound to another namespace.
+    fn check_namespace(&self, prefix: &str, namespace: &str) -> Result<()> {
+        match self
+            .namespaces
+            .binary_search_by(|(p, _)| p.as_str().cmp(prefix))
+        {
+            Ok(i) if self.namespaces[i].1 == namespace => Ok(()),
+            Ok(_) => Err(Error::NamespaceConflict {
+                prefix: prefix.to_owned(),
+            }),
+            Err(_) => Err(Error::UndeclaredNamespace {
+                prefix: prefix.to_owned(),
+            }),
+        }
+    }
+
+    /// Fails with [`Error::Invalid`] if validation is enabled and the entry has errors.
+    pub fn validate(&self, entry: &UrlEntry) -> Result<()> {
+        if let Some(options) = &self.validate {
+            let report = validate_entry(entry, options);
+            if report.has_errors() {
+                return Err(Error::Invalid(report));
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends the `<url>` elements of the entries to `out`, which is left with part of an
+    /// entry on errors.
+    pub fn render<'a>(
+        &self,
+        entries: impl IntoIterator<Item = UrlEntryRef<'a>>,
+        out: &mut Vec<u8>,
+    ) -> Result<()> {
+        for entry in entries {
+            self.render_entry(entry, out)?;
+        }
+        Ok(())
+    }
+
+    fn render_entry(&self, entry: UrlEntryRef<'_>, out: &mut Vec<u8>) -> Result<()> {
+        let mut entry = entry;
+        if let (None, Some(strategy)) = (entry.priority, &self.priority) {
+            entry.priority = strategy.priority(entry);
+        }
+        let entry = self.defaults.apply(entry);
+        for extension in entry.extensions.iter() {
+            self.check_namespace(extension.prefix(), extension.namespace())?;
+        }
+        if entry.mobile {
+            self.check_namespace("mobile", MOBILE_NAMESPACE)?;
+        }
+
+        out.extend_from_slice(b"\n    <url>");
+        element(out, "loc", entry.loc);
+        if let Some(lastmod) = &entry.lastmod {
+            element(out, "lastmod", &format::lastmod(lastmod, &mut [0; 20]));
+        }
+        if let Some(changefreq) = entry.changefreq {
+            element(out, "changefreq", changefreq.as_str());
+        }
+        if let Some(priority) = entry.priority {
+            element(out, "priority", &format::priority(priority, &mut [0; 3]));
+        }
+        if !entry.extensions.is_empty() {
+            write_extensions(entry.extensions, out)?;
+        }
+        if entry.mobile {
+            out.extend_from_slice(b"\n        <mobile:mobile/>");
+        }
+        out.extend_from_slice(b"\n    </url>");
+        Ok(())
+    }
+}
+
+/// Appends the elements of the extensions, written by quick-xml after an opening
+/// `<urlset>` and `<url>` so they get the indentation they have inside them.
+fn write_extensions(extensions: &Extensions, out: &mut Vec<u8>) -> Result<()> {
+    const PREFIX: &[u8] = b"<urlset>\n    <url>";
+
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 4);
+    writer.write_event(Event::Start(BytesStart::new("urlset")))?;
+    writer.write_event(Event::Start(BytesStart::new("url")))?;
+    for extension in extensions.iter() {
+        extension.write(&mut ExtensionWriter::new(&mut writer))?;
+    }
+    out.extend_from_slice(&writer.into_inner()[PREFIX.len()..]);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use quick_xml::events::{BytesDecl, BytesEnd};
+
+    use super::*;
+    use crate::{write_tag, ChangeFreq, SitemapExtension};
+
+    #[derive(Debug)]
+    struct Tag;
+
+    impl SitemapExtension for Tag {
+        fn prefix(&self) -> &str {
+            "x"
+        }
+
+        fn namespace(&self) -> &str {
+            "urn:x?a=\"1\""
+        }
+
+        fn write(&self, writer: &mut ExtensionWriter<'_>) -> Result<()> {
+            writer.start("x:a", &[("k", "<v>")])?;
+            writer.text_element("x:b", "t & 'u'")?;
+            writer.end("x:a")?;
+            writer.empty("x:c", &[])
+        }
+    }
+
+    #[test]
+    fn same_as_quick_xml() {
+        let lastmod = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let mut entry = UrlEntry::new(
+            "https://a.com/?a=1&b='2'".parse().unwrap(),
+            Some(lastmod),
+            Some(ChangeFreq::Daily),
+            Some(0.5),
+        );
+        entry.extensions.push(Tag);
+        entry.mobile = true;
+        let namespaces = vec![
+            ("mobile".to_owned(), MOBILE_NAMESPACE.to_owned()),
+            ("x".to_owned(), Tag.namespace().to_owned()),
+        ];
+
+        let renderer = Renderer {
+            namespaces: namespaces.clone(),
+            ..Renderer::default()
+        };
+        let mut out = Vec::new();
+        head(&mut out, &namespaces);
+        for _ in 0..2 {
+            renderer
+                .render([UrlEntryRef::from(&entry)], &mut out)
+                .unwrap();
+        }
+        out.extend_from_slice(TAIL);
+
+        let mut writer = Writer::new_with_indent(Vec::new(), b' ', 4);
+        let decl = BytesDecl::new("1.0", Some("UTF-8"), None);
+        writer.write_event(Event::Decl(decl)).unwrap();
+        let mut urlset = BytesStart::new("urlset");
+        urlset.push_attribute(("xmlns", "http://www.sitemaps.org/schemas/sitemap/0.9"));
+        for (prefix, namespace) in &namespaces {
+            urlset.push_attribute((format!("xmlns:{prefix}").as_str(), namespace.as_str()));
+        }
+        writer.write_event(Event::Start(urlset)).unwrap();
+        for _ in 0..2 {
+            writer
+                .write_event(Event::Start(BytesStart::new("url")))
+                .unwrap();
+            write_tag(&mut writer, "loc", entry.loc.as_str()).unwrap();
+            write_tag(&mut writer, "lastmod", "2024-01-02T03:04:05Z").unwrap();
+            write_tag(&mut writer, "changefreq", "daily").unwrap();
+            write_tag(&mut writer, "priority", "0.5").unwrap();
+            Tag.write(&mut ExtensionWriter::new(&mut writer)).unwrap();
+            let mobile = BytesStart::new("mobile:mobile");
+            writer.write_event(Event::Empty(mobile)).unwrap();
+            writer
+                .write_event(Event::End(BytesEnd::new("url")))
+                .unwrap();
+        }
+        writer
+            .write_event(Event::End(BytesEnd::new("urlset")))
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(writer.into_inner()).unwrap()
+        );
+    }
+}
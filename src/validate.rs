@@ -0,0 +1,737 @@
+//! Checks of entries against the sitemap protocol.
+//!
+//! ```rust
+//! use sitewriter::{validate::{validate, IssueKind}, UrlEntry};
+//!
+//! let urls = vec![UrlEntry::new("https://example.com/".parse()?, None, None, Some(1.5))];
+//! let report = validate(&urls);
+//! assert_eq!(report.issues[0].kind, IssueKind::PriorityOutOfRange);
+//! assert!(report.has_errors());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{fmt::Display, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use quick_xml::{events::Event, Reader};
+use url::{Origin, Url};
+
+use crate::{extension, normalize::has_userinfo, output::CountingWriter, Limits, UrlEntry};
+
+/// The maximum length of a loc.
+pub const MAX_LOC_LEN: usize = 2048;
+
+/// The maximum number of `<image:image>` elements of an entry.
+pub const MAX_IMAGES: usize = 1000;
+
+/// The maximum number of `<video:tag>` elements of a `<video:video>`.
+pub const MAX_VIDEO_TAGS: usize = 32;
+
+/// The namespace of Google's image extension.
+pub const IMAGE_NAMESPACE: &str = "http://www.google.com/schemas/sitemap-image/1.1";
+
+/// The namespace of Google's video extension.
+pub const VIDEO_NAMESPACE: &str = "http://www.google.com/schemas/sitemap-video/1.1";
+
+/// How serious an issue is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// Search engines may ignore or misread the entry.
+    Warning,
+    /// The entry breaks the protocol.
+    Error,
+}
+
+/// What is wrong with an entry.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum IssueKind {
+    /// The loc is longer than [`MAX_LOC_LEN`].
+    LocTooLong,
+    /// The loc isn't an http or https url.
+    UnsupportedScheme,
+    /// The priority isn't between 0.0 and 1.0.
+    PriorityOutOfRange,
+    /// The lastmod is in the future.
+    LastmodInFuture,
+    /// The loc has a port other than the default of its scheme, and
+    /// [`ValidateOptions::allow_non_default_ports`] is off.
+    NonDefaultPort,
+    /// The loc isn't on the scheme, host and port of [`ValidateOptions::site`].
+    HostMismatch,
+    /// The loc has a `user:password@` part. It is stripped when writing, but likely means
+    /// credentials got into the data.
+    Userinfo,
+    /// The loc has another scheme than [`ValidateOptions::sitemap_url`].
+    SitemapSchemeMismatch,
+    /// The loc is on another host or port than [`ValidateOptions::sitemap_url`].
+    SitemapHostMismatch,
+    /// The loc isn't under the directory of [`ValidateOptions::sitemap_url`].
+    OutsideSitemapPath,
+    /// The loc has characters that RFC 3986 requires to be percent-encoded, such as `|` or
+    /// `^`, which url parsing leaves as they are.
+    UnescapedCharacters,
+    /// The host has non-ASCII characters instead of its punycode form. Parsing punycodes the
+    /// hosts of http and https urls, so only other schemes can have them.
+    NonAsciiHost,
+    /// The loc has a parameter that looks like a session id, such as `PHPSESSID` or
+    /// `;jsessionid=`, so it is likely a duplicate of the page without it.
+    SessionId,
+    /// The entry has more than [`MAX_IMAGES`] images, the ones after are ignored.
+    TooManyImages,
+    /// A video of the entry has more than [`MAX_VIDEO_TAGS`] tags, the ones after are
+    /// ignored.
+    TooManyVideoTags,
+    /// The sitemap has more entries than [`ValidateOptions::limits`] allows. Reported on
+    /// the first entry over the limit.
+    TooManyEntries,
+    /// The sitemap generated from the entries is larger than [`ValidateOptions::limits`]
+    /// allows. Reported on the last entry.
+    TooManyBytes,
+}
+
+impl IssueKind {
+    /// How serious the issue is.
+    #[must_use]
+    pub const fn severity(self) -> Severity {
+        match self {
+            Self::LocTooLong
+            | Self::UnsupportedScheme
+            | Self::PriorityOutOfRange
+            | Self::NonDefaultPort
+            | Self::HostMismatch
+            | Self::Userinfo
+            | Self::SitemapSchemeMismatch
+            | Self::SitemapHostMismatch
+            | Self::OutsideSitemapPath
+            | Self::TooManyEntries
+            | Self::TooManyBytes => Severity::Error,
+            Self::LastmodInFuture
+            | Self::UnescapedCharacters
+            | Self::NonAsciiHost
+            | Self::SessionId
+            | Self::TooManyImages
+            | Self::TooManyVideoTags => Severity::Warning,
+        }
+    }
+
+    /// A stable code identifying the issue, for machine-readable output.
+    #[must_use]
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::LocTooLong => "loc-too-long",
+            Self::UnsupportedScheme => "unsupported-scheme",
+            Self::PriorityOutOfRange => "priority-out-of-range",
+            Self::LastmodInFuture => "lastmod-in-future",
+            Self::NonDefaultPort => "non-default-port",
+            Self::HostMismatch => "host-mismatch",
+            Self::Userinfo => "userinfo",
+            Self::SitemapSchemeMismatch => "sitemap-scheme-mismatch",
+            Self::SitemapHostMismatch => "sitemap-host-mismatch",
+            Self::OutsideSitemapPath => "outside-sitemap-path",
+            Self::UnescapedCharacters => "unescaped-characters",
+            Self::NonAsciiHost => "non-ascii-host",
+            Self::SessionId => "session-id",
+            Self::TooManyImages => "too-many-images",
+            Self::TooManyVideoTags => "too-many-video-tags",
+            Self::TooManyEntries => "too-many-entries",
+            Self::TooManyBytes => "too-many-bytes",
+        }
+    }
+}
+
+impl Display for IssueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match self {
+            Self::LocTooLong => "loc is longer than 2048 characters",
+            Self::UnsupportedScheme => "loc is not an http or https url",
+            Self::PriorityOutOfRange => "priority is not between 0.0 and 1.0",
+            Self::LastmodInFuture => "lastmod is in the future",
+            Self::NonDefaultPort => "loc has a non-default port",
+            Self::HostMismatch => "loc is on another scheme, host or port than the site",
+            Self::Userinfo => "loc contains credentials",
+            Self::SitemapSchemeMismatch => "loc has another scheme than the sitemap",
+            Self::SitemapHostMismatch => "loc is on another host or port than the sitemap",
+            Self::OutsideSitemapPath => "loc is not under the directory of the sitemap",
+            Self::UnescapedCharacters => "loc has characters that should be percent-encoded",
+            Self::NonAsciiHost => "host is not punycoded",
+            Self::SessionId => "loc has a session id parameter",
+            Self::TooManyImages => "entry has more than 1000 images",
+            Self::TooManyVideoTags => "video has more than 32 tags",
+            Self::TooManyEntries => "sitemap has more entries than the limit",
+            Self::TooManyBytes => "sitemap is larger than the size limit",
+        };
+        f.write_str(what)
+    }
+}
+
+/// A problem found in an entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Issue {
+    /// What is wrong.
+    pub kind: IssueKind,
+    /// The loc of the entry.
+    pub loc: Url,
+    /// The [`source`](UrlEntry::source) of the entry.
+    pub source: Option<Arc<str>>,
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.loc, self.kind)?;
+        if let Some(source) = &self.source {
+            write!(f, " (from {source})")?;
+        }
+        Ok(())
+    }
+}
+
+/// The issues found by [`validate`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ValidationReport {
+    /// The issues, in entry order, followed by the issues of the whole sitemap.
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    /// Whether no issue was found.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Whether an issue with [`Severity::Error`] was found.
+    #[must_use]
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    /// The issues with [`Severity::Error`].
+    pub fn errors(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|i| i.kind.severity() == Severity::Error)
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{issue}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Options of [`validate_with`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ValidateOptions {
+    /// Allow locs with an explicit non-default port, such as staging hosts on `:8080`.
+    pub allow_non_default_ports: bool,
+    /// The url of the site, every loc must have the same scheme, host and port.
+    ///
+    /// Ports are compared after applying the scheme defaults, so `https://a.com:443/` is on
+    /// the same site as `https://a.com/`.
+    pub site: Option<Url>,
+    /// The url the sitemap is published at.
+    ///
+    /// Per the protocol, a sitemap only lists urls with its scheme and host, under its
+    /// directory: a sitemap at `https://a.com/shop/sitemap.xml` can list
+    /// `https://a.com/shop/hat` but not `http://a.com/shop/hat`, `https://b.com/shop/hat`
+    /// or `https://a.com/blog/`. Each loc gets at most one of the matching issues.
+    ///
+    /// ```rust
+    /// use sitewriter::{validate::{validate_with, IssueKind, ValidateOptions}, UrlEntry};
+    ///
+    /// let urls = vec![UrlEntry::new("https://example.com/blog/".parse()?, None, None, None)];
+    /// let options = ValidateOptions {
+    ///     sitemap_url: Some("https://example.com/shop/sitemap.xml".parse()?),
+    ///     ..Default::default()
+    /// };
+    /// let report = validate_with(&urls, &options);
+    /// assert_eq!(report.issues[0].kind, IssueKind::OutsideSitemapPath);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub sitemap_url: Option<Url>,
+    /// The limits the entries must fit in as a single sitemap, unchecked by default since
+    /// entries are usually split within them.
+    ///
+    /// The size is the one of the sitemap generated from the entries by
+    /// [`generate_bytes`](crate::generate_bytes). Use [`Limits::legacy_10mb`] for
+    /// search engines that still expect the limits of the protocol before 2016.
+    pub limits: Option<Limits>,
+}
+
+impl Default for ValidateOptions {
+    fn default() -> Self {
+        Self {
+            allow_non_default_ports: true,
+            site: None,
+            sitemap_url: None,
+            limits: None,
+        }
+    }
+}
+
+/// Checks the entries against the sitemap protocol, with the default options.
+#[must_use]
+pub fn validate(urls: &[UrlEntry]) -> ValidationReport {
+    validate_with(urls, &ValidateOptions::default())
+}
+
+/// Checks the loc length, the scheme and the priority of `entry`, for
+/// [`UrlEntryBuilder::build`](crate::UrlEntryBuilder::build).
+pub(crate) fn check_entry(entry: &UrlEntry) -> crate::Result<()> {
+    let mut report = validate_entry(entry, &ValidateOptions::default());
+    report.issues.retain(|issue| {
+        matches!(
+            issue.kind,
+            IssueKind::LocTooLong | IssueKind::UnsupportedScheme | IssueKind::PriorityOutOfRange
+        )
+    });
+    if report.is_empty() {
+        Ok(())
+    } else {
+        Err(crate::Error::Invalid(report))
+    }
+}
+
+/// Checks the entries against the sitemap protocol and the given options.
+#[must_use]
+pub fn validate_with(urls: &[UrlEntry], options: &ValidateOptions) -> ValidationReport {
+    let now = Utc::now();
+    let site = options.site.as_ref().map(Url::origin);
+    let mut issues = Vec::new();
+
+    for entry in urls {
+        entry_issues(entry, options, now, site.as_ref(), &mut issues);
+    }
+
+    let issue = |kind, entry: &UrlEntry| Issue {
+        kind,
+        loc: entry.loc.clone(),
+        source: entry.source.clone(),
+    };
+    if let Some(limits) = options.limits {
+        if let Some(entry) = urls.get(limits.max_entries) {
+            issues.push(issue(IssueKind::TooManyEntries, entry));
+        }
+        if let Some(last) = urls.last() {
+            // A sitemap that can't be generated, such as with conflicting namespaces, fails
+            // when written instead.
+            let size = crate::generate_iter(CountingWriter::new(std::io::sink()), urls)
+                .map_or(0, |writer| writer.count());
+            if size > limits.max_size {
+                issues.push(issue(IssueKind::TooManyBytes, last));
+            }
+        }
+    }
+
+    ValidationReport { issues }
+}
+
+/// Checks a single entry, without the limits of the whole sitemap.
+pub(crate) fn validate_entry(entry: &UrlEntry, options: &ValidateOptions) -> ValidationReport {
+    let site = options.site.as_ref().map(Url::origin);
+    let mut issues = Vec::new();
+    entry_issues(entry, options, Utc::now(), site.as_ref(), &mut issues);
+    ValidationReport { issues }
+}
+
+/// Adds the issues of `entry` to `issues`.
+fn entry_issues(
+    entry: &UrlEntry,
+    options: &ValidateOptions,
+    now: DateTime<Utc>,
+    site: Option<&Origin>,
+    issues: &mut Vec<Issue>,
+) {
+    let mut push = |kind| {
+        issues.push(Issue {
+            kind,
+            loc: entry.loc.clone(),
+            source: entry.source.clone(),
+        });
+    };
+
+    if entry.loc.as_str().len() > MAX_LOC_LEN {
+        push(IssueKind::LocTooLong);
+    }
+    if has_userinfo(&entry.loc) {
+        push(IssueKind::Userinfo);
+    }
+    if !matches!(entry.loc.scheme(), "http" | "https") {
+        push(IssueKind::UnsupportedScheme);
+    }
+    if entry.priority.is_some_and(|p| !(0.0..=1.0).contains(&p)) {
+        push(IssueKind::PriorityOutOfRange);
+    }
+    if entry.lastmod.is_some_and(|lastmod| lastmod > now) {
+        push(IssueKind::LastmodInFuture);
+    }
+    if entry.loc.as_str().chars().any(|c| !is_uri_char(c)) {
+        push(IssueKind::UnescapedCharacters);
+    }
+    if entry.loc.host_str().is_some_and(|host| !host.is_ascii()) {
+        push(IssueKind::NonAsciiHost);
+    }
+    if has_session_id(&entry.loc) {
+        push(IssueKind::SessionId);
+    }
+    if !entry.extensions.is_empty() {
+        let (images, video_tags) = media_counts(entry);
+        if images > MAX_IMAGES {
+            push(IssueKind::TooManyImages);
+        }
+        if video_tags > MAX_VIDEO_TAGS {
+            push(IssueKind::TooManyVideoTags);
+        }
+    }
+    // Url parsing drops default ports, so any port left is a non-default one.
+    if !options.allow_non_default_ports && entry.loc.port().is_some() {
+        push(IssueKind::NonDefaultPort);
+    }
+    if site.is_some_and(|site| *site != entry.loc.origin()) {
+        push(IssueKind::HostMismatch);
+    }
+    if let Some(sitemap) = &options.sitemap_url {
+        if let Some(kind) = scope_issue(sitemap, &entry.loc) {
+            push(kind);
+        }
+    }
+}
+
+/// Whether `c` may appear unescaped in a URI, per RFC 3986.
+pub(crate) const fn is_uri_char(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+        || matches!(
+            c,
+            '-' | '.'
+                | '_'
+                | '~'
+                | ':'
+                | '/'
+                | '?'
+                | '#'
+                | '['
+                | ']'
+                | '@'
+                | '!'
+                | '$'
+                | '&'
+                | '\''
+                | '('
+                | ')'
+                | '*'
+                | '+'
+                | ','
+                | ';'
+                | '='
+                | '%'
+        )
+}
+
+/// The parameter names used for session ids by common frameworks, lowercase.
+const SESSION_PARAMS: &[&str] = &[
+    "sid",
+    "sessionid",
+    "session_id",
+    "jsessionid",
+    "phpsessid",
+    "aspsessionid",
+    "cfid",
+    "cftoken",
+];
+
+/// Whether the query or the path parameters of `loc` have a session id.
+fn has_session_id(loc: &Url) -> bool {
+    let is_session = |name: &str| {
+        let name = name.to_ascii_lowercase();
+        SESSION_PARAMS.contains(&name.as_str())
+    };
+    let mut path_params = loc
+        .path()
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split('=').next());
+    loc.query_pairs().any(|(name, _)| is_session(&name)) || path_params.any(is_session)
+}
+
+/// The number of images of `entry` and the most tags of one of its videos.
+///
+/// The extensions are counted from the XML they write, so both the types of the crate
+/// users and the [`RawExtension`](crate::extension::RawExtension)s kept by the parser are
+/// checked.
+fn media_counts(entry: &UrlEntry) -> (usize, usize) {
+    let mut images = 0;
+    let mut video_tags = 0;
+    for ext in entry.extensions.iter() {
+        let namespace = ext.namespace();
+        if namespace != IMAGE_NAMESPACE && namespace != VIDEO_NAMESPACE {
+            continue;
+        }
+        let Ok(xml) = extension::to_xml(ext) else {
+            continue;
+        };
+        let mut reader = Reader::from_str(&xml);
+        let mut depth = 0;
+        let mut tags = 0;
+        loop {
+            let (e, empty) = match reader.read_event() {
+                Ok(Event::Start(e)) => (e, false),
+                Ok(Event::Empty(e)) => (e, true),
+                Ok(Event::End(_)) => {
+                    depth -= 1;
+                    continue;
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                Ok(_) => continue,
+            };
+            match (depth, e.local_name().as_ref(), namespace) {
+                (0, b"image", IMAGE_NAMESPACE) => images += 1,
+                (0, b"video", VIDEO_NAMESPACE) => tags = 0,
+                (1, b"tag", VIDEO_NAMESPACE) => {
+                    tags += 1;
+                    video_tags = video_tags.max(tags);
+                }
+                _ => {}
+            }
+            if !empty {
+                depth += 1;
+            }
+        }
+    }
+    (images, video_tags)
+}
+
+/// Whether `loc` is outside of the urls a sitemap published at `sitemap` may list.
+fn scope_issue(sitemap: &Url, loc: &Url) -> Option<IssueKind> {
+    if loc.scheme() != sitemap.scheme() {
+        return Some(IssueKind::SitemapSchemeMismatch);
+    }
+    if loc.host() != sitemap.host()
+        || loc.port_or_known_default() != sitemap.port_or_known_default()
+    {
+        return Some(IssueKind::SitemapHostMismatch);
+    }
+    let path = sitemap.path();
+    let dir = &path[..path.rfind('/').map_or(0, |i| i + 1)];
+    (!loc.path().starts_with(dir)).then_some(IssueKind::OutsideSitemapPath)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn finds_issues() {
+        let long = format!("https://a.com/{}", "a".repeat(MAX_LOC_LEN));
+        let urls = vec![
+            UrlEntry::new("https://a.com/".parse().unwrap(), None, None, Some(0.5)),
+            UrlEntry::new(long.parse().unwrap(), None, None, None),
+            UrlEntry::new("ftp://a.com/".parse().unwrap(), None, None, Some(-0.1)),
+            UrlEntry::new(
+                "https://a.com/later".parse().unwrap(),
+                Some(Utc::now() + Duration::days(1)),
+                None,
+                None,
+            ),
+        ];
+
+        let report = validate(&urls);
+        let kinds: Vec<IssueKind> = report.issues.iter().map(|i| i.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                IssueKind::LocTooLong,
+                IssueKind::UnsupportedScheme,
+                IssueKind::PriorityOutOfRange,
+                IssueKind::LastmodInFuture,
+            ]
+        );
+        assert_eq!(report.errors().count(), 3);
+    }
+
+    #[test]
+    fn ports_and_hosts() {
+        let urls: Vec<UrlEntry> = [
+            "https://a.com:443/",
+            "https://a.com:8443/",
+            "http://a.com/",
+            "https://b.com/",
+        ]
+        .iter()
+        .map(|loc| UrlEntry::new(loc.parse().unwrap(), None, None, None))
+        .collect();
+        let kinds = |options: &ValidateOptions| -> Vec<(String, IssueKind)> {
+            validate_with(&urls, options)
+                .issues
+                .into_iter()
+                .map(|i| (i.loc.to_string(), i.kind))
+                .collect()
+        };
+
+        assert!(validate(&urls).is_empty());
+        assert_eq!(
+            kinds(&ValidateOptions {
+                allow_non_default_ports: false,
+                site: Some("https://a.com/".parse().unwrap()),
+                sitemap_url: None,
+                limits: None,
+            }),
+            [
+                ("https://a.com:8443/".to_owned(), IssueKind::NonDefaultPort),
+                ("https://a.com:8443/".to_owned(), IssueKind::HostMismatch),
+                ("http://a.com/".to_owned(), IssueKind::HostMismatch),
+                ("https://b.com/".to_owned(), IssueKind::HostMismatch),
+            ]
+        );
+        let staging = ValidateOptions {
+            site: Some("https://a.com:8443/".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(kinds(&staging).len(), 3);
+    }
+
+    #[test]
+    fn lints() {
+        let urls: Vec<UrlEntry> = [
+            "https://a.com/a|b",
+            "https://bücher.de/",
+            "https://a.com/cart;jsessionid=123",
+            "https://a.com/?PHPSESSID=abc&page=2",
+            "https://a.com/?side=1",
+        ]
+        .iter()
+        .map(|loc| UrlEntry::new(loc.parse().unwrap(), None, None, None))
+        .collect();
+
+        let report = validate(&urls);
+        let kinds: Vec<IssueKind> = report.issues.iter().map(|i| i.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                IssueKind::UnescapedCharacters,
+                IssueKind::SessionId,
+                IssueKind::SessionId
+            ]
+        );
+        assert!(!report.has_errors());
+    }
+
+    #[test]
+    fn sitemap_scope() {
+        let urls: Vec<UrlEntry> = [
+            "https://a.com/shop/",
+            "https://a.com:443/shop/hats/red",
+            "http://a.com/shop/hat",
+            "https://b.com/shop/hat",
+            "https://a.com:8443/shop/hat",
+            "https://a.com/shopping",
+            "https://a.com/",
+        ]
+        .iter()
+        .map(|loc| UrlEntry::new(loc.parse().unwrap(), None, None, None))
+        .collect();
+        let options = ValidateOptions {
+            sitemap_url: Some("https://a.com/shop/sitemap.xml".parse().unwrap()),
+            ..Default::default()
+        };
+
+        let report = validate_with(&urls, &options);
+        let issues: Vec<(&str, IssueKind)> = report
+            .issues
+            .iter()
+            .map(|i| (i.loc.as_str(), i.kind))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                ("http://a.com/shop/hat", IssueKind::SitemapSchemeMismatch),
+                ("https://b.com/shop/hat", IssueKind::SitemapHostMismatch),
+                (
+                    "https://a.com:8443/shop/hat",
+                    IssueKind::SitemapHostMismatch
+                ),
+                ("https://a.com/shopping", IssueKind::OutsideSitemapPath),
+                ("https://a.com/", IssueKind::OutsideSitemapPath),
+            ]
+        );
+    }
+
+    #[test]
+    fn limits() {
+        let urls: Vec<UrlEntry> = ["https://a.com/1", "https://a.com/2", "https://a.com/3"]
+            .iter()
+            .map(|loc| UrlEntry::new(loc.parse().unwrap(), None, None, None))
+            .collect();
+        let size = crate::generate_bytes(&urls).len();
+        let report = |limits| {
+            let options = ValidateOptions {
+                limits: Some(limits),
+                ..Default::default()
+            };
+            validate_with(&urls, &options)
+                .issues
+                .into_iter()
+                .map(|i| (i.loc.to_string(), i.kind))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(report(Limits::new(3, size)).is_empty());
+        assert_eq!(
+            report(Limits::new(2, size - 1)),
+            [
+                ("https://a.com/3".to_owned(), IssueKind::TooManyEntries),
+                ("https://a.com/3".to_owned(), IssueKind::TooManyBytes),
+            ]
+        );
+        assert_eq!(IssueKind::TooManyBytes.code(), "too-many-bytes");
+    }
+
+    #[test]
+    fn media_limits() {
+        let image = "<image:image><image:loc>https://a.com/a.png</image:loc></image:image>";
+        let video = |tags: usize| {
+            let tags: String = (0..tags)
+                .map(|i| format!("<video:tag>t{i}</video:tag>"))
+                .collect();
+            format!("<video:video><video:title>V</video:title>{tags}</video:video>")
+        };
+        let xml = format!(
+            r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+                xmlns:image="{IMAGE_NAMESPACE}" xmlns:video="{VIDEO_NAMESPACE}">
+            <url><loc>https://a.com/ok</loc>{}{}</url>
+            <url><loc>https://a.com/many</loc>{}{}{}</url>
+            </urlset>"#,
+            image.repeat(MAX_IMAGES),
+            video(MAX_VIDEO_TAGS),
+            image.repeat(MAX_IMAGES + 1),
+            video(MAX_VIDEO_TAGS + 1),
+            video(1),
+        );
+        let urls = crate::Parser::new()
+            .keep_extensions(true)
+            .parse_str(&xml)
+            .unwrap();
+
+        let report = validate(&urls);
+        let issues: Vec<(&str, IssueKind)> = report
+            .issues
+            .iter()
+            .map(|i| (i.loc.as_str(), i.kind))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                ("https://a.com/many", IssueKind::TooManyImages),
+                ("https://a.com/many", IssueKind::TooManyVideoTags),
+            ]
+        );
+    }
+}
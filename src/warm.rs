@@ -0,0 +1,213 @@
+//! Warming CDN caches once the sitemaps are published.
+//!
+//! Requesting the published sitemaps, and optionally the first content urls, makes the CDN
+//! fetch them from the origin before crawlers ask for them. It is meant as the last step of
+//! a publish, after the upload is verified:
+//!
+//! ```rust
+//! use sitewriter::{
+//!     client::HttpResponse,
+//!     warm::{warm_caches_with, WarmOptions},
+//!     Url, UrlEntry,
+//! };
+//!
+//! let sitemaps: Vec<Url> = vec!["https://example.com/sitemap.xml".parse()?];
+//! let urls = vec![UrlEntry::new("https://example.com/".parse()?, None, None, None)];
+//! let options = WarmOptions {
+//!     content_urls: 100,
+//!     ..Default::default()
+//! };
+//!
+//! let client = |_: &Url| Ok(HttpResponse::ok(""));
+//! let report = warm_caches_with(&client, &sitemaps, &urls, &options);
+//! assert_eq!(report.requests.len(), 2);
+//! assert_eq!(report.failures().count(), 0);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use url::Url;
+
+use crate::{
+    client::HttpClient,
+    politeness::{PolitenessOptions, Throttle},
+    UrlEntry,
+};
+
+/// Options of [`warm_caches_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct WarmOptions {
+    /// How many content urls to request after the sitemaps, the first ones of the entries.
+    pub content_urls: usize,
+    /// The limits on concurrent requests, `max_concurrency` is the number of threads used.
+    pub politeness: PolitenessOptions,
+}
+
+/// One request sent by [`warm_caches_with`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Warmed {
+    /// The requested url.
+    pub url: Url,
+    /// The status of the response, or why none was received.
+    pub status: Result<u16, String>,
+}
+
+impl Warmed {
+    /// Whether a `2xx` response was received.
+    #[must_use]
+    pub const fn is_success(&self) -> bool {
+        matches!(self.status, Ok(200..=299))
+    }
+}
+
+/// The result of [`warm_caches_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct WarmReport {
+    /// The requests, the sitemaps first, in the order they were given.
+    pub requests: Vec<Warmed>,
+}
+
+impl WarmReport {
+    /// The requests that didn't get a `2xx` response.
+    pub fn failures(&self) -> impl Iterator<Item = &Warmed> {
+        self.requests.iter().filter(|warmed| !warmed.is_success())
+    }
+}
+
+/// Requests the sitemaps with the `ureq` client, see [`warm_caches_with`].
+#[cfg(feature = "fetch")]
+#[must_use]
+pub fn warm_caches(sitemaps: &[Url], urls: &[UrlEntry], options: &WarmOptions) -> WarmReport {
+    warm_caches_with(
+        &crate::client::UreqClient::default(),
+        sitemaps,
+        urls,
+        options,
+    )
+}
+
+/// Requests the sitemaps, then the first [`content_urls`](WarmOptions::content_urls) locs
+/// of the entries, on several threads within the limits of the options.
+///
+/// Failed requests are reported in the [`WarmReport`], a cold cache isn't worth failing a
+/// publish for.
+pub fn warm_caches_with<C>(
+    client: &C,
+    sitemaps: &[Url],
+    urls: &[UrlEntry],
+    options: &WarmOptions,
+) -> WarmReport
+where
+    C: HttpClient + Sync,
+{
+    let targets: Vec<&Url> = sitemaps
+        .iter()
+        .chain(
+            urls.iter()
+                .take(options.content_urls)
+                .map(|entry| &entry.loc),
+        )
+        .collect();
+
+    let throttle = Throttle::new(options.politeness.clone());
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Warmed>>> = Mutex::new(vec![None; targets.len()]);
+
+    let workers = options
+        .politeness
+        .max_concurrency
+        .clamp(1, targets.len().max(1));
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&url) = targets.get(i) else {
+                    break;
+                };
+
+                let status = {
+                    let _permit = throttle.acquire(url);
+                    client
+                        .get(url)
+                        .map(|response| response.status)
+                        .map_err(|e| e.to_string())
+                };
+                let warmed = Warmed {
+                    url: url.clone(),
+                    status,
+                };
+                results.lock().unwrap_or_else(|e| e.into_inner())[i] = Some(warmed);
+            });
+        }
+    });
+
+    WarmReport {
+        requests: results
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+            .into_iter()
+            .flatten()
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{client::HttpResponse, Error, Result};
+
+    #[test]
+    fn requests_sitemaps_then_content() {
+        let sitemaps: Vec<Url> = vec!["https://a.com/sitemap.xml".parse().unwrap()];
+        let urls: Vec<UrlEntry> = ["https://a.com/", "https://a.com/gone", "https://a.com/x"]
+            .iter()
+            .map(|loc| UrlEntry::new(loc.parse().unwrap(), None, None, None))
+            .collect();
+        let options = WarmOptions {
+            content_urls: 2,
+            politeness: PolitenessOptions {
+                per_host_delay: Duration::ZERO,
+                ..Default::default()
+            },
+        };
+
+        let client = |url: &Url| -> Result<HttpResponse> {
+            match url.path() {
+                "/gone" => Ok(HttpResponse::status(404)),
+                _ => Ok(HttpResponse::ok("")),
+            }
+        };
+        let report = warm_caches_with(&client, &sitemaps, &urls, &options);
+
+        let urls: Vec<&str> = report.requests.iter().map(|w| w.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://a.com/sitemap.xml",
+                "https://a.com/",
+                "https://a.com/gone"
+            ]
+        );
+        let failures: Vec<_> = report.failures().collect();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].status, Ok(404));
+
+        let unreachable = |url: &Url| -> Result<HttpResponse> {
+            Err(Error::Request {
+                url: url.clone(),
+                source: "refused".into(),
+            })
+        };
+        let report = warm_caches_with(&unreachable, &sitemaps, &[], &options);
+        assert!(report.requests[0].status.is_err());
+    }
+}
@@ -0,0 +1,548 @@
+//! Submitting sitemaps through the webmaster tools of search engines.
+//!
+//! Google and Bing retired their [pings](crate::ping), sitemaps are submitted through the
+//! [Search Console API](https://developers.google.com/webmaster-tools/v1/sitemaps) and the
+//! [Bing Webmaster API](https://learn.microsoft.com/en-us/bingwebmaster/) instead. Both
+//! also list the registered sitemaps of a site with their status. The requests are sent
+//! with [`HttpClient::send`], so the client must support `PUT` and `POST` requests, as
+//! [`UreqClient`](crate::client::UreqClient) does.
+//!
+//! ```rust,no_run
+//! use sitewriter::webmaster::{BingWebmaster, SearchConsole};
+//!
+//! let sitemap = "https://example.com/sitemap.xml".parse()?;
+//!
+//! // An OAuth access token with the webmasters scope.
+//! let google = SearchConsole::new("ya29.token");
+//! google.submit("sc-domain:example.com", &sitemap)?;
+//! for registered in google.list("sc-domain:example.com")? {
+//!     println!("{} pending: {}", registered.url, registered.pending);
+//! }
+//!
+//! let bing = BingWebmaster::new("api-key");
+//! bing.submit(&"https://example.com/".parse()?, &sitemap)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt;
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use url::{ParseError, Url};
+
+use crate::{
+    client::{HttpClient, HttpRequest, HttpResponse},
+    parse::parse_lastmod,
+    Error, Result,
+};
+
+/// What the `Debug` output shows instead of the credentials.
+const REDACTED: &str = "<redacted>";
+
+/// A sitemap registered in the webmaster tools of a site.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RegisteredSitemap {
+    /// The url of the sitemap.
+    pub url: Url,
+    /// When it was last submitted.
+    pub submitted: Option<DateTime<Utc>>,
+    /// When the search engine last downloaded it.
+    pub downloaded: Option<DateTime<Utc>>,
+    /// Whether it wasn't processed yet.
+    pub pending: bool,
+    /// The status reported by the search engine, if it reports one.
+    pub status: Option<String>,
+    /// The number of urls found in it, if known.
+    pub url_count: Option<u64>,
+    /// The number of errors found in it, if known.
+    pub errors: Option<u64>,
+    /// The number of warnings found in it, if known.
+    pub warnings: Option<u64>,
+}
+
+/// The Google Search Console API, authorized with an OAuth access token.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SearchConsole {
+    access_token: String,
+    endpoint: Url,
+}
+
+impl fmt::Debug for SearchConsole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SearchConsole")
+            .field("access_token", &REDACTED)
+            .field("endpoint", &self.endpoint)
+            .finish()
+    }
+}
+
+impl SearchConsole {
+    /// The API authorized with `access_token`, which needs the
+    /// `https://www.googleapis.com/auth/webmasters` scope.
+    #[must_use]
+    pub fn new(access_token: impl Into<String>) -> Self {
+        Self {
+            access_token: access_token.into(),
+            endpoint: "https://www.googleapis.com/webmasters/v3/"
+                .parse()
+                .expect("valid url"),
+        }
+    }
+
+    /// Overrides the base url of the API, for tests and proxies.
+    ///
+    /// The requests fail with [`Error::InvalidUrl`] if it can't be a base, such as a
+    /// `mailto:` url.
+    #[must_use]
+    pub fn endpoint(mut self, endpoint: Url) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    /// Submits `sitemap` for `site`, with the default client.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`SearchConsole::submit_with`].
+    #[cfg(feature = "fetch")]
+    pub fn submit(&self, site: &str, sitemap: &Url) -> Result<()> {
+        self.submit_with(&crate::client::UreqClient::default(), site, sitemap)
+    }
+
+    /// Submits `sitemap` for `site`, a url prefix property such as `https://example.com/`
+    /// or a domain property such as `sc-domain:example.com`.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::Request`] if the request fails or the API answers with an
+    /// error status.
+    pub fn submit_with<C: HttpClient>(&self, client: &C, site: &str, sitemap: &Url) -> Result<()> {
+        let url = self.url(&["sites", site, "sitemaps", sitemap.as_str()])?;
+        send(client, self.authorized(HttpRequest::new("PUT", url)))?;
+        Ok(())
+    }
+
+    /// Lists the sitemaps registered for `site`, with the default client.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`SearchConsole::list_with`].
+    #[cfg(feature = "fetch")]
+    pub fn list(&self, site: &str) -> Result<Vec<RegisteredSitemap>> {
+        self.list_with(&crate::client::UreqClient::default(), site)
+    }
+
+    /// Lists the sitemaps registered for `site`, see [`SearchConsole::submit_with`].
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::Request`] if the request fails or the API answers with an
+    /// error status, or [`Error::Source`] if the answer can't be read.
+    pub fn list_with<C: HttpClient>(
+        &self,
+        client: &C,
+        site: &str,
+    ) -> Result<Vec<RegisteredSitemap>> {
+        #[derive(Deserialize)]
+        struct List {
+            #[serde(default)]
+            sitemap: Vec<Sitemap>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Sitemap {
+            path: String,
+            last_submitted: Option<String>,
+            last_downloaded: Option<String>,
+            #[serde(default)]
+            is_pending: bool,
+            errors: Option<String>,
+            warnings: Option<String>,
+            #[serde(default)]
+            contents: Vec<Contents>,
+        }
+
+        #[derive(Deserialize)]
+        struct Contents {
+            submitted: Option<String>,
+        }
+
+        let url = self.url(&["sites", site, "sitemaps"])?;
+        let response = send(client, self.authorized(HttpRequest::new("GET", url)))?;
+        let list: List = json(&response)?;
+        Ok(list
+            .sitemap
+            .into_iter()
+            .filter_map(|sitemap| {
+                Some(RegisteredSitemap {
+                    url: sitemap.path.parse().ok()?,
+                    submitted: sitemap.last_submitted.as_deref().and_then(parse_lastmod),
+                    downloaded: sitemap.last_downloaded.as_deref().and_then(parse_lastmod),
+                    pending: sitemap.is_pending,
+                    status: None,
+                    url_count: sitemap
+                        .contents
+                        .iter()
+                        .filter_map(|contents| contents.submitted.as_deref()?.parse::<u64>().ok())
+                        .reduce(|a, b| a + b),
+                    // The API encodes 64-bit integers as strings.
+                    errors: sitemap.errors.and_then(|n| n.parse().ok()),
+                    warnings: sitemap.warnings.and_then(|n| n.parse().ok()),
+                })
+            })
+            .collect())
+    }
+
+    /// The url of the API path made of `segments`, which are percent-encoded.
+    fn url(&self, segments: &[&str]) -> Result<Url> {
+        let mut url = self.endpoint.clone();
+        url.path_segments_mut()
+            .map_err(|()| Error::InvalidUrl {
+                value: self.endpoint.to_string(),
+                source: ParseError::RelativeUrlWithCannotBeABaseBase,
+            })?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    fn authorized(&self, request: HttpRequest) -> HttpRequest {
+        request.header("Authorization", format!("Bearer {}", self.access_token))
+    }
+}
+
+/// The Bing Webmaster API, authorized with an API key.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BingWebmaster {
+    api_key: String,
+    endpoint: Url,
+}
+
+impl fmt::Debug for BingWebmaster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BingWebmaster")
+            .field("api_key", &REDACTED)
+            .field("endpoint", &self.endpoint)
+            .finish()
+    }
+}
+
+impl BingWebmaster {
+    /// The API authorized with `api_key`, generated in the settings of Bing Webmaster
+    /// Tools.
+    #[must_use]
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            endpoint: "https://ssl.bing.com/webmaster/api.svc/json/"
+                .parse()
+                .expect("valid url"),
+        }
+    }
+
+    /// Overrides the base url of the API, for tests and proxies.
+    ///
+    /// The requests fail with [`Error::InvalidUrl`] if it can't be a base, such as a
+    /// `mailto:` url.
+    #[must_use]
+    pub fn endpoint(mut self, endpoint: Url) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    /// Submits `sitemap` for `site`, with the default client.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`BingWebmaster::submit_with`].
+    #[cfg(feature = "fetch")]
+    pub fn submit(&self, site: &Url, sitemap: &Url) -> Result<()> {
+        self.submit_with(&crate::client::UreqClient::default(), site, sitemap)
+    }
+
+    /// Submits `sitemap` for `site`, the url of a site verified in Bing Webmaster Tools.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::Request`] if the request fails or the API answers with an
+    /// error status.
+    pub fn submit_with<C: HttpClient>(&self, client: &C, site: &Url, sitemap: &Url) -> Result<()> {
+        let body = serde_json::json!({
+            "siteUrl": site.as_str(),
+            "feedUrl": sitemap.as_str(),
+        });
+        let request = HttpRequest::new("POST", self.url("SubmitFeed", &[])?)
+            .header("Content-Type", "application/json; charset=utf-8")
+            .body(body.to_string());
+        send(client, request)?;
+        Ok(())
+    }
+
+    /// Lists the sitemaps registered for `site`, with the default client.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`BingWebmaster::list_with`].
+    #[cfg(feature = "fetch")]
+    pub fn list(&self, site: &Url) -> Result<Vec<RegisteredSitemap>> {
+        self.list_with(&crate::client::UreqClient::default(), site)
+    }
+
+    /// Lists the sitemaps registered for `site`, see [`BingWebmaster::submit_with`].
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::Request`] if the request fails or the API answers with an
+    /// error status, or [`Error::Source`] if the answer can't be read.
+    pub fn list_with<C: HttpClient>(
+        &self,
+        client: &C,
+        site: &Url,
+    ) -> Result<Vec<RegisteredSitemap>> {
+        #[derive(Deserialize)]
+        struct List {
+            #[serde(default)]
+            d: Vec<Feed>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Feed {
+            url: String,
+            submitted: Option<String>,
+            last_crawled: Option<String>,
+            status: Option<String>,
+            url_count: Option<u64>,
+        }
+
+        let url = self.url("GetFeeds", &[("siteUrl", site.as_str())])?;
+        let response = send(client, HttpRequest::new("GET", url))?;
+        let list: List = json(&response)?;
+        Ok(list
+            .d
+            .into_iter()
+            .filter_map(|feed| {
+                Some(RegisteredSitemap {
+                    url: feed.url.parse().ok()?,
+                    submitted: feed.submitted.as_deref().and_then(parse_bing_date),
+                    downloaded: feed.last_crawled.as_deref().and_then(parse_bing_date),
+                    pending: feed
+                        .status
+                        .as_deref()
+                        .is_some_and(|status| status.eq_ignore_ascii_case("pending")),
+                    status: feed.status,
+                    url_count: feed.url_count,
+                    errors: None,
+                    warnings: None,
+                })
+            })
+            .collect())
+    }
+
+    /// The url of the API method with the query `pairs` and the API key.
+    fn url(&self, method: &str, pairs: &[(&str, &str)]) -> Result<Url> {
+        let mut url = self
+            .endpoint
+            .join(method)
+            .map_err(|source| Error::InvalidUrl {
+                value: self.endpoint.to_string(),
+                source,
+            })?;
+        url.query_pairs_mut()
+            .append_pair("apikey", &self.api_key)
+            .extend_pairs(pairs);
+        Ok(url)
+    }
+}
+
+/// Sends `request`, failing on an error status.
+///
+/// The errors carry the url without its query, which holds the Bing API key.
+fn send<C: HttpClient>(client: &C, request: HttpRequest) -> Result<HttpResponse> {
+    let mut redacted = request.url.clone();
+    redacted.set_query(None);
+    let response = client.send(&request).map_err(|e| match e {
+        Error::Request { source, .. } => {
+            let message = source.to_string();
+            Error::Request {
+                source: if message.contains(request.url.as_str()) {
+                    message
+                        .replace(request.url.as_str(), redacted.as_str())
+                        .into()
+                } else {
+                    source
+                },
+                url: redacted.clone(),
+            }
+        }
+        e => e,
+    })?;
+    if !response.is_success() {
+        return Err(Error::Request {
+            url: redacted,
+            source: format!(
+                "unexpected status {}: {}",
+                response.status,
+                String::from_utf8_lossy(&response.body).trim()
+            )
+            .into(),
+        });
+    }
+    Ok(response)
+}
+
+fn json<'a, T: Deserialize<'a>>(response: &'a HttpResponse) -> Result<T> {
+    serde_json::from_slice(&response.body).map_err(|e| Error::Source(Box::new(e)))
+}
+
+/// Parses a date in the `/Date(1714550400000)/` format of the Bing API, the milliseconds
+/// since the epoch optionally followed by an offset which doesn't change the instant.
+fn parse_bing_date(text: &str) -> Option<DateTime<Utc>> {
+    let inner = text.strip_prefix("/Date(")?.strip_suffix(")/")?;
+    // The first character may be the sign of the milliseconds.
+    let end = inner
+        .char_indices()
+        .skip(1)
+        .find(|&(_, c)| c == '+' || c == '-')
+        .map_or(inner.len(), |(i, _)| i);
+    Utc.timestamp_millis_opt(inner[..end].parse().ok()?)
+        .single()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Records the requests and answers them with a fixed response.
+    struct Recorder {
+        requests: Mutex<Vec<HttpRequest>>,
+        response: HttpResponse,
+    }
+
+    impl Recorder {
+        fn new(response: HttpResponse) -> Self {
+            Self {
+                requests: Mutex::default(),
+                response,
+            }
+        }
+    }
+
+    impl HttpClient for Recorder {
+        fn get(&self, url: &Url) -> Result<HttpResponse> {
+            self.send(&HttpRequest::new("GET", url.clone()))
+        }
+
+        fn send(&self, request: &HttpRequest) -> Result<HttpResponse> {
+            self.requests.lock().unwrap().push(request.clone());
+            Ok(self.response.clone())
+        }
+    }
+
+    #[test]
+    fn search_console() {
+        let google = SearchConsole::new("token");
+        let sitemap: Url = "https://a.com/sitemap.xml".parse().unwrap();
+
+        let client = Recorder::new(HttpResponse::status(204));
+        google
+            .submit_with(&client, "sc-domain:a.com", &sitemap)
+            .unwrap();
+        let request = &client.requests.lock().unwrap()[0];
+        assert_eq!(request.method, "PUT");
+        assert_eq!(
+            request.url.as_str(),
+            "https://www.googleapis.com/webmasters/v3/sites/sc-domain:a.com/sitemaps/https:%2F%2Fa.com%2Fsitemap.xml"
+        );
+        assert_eq!(request.headers[0].1, "Bearer token");
+
+        let client = Recorder::new(HttpResponse::ok(
+            r#"{"sitemap": [{
+                "path": "https://a.com/sitemap.xml",
+                "lastSubmitted": "2024-05-01T08:00:00.000Z",
+                "isPending": true,
+                "errors": "2",
+                "warnings": "0",
+                "contents": [{"type": "web", "submitted": "10"}, {"type": "image", "submitted": "5"}]
+            }]}"#,
+        ));
+        let list = google.list_with(&client, "https://a.com/").unwrap();
+        assert_eq!(list[0].url, sitemap);
+        assert!(list[0].pending);
+        assert_eq!(list[0].submitted.unwrap().timestamp(), 1_714_550_400);
+        assert_eq!(list[0].url_count, Some(15));
+        assert_eq!(list[0].errors, Some(2));
+
+        let client = Recorder::new(HttpResponse {
+            body: b"{\"error\": \"forbidden\"}".to_vec(),
+            ..HttpResponse::status(403)
+        });
+        let error = google.list_with(&client, "https://a.com/").unwrap_err();
+        assert!(error.to_string().contains("unexpected status 403"));
+    }
+
+    #[test]
+    fn bing() {
+        let bing = BingWebmaster::new("key");
+        let site: Url = "https://a.com/".parse().unwrap();
+        let sitemap: Url = "https://a.com/sitemap.xml".parse().unwrap();
+
+        let client = Recorder::new(HttpResponse::ok(r#"{"d": null}"#));
+        bing.submit_with(&client, &site, &sitemap).unwrap();
+        let request = &client.requests.lock().unwrap()[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(
+            request.url.as_str(),
+            "https://ssl.bing.com/webmaster/api.svc/json/SubmitFeed?apikey=key"
+        );
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(body["feedUrl"], "https://a.com/sitemap.xml");
+
+        let client = Recorder::new(HttpResponse::ok(
+            r#"{"d": [{
+                "Url": "https://a.com/sitemap.xml",
+                "Submitted": "/Date(1714550400000-0700)/",
+                "LastCrawled": null,
+                "Status": "Pending",
+                "UrlCount": 12
+            }]}"#,
+        ));
+        let list = bing.list_with(&client, &site).unwrap();
+        assert!(client.requests.lock().unwrap()[0]
+            .url
+            .as_str()
+            .ends_with("GetFeeds?apikey=key&siteUrl=https%3A%2F%2Fa.com%2F"));
+        assert_eq!(list[0].submitted.unwrap().timestamp(), 1_714_550_400);
+        assert_eq!(list[0].downloaded, None);
+        assert!(list[0].pending);
+        assert_eq!(list[0].url_count, Some(12));
+
+        let client = Recorder::new(HttpResponse::status(401));
+        let Err(Error::Request { url, source }) = bing.list_with(&client, &site) else {
+            panic!("the request should fail");
+        };
+        assert!(url.query().is_none());
+        assert!(!source.to_string().contains("apikey"));
+        assert!(!format!("{bing:?}").contains("\"key\""));
+        assert!(!format!("{:?}", SearchConsole::new("token")).contains("\"token\""));
+
+        let mailto = BingWebmaster::new("key").endpoint("mailto:a@a.com".parse().unwrap());
+        assert!(matches!(
+            mailto.submit_with(&client, &site, &sitemap),
+            Err(Error::InvalidUrl { .. })
+        ));
+    }
+
+    #[test]
+    fn bing_dates() {
+        assert_eq!(
+            parse_bing_date("/Date(-1000+0100)/").unwrap().timestamp(),
+            -1
+        );
+        assert_eq!(parse_bing_date("/Date()/"), None);
+        assert_eq!(parse_bing_date("/Date(é)/"), None);
+    }
+}
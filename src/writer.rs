@@ -0,0 +1,522 @@
+//! Incremental writing of a sitemap, one entry at a time.
+//!
+//! A [`SitemapWriter`] writes each entry as soon as it is given, so the entries don't have
+//! to be in memory together. Since the `<urlset>` element comes first, the namespaces of
+//! the extensions have to be declared up front.
+//!
+//! An entry that fails, because an extension fails or uses an undeclared namespace, or
+//! because it doesn't pass the optional validation, aborts the writing by default. An
+//! [`on_entry_error`](SitemapWriter::on_entry_error) callback can skip it instead, the
+//! output never contains part of a failed entry:
+//!
+//! ```rust
+//! use sitewriter::{validate::ValidateOptions, writer::EntryAction, SitemapWriter, UrlEntry};
+//!
+//! let mut writer = SitemapWriter::new(Vec::new())
+//!     .validate(ValidateOptions::default())
+//!     .on_entry_error(|entry, error| {
+//!         eprintln!("skipping {}: {error}", entry.loc);
+//!         EntryAction::Skip
+//!     });
+//! writer.write(&UrlEntry::new("https://example.com/".parse()?, None, None, None))?;
+//! writer.write(&UrlEntry::new("ftp://example.com/".parse()?, None, None, None))?;
+//! assert_eq!((writer.written(), writer.skipped()), (1, 1));
+//!
+//! let xml = String::from_utf8(writer.finish()?)?;
+//! assert!(!xml.contains("ftp"));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::io::Write;
+
+use crate::{
+    extension, loc_str,
+    output::CountingWriter,
+    priority::PriorityStrategy,
+    progress::{ProgressEvent, ProgressHook},
+    urlset::{self, Renderer},
+    validate::ValidateOptions,
+    EntryDefaults, Error, Limits, Result, ToUrlEntry, Url, UrlEntry, UrlEntryRef,
+};
+
+/// What to do with an entry that failed, decided by the
+/// [`on_entry_error`](SitemapWriter::on_entry_error) callback.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EntryAction {
+    /// Leave the entry out and go on.
+    Skip,
+    /// Stop, [`SitemapWriter::write`] returns the error.
+    Abort,
+}
+
+type ErrorHandler = Box<dyn FnMut(UrlEntryRef<'_>, &Error) -> EntryAction + Send>;
+
+/// Writes a sitemap one entry at a time.
+///
+/// Each entry is serialized into a buffer first, the inner writer only gets whole entries.
+pub struct SitemapWriter<W: Write> {
+    writer: CountingWriter<W>,
+    renderer: Renderer,
+    on_entry_error: Option<ErrorHandler>,
+    pub(crate) progress: Option<ProgressHook>,
+    limits: Option<Limits>,
+    ascii_locs: bool,
+    scratch: Vec<u8>,
+    started: bool,
+    written: usize,
+    skipped: usize,
+}
+
+impl<W: Write> std::fmt::Debug for SitemapWriter<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SitemapWriter")
+            .field("namespaces", &self.renderer.namespaces)
+            .field("validate", &self.renderer.validate)
+            .field("written", &self.written)
+            .field("skipped", &self.skipped)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: Write> SitemapWriter<W> {
+    /// Creates a writer, nothing is written until the first entry or [`SitemapWriter::finish`].
+    pub fn new(inner: W) -> Self {
+        Self {
+            writer: CountingWriter::new(inner),
+            renderer: Renderer::default(),
+            on_entry_error: None,
+            progress: None,
+            limits: None,
+            ascii_locs: false,
+            scratch: Vec::new(),
+            started: false,
+            written: 0,
+            skipped: 0,
+        }
+    }
+
+    /// Declares a namespace used by the extensions of the entries.
+    ///
+    /// Entries with [`UrlEntry::mobile`] need the `mobile` prefix bound to
+    /// [`MOBILE_NAMESPACE`](crate::MOBILE_NAMESPACE).
+    #[must_use]
+    pub fn namespace(mut self, prefix: impl Into<String>, namespace: impl Into<String>) -> Self {
+        self.renderer
+            .namespaces
+            .push((prefix.into(), namespace.into()));
+        self
+    }
+
+    /// Checks every entry with [`validate_with`], an entry with an error fails with
+    /// [`Error::Invalid`].
+    #[must_use]
+    pub fn validate(mut self, options: ValidateOptions) -> Self {
+        self.renderer.validate = Some(options);
+        self
+    }
+
+    /// Sets the callback deciding what to do with an entry that failed.
+    ///
+    /// Errors of the underlying writer always abort.
+    #[must_use]
+    pub fn on_entry_error(
+        mut self,
+        callback: impl FnMut(UrlEntryRef<'_>, &Error) -> EntryAction + Send + 'static,
+    ) -> Self {
+        self.on_entry_error = Some(Box::new(callback));
+        self
+    }
+
+    /// Sets the hook told of the entries written, see [`progress`](crate::progress).
+    #[must_use]
+    pub fn on_progress(mut self, hook: impl FnMut(ProgressEvent) + Send + 'static) -> Self {
+        self.progress = Some(ProgressHook::new(hook));
+        self
+    }
+
+    /// Keeps the sitemap within `limits`: an entry that would take it over a limit isn't
+    /// written and fails with [`Error::LimitExceeded`], whatever the
+    /// [`on_entry_error`](SitemapWriter::on_entry_error) callback.
+    ///
+    /// The sitemap can then be finished and the entry written to the next one:
+    ///
+    /// ```rust
+    /// use sitewriter::{Error, Limits, SitemapWriter, UrlEntry};
+    ///
+    /// let urls = vec![UrlEntry::new("https://example.com/".parse()?, None, None, None); 3];
+    /// let limits = Limits::new(2, sitewriter::MAX_SIZE);
+    ///
+    /// let mut sitemaps = Vec::new();
+    /// let mut writer = SitemapWriter::new(Vec::new()).limits(limits);
+    /// for entry in &urls {
+    ///     if let Err(Error::LimitExceeded { .. }) = writer.write(entry) {
+    ///         sitemaps.push(writer.finish()?);
+    ///         writer = SitemapWriter::new(Vec::new()).limits(limits);
+    ///         writer.write(entry)?;
+    ///     }
+    /// }
+    /// sitemaps.push(writer.finish()?);
+    /// assert_eq!(sitemaps.len(), 2);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[must_use]
+    pub const fn limits(mut self, limits: Limits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Sets the values written for the entries whose lastmod, changefreq or priority is
+    /// `None`.
+    #[must_use]
+    pub const fn defaults(mut self, defaults: EntryDefaults) -> Self {
+        self.renderer.defaults = defaults;
+        self
+    }
+
+    /// Computes the priority of the entries without one with `strategy`, before the
+    /// [defaults](SitemapWriter::defaults) apply.
+    #[must_use]
+    pub fn priority_strategy(mut self, strategy: impl PriorityStrategy + 'static) -> Self {
+        self.renderer.priority = Some(Box::new(strategy));
+        self
+    }
+
+    /// Writes the locs given to [`SitemapWriter::write_ref`] in ASCII, with the domain in
+    /// its punycode form and the rest percent-encoded, since some crawlers mishandle raw
+    /// Unicode. A loc that isn't a valid url then fails with [`Error::InvalidUrl`].
+    ///
+    /// The locs of [`UrlEntry`] are always ASCII.
+    #[must_use]
+    pub const fn ascii_locs(mut self, ascii: bool) -> Self {
+        self.ascii_locs = ascii;
+        self
+    }
+
+    /// The inner writer, with what was written since it was last drained.
+    #[cfg(feature = "body")]
+    pub(crate) const fn get_mut(&mut self) -> &mut W {
+        self.writer.get_mut()
+    }
+
+    /// The number of entries written so far.
+    #[must_use]
+    pub const fn written(&self) -> usize {
+        self.written
+    }
+
+    /// The number of entries skipped so far.
+    #[must_use]
+    pub const fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Writes an entry.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if writing fails, if two namespaces share a prefix, or if the entry
+    /// fails and the callback doesn't skip it.
+    pub fn write(&mut self, entry: &UrlEntry) -> Result<()> {
+        let loc = loc_str(&entry.loc);
+        let entry_ref = UrlEntryRef {
+            loc: &loc,
+            ..entry.into()
+        };
+        match self.renderer.validate(entry) {
+            Ok(()) => self.write_ref(entry_ref),
+            Err(error) => {
+                self.start()?;
+                self.recover(entry_ref, error)
+            }
+        }
+    }
+
+    /// Writes a borrowed entry, whose loc is written as is.
+    ///
+    /// The entry isn't validated, even with [`SitemapWriter::validate`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` as [`SitemapWriter::write`].
+    pub fn write_ref(&mut self, entry: UrlEntryRef<'_>) -> Result<()> {
+        self.start()?;
+
+        let encoded;
+        let entry = if self.ascii_locs && !entry.loc.is_ascii() {
+            match Url::parse(entry.loc) {
+                Ok(url) => {
+                    encoded = String::from(url);
+                    UrlEntryRef {
+                        loc: &encoded,
+                        ..entry
+                    }
+                }
+                Err(source) => {
+                    let error = Error::InvalidUrl {
+                        value: entry.loc.to_owned(),
+                        source,
+                    };
+                    return self.recover(entry, error);
+                }
+            }
+        } else {
+            entry
+        };
+
+        self.scratch.clear();
+        let error = match self.renderer.render([entry], &mut self.scratch) {
+            Ok(()) => {
+                let limited = self.check_limits(1, self.scratch.len());
+                if limited.is_ok() {
+                    self.writer.write_all(&self.scratch)?;
+                    self.written += 1;
+                    self.report_progress();
+                }
+                return limited;
+            }
+            Err(error) => error,
+        };
+        self.recover(entry, error)
+    }
+
+    /// Fails if `entries` more entries of `size` bytes would take the sitemap over its
+    /// limits.
+    const fn check_limits(&self, entries: usize, size: usize) -> Result<()> {
+        let Some(limits) = &self.limits else {
+            return Ok(());
+        };
+        let size = self.writer.count() + size + urlset::TAIL.len();
+        limits.check(self.written + entries, size)
+    }
+
+    /// Tells the progress hook of the entries written so far.
+    fn report_progress(&self) {
+        if let Some(hook) = &self.progress {
+            hook.report(ProgressEvent::EntriesWritten {
+                entries: self.written,
+                bytes: self.writer.count(),
+            });
+        }
+    }
+
+    /// Asks the callback what to do with an entry that failed.
+    fn recover(&mut self, entry: UrlEntryRef<'_>, error: Error) -> Result<()> {
+        let action = self
+            .on_entry_error
+            .as_mut()
+            .map_or(EntryAction::Abort, |callback| callback(entry, &error));
+        match action {
+            EntryAction::Skip => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(loc = entry.loc, %error, "skipped an entry that failed to write");
+                self.skipped += 1;
+                Ok(())
+            }
+            EntryAction::Abort => Err(error),
+        }
+    }
+
+    /// Writes the entries as they are read from `entries`, such as the rows of a database
+    /// query, stopping at the first error of the source.
+    ///
+    /// # Errors
+    ///
+    /// Will return [`Error::Source`] with the error of the source, or `Err` as
+    /// [`SitemapWriter::write`].
+    pub fn write_all<I, E>(&mut self, entries: I) -> Result<()>
+    where
+        I: IntoIterator<Item = std::result::Result<UrlEntry, E>>,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        for entry in entries {
+            self.write(&entry.map_err(|e| Error::Source(e.into()))?)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the entry of each item, see [`ToUrlEntry`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` on the first entry failing like in [`SitemapWriter::write`].
+    pub fn add_all<I>(&mut self, base: &Url, items: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: ToUrlEntry,
+    {
+        for item in items {
+            self.write(&item.to_url_entry(base))?;
+        }
+        Ok(())
+    }
+
+    /// Closes the sitemap and returns the inner writer.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if writing fails.
+    pub fn finish(mut self) -> Result<W> {
+        self.start()?;
+        self.writer.write_all(urlset::TAIL)?;
+        Ok(self.writer.into_inner())
+    }
+
+    fn start(&mut self) -> Result<()> {
+        if self.started {
+            return Ok(());
+        }
+
+        let mut namespaces = Vec::with_capacity(self.renderer.namespaces.len());
+        for (prefix, namespace) in &self.renderer.namespaces {
+            extension::declare(&mut namespaces, prefix, namespace)?;
+        }
+        self.renderer.namespaces = namespaces;
+
+        let mut head = Vec::new();
+        urlset::head(&mut head, &self.renderer.namespaces);
+        self.writer.write_all(&head)?;
+        self.started = true;
+        Ok(())
+    }
+
+    /// Writes the entries, rendered on the threads of the rayon pool, stopping at the first
+    /// failed entry.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn write_par(&mut self, entries: &[&UrlEntry]) -> Result<()> {
+        use rayon::prelude::*;
+
+        /// The entries rendered by each task.
+        const CHUNK: usize = 1024;
+
+        self.start()?;
+        let renderer = &self.renderer;
+        let chunks: Vec<Vec<u8>> = entries
+            .par_chunks(CHUNK)
+            .map(|chunk| {
+                let locs = chunk
+                    .iter()
+                    .map(|&entry| {
+                        renderer.validate(entry)?;
+                        Ok(loc_str(&entry.loc))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let refs = chunk.iter().zip(&locs).map(|(&entry, loc)| UrlEntryRef {
+                    loc,
+                    ..entry.into()
+                });
+                let mut rendered = Vec::new();
+                renderer.render(refs, &mut rendered)?;
+                Ok(rendered)
+            })
+            .collect::<Result<_>>()?;
+
+        let size = chunks.iter().map(Vec::len).sum();
+        self.check_limits(entries.len(), size)?;
+        for chunk in chunks {
+            self.writer.write_all(&chunk)?;
+        }
+        self.written += entries.len();
+        self.report_progress();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExtensionWriter;
+
+    #[derive(Debug)]
+    struct Failing;
+
+    impl crate::SitemapExtension for Failing {
+        fn prefix(&self) -> &str {
+            "f"
+        }
+
+        fn namespace(&self) -> &str {
+            "urn:f"
+        }
+
+        fn write(&self, writer: &mut ExtensionWriter<'_>) -> Result<()> {
+            writer.start("f:partial", &[])?;
+            Err(Error::MissingElement("f:partial"))
+        }
+    }
+
+    #[test]
+    fn stops_at_limits() {
+        let entry = UrlEntry::new("https://a.com/".parse().unwrap(), None, None, None);
+        let one = crate::generate_bytes(std::slice::from_ref(&entry));
+
+        let mut writer = SitemapWriter::new(Vec::new())
+            .limits(Limits::new(10, one.len()))
+            .on_entry_error(|_, _| EntryAction::Skip);
+        writer.write(&entry).unwrap();
+        let err = writer.write(&entry).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::LimitExceeded {
+                limit: crate::Limit::Size,
+                ..
+            }
+        ));
+        assert_eq!((writer.written(), writer.skipped()), (1, 0));
+        assert_eq!(writer.finish().unwrap(), one);
+    }
+
+    #[test]
+    fn skips_failed_entries_entirely() {
+        let good = UrlEntry::new("https://a.com/".parse().unwrap(), None, None, None);
+        let mut failing = UrlEntry::new("https://a.com/f".parse().unwrap(), None, None, None);
+        failing.extensions.push(Failing);
+        let mut undeclared = UrlEntry::new("https://a.com/m".parse().unwrap(), None, None, None);
+        undeclared.mobile = true;
+
+        let mut writer = SitemapWriter::new(Vec::new())
+            .namespace("f", "urn:f")
+            .on_entry_error(|_, _| EntryAction::Skip);
+        for entry in [&good, &failing, &undeclared] {
+            writer.write(entry).unwrap();
+        }
+        assert_eq!((writer.written(), writer.skipped()), (1, 2));
+
+        let xml = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert!(!xml.contains("partial"));
+        let expected = crate::generate_str(&[good]).replace(
+            "<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\"",
+            "<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\" xmlns:f=\"urn:f\"",
+        );
+        assert_eq!(xml, expected);
+    }
+
+    #[test]
+    fn stops_at_source_errors() {
+        let rows = (0..5).map(|i| match i {
+            3 => Err("connection lost"),
+            _ => Ok(UrlEntry::new(
+                format!("https://a.com/{i}").parse().unwrap(),
+                None,
+                None,
+                None,
+            )),
+        });
+
+        let mut writer = SitemapWriter::new(Vec::new());
+        let result = writer.write_all(rows);
+        assert!(matches!(result, Err(Error::Source(e)) if e.to_string() == "connection lost"));
+        assert_eq!(writer.written(), 3);
+    }
+
+    #[test]
+    fn aborts_by_default() {
+        let mut entry = UrlEntry::new("https://a.com/".parse().unwrap(), None, None, None);
+        entry.mobile = true;
+
+        let mut writer = SitemapWriter::new(Vec::new());
+        assert!(matches!(
+            writer.write(&entry),
+            Err(Error::UndeclaredNamespace { prefix }) if prefix == "mobile"
+        ));
+    }
+}
@@ -0,0 +1,115 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use std::process::Command;
+
+use common::TempDir;
+
+#[test]
+fn generates_from_a_config() {
+    let dir = TempDir::new("cli");
+    std::fs::create_dir_all(dir.join("public/blog")).unwrap();
+    std::fs::write(dir.join("public/blog/index.html"), "").unwrap();
+    std::fs::write(
+        dir.join("public/robots.txt"),
+        "User-agent: *\nSitemap: old\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("sitewriter.toml"),
+        r#"
+base_url = "https://a.com/"
+output_dir = "public"
+robots = "public/robots.txt"
+
+[[routes]]
+path = "/"
+changefreq = "daily"
+lastmod = "2024-05-01"
+
+[[scan]]
+dir = "public"
+"#,
+    )
+    .unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_sitewriter"))
+        .arg("generate")
+        .arg(dir.join("sitewriter.toml"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let part = std::fs::read_to_string(dir.join("public/sitemap-1.xml")).unwrap();
+    let urls = sitewriter::parse_str(&part).unwrap();
+    let locs: Vec<&str> = urls.iter().map(|u| u.loc.as_str()).collect();
+    assert_eq!(locs, ["https://a.com/", "https://a.com/blog/"]);
+    assert_eq!(
+        std::fs::read_to_string(dir.join("public/robots.txt")).unwrap(),
+        "User-agent: *\nSitemap: https://a.com/sitemap_index.xml\n"
+    );
+
+    let status = Command::new(env!("CARGO_BIN_EXE_sitewriter"))
+        .arg("unknown")
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(2));
+}
+
+#[test]
+fn subcommands() {
+    let dir = TempDir::new("cli-sub");
+    let urls = |locs: &[&str]| {
+        let urls: Vec<sitewriter::UrlEntry> = locs
+            .iter()
+            .map(|loc| sitewriter::UrlEntry::try_new(*loc, None, None, None).unwrap())
+            .collect();
+        sitewriter::generate_bytes(&urls)
+    };
+    std::fs::write(
+        dir.join("old.xml"),
+        urls(&["https://a.com/", "https://a.com/a"]),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("new.xml"),
+        urls(&["https://a.com/", "https://a.com/b"]),
+    )
+    .unwrap();
+    std::fs::write(dir.join("bad.xml"), urls(&["ftp://a.com/"])).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_sitewriter"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+
+    assert!(run(&["validate", "old.xml", "new.xml"]).status.success());
+    assert!(!run(&["validate", "old.xml", "bad.xml"]).status.success());
+    assert!(run(&["validate", "--legacy-10mb", "old.xml"])
+        .status
+        .success());
+
+    let diff = run(&["diff", "old.xml", "new.xml"]);
+    assert_eq!(diff.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(diff.stdout).unwrap(),
+        "- https://a.com/a\n+ https://a.com/b\n"
+    );
+    assert!(run(&["diff", "old.xml", "old.xml"]).status.success());
+
+    assert!(
+        run(&["merge", "index.xml", "https://a.com/", "old.xml", "new.xml"])
+            .status
+            .success()
+    );
+    let index = std::fs::read_to_string(dir.join("index.xml")).unwrap();
+    assert!(index.contains("<loc>https://a.com/new.xml</loc>"));
+
+    assert!(run(&["split", "new.xml", "out", "https://a.com/"])
+        .status
+        .success());
+    assert!(dir.join("out/sitemap_index.xml").exists());
+}
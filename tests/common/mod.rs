@@ -0,0 +1,51 @@
+//! Helpers shared by the integration tests, which don't see the `cfg(test)` items of the
+//! crate.
+
+use std::{
+    ffi::OsStr,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+/// A directory under the system temp dir, removed with its contents when dropped, so a
+/// failed assertion doesn't leave it behind.
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates an empty `sitewriter-{name}-{pid}` directory, `name` being unique to the
+    /// test.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("sitewriter-{name}-{}", std::process::id()));
+        // Left over by a process that was killed with the same pid.
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<OsStr> for TempDir {
+    fn as_ref(&self) -> &OsStr {
+        self.0.as_os_str()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,114 @@
+//! Runs the code of the examples so they keep working.
+
+#[allow(dead_code)]
+mod common;
+
+#[allow(dead_code)]
+#[path = "../examples/from_database.rs"]
+mod from_database;
+
+#[allow(dead_code)]
+#[path = "../examples/dedup.rs"]
+mod dedup;
+
+#[test]
+fn from_database() {
+    let db = from_database::FakeDb::new(250);
+    let base = "https://example.com/".parse().unwrap();
+
+    let bytes = from_database::write_sitemap(&db, &base, Vec::new()).unwrap();
+    let xml = String::from_utf8(bytes).unwrap();
+
+    assert_eq!(xml.matches("<url>").count(), 250);
+    assert!(xml.contains("<loc>https://example.com/blog/post-0</loc>"));
+    assert!(xml.contains("<loc>https://example.com/blog/post-249</loc>"));
+}
+
+#[test]
+fn dedup() {
+    let urls = dedup::dedup(dedup::sources());
+    let locs: Vec<&str> = urls.iter().map(|e| e.loc.as_str()).collect();
+
+    assert_eq!(
+        locs,
+        [
+            "https://example.com/blog/",
+            "https://example.com/about/",
+            "https://example.com/shop/",
+            "https://example.com/docs/",
+        ]
+    );
+    assert_eq!(urls[1].lastmod, dedup::sources()[3].lastmod);
+}
+
+#[allow(dead_code)]
+#[path = "../examples/hreflang.rs"]
+mod hreflang;
+
+#[allow(dead_code)]
+#[path = "../examples/images_videos.rs"]
+mod images_videos;
+
+#[cfg(feature = "gzip")]
+#[allow(dead_code)]
+#[path = "../examples/split_gzip.rs"]
+mod split_gzip;
+
+#[cfg(feature = "axum")]
+#[allow(dead_code)]
+#[path = "../examples/axum_server.rs"]
+mod axum_server;
+
+#[test]
+fn hreflang() {
+    let urls = hreflang::entries(&["pricing"], &["en", "de"]);
+    let xml = sitewriter::generate_str(&urls);
+
+    assert_eq!(xml.matches("<url>").count(), 2);
+    assert_eq!(xml.matches("<xhtml:link").count(), 4);
+    assert!(xml.contains(r#"xmlns:xhtml="http://www.w3.org/1999/xhtml""#));
+    assert!(xml.contains(
+        r#"<xhtml:link rel="alternate" hreflang="de" href="https://example.com/de/pricing"/>"#
+    ));
+}
+
+#[test]
+fn images_videos() {
+    let xml = sitewriter::generate_str(&[images_videos::product("kettle", 2)]);
+
+    assert!(xml.contains(r#"xmlns:image="http://www.google.com/schemas/sitemap-image/1.1""#));
+    assert!(xml.contains(r#"xmlns:video="http://www.google.com/schemas/sitemap-video/1.1""#));
+    assert_eq!(xml.matches("<image:image>").count(), 2);
+    assert!(xml.contains("<image:loc>https://example.com/img/kettle-2.jpg</image:loc>"));
+    assert!(xml.contains("<video:title>The kettle in action</video:title>"));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn split_gzip() {
+    let dir = common::TempDir::new("cookbook");
+    let base = "https://example.com/maps/".parse().unwrap();
+
+    let files = split_gzip::publish(&split_gzip::pages(250), &dir, base, 100).unwrap();
+
+    assert_eq!(files.len(), 4);
+    assert_eq!(
+        files[0].url.as_str(),
+        "https://example.com/maps/sitemap_index.xml"
+    );
+    assert_eq!(files[1..].iter().map(|f| f.url_count).sum::<usize>(), 250);
+    let part = std::fs::read(dir.join("sitemap-3.xml.gz")).unwrap();
+    assert_eq!(&part[..2], [0x1f, 0x8b]);
+    let index = std::fs::read_to_string(dir.join("sitemap_index.xml")).unwrap();
+    assert!(index.contains("https://example.com/maps/sitemap-3.xml.gz"));
+}
+
+#[cfg(feature = "axum")]
+#[test]
+fn axum_server() {
+    let response = axum_server::response();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/xml");
+    assert_eq!(response.headers()["cache-control"], "public, max-age=600");
+}
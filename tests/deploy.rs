@@ -0,0 +1,22 @@
+#![cfg(all(feature = "fetch", feature = "manifest", feature = "test-server"))]
+
+use sitewriter::{
+    deploy::{verify_deployment, FileStatus},
+    manifest::{Manifest, ManifestFile},
+    test_server::{MockResponse, TestServer},
+};
+
+#[test]
+fn verifies_against_a_server() {
+    let server = TestServer::start().unwrap();
+    server.route("/sitemap.xml", MockResponse::ok("<urlset/>"));
+
+    let mut manifest = Manifest::new();
+    manifest.push(ManifestFile::new("sitemap.xml", b"<urlset/>", 0));
+    manifest.push(ManifestFile::new("missing.xml", b"<urlset/>", 0));
+
+    let report = verify_deployment(&manifest, &server.url("/")).unwrap();
+    assert_eq!(report.files[0].status, FileStatus::Ok);
+    assert_eq!(report.files[1].status, FileStatus::Missing(404));
+    assert_eq!(server.requests().len(), 2);
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "derive")]
+
+use chrono::{TimeZone, Utc};
+use sitewriter::{ChangeFreq, ToUrlEntry, Url};
+
+#[derive(ToUrlEntry)]
+struct Page<'a> {
+    #[sitemap(loc)]
+    path: &'a str,
+    #[sitemap(lastmod)]
+    updated: chrono::DateTime<Utc>,
+    #[sitemap(changefreq)]
+    changefreq: Option<ChangeFreq>,
+    #[sitemap(priority)]
+    priority: f32,
+}
+
+#[derive(ToUrlEntry)]
+#[sitemap(priority = 1)]
+#[sitemap(changefreq = "daily")]
+struct Product(
+    #[sitemap(loc = "products/{}")] u32,
+    #[sitemap(changefreq)] Option<ChangeFreq>,
+);
+
+#[test]
+fn derived_entries() {
+    let base: Url = "https://example.com/shop/".parse().unwrap();
+    let updated = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+    let page = Page {
+        path: "about",
+        updated,
+        changefreq: None,
+        priority: 0.3,
+    };
+    let entry = page.to_url_entry(&base);
+    assert_eq!(entry.loc.as_str(), "https://example.com/shop/about");
+    assert_eq!(entry.lastmod, Some(updated));
+    assert_eq!(entry.changefreq, None);
+    assert_eq!(entry.priority, Some(0.3));
+
+    let entry = Product(7, Some(ChangeFreq::Never)).to_url_entry(&base);
+    assert_eq!(entry.loc.as_str(), "https://example.com/shop/products/7");
+    assert_eq!(entry.changefreq, Some(ChangeFreq::Never));
+    assert_eq!(entry.priority, Some(1.0));
+    assert_eq!(
+        Product(8, None).to_url_entry(&base).changefreq,
+        Some(ChangeFreq::Daily)
+    );
+}
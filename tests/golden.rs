@@ -0,0 +1,70 @@
+use chrono::{TimeZone, Utc};
+use sitewriter::{snapshot::Snapshot, ChangeFreq, Sitemap, SortOrder, UrlEntry};
+
+fn snapshot() -> Snapshot {
+    Snapshot::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"))
+}
+
+fn urls() -> Vec<UrlEntry> {
+    vec![
+        UrlEntry {
+            loc: "https://example.com/search?q=a&page='2'".parse().unwrap(),
+            changefreq: None,
+            priority: Some(0.3),
+            lastmod: None,
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
+        },
+        UrlEntry {
+            loc: "https://example.com/".parse().unwrap(),
+            changefreq: Some(ChangeFreq::Daily),
+            priority: Some(1.0),
+            lastmod: Some(Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap()),
+            extensions: Default::default(),
+            mobile: false,
+            source: None,
+        },
+        UrlEntry::new(
+            "https://example.com/blog".parse().unwrap(),
+            None,
+            None,
+            None,
+        ),
+    ]
+}
+
+#[test]
+fn basic() {
+    snapshot().assert_sitemap("basic.xml", &Sitemap::new(), &urls());
+}
+
+#[test]
+fn sorted_by_loc() {
+    snapshot().assert_sitemap(
+        "sorted_by_loc.xml",
+        &Sitemap::new().sort(SortOrder::Loc),
+        &urls(),
+    );
+}
+
+#[test]
+fn generation_is_byte_stable() {
+    let sitemap = Sitemap::new().sort(SortOrder::Loc);
+    let first = sitemap.generate_bytes(&urls());
+    let mut reversed = urls();
+    reversed.reverse();
+    assert_eq!(sitemap.generate_bytes(&reversed), first);
+    assert_eq!(sitewriter::canonicalize(&first).unwrap(), first);
+
+    let golden = std::fs::read(snapshot().path("sorted_by_loc.xml")).unwrap();
+    assert_eq!(sitewriter::canonicalize(&golden).unwrap(), first);
+}
+
+#[test]
+fn golden_files_follow_the_schema() {
+    for name in ["basic.xml", "sorted_by_loc.xml"] {
+        let xml = std::fs::read(snapshot().path(name)).unwrap();
+        assert_eq!(Sitemap::validate_xml(&xml), [], "{name}");
+    }
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "test-server")]
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+};
+
+use sitewriter::test_server::{MockResponse, TestServer};
+
+fn send(server: &TestServer, request: &str) -> String {
+    let mut stream = TcpStream::connect(server.addr()).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn serves_routes() {
+    let server = TestServer::start().unwrap();
+    server.route(
+        "/sitemap.xml",
+        MockResponse::ok("<urlset/>").header("Content-Type", "application/xml"),
+    );
+
+    let response = send(&server, "GET /sitemap.xml HTTP/1.1\r\nHost: x\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("Content-Type: application/xml\r\n"));
+    assert!(response.ends_with("\r\n\r\n<urlset/>"));
+
+    let response = send(&server, "GET /sitemap.xml?v=2 HTTP/1.1\r\nHost: x\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+
+    let response = send(&server, "GET /missing HTTP/1.1\r\nHost: x\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
+
+#[test]
+fn records_requests() {
+    let server = TestServer::start().unwrap();
+    server.route("/hook", MockResponse::status(204));
+
+    let response = send(
+        &server,
+        "POST /hook HTTP/1.1\r\nHost: x\r\nX-Token: abc\r\nContent-Length: 5\r\n\r\nhello",
+    );
+    assert!(response.starts_with("HTTP/1.1 204 No Content\r\n"));
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].path, "/hook");
+    assert_eq!(requests[0].header("x-token"), Some("abc"));
+    assert_eq!(requests[0].body, b"hello");
+
+    server.clear_requests();
+    assert!(server.requests().is_empty());
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn ureq_client_limits_bodies() {
+    use sitewriter::{
+        client::{HttpClient, UreqClient},
+        Error, Limit,
+    };
+
+    let server = TestServer::start().unwrap();
+    server.route("/sitemap.xml", MockResponse::ok("0123456789"));
+    let url = server.url("/sitemap.xml");
+
+    let response = UreqClient::default().max_body(10).get(&url).unwrap();
+    assert_eq!(response.body, b"0123456789");
+    assert!(matches!(
+        UreqClient::default().max_body(9).get(&url),
+        Err(Error::LimitExceeded {
+            limit: Limit::Size,
+            max: 9
+        })
+    ));
+}
@@ -0,0 +1,49 @@
+#![cfg(target_arch = "wasm32")]
+
+// Run with `wasm-pack test --node` or `--headless --firefox`.
+
+use chrono::{TimeDelta, Utc};
+use sitewriter::{transform::LastmodPolicy, Sitemap, SitemapWriter, UrlEntry};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn urls() -> Vec<UrlEntry> {
+    vec![
+        UrlEntry::new(
+            "https://example.com/".parse().unwrap(),
+            None,
+            None,
+            Some(1.0),
+        ),
+        UrlEntry::new(
+            "https://example.com/blog".parse().unwrap(),
+            None,
+            None,
+            None,
+        ),
+    ]
+}
+
+#[wasm_bindgen_test]
+fn generates_in_memory() {
+    let xml = Sitemap::new().generate_str(&urls());
+    assert!(xml.contains("<loc>https://example.com/blog</loc>"));
+
+    let mut out = String::new();
+    Sitemap::new().generate_fmt(&mut out, &urls()).unwrap();
+    assert_eq!(out, xml);
+
+    let mut writer = SitemapWriter::new(Vec::new());
+    for entry in &urls() {
+        writer.write(entry).unwrap();
+    }
+    assert_eq!(writer.finish().unwrap(), xml.into_bytes());
+}
+
+#[wasm_bindgen_test]
+fn reads_the_clock() {
+    let policy = LastmodPolicy::new().clamp_future();
+    let mut urls = urls();
+    urls[0].lastmod = Some(Utc::now() + TimeDelta::days(1));
+    let sitemap = Sitemap::new().transform(policy);
+    assert!(sitemap.generate_str(&urls).contains("<lastmod>"));
+}